@@ -0,0 +1,219 @@
+//! The unsafe half of the fingerprint plugin ABI.
+//!
+//! `fingerprint` forbids unsafe code; every raw pointer and every call into a
+//! loaded library lives here instead, behind safe functions that exchange
+//! plain bytes. The host side is [`Plugin`] and [`Evaluator`]; the export side
+//! is [`evaluate_export`] and [`free_export`], which a generated crate's
+//! `fingerprint_plugin_evaluate` and `fingerprint_plugin_free` call.
+
+use std::ffi::{CStr, CString, c_char};
+use std::path::Path;
+
+/// `extern "C" fn() -> u32`: the ABI version the library was built against.
+pub const ABI_VERSION_SYMBOL: &str = "fingerprint_plugin_abi_version";
+/// `extern "C" fn() -> *const c_char`: `PluginMetadata` as JSON, owned by the library.
+pub const METADATA_SYMBOL: &str = "fingerprint_plugin_metadata";
+/// `extern "C" fn(*const c_char) -> *mut c_char`: `PluginRequest` JSON in,
+/// `PluginResponse` JSON out. The response is released with `FREE_SYMBOL`.
+pub const EVALUATE_SYMBOL: &str = "fingerprint_plugin_evaluate";
+/// `extern "C" fn(*mut c_char)`: release a response returned by `EVALUATE_SYMBOL`.
+pub const FREE_SYMBOL: &str = "fingerprint_plugin_free";
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type MetadataFn = unsafe extern "C" fn() -> *const c_char;
+type EvaluateFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+fn missing(symbol: &str) -> String {
+    format!("missing export `{symbol}`")
+}
+
+/// A shared library mapped into the process.
+pub struct Plugin {
+    library: libloading::Library,
+}
+
+impl Plugin {
+    /// Load the library at `path`.
+    ///
+    /// Loading runs the library's initializers, so the caller must already
+    /// have decided to trust the file.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        // SAFETY: running the initializers of a trusted library is the
+        // documented contract of this function.
+        let library = unsafe { libloading::Library::new(path) }
+            .map_err(|error| format!("failed to load library: {error}"))?;
+        Ok(Self { library })
+    }
+
+    /// The plugin ABI version the library reports.
+    pub fn abi_version(&self) -> Result<u32, String> {
+        // SAFETY: every plugin ABI version exports this symbol with this type.
+        unsafe {
+            let abi_version = self
+                .library
+                .get::<AbiVersionFn>(ABI_VERSION_SYMBOL.as_bytes())
+                .map_err(|_| {
+                    format!("{}; not a fingerprint plugin", missing(ABI_VERSION_SYMBOL))
+                })?;
+            Ok(abi_version())
+        }
+    }
+
+    /// The metadata JSON the library reports.
+    ///
+    /// Call only after `abi_version` returned the expected version: the
+    /// symbol types below are those of that version.
+    pub fn metadata(&self) -> Result<Vec<u8>, String> {
+        // SAFETY: the metadata export returns a NUL-terminated string owned
+        // by the library, which stays loaded while `self` lives.
+        unsafe {
+            let metadata = self
+                .library
+                .get::<MetadataFn>(METADATA_SYMBOL.as_bytes())
+                .map_err(|_| missing(METADATA_SYMBOL))?;
+            let metadata = metadata();
+            if metadata.is_null() {
+                return Err("plugin returned no metadata".to_owned());
+            }
+            Ok(CStr::from_ptr(metadata).to_bytes().to_vec())
+        }
+    }
+
+    /// Resolve the evaluate and free exports, keeping the library loaded for
+    /// as long as the evaluator lives.
+    pub fn into_evaluator(self) -> Result<Evaluator, String> {
+        // SAFETY: the symbol types match the ABI version the caller checked;
+        // the copied function pointers stay valid because the evaluator owns
+        // the library.
+        let (evaluate, free) = unsafe {
+            let evaluate = *self
+                .library
+                .get::<EvaluateFn>(EVALUATE_SYMBOL.as_bytes())
+                .map_err(|_| missing(EVALUATE_SYMBOL))?;
+            let free = *self
+                .library
+                .get::<FreeFn>(FREE_SYMBOL.as_bytes())
+                .map_err(|_| missing(FREE_SYMBOL))?;
+            (evaluate, free)
+        };
+        Ok(Evaluator {
+            evaluate,
+            free,
+            _library: Some(self.library),
+        })
+    }
+}
+
+/// The evaluate export of a loaded plugin.
+pub struct Evaluator {
+    evaluate: EvaluateFn,
+    free: FreeFn,
+    /// Keeps the library mapped while `evaluate` and `free` are callable.
+    _library: Option<libloading::Library>,
+}
+
+impl Evaluator {
+    /// Send request JSON to the plugin and return its response JSON.
+    pub fn call(&self, request: &[u8]) -> Result<Vec<u8>, String> {
+        let request =
+            CString::new(request).map_err(|_| "plugin request contains a NUL byte".to_owned())?;
+
+        // SAFETY: the response is a NUL-terminated string owned by the
+        // library until it is passed back to `free`, exactly once.
+        unsafe {
+            let response = (self.evaluate)(request.as_ptr());
+            if response.is_null() {
+                return Err("plugin returned no response".to_owned());
+            }
+            let bytes = CStr::from_ptr(response).to_bytes().to_vec();
+            (self.free)(response);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Implement a generated crate's `fingerprint_plugin_evaluate`: hand the
+/// request bytes (`None` for a null pointer) to `respond` and return its JSON
+/// as a string released by [`free_export`].
+///
+/// `respond` must not panic; the `fingerprint` side catches evaluation
+/// panics before they reach this boundary.
+///
+/// # Safety
+///
+/// `request` must be null or point to a NUL-terminated string that stays
+/// valid for the duration of the call.
+pub unsafe fn evaluate_export(
+    request: *const c_char,
+    respond: impl FnOnce(Option<&[u8]>) -> String,
+) -> *mut c_char {
+    // SAFETY: the caller guarantees a valid NUL-terminated string.
+    let request = (!request.is_null()).then(|| unsafe { CStr::from_ptr(request) }.to_bytes());
+    CString::new(respond(request))
+        .expect("JSON output never contains a NUL byte")
+        .into_raw()
+}
+
+/// Implement a generated crate's `fingerprint_plugin_free`.
+///
+/// # Safety
+///
+/// `response` must be null or a pointer returned by [`evaluate_export`] in
+/// the same library, not yet released.
+pub unsafe fn free_export(response: *mut c_char) {
+    if !response.is_null() {
+        // SAFETY: the caller guarantees the pointer came from `CString::into_raw`.
+        drop(unsafe { CString::from_raw(response) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The exports a generated crate provides, linked in-process.
+    unsafe extern "C" fn echo_evaluate(request: *const c_char) -> *mut c_char {
+        unsafe {
+            evaluate_export(request, |request| match request {
+                Some(bytes) => format!("echo:{}", String::from_utf8_lossy(bytes)),
+                None => "null".to_owned(),
+            })
+        }
+    }
+
+    unsafe extern "C" fn echo_free(response: *mut c_char) {
+        unsafe { free_export(response) }
+    }
+
+    #[test]
+    fn evaluator_round_trips_through_the_exports() {
+        let evaluator = Evaluator {
+            evaluate: echo_evaluate,
+            free: echo_free,
+            _library: None,
+        };
+
+        assert_eq!(evaluator.call(b"{}").expect("call plugin"), b"echo:{}");
+        assert!(evaluator.call(b"a\0b").is_err());
+        // SAFETY: a null request is part of the export contract.
+        let response = unsafe { echo_evaluate(std::ptr::null()) };
+        // SAFETY: `response` came from `echo_evaluate` and is released once.
+        let bytes = unsafe { CStr::from_ptr(response) }.to_bytes().to_vec();
+        unsafe { echo_free(response) };
+        assert_eq!(bytes, b"null");
+    }
+
+    #[test]
+    fn open_reports_files_that_are_not_libraries() {
+        let path = std::env::temp_dir().join(format!(
+            "fingerprint-plugin-ffi-{}.{}",
+            std::process::id(),
+            std::env::consts::DLL_EXTENSION
+        ));
+        std::fs::write(&path, "not a library").expect("write bogus library");
+        let opened = Plugin::open(&path);
+        std::fs::remove_file(&path).expect("remove bogus library");
+        assert!(opened.is_err_and(|error| error.starts_with("failed to load library")));
+    }
+}
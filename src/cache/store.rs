@@ -0,0 +1,406 @@
+use crate::registry::FingerprintResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// On-disk format tag written into every cache entry.
+pub const CACHE_FORMAT: &str = "fingerprint-cache.v0";
+
+/// Everything a cached fingerprint result depends on.
+///
+/// Two evaluations with equal keys produce identical results, so a hit can be
+/// substituted without opening the document. The file's size and modification
+/// time are part of the key, so a file rewritten under an unchanged
+/// `bytes_hash` is re-evaluated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub bytes_hash: String,
+    pub fingerprint_id: String,
+    pub definition_hash: String,
+    pub tool_version: String,
+    /// Document variant the fingerprint was evaluated against.
+    pub document_kind: String,
+    pub diagnose: bool,
+    /// Hash of the sidecar `text_path` content for PDF records, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_path_hash: Option<String>,
+    pub file_size: u64,
+    /// Modification time in nanoseconds since the Unix epoch, when available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_ns: Option<u64>,
+}
+
+impl CacheKey {
+    /// Stable digest of the key, used as the entry file name.
+    pub fn digest(&self) -> Result<String, String> {
+        let bytes = serde_json::to_vec(self)
+            .map_err(|error| format!("failed to serialize cache key: {error}"))?;
+        Ok(blake3::hash(&bytes).to_hex().to_string())
+    }
+}
+
+/// One stored evaluation result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub format: String,
+    pub key: CacheKey,
+    pub created_at: String,
+    pub result: FingerprintResult,
+}
+
+/// Which entries `invalidate` removes. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvalidateFilter {
+    pub fingerprint_id: Option<String>,
+    pub bytes_hash: Option<String>,
+    pub definition_hash: Option<String>,
+}
+
+impl InvalidateFilter {
+    fn matches(&self, key: &CacheKey) -> bool {
+        self.fingerprint_id
+            .as_ref()
+            .is_none_or(|id| *id == key.fingerprint_id)
+            && self
+                .bytes_hash
+                .as_ref()
+                .is_none_or(|hash| *hash == key.bytes_hash)
+            && self.definition_hash.as_ref().is_none_or(|hash| {
+                // Compiled and plugin keys append `@<source>@<version>` to the
+                // definition hash; the bare hash still selects them.
+                key.definition_hash
+                    .strip_prefix(hash.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('@'))
+            })
+    }
+}
+
+/// Summary of cache contents reported by `cache inspect`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub cache_dir: String,
+    pub entries: u64,
+    pub bytes: u64,
+    pub unreadable: u64,
+    pub fingerprints: BTreeMap<String, u64>,
+    pub tool_versions: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest: Option<String>,
+}
+
+/// Persistent evaluation cache: one JSON file per entry, sharded by digest prefix.
+#[derive(Debug, Clone)]
+pub struct EvalCache {
+    dir: PathBuf,
+}
+
+impl EvalCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up a result. Missing, unreadable or mismatched entries are misses.
+    pub fn get(&self, key: &CacheKey) -> Option<FingerprintResult> {
+        let contents = fs::read(self.entry_path(&key.digest().ok()?)).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&contents).ok()?;
+        (entry.format == CACHE_FORMAT && entry.key == *key).then_some(entry.result)
+    }
+
+    /// Store a result, replacing any previous entry for the key.
+    pub fn put(&self, key: &CacheKey, result: &FingerprintResult) -> Result<(), String> {
+        let digest = key.digest()?;
+        let path = self.entry_path(&digest);
+        let parent = path
+            .parent()
+            .expect("cache entry path always has a shard directory");
+        fs::create_dir_all(parent).map_err(|error| {
+            format!(
+                "failed to create cache directory '{}': {error}",
+                parent.display()
+            )
+        })?;
+
+        let entry = CacheEntry {
+            format: CACHE_FORMAT.to_owned(),
+            key: key.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            result: result.clone(),
+        };
+        let bytes = serde_json::to_vec(&entry)
+            .map_err(|error| format!("failed to serialize cache entry: {error}"))?;
+
+        // Write-then-rename so concurrent readers never see a partial entry.
+        let temp_path = parent.join(format!(
+            ".{digest}.{}.{:?}.tmp",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        fs::write(&temp_path, bytes).map_err(|error| {
+            format!(
+                "failed to write cache entry '{}': {error}",
+                temp_path.display()
+            )
+        })?;
+        fs::rename(&temp_path, &path).map_err(|error| {
+            let _ = fs::remove_file(&temp_path);
+            format!("failed to commit cache entry '{}': {error}", path.display())
+        })
+    }
+
+    /// Summarize entry counts, size and age.
+    pub fn inspect(&self) -> Result<CacheStats, String> {
+        let mut stats = CacheStats {
+            cache_dir: self.dir.display().to_string(),
+            ..CacheStats::default()
+        };
+
+        for path in self.entry_files()? {
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            stats.bytes = stats.bytes.saturating_add(size);
+            let Some(entry) = read_entry(&path) else {
+                stats.unreadable += 1;
+                continue;
+            };
+            stats.entries += 1;
+            *stats
+                .fingerprints
+                .entry(entry.key.fingerprint_id)
+                .or_default() += 1;
+            *stats
+                .tool_versions
+                .entry(entry.key.tool_version)
+                .or_default() += 1;
+            if stats
+                .oldest
+                .as_ref()
+                .is_none_or(|oldest| entry.created_at < *oldest)
+            {
+                stats.oldest = Some(entry.created_at.clone());
+            }
+            if stats
+                .newest
+                .as_ref()
+                .is_none_or(|newest| entry.created_at > *newest)
+            {
+                stats.newest = Some(entry.created_at);
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Remove unreadable entries, entries written by another tool version, and
+    /// (when `before` is set) entries created before that RFC3339 timestamp.
+    /// Returns the number of entries removed.
+    pub fn prune(&self, tool_version: &str, before: Option<&str>) -> Result<u64, String> {
+        let cutoff = before
+            .map(|value| {
+                chrono::DateTime::parse_from_rfc3339(value)
+                    .map_err(|error| format!("invalid --before timestamp '{value}': {error}"))
+            })
+            .transpose()?;
+
+        self.remove_where(|entry| {
+            let Some(entry) = entry else {
+                return true;
+            };
+            if entry.format != CACHE_FORMAT || entry.key.tool_version != tool_version {
+                return true;
+            }
+            cutoff.is_some_and(|cutoff| {
+                chrono::DateTime::parse_from_rfc3339(&entry.created_at)
+                    .map_or(true, |created_at| created_at < cutoff)
+            })
+        })
+    }
+
+    /// Remove entries matching a filter. Returns the number of entries removed.
+    pub fn invalidate(&self, filter: &InvalidateFilter) -> Result<u64, String> {
+        self.remove_where(|entry| entry.is_some_and(|entry| filter.matches(&entry.key)))
+    }
+
+    fn remove_where<F>(&self, mut remove: F) -> Result<u64, String>
+    where
+        F: FnMut(Option<&CacheEntry>) -> bool,
+    {
+        let mut removed = 0u64;
+        for path in self.entry_files()? {
+            let entry = read_entry(&path);
+            if remove(entry.as_ref()) {
+                fs::remove_file(&path).map_err(|error| {
+                    format!("failed to remove cache entry '{}': {error}", path.display())
+                })?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn entry_path(&self, digest: &str) -> PathBuf {
+        self.dir.join(&digest[..2]).join(format!("{digest}.json"))
+    }
+
+    fn entry_files(&self) -> Result<Vec<PathBuf>, String> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        let shards = fs::read_dir(&self.dir).map_err(|error| {
+            format!(
+                "failed to read cache directory '{}': {error}",
+                self.dir.display()
+            )
+        })?;
+        for shard in shards.flatten() {
+            let shard_path = shard.path();
+            if !shard_path.is_dir() {
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(&shard_path) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn read_entry(path: &Path) -> Option<CacheEntry> {
+    let contents = fs::read(path).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Resolve the cache directory from `$FINGERPRINT_CACHE` or `~/.fingerprint/cache`.
+pub fn default_cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("FINGERPRINT_CACHE")
+        && !dir.trim().is_empty()
+    {
+        return PathBuf::from(dir);
+    }
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".fingerprint")
+        .join("cache")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::AssertionResult;
+    use tempfile::TempDir;
+
+    fn key(fingerprint_id: &str, bytes_hash: &str) -> CacheKey {
+        CacheKey {
+            bytes_hash: bytes_hash.to_owned(),
+            fingerprint_id: fingerprint_id.to_owned(),
+            definition_hash: "blake3:def".to_owned(),
+            tool_version: "0.6.2".to_owned(),
+            document_kind: "csv".to_owned(),
+            diagnose: false,
+            text_path_hash: None,
+            file_size: 29,
+            modified_ns: Some(1_700_000_000_000_000_000),
+        }
+    }
+
+    fn result() -> FingerprintResult {
+        FingerprintResult {
+            matched: true,
+            reason: None,
+            assertions: vec![AssertionResult {
+                name: "filename_regex".to_owned(),
+                passed: true,
+                detail: None,
+                context: None,
+            }],
+            extracted: None,
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn put_then_get_round_trips_results() {
+        let dir = TempDir::new().expect("create cache dir");
+        let cache = EvalCache::new(dir.path());
+        let key = key("csv.v0", "blake3:aaa");
+
+        assert!(cache.get(&key).is_none());
+        cache.put(&key, &result()).expect("store entry");
+
+        let cached = cache.get(&key).expect("cache hit");
+        assert!(cached.matched);
+        assert_eq!(cached.assertions[0].name, "filename_regex");
+
+        let mut other_definition = key.clone();
+        other_definition.definition_hash = "blake3:changed".to_owned();
+        assert!(cache.get(&other_definition).is_none());
+    }
+
+    #[test]
+    fn inspect_invalidate_and_prune_manage_entries() {
+        let dir = TempDir::new().expect("create cache dir");
+        let cache = EvalCache::new(dir.path());
+        cache
+            .put(&key("csv.v0", "blake3:aaa"), &result())
+            .expect("store entry");
+        cache
+            .put(&key("csv.v0", "blake3:bbb"), &result())
+            .expect("store entry");
+        let mut old_version = key("xlsx.v0", "blake3:aaa");
+        old_version.tool_version = "0.5.0".to_owned();
+        cache.put(&old_version, &result()).expect("store entry");
+
+        let stats = cache.inspect().expect("inspect cache");
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.fingerprints.get("csv.v0"), Some(&2));
+        assert_eq!(stats.tool_versions.get("0.5.0"), Some(&1));
+
+        let removed = cache
+            .invalidate(&InvalidateFilter {
+                bytes_hash: Some("blake3:bbb".to_owned()),
+                ..InvalidateFilter::default()
+            })
+            .expect("invalidate entries");
+        assert_eq!(removed, 1);
+
+        let mut plugin = key("plugin.v1", "blake3:ccc");
+        plugin.definition_hash = "blake3:def@plugin:/opt/libplugin.so@0.6.2".to_owned();
+        cache.put(&plugin, &result()).expect("store entry");
+        let removed = cache
+            .invalidate(&InvalidateFilter {
+                fingerprint_id: Some("plugin.v1".to_owned()),
+                definition_hash: Some("blake3:def".to_owned()),
+                ..InvalidateFilter::default()
+            })
+            .expect("invalidate by bare definition hash");
+        assert_eq!(removed, 1);
+
+        let removed = cache.prune("0.6.2", None).expect("prune cache");
+        assert_eq!(removed, 1);
+        assert_eq!(cache.inspect().expect("inspect cache").entries, 1);
+
+        let removed = cache
+            .prune("0.6.2", Some("2999-01-01T00:00:00Z"))
+            .expect("prune by age");
+        assert_eq!(removed, 1);
+        assert_eq!(cache.inspect().expect("inspect cache").entries, 0);
+    }
+}
@@ -23,10 +23,46 @@ pub struct Cli {
     #[arg(long)]
     pub jobs: Option<usize>,
 
+    /// Skip records whose file exceeds this many bytes (0 disables; default: 1 GiB)
+    #[arg(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+
+    /// Skip ZIP-based records that decompress to more bytes (0 disables; default: 4 GiB)
+    #[arg(long, value_name = "BYTES")]
+    pub max_decompressed_size: Option<u64>,
+
+    /// Per-record evaluation timeout in seconds (0 disables; default: 300)
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Write JSONL output to this file instead of stdout
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Periodically record progress to this checkpoint file (requires INPUT and --output)
+    #[arg(long, value_name = "FILE", requires_all = ["input", "output"])]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Resume from --checkpoint, appending the remaining records to --output
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// Reuse and record fingerprint results in the on-disk evaluation cache
+    #[arg(long)]
+    pub cache: bool,
+
+    /// Evaluation cache directory (implies --cache; default: $FINGERPRINT_CACHE or ~/.fingerprint/cache)
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
     /// Suppress witness ledger recording
     #[arg(long)]
     pub no_witness: bool,
 
+    /// Also witness each record's bytes_hash and classification, linked to the run
+    #[arg(long, conflicts_with = "no_witness")]
+    pub witness_records: bool,
+
     /// Emit progress to stderr
     #[arg(long)]
     pub progress: bool,
@@ -48,9 +84,9 @@ pub struct Cli {
 pub enum Command {
     /// Compile DSL fingerprint to Rust crate
     Compile {
-        /// DSL fingerprint file (.fp.yaml)
+        /// DSL fingerprint file (.fp.yaml), or several files, directories and packs to compile into one crate
         #[arg(value_name = "YAML", required_unless_present = "schema")]
-        yaml: Option<PathBuf>,
+        yaml: Vec<PathBuf>,
 
         /// Output directory for generated crate
         #[arg(long, requires = "yaml")]
@@ -60,15 +96,53 @@ pub enum Command {
         #[arg(long, requires = "yaml")]
         check: bool,
 
+        /// Definition to compile when the file holds several (e.g. template instances)
+        #[arg(long = "fp", value_name = "ID", requires = "yaml")]
+        fingerprint_id: Option<String>,
+
+        /// Generate a crate that builds a `fingerprint` binary with every definition statically linked
+        #[arg(long, requires = "out", conflicts_with_all = ["check", "fingerprint_id"])]
+        binary: bool,
+
+        /// Crate name for a set crate (default: fingerprint-<pack> for a single pack, else fingerprint-custom)
+        #[arg(long = "name", value_name = "CRATE", requires = "out")]
+        crate_name: Option<String>,
+
         /// Print JSON Schema for .fp.yaml and exit
-        #[arg(long, conflicts_with_all = ["yaml", "out", "check"])]
+        #[arg(long, conflicts_with_all = ["yaml", "out", "check", "fingerprint_id"])]
         schema: bool,
     },
+    /// Print the .fp.yaml a compiled fingerprint was generated from
+    Decompile {
+        /// Compiled fingerprint ID
+        #[arg(value_name = "ID")]
+        fingerprint_id: String,
+
+        /// Write the definition to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Run static checks over fingerprint definitions
+    Lint {
+        /// `.fp.yaml` files or directories of them
+        #[arg(value_name = "PATH", required = true)]
+        paths: Vec<PathBuf>,
+    },
     /// Query the witness ledger
     Witness {
         #[command(subcommand)]
         action: WitnessAction,
     },
+    /// Install fingerprint packs into the definitions directory
+    Pack {
+        #[command(subcommand)]
+        action: PackAction,
+    },
+    /// Inspect and maintain the evaluation cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Infer fingerprint definition from example documents
     Infer {
         /// Directory of example documents
@@ -103,6 +177,18 @@ pub enum Command {
         /// JSONL input file (default: stdin)
         #[arg(long, value_name = "FILE")]
         input: Option<PathBuf>,
+
+        /// Walk a directory tree instead of reading JSONL
+        #[arg(long, value_name = "DIR", conflicts_with = "input")]
+        dir: Option<PathBuf>,
+
+        /// With --dir, follow symlinks instead of skipping them
+        #[arg(long, requires = "dir")]
+        follow_symlinks: bool,
+
+        /// With --dir, include paths excluded by ignore files
+        #[arg(long, requires = "dir")]
+        no_ignore: bool,
     },
     /// Infer fingerprint from a document + field values
     InferSchema {
@@ -135,6 +221,10 @@ pub enum WitnessAction {
         #[command(flatten)]
         filters: WitnessFilters,
 
+        /// Only output these fields (comma-separated; dotted paths such as params.jobs)
+        #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+        fields: Vec<String>,
+
         /// Emit a JSON array instead of JSONL
         #[arg(long)]
         json: bool,
@@ -144,6 +234,10 @@ pub enum WitnessAction {
         #[command(flatten)]
         filters: WitnessFilters,
 
+        /// Only output these fields (comma-separated; dotted paths such as params.jobs)
+        #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+        fields: Vec<String>,
+
         /// Emit JSON output
         #[arg(long)]
         json: bool,
@@ -153,10 +247,100 @@ pub enum WitnessAction {
         #[command(flatten)]
         filters: WitnessFilters,
 
+        /// Count per distinct value of these fields (comma-separated; `day` is the UTC date)
+        #[arg(long = "group-by", value_name = "FIELDS", value_delimiter = ',')]
+        group_by: Vec<String>,
+
+        /// Emit JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Recompute every record ID and check the ledger's hash chain
+    Verify {
+        /// Emit JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-run a recorded invocation and compare its output hash
+    Replay {
+        /// Witness record ID (or a unique prefix of it)
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Recorded output to diff against on mismatch (default: the run's --output file)
+        #[arg(long, value_name = "FILE")]
+        expected: Option<PathBuf>,
+
         /// Emit JSON output
         #[arg(long)]
         json: bool,
     },
+    /// Show how a document was classified by runs with --witness-records
+    History {
+        /// The document's bytes_hash
+        #[arg(value_name = "BYTES_HASH")]
+        bytes_hash: String,
+
+        /// Emit a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PackAction {
+    /// Validate a pack directory or archive and install it as a unit
+    Install {
+        /// Pack directory or ZIP archive with pack.yaml at its root
+        #[arg(value_name = "PACK")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// Summarize cache entries
+    Inspect {
+        /// Cache directory (default: $FINGERPRINT_CACHE or ~/.fingerprint/cache)
+        #[arg(long, value_name = "DIR")]
+        cache_dir: Option<PathBuf>,
+
+        /// Emit JSON output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove entries from other tool versions, unreadable entries, and old entries
+    Prune {
+        /// Cache directory (default: $FINGERPRINT_CACHE or ~/.fingerprint/cache)
+        #[arg(long, value_name = "DIR")]
+        cache_dir: Option<PathBuf>,
+
+        /// Also remove entries created before this RFC3339 timestamp
+        #[arg(long)]
+        before: Option<String>,
+    },
+    /// Remove entries matching the given filters
+    Invalidate {
+        /// Cache directory (default: $FINGERPRINT_CACHE or ~/.fingerprint/cache)
+        #[arg(long, value_name = "DIR")]
+        cache_dir: Option<PathBuf>,
+
+        /// Only remove entries for this fingerprint ID
+        #[arg(long = "fp", value_name = "ID")]
+        fingerprint_id: Option<String>,
+
+        /// Only remove entries for this document hash
+        #[arg(long = "bytes-hash")]
+        bytes_hash: Option<String>,
+
+        /// Only remove entries for this definition hash
+        #[arg(long = "definition-hash")]
+        definition_hash: Option<String>,
+
+        /// Remove every entry
+        #[arg(long, conflicts_with_all = ["fingerprint_id", "bytes_hash", "definition_hash"])]
+        all: bool,
+    },
 }
 
 #[derive(Debug, Args, Clone, Default, PartialEq, Eq)]
@@ -180,6 +364,24 @@ pub struct WitnessFilters {
     /// Only include records whose inputs include this hash
     #[arg(long = "input-hash")]
     pub input_hash: Option<String>,
+
+    /// Only include records whose params field equals VALUE, or lists it
+    /// (e.g. fingerprints=csv.v0); KEY alone requires the field to be set
+    /// and not false (repeatable)
+    #[arg(long = "param", value_name = "KEY[=VALUE]")]
+    pub params: Vec<String>,
+
+    /// Only include records with this binary hash (a trailing `*` matches a prefix)
+    #[arg(long = "binary-hash")]
+    pub binary_hash: Option<String>,
+
+    /// Only include records with this output hash (a trailing `*` matches a prefix)
+    #[arg(long = "output-hash")]
+    pub output_hash: Option<String>,
+
+    /// Only include records with an input path matching this glob
+    #[arg(long = "input-path", value_name = "GLOB")]
+    pub input_path: Option<String>,
 }
 
 impl WitnessFilters {
@@ -189,12 +391,16 @@ impl WitnessFilters {
             || self.until.is_some()
             || self.outcome.is_some()
             || self.input_hash.is_some()
+            || !self.params.is_empty()
+            || self.binary_hash.is_some()
+            || self.output_hash.is_some()
+            || self.input_path.is_some()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Cli, Command, WitnessAction};
+    use super::{CacheAction, Cli, Command, PackAction, WitnessAction};
     use clap::Parser;
     use std::path::PathBuf;
 
@@ -208,6 +414,12 @@ mod tests {
             "csv.v0",
             "--jobs",
             "4",
+            "--max-file-size",
+            "1048576",
+            "--max-decompressed-size",
+            "0",
+            "--timeout",
+            "30",
             "--no-witness",
             "--progress",
             "--diagnose",
@@ -219,6 +431,9 @@ mod tests {
             vec!["argus-model.v1".to_owned(), "csv.v0".to_owned()]
         );
         assert_eq!(cli.jobs, Some(4));
+        assert_eq!(cli.max_file_size, Some(1_048_576));
+        assert_eq!(cli.max_decompressed_size, Some(0));
+        assert_eq!(cli.timeout, Some(30));
         assert!(cli.no_witness);
         assert!(cli.progress);
         assert!(cli.diagnose);
@@ -233,6 +448,8 @@ mod tests {
             "--out",
             "out-dir",
             "--check",
+            "--fp",
+            "argus-model.v1",
         ]);
 
         let command = cli.command;
@@ -241,16 +458,129 @@ mod tests {
             yaml,
             out,
             check,
+            fingerprint_id,
             schema,
+            ..
         }) = command
         {
-            assert_eq!(yaml, Some(PathBuf::from("argus-model.fp.yaml")));
+            assert_eq!(yaml, vec![PathBuf::from("argus-model.fp.yaml")]);
             assert_eq!(out, Some(PathBuf::from("out-dir")));
             assert!(check);
+            assert_eq!(fingerprint_id.as_deref(), Some("argus-model.v1"));
             assert!(!schema);
         }
     }
 
+    #[test]
+    fn parses_compile_binary_with_several_inputs() {
+        let cli = Cli::parse_from([
+            "fingerprint",
+            "compile",
+            "--binary",
+            "a.fp.yaml",
+            "cmbs-pack/",
+            "--out",
+            "custom",
+            "--name",
+            "fingerprint-cmbs",
+        ]);
+
+        match cli.command {
+            Some(Command::Compile {
+                yaml,
+                out,
+                binary,
+                crate_name,
+                ..
+            }) => {
+                assert_eq!(
+                    yaml,
+                    vec![PathBuf::from("a.fp.yaml"), PathBuf::from("cmbs-pack/")]
+                );
+                assert_eq!(out, Some(PathBuf::from("custom")));
+                assert!(binary);
+                assert_eq!(crate_name.as_deref(), Some("fingerprint-cmbs"));
+            }
+            other => panic!("expected compile command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_decompile_subcommand() {
+        let cli = Cli::parse_from([
+            "fingerprint",
+            "decompile",
+            "argus-model.v1",
+            "--out",
+            "argus-model.fp.yaml",
+        ]);
+
+        match cli.command {
+            Some(Command::Decompile {
+                fingerprint_id,
+                out,
+            }) => {
+                assert_eq!(fingerprint_id, "argus-model.v1");
+                assert_eq!(out, Some(PathBuf::from("argus-model.fp.yaml")));
+            }
+            other => panic!("expected decompile command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_struct_check_dir_options() {
+        let cli = Cli::parse_from([
+            "fingerprint",
+            "struct-check",
+            "--rules",
+            "package.sf.yaml",
+            "--dir",
+            "deliveries/q3",
+            "--follow-symlinks",
+        ]);
+
+        match cli.command {
+            Some(Command::StructCheck {
+                input,
+                dir,
+                follow_symlinks,
+                no_ignore,
+                ..
+            }) => {
+                assert_eq!(input, None);
+                assert_eq!(dir, Some(PathBuf::from("deliveries/q3")));
+                assert!(follow_symlinks);
+                assert!(!no_ignore);
+            }
+            other => panic!("expected struct-check command, got {other:?}"),
+        }
+
+        let conflict = Cli::try_parse_from([
+            "fingerprint",
+            "struct-check",
+            "--rules",
+            "package.sf.yaml",
+            "--dir",
+            "deliveries/q3",
+            "--input",
+            "inventory.jsonl",
+        ]);
+        assert!(conflict.is_err());
+    }
+
+    #[test]
+    fn parses_lint_subcommand_with_several_paths() {
+        let cli = Cli::parse_from(["fingerprint", "lint", "a.fp.yaml", "defs/"]);
+
+        match cli.command {
+            Some(Command::Lint { paths }) => assert_eq!(
+                paths,
+                vec![PathBuf::from("a.fp.yaml"), PathBuf::from("defs/")]
+            ),
+            other => panic!("expected lint command, got {other:?}"),
+        }
+    }
+
     #[test]
     fn parses_compile_schema_subcommand_without_yaml() {
         let cli = Cli::parse_from(["fingerprint", "compile", "--schema"]);
@@ -262,15 +592,102 @@ mod tests {
             out,
             check,
             schema,
+            ..
         }) = command
         {
-            assert_eq!(yaml, None);
+            assert!(yaml.is_empty());
             assert_eq!(out, None);
             assert!(!check);
             assert!(schema);
         }
     }
 
+    #[test]
+    fn checkpoint_requires_input_and_output() {
+        let cli = Cli::parse_from([
+            "fingerprint",
+            "manifest.jsonl",
+            "--fp",
+            "csv.v0",
+            "--output",
+            "out.jsonl",
+            "--checkpoint",
+            "run.checkpoint",
+            "--resume",
+        ]);
+        assert_eq!(cli.output, Some(PathBuf::from("out.jsonl")));
+        assert_eq!(cli.checkpoint, Some(PathBuf::from("run.checkpoint")));
+        assert!(cli.resume);
+
+        assert!(
+            Cli::try_parse_from([
+                "fingerprint",
+                "--fp",
+                "csv.v0",
+                "--checkpoint",
+                "run.checkpoint"
+            ])
+            .is_err()
+        );
+        assert!(Cli::try_parse_from(["fingerprint", "manifest.jsonl", "--resume"]).is_err());
+    }
+
+    #[test]
+    fn parses_pack_install_subcommand() {
+        let cli = Cli::parse_from(["fingerprint", "pack", "install", "cmbs-pack.zip"]);
+        let Some(Command::Pack {
+            action: PackAction::Install { path },
+        }) = cli.command
+        else {
+            panic!("expected pack install subcommand");
+        };
+        assert_eq!(path, PathBuf::from("cmbs-pack.zip"));
+    }
+
+    #[test]
+    fn parses_cache_flags_and_subcommands() {
+        let run = Cli::parse_from(["fingerprint", "--fp", "csv.v0", "--cache-dir", "cache"]);
+        assert!(!run.cache);
+        assert_eq!(run.cache_dir, Some(PathBuf::from("cache")));
+
+        let invalidate = Cli::parse_from([
+            "fingerprint",
+            "cache",
+            "invalidate",
+            "--fp",
+            "csv.v0",
+            "--bytes-hash",
+            "blake3:abc",
+        ]);
+        let Some(Command::Cache {
+            action:
+                CacheAction::Invalidate {
+                    fingerprint_id,
+                    bytes_hash,
+                    all,
+                    ..
+                },
+        }) = invalidate.command
+        else {
+            panic!("expected cache invalidate subcommand");
+        };
+        assert_eq!(fingerprint_id.as_deref(), Some("csv.v0"));
+        assert_eq!(bytes_hash.as_deref(), Some("blake3:abc"));
+        assert!(!all);
+
+        assert!(
+            Cli::try_parse_from([
+                "fingerprint",
+                "cache",
+                "invalidate",
+                "--all",
+                "--fp",
+                "csv.v0"
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn parses_witness_subcommands() {
         let query = Cli::parse_from([
@@ -287,6 +704,18 @@ mod tests {
             "ALL_MATCHED",
             "--input-hash",
             "blake3:abc",
+            "--param",
+            "fingerprints=csv.v0",
+            "--param",
+            "diagnose",
+            "--binary-hash",
+            "blake3:9f2c*",
+            "--output-hash",
+            "blake3:51ad",
+            "--input-path",
+            "*.jsonl",
+            "--fields",
+            "id,params.jobs",
             "--json",
         ]);
         let last = Cli::parse_from([
@@ -303,6 +732,8 @@ mod tests {
             "count",
             "--since",
             "2026-02-01T00:00:00Z",
+            "--group-by",
+            "day,outcome",
         ]);
 
         let query_command = query.command;
@@ -313,7 +744,12 @@ mod tests {
             })
         ));
         if let Some(Command::Witness {
-            action: WitnessAction::Query { filters, json },
+            action:
+                WitnessAction::Query {
+                    filters,
+                    fields,
+                    json,
+                },
         }) = query_command
         {
             assert_eq!(filters.tool.as_deref(), Some("fingerprint"));
@@ -321,6 +757,11 @@ mod tests {
             assert_eq!(filters.until.as_deref(), Some("2026-01-31T23:59:59Z"));
             assert_eq!(filters.outcome.as_deref(), Some("ALL_MATCHED"));
             assert_eq!(filters.input_hash.as_deref(), Some("blake3:abc"));
+            assert_eq!(filters.params, ["fingerprints=csv.v0", "diagnose"]);
+            assert_eq!(filters.binary_hash.as_deref(), Some("blake3:9f2c*"));
+            assert_eq!(filters.output_hash.as_deref(), Some("blake3:51ad"));
+            assert_eq!(filters.input_path.as_deref(), Some("*.jsonl"));
+            assert_eq!(fields, ["id", "params.jobs"]);
             assert!(json);
         }
 
@@ -332,10 +773,16 @@ mod tests {
             })
         ));
         if let Some(Command::Witness {
-            action: WitnessAction::Last { filters, json },
+            action:
+                WitnessAction::Last {
+                    filters,
+                    fields,
+                    json,
+                },
         }) = last_command
         {
             assert_eq!(filters.tool.as_deref(), Some("fingerprint"));
+            assert!(fields.is_empty());
             assert!(json);
         }
 
@@ -347,14 +794,36 @@ mod tests {
             })
         ));
         if let Some(Command::Witness {
-            action: WitnessAction::Count { filters, json },
+            action:
+                WitnessAction::Count {
+                    filters,
+                    group_by,
+                    json,
+                },
         }) = count_command
         {
             assert_eq!(filters.since.as_deref(), Some("2026-02-01T00:00:00Z"));
+            assert_eq!(group_by, ["day", "outcome"]);
             assert!(!json);
         }
     }
 
+    #[test]
+    fn parses_witness_history_and_witness_records_flag() {
+        let history =
+            Cli::parse_from(["fingerprint", "witness", "history", "sha256:abc", "--json"]);
+        assert!(matches!(
+            history.command,
+            Some(Command::Witness {
+                action: WitnessAction::History { ref bytes_hash, json: true }
+            }) if bytes_hash == "sha256:abc"
+        ));
+
+        let run = Cli::parse_from(["fingerprint", "--fp", "csv.v0", "--witness-records"]);
+        assert!(run.witness_records);
+        assert!(Cli::try_parse_from(["fingerprint", "--witness-records", "--no-witness"]).is_err());
+    }
+
     #[test]
     fn parses_fingerprint_alias_for_fp_flag() {
         let cli = Cli::parse_from([
@@ -1,5 +1,5 @@
 pub mod args;
 pub mod exit;
 
-pub use args::{Cli, Command, WitnessAction, WitnessFilters};
+pub use args::{CacheAction, Cli, Command, PackAction, WitnessAction, WitnessFilters};
 pub use exit::Outcome;
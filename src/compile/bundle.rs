@@ -0,0 +1,594 @@
+use crate::compile::codegen::generate_module;
+use crate::compile::crate_gen::sanitize_crate_name;
+use crate::compile::lint::load_definitions;
+use crate::dsl::diagnostics::{Diagnostic, E_DUPLICATE_ID, E_ORPHAN_PARENT, E_PARENT_CYCLE};
+use crate::dsl::pack::{PACK_MANIFEST, PackManifest, load_pack};
+use crate::dsl::parser::FingerprintDefinition;
+use crate::registry::builtin::register_builtins;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Crate name used for a bundle whose inputs are not a single pack.
+pub const DEFAULT_BUNDLE_NAME: &str = "fingerprint-custom";
+/// Crate version used for a bundle whose inputs are not a single pack.
+pub const DEFAULT_BUNDLE_VERSION: &str = "0.1.0";
+
+/// A definition gathered for a bundle, with the file it was read from.
+#[derive(Debug, Clone)]
+pub struct BundleDefinition {
+    pub origin: String,
+    pub definition: FingerprintDefinition,
+}
+
+/// Definitions gathered from `.fp.yaml` files, directories and packs, in
+/// input order.
+#[derive(Debug, Clone)]
+pub struct BundleInput {
+    pub definitions: Vec<BundleDefinition>,
+    pub packs: Vec<PackManifest>,
+    /// Inputs that were `.fp.yaml` files or directories rather than packs.
+    pub files: Vec<PathBuf>,
+}
+
+impl BundleInput {
+    /// Crate name and version for the generated crate: the pack's own when
+    /// the only input is one pack, the defaults otherwise.
+    pub fn identity(&self) -> (String, String) {
+        match self.packs.as_slice() {
+            [pack] if self.files.is_empty() => (
+                sanitize_crate_name(&format!("fingerprint-{}", pack.pack)),
+                pack.version.clone(),
+            ),
+            _ => (
+                DEFAULT_BUNDLE_NAME.to_owned(),
+                DEFAULT_BUNDLE_VERSION.to_owned(),
+            ),
+        }
+    }
+}
+
+/// Load definitions from `.fp.yaml` files, directories of them, pack
+/// directories and pack archives.
+pub fn load_bundle_input(paths: &[PathBuf]) -> Result<BundleInput, String> {
+    let mut definitions = Vec::new();
+    let mut packs = Vec::new();
+    let mut files = Vec::new();
+
+    for path in paths {
+        if is_pack(path) {
+            let pack = load_pack(path)?;
+            definitions.extend(pack.definitions.into_iter().map(|entry| BundleDefinition {
+                origin: format!("{}/{}", pack.manifest.pack, entry.file),
+                definition: entry.definition,
+            }));
+            packs.push(pack.manifest);
+        } else {
+            definitions.extend(
+                load_definitions(std::slice::from_ref(path))?
+                    .into_iter()
+                    .map(|parsed| BundleDefinition {
+                        origin: parsed.source.origin,
+                        definition: parsed.definition,
+                    }),
+            );
+            files.push(path.clone());
+        }
+    }
+
+    if definitions.is_empty() {
+        return Err("no fingerprint definitions to compile".to_owned());
+    }
+    Ok(BundleInput {
+        definitions,
+        packs,
+        files,
+    })
+}
+
+/// Whether `path` is a pack directory or pack archive.
+pub fn is_pack(path: &Path) -> bool {
+    path.join(PACK_MANIFEST).is_file()
+        || (path.is_file() && path.extension().is_some_and(|ext| ext == "zip"))
+}
+
+/// Check parent/child relationships across a set compiled into one crate.
+///
+/// Reports duplicate IDs, parents that are neither in the set nor builtin,
+/// and parent cycles. Diagnostics follow input order; a cycle is reported
+/// once, on its first member in input order.
+pub fn check_definition_set(definitions: &[BundleDefinition]) -> Vec<Diagnostic> {
+    let builtins: BTreeSet<String> = register_builtins()
+        .iter()
+        .map(|fingerprint| fingerprint.id().to_owned())
+        .collect();
+    let mut first_origin: BTreeMap<&str, &str> = BTreeMap::new();
+    let mut parents: BTreeMap<&str, Option<&str>> = BTreeMap::new();
+    for entry in definitions {
+        let id = entry.definition.fingerprint_id.as_str();
+        first_origin.entry(id).or_insert(entry.origin.as_str());
+        parents
+            .entry(id)
+            .or_insert(entry.definition.parent.as_deref());
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut reported_cycles = BTreeSet::new();
+    for entry in definitions {
+        let id = entry.definition.fingerprint_id.as_str();
+        let diagnostic = |code, section, message: String| Diagnostic {
+            file: Some(entry.origin.clone()),
+            fingerprint_id: Some(id.to_owned()),
+            section: Some(section),
+            ..Diagnostic::new(code, message)
+        };
+
+        if !seen.insert(id) {
+            diagnostics.push(diagnostic(
+                E_DUPLICATE_ID,
+                "fingerprint_id",
+                format!(
+                    "duplicate fingerprint ID '{id}', first defined in {}",
+                    first_origin[id]
+                ),
+            ));
+            continue;
+        }
+
+        let Some(parent) = entry.definition.parent.as_deref() else {
+            continue;
+        };
+        if !parents.contains_key(parent) && !builtins.contains(parent) {
+            diagnostics.push(diagnostic(
+                E_ORPHAN_PARENT,
+                "parent",
+                format!("parent '{parent}' is not in the compiled set or builtin"),
+            ));
+            continue;
+        }
+
+        let mut chain = vec![id];
+        let mut current = parent;
+        while let Some(next) = parents.get(current) {
+            if let Some(position) = chain.iter().position(|member| *member == current) {
+                let cycle = &chain[position..];
+                if cycle.contains(&id) && reported_cycles.insert(cycle.iter().min().copied()) {
+                    let mut members = cycle.to_vec();
+                    members.push(current);
+                    diagnostics.push(diagnostic(
+                        E_PARENT_CYCLE,
+                        "parent",
+                        format!("parent cycle {}", members.join(" -> ")),
+                    ));
+                }
+                break;
+            }
+            chain.push(current);
+            match *next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Generate a library crate with one module per fingerprint and a
+/// `register_all()` function returning every compiled fingerprint.
+///
+/// Definitions must already be validated and checked as a set. Output is
+/// deterministic: modules are ordered by fingerprint ID, whatever the input
+/// order.
+pub fn generate_set_crate(
+    definitions: &[FingerprintDefinition],
+    crate_name: &str,
+    version: &str,
+    out_dir: &Path,
+) -> Result<(), String> {
+    let definitions = sorted_by_id(definitions)?;
+    write_fingerprint_modules(&definitions, out_dir)?;
+
+    let target = format!(
+        "[lib]\nname = \"{}\"\npath = \"src/lib.rs\"\n",
+        crate_name.replace('-', "_")
+    );
+    write(
+        &out_dir.join("Cargo.toml"),
+        &generate_set_cargo_toml(&definitions, crate_name, version, "library", &target),
+    )?;
+    write(
+        &out_dir.join("src").join("lib.rs"),
+        &format!(
+            r#"//! {crate_name}: {count} compiled fingerprints.
+//!
+//! DO NOT EDIT MANUALLY.
+
+pub mod fingerprints;
+
+pub use fingerprints::register_all;
+"#,
+            count = definitions.len(),
+        ),
+    )
+}
+
+/// Generate a crate that builds a `fingerprint` binary with every definition
+/// statically linked, one module per fingerprint.
+///
+/// Same requirements and module layout as [`generate_set_crate`].
+pub fn generate_binary_crate(
+    definitions: &[FingerprintDefinition],
+    crate_name: &str,
+    version: &str,
+    out_dir: &Path,
+) -> Result<(), String> {
+    let definitions = sorted_by_id(definitions)?;
+    write_fingerprint_modules(&definitions, out_dir)?;
+
+    let target = "[[bin]]\nname = \"fingerprint\"\npath = \"src/main.rs\"\n";
+    write(
+        &out_dir.join("Cargo.toml"),
+        &generate_set_cargo_toml(&definitions, crate_name, version, "binary", target),
+    )?;
+    write(&out_dir.join("src").join("main.rs"), BINARY_MAIN)
+}
+
+/// Definitions ordered by fingerprint ID; duplicate IDs are refused.
+fn sorted_by_id(
+    definitions: &[FingerprintDefinition],
+) -> Result<Vec<&FingerprintDefinition>, String> {
+    let mut definitions: Vec<&FingerprintDefinition> = definitions.iter().collect();
+    definitions.sort_by(|left, right| left.fingerprint_id.cmp(&right.fingerprint_id));
+    if let Some(pair) = definitions
+        .windows(2)
+        .find(|pair| pair[0].fingerprint_id == pair[1].fingerprint_id)
+    {
+        return Err(format!(
+            "duplicate fingerprint ID '{}'",
+            pair[0].fingerprint_id
+        ));
+    }
+    Ok(definitions)
+}
+
+/// Write `src/fingerprints/`: one module per definition plus `mod.rs` with
+/// `register_all()`.
+fn write_fingerprint_modules(
+    definitions: &[&FingerprintDefinition],
+    out_dir: &Path,
+) -> Result<(), String> {
+    let modules = module_names(definitions);
+    let fingerprints_dir = out_dir.join("src").join("fingerprints");
+    fs::create_dir_all(&fingerprints_dir).map_err(|error| {
+        format!(
+            "Failed to create output directory '{}': {error}",
+            fingerprints_dir.display()
+        )
+    })?;
+
+    write(
+        &fingerprints_dir.join("mod.rs"),
+        &generate_fingerprints_mod(&modules),
+    )?;
+    for (definition, module) in definitions.iter().zip(&modules) {
+        write(
+            &fingerprints_dir.join(format!("{module}.rs")),
+            &generate_module(definition)?,
+        )?;
+    }
+    Ok(())
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents)
+        .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+}
+
+/// Rust module name per definition; IDs that sanitize to the same name get
+/// a numeric suffix in ID order.
+fn module_names(definitions: &[&FingerprintDefinition]) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    definitions
+        .iter()
+        .map(|definition| {
+            let base = sanitize_crate_name(&definition.fingerprint_id).replace('-', "_");
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !seen.insert(name.clone()) {
+                name = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn generate_set_cargo_toml(
+    definitions: &[&FingerprintDefinition],
+    crate_name: &str,
+    version: &str,
+    kind: &str,
+    target: &str,
+) -> String {
+    let ids: Vec<&str> = definitions
+        .iter()
+        .map(|definition| definition.fingerprint_id.as_str())
+        .collect();
+
+    format!(
+        r#"[package]
+name = "{crate_name}"
+version = "{version}"
+edition = "2021"
+description = "fingerprint {kind} with {count} compiled definitions"
+
+# Generated by fingerprint v{compiler_version}
+# Fingerprints: {ids}
+
+[dependencies]
+fingerprint = {{ version = "{compiler_version}" }}
+
+{target}
+# Standalone workspace, so the crate builds wherever it is generated.
+[workspace]
+"#,
+        count = definitions.len(),
+        compiler_version = env!("CARGO_PKG_VERSION"),
+        ids = ids.join(", "),
+    )
+}
+
+const BINARY_MAIN: &str = r#"//! fingerprint binary with compiled definitions statically linked.
+//!
+//! DO NOT EDIT MANUALLY.
+
+mod fingerprints;
+
+use fingerprint::registry::compiled::CompiledSet;
+
+fn main() -> std::process::ExitCode {
+    let code = fingerprint::run_with_compiled(CompiledSet {
+        crate_name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        register: fingerprints::register_all,
+    });
+    std::process::ExitCode::from(code)
+}
+"#;
+
+fn generate_fingerprints_mod(modules: &[String]) -> String {
+    let declarations: String = modules
+        .iter()
+        .map(|module| format!("pub mod {module};\n"))
+        .collect();
+    let registrations: String = modules
+        .iter()
+        .map(|module| format!("        Box::new({module}::GeneratedFingerprint {{}}),\n"))
+        .collect();
+
+    format!(
+        r#"//! Compiled fingerprints, one module per definition.
+//!
+//! This file was auto-generated by fingerprint v{compiler_version}.
+//! DO NOT EDIT MANUALLY.
+
+// Each module keeps its FINGERPRINT_METADATA for provenance.
+#![allow(dead_code)]
+
+{declarations}
+use fingerprint::Fingerprint;
+
+/// Every compiled fingerprint, ordered by fingerprint ID.
+pub fn register_all() -> Vec<Box<dyn Fingerprint>> {{
+    vec![
+{registrations}    ]
+}}
+"#,
+        compiler_version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn definition(fingerprint_id: &str) -> FingerprintDefinition {
+        FingerprintDefinition {
+            fingerprint_id: fingerprint_id.to_owned(),
+            format: "csv".to_owned(),
+            valid_from: None,
+            valid_until: None,
+            parent: None,
+            assertions: vec![],
+            extract: vec![],
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn generate_binary_crate_writes_one_module_per_fingerprint() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let definitions = vec![
+            definition("loan-tape.v2"),
+            definition("deal-setup.v1"),
+            definition("loan_tape.v2"),
+        ];
+
+        generate_binary_crate(&definitions, "fingerprint-cmbs", "2.1.0", tmp.path())
+            .expect("generate binary crate");
+
+        let cargo_toml =
+            fs::read_to_string(tmp.path().join("Cargo.toml")).expect("read Cargo.toml");
+        assert!(cargo_toml.contains("name = \"fingerprint-cmbs\""));
+        assert!(cargo_toml.contains("version = \"2.1.0\""));
+        assert!(cargo_toml.contains("[[bin]]\nname = \"fingerprint\""));
+
+        let main_rs = fs::read_to_string(tmp.path().join("src/main.rs")).expect("read main.rs");
+        assert!(main_rs.contains("fingerprint::run_with_compiled"));
+
+        let fingerprints = tmp.path().join("src/fingerprints");
+        let mod_rs = fs::read_to_string(fingerprints.join("mod.rs")).expect("read mod.rs");
+        assert!(
+            mod_rs.contains(
+                "pub mod deal_setup_v1;\npub mod loan_tape_v2;\npub mod loan_tape_v2_2;\n"
+            )
+        );
+        assert!(mod_rs.contains("Box::new(loan_tape_v2_2::GeneratedFingerprint {}),"));
+
+        let module =
+            fs::read_to_string(fingerprints.join("loan_tape_v2_2.rs")).expect("read module");
+        assert!(module.contains("\"loan_tape.v2\""));
+        assert!(
+            !module.contains("no_mangle"),
+            "bundled modules must not export the plugin ABI"
+        );
+    }
+
+    fn entry(fingerprint_id: &str, parent: Option<&str>) -> BundleDefinition {
+        BundleDefinition {
+            origin: format!("{fingerprint_id}.fp.yaml"),
+            definition: FingerprintDefinition {
+                parent: parent.map(str::to_owned),
+                ..definition(fingerprint_id)
+            },
+        }
+    }
+
+    #[test]
+    fn check_definition_set_reports_duplicates_orphans_and_cycles() {
+        let definitions = vec![
+            entry("root.v1", None),
+            entry("child.v1", Some("root.v1")),
+            entry("csv-child.v1", Some("csv.v0")),
+            entry("root.v1", None),
+            entry("orphan.v1", Some("missing.v1")),
+            entry("loop-b.v1", Some("loop-a.v1")),
+            entry("loop-a.v1", Some("loop-b.v1")),
+            entry("into-loop.v1", Some("loop-a.v1")),
+        ];
+
+        let diagnostics = check_definition_set(&definitions);
+        let found: Vec<(&str, &str, &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.code,
+                    diagnostic.fingerprint_id.as_deref().unwrap_or_default(),
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    E_DUPLICATE_ID,
+                    "root.v1",
+                    "duplicate fingerprint ID 'root.v1', first defined in root.v1.fp.yaml"
+                ),
+                (
+                    E_ORPHAN_PARENT,
+                    "orphan.v1",
+                    "parent 'missing.v1' is not in the compiled set or builtin"
+                ),
+                (
+                    E_PARENT_CYCLE,
+                    "loop-b.v1",
+                    "parent cycle loop-b.v1 -> loop-a.v1 -> loop-b.v1"
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[1].section, Some("parent"));
+        assert_eq!(diagnostics[1].file.as_deref(), Some("orphan.v1.fp.yaml"));
+    }
+
+    #[test]
+    fn generate_set_crate_writes_library_with_register_all() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let definitions = vec![definition("b.v1"), definition("a.v1")];
+
+        generate_set_crate(&definitions, "fingerprint-custom", "0.1.0", tmp.path())
+            .expect("generate set crate");
+
+        let cargo_toml =
+            fs::read_to_string(tmp.path().join("Cargo.toml")).expect("read Cargo.toml");
+        assert!(cargo_toml.contains("[lib]\nname = \"fingerprint_custom\""));
+        assert!(!cargo_toml.contains("[[bin]]"));
+        assert!(!tmp.path().join("src/main.rs").exists());
+        let lib_rs = fs::read_to_string(tmp.path().join("src/lib.rs")).expect("read lib.rs");
+        assert!(lib_rs.contains("pub use fingerprints::register_all;"));
+        let mod_rs =
+            fs::read_to_string(tmp.path().join("src/fingerprints/mod.rs")).expect("read mod.rs");
+        assert!(mod_rs.contains("pub mod a_v1;\npub mod b_v1;\n"));
+
+        let other = TempDir::new().expect("create second temp dir");
+        let reversed: Vec<_> = definitions.into_iter().rev().collect();
+        generate_set_crate(&reversed, "fingerprint-custom", "0.1.0", other.path())
+            .expect("generate set crate again");
+        for file in [
+            "Cargo.toml",
+            "src/lib.rs",
+            "src/fingerprints/mod.rs",
+            "src/fingerprints/a_v1.rs",
+        ] {
+            assert_eq!(
+                fs::read_to_string(tmp.path().join(file)).expect("read first"),
+                fs::read_to_string(other.path().join(file)).expect("read second"),
+                "{file} should not depend on input order"
+            );
+        }
+    }
+
+    #[test]
+    fn generate_binary_crate_refuses_duplicate_ids() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let definitions = vec![definition("dup.v1"), definition("dup.v1")];
+
+        let error = generate_binary_crate(&definitions, "fingerprint-custom", "0.1.0", tmp.path())
+            .expect_err("duplicate IDs are refused");
+        assert_eq!(error, "duplicate fingerprint ID 'dup.v1'");
+    }
+
+    #[test]
+    fn bundle_identity_follows_a_single_pack() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let pack_dir = tmp.path().join("cmbs");
+        fs::create_dir_all(&pack_dir).expect("create pack dir");
+        fs::write(
+            pack_dir.join(PACK_MANIFEST),
+            "pack: cmbs\nversion: 2.1.0\ndefinitions:\n  - setup.fp.yaml\n",
+        )
+        .expect("write manifest");
+        fs::write(
+            pack_dir.join("setup.fp.yaml"),
+            "fingerprint_id: cmbs-setup.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+        )
+        .expect("write definition");
+        let loose = tmp.path().join("extra.fp.yaml");
+        fs::write(
+            &loose,
+            "fingerprint_id: extra.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+        )
+        .expect("write loose definition");
+
+        let pack_only = load_bundle_input(std::slice::from_ref(&pack_dir)).expect("load pack");
+        assert_eq!(
+            pack_only.identity(),
+            ("fingerprint-cmbs".to_owned(), "2.1.0".to_owned())
+        );
+        assert_eq!(pack_only.definitions[0].origin, "cmbs/setup.fp.yaml");
+
+        let mixed = load_bundle_input(&[pack_dir, loose]).expect("load pack and file");
+        assert_eq!(mixed.definitions.len(), 2);
+        assert_eq!(
+            mixed.identity(),
+            (
+                DEFAULT_BUNDLE_NAME.to_owned(),
+                DEFAULT_BUNDLE_VERSION.to_owned()
+            )
+        );
+    }
+}
@@ -1,30 +1,125 @@
-use crate::dsl::assertions::NamedAssertion;
+use crate::dsl::assertions::{
+    NamedAssertion, assertion_type_name, not_implemented, parse_cell_ref, parse_column_ref,
+    parse_range_ref, parse_row_range_ref,
+};
 use crate::dsl::parser::{ContentHashConfig, ExtractSection, FingerprintDefinition};
-use blake3::Hasher;
-use serde_yaml;
+use crate::dsl::plan::{canonical_definition, source_hash};
+use std::collections::HashMap;
 
 /// Generate Rust source implementing the Fingerprint trait from a parsed DSL definition.
+///
+/// The source also exports the plugin ABI, so the crate's cdylib can be
+/// loaded from `FINGERPRINT_PATH`.
 pub fn generate_rust(def: &FingerprintDefinition) -> Result<String, String> {
-    let source_hash = compute_definition_hash(def)?;
+    let mut rust_code = generate_module(def)?;
+    rust_code.push_str(PLUGIN_EXPORTS);
+    Ok(rust_code)
+}
+
+/// Plugin ABI exports appended to a standalone fingerprint crate.
+///
+/// Only one fingerprint per library may export these symbols, so bundled
+/// modules leave them out.
+const PLUGIN_EXPORTS: &str = r#"
+// Plugin ABI: `fingerprint` loads this crate's cdylib from FINGERPRINT_PATH.
+use fingerprint::registry::plugin::{self, PluginMetadata};
+use std::ffi::{c_char, CString};
+use std::sync::OnceLock;
+
+#[no_mangle]
+pub extern "C" fn fingerprint_plugin_abi_version() -> u32 {
+    plugin::PLUGIN_ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn fingerprint_plugin_metadata() -> *const c_char {
+    static METADATA: OnceLock<CString> = OnceLock::new();
+    METADATA
+        .get_or_init(|| {
+            plugin::metadata_cstring(&PluginMetadata {
+                crate_name: env!("CARGO_PKG_NAME").to_owned(),
+                fingerprint_id: FINGERPRINT_METADATA.fingerprint_id.to_owned(),
+                format: FINGERPRINT_METADATA.format.to_owned(),
+                parent: FINGERPRINT_METADATA.parent.map(str::to_owned),
+                compiler_version: FINGERPRINT_METADATA.compiler_version.to_owned(),
+                source_hash: FINGERPRINT_METADATA.source_hash.to_owned(),
+                definition: Some(FINGERPRINT_DEFINITION.to_owned()),
+            })
+        })
+        .as_ptr()
+}
+
+/// # Safety
+///
+/// `request` must be a NUL-terminated string valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn fingerprint_plugin_evaluate(request: *const c_char) -> *mut c_char {
+    unsafe {
+        plugin::ffi::evaluate_export(request, |request| {
+            plugin::respond_json(&GeneratedFingerprint {}, request)
+        })
+    }
+}
+
+/// # Safety
+///
+/// `response` must come from `fingerprint_plugin_evaluate` and not be released yet.
+#[no_mangle]
+pub unsafe extern "C" fn fingerprint_plugin_free(response: *mut c_char) {
+    unsafe { plugin::ffi::free_export(response) }
+}
+"#;
+
+/// Generate a Rust module implementing the Fingerprint trait as
+/// `GeneratedFingerprint`, with its `FINGERPRINT_METADATA`.
+///
+/// Each assertion becomes a direct call into `fingerprint::dsl::native`, with
+/// regexes compiled once into statics and spreadsheet references parsed here.
+/// Outside diagnose mode the generated code returns at the first failure.
+pub fn generate_module(def: &FingerprintDefinition) -> Result<String, String> {
+    let canonical = canonical_definition(def)?;
+    let source_hash = source_hash(&canonical);
     let compiler_version = env!("CARGO_PKG_VERSION");
     let valid_from = option_string_literal(def.valid_from.as_deref());
     let valid_until = option_string_literal(def.valid_until.as_deref());
     let parent = option_string_literal(def.parent.as_deref());
 
-    let assertions_code = generate_assertions_code(&def.assertions)?;
-    let extracted_code = generate_extracted_code(&def.extract);
+    let mut statics = Statics::default();
+    let assertions_code = generate_assertions_code(&def.assertions, &mut statics)?;
+    let extracted_code = generate_extracted_code(&def.extract, &mut statics);
     let content_hash_code = generate_content_hash_code(&def.content_hash);
 
-    let needs_column_pattern = def.assertions.iter().any(|a| {
+    let mut imports = vec![
+        "use fingerprint::{Document, Fingerprint, FingerprintResult};",
+        "use fingerprint::dsl::assertions::diagnose_mode;",
+        "use fingerprint::dsl::native;",
+    ];
+    if !def.assertions.is_empty() {
+        imports.push("use fingerprint::dsl::assertions::Assertion::*;");
+    }
+    if def.assertions.iter().any(|a| {
         matches!(
             a.assertion,
             crate::dsl::assertions::Assertion::HeaderRowMatch { .. }
         )
-    });
-    let column_pattern_import = if needs_column_pattern {
-        ", ColumnPattern"
+    }) {
+        imports.push("use fingerprint::dsl::assertions::ColumnPattern;");
+    }
+    if statics.has_regexes {
+        imports.push("use fingerprint::dsl::native::Regex;");
+    }
+    if !statics.declarations.is_empty() {
+        imports.push("use std::sync::LazyLock;");
+    }
+    let statics_code = if statics.declarations.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n\n", statics.declarations.join("\n"))
+    };
+    let evaluation_binding = if def.assertions.is_empty() {
+        "let evaluation"
     } else {
-        ""
+        "let mut evaluation"
     };
 
     let rust_code = format!(
@@ -33,11 +128,9 @@ pub fn generate_rust(def: &FingerprintDefinition) -> Result<String, String> {
 //! This file was auto-generated by fingerprint v{compiler_version}.
 //! DO NOT EDIT MANUALLY.
 
-use fingerprint::{{Document, Fingerprint, FingerprintResult}};
-use fingerprint::dsl::assertions::{{diagnose_mode, evaluate_named_assertions_with_diagnose{column_pattern_import}, NamedAssertion}};
-use fingerprint::dsl::assertions::Assertion::*;
+{imports}
 
-pub struct GeneratedFingerprint {{}}
+{statics_code}pub struct GeneratedFingerprint {{}}
 
 impl Fingerprint for GeneratedFingerprint {{
     fn id(&self) -> &str {{
@@ -52,29 +145,28 @@ impl Fingerprint for GeneratedFingerprint {{
         {parent}
     }}
 
-    fn fingerprint(&self, doc: &Document) -> FingerprintResult {{
-        let diagnose = diagnose_mode();
-
-{assertions_code}
+    fn definition_hash(&self) -> Option<&str> {{
+        Some("{source_hash}")
+    }}
 
-        // Extract content if all assertions passed
-        let extracted = if all_passed {{
-{extracted_code}
-        }} else {{
-            None
-        }};
+    fn definition_source(&self) -> Option<&str> {{
+        Some(FINGERPRINT_DEFINITION)
+    }}
 
-        // Compute content hash if enabled and assertions passed
-        let content_hash = if all_passed {{
-{content_hash_code}
-        }} else {{
-            None
+    fn fingerprint(&self, doc: &Document) -> FingerprintResult {{
+        {evaluation_binding} = native::Evaluation::new(doc, diagnose_mode());
+{assertions_code}
+        let assertions = match evaluation.finish() {{
+            Ok(assertions) => assertions,
+            Err(no_match) => return no_match,
         }};
+        let extracted = {extracted_code};
+        let content_hash = {content_hash_code};
 
         FingerprintResult {{
-            matched: all_passed,
-            reason: first_failure_reason,
-            assertions: passed_assertions,
+            matched: true,
+            reason: None,
+            assertions,
             extracted,
             content_hash,
         }}
@@ -93,6 +185,9 @@ pub const FINGERPRINT_METADATA: FingerprintMetadata = FingerprintMetadata {{
     source: "dsl",
 }};
 
+/// Canonical YAML of the definition; its BLAKE3 hash is `source_hash`.
+pub const FINGERPRINT_DEFINITION: &str = {definition};
+
 #[derive(Debug, Clone)]
 pub struct FingerprintMetadata {{
     pub fingerprint_id: &'static str,
@@ -105,6 +200,7 @@ pub struct FingerprintMetadata {{
     pub source: &'static str,
 }}
 "#,
+        imports = imports.join("\n"),
         compiler_version = compiler_version,
         fingerprint_id = def.fingerprint_id,
         format = def.format,
@@ -112,6 +208,7 @@ pub struct FingerprintMetadata {{
         valid_from = valid_from,
         valid_until = valid_until,
         source_hash = source_hash,
+        definition = format_args!("{canonical:?}"),
         assertions_code = assertions_code,
         extracted_code = extracted_code,
         content_hash_code = content_hash_code,
@@ -127,46 +224,272 @@ fn option_string_literal(value: Option<&str>) -> String {
     }
 }
 
-/// Compute a deterministic hash of the fingerprint definition.
-fn compute_definition_hash(def: &FingerprintDefinition) -> Result<String, String> {
-    // Serialize to a canonical form (sorted keys) for consistent hashing
-    let yaml_bytes = serde_yaml::to_string(def)
-        .map_err(|e| format!("Failed to serialize definition for hashing: {}", e))?;
+/// Statics emitted ahead of the fingerprint: compiled regexes, shared by
+/// identical patterns, and the extract plan.
+#[derive(Default)]
+struct Statics {
+    declarations: Vec<String>,
+    regex_names: HashMap<String, String>,
+    has_regexes: bool,
+}
 
-    let mut hasher = Hasher::new();
-    hasher.update(yaml_bytes.as_bytes());
-    let hash = hasher.finalize();
+impl Statics {
+    /// Reference to a static holding `pattern` compiled.
+    fn regex(&mut self, pattern: &str) -> String {
+        if let Some(name) = self.regex_names.get(pattern) {
+            return format!("&{name}");
+        }
+        let name = format!("REGEX_{}", self.declarations.len());
+        self.declarations.push(format!(
+            "static {name}: LazyLock<Regex> = LazyLock::new(|| native::regex({pattern:?}));"
+        ));
+        self.regex_names.insert(pattern.to_owned(), name.clone());
+        self.has_regexes = true;
+        format!("&{name}")
+    }
 
-    Ok(format!("blake3:{}", hash.to_hex()))
+    /// Reference to a static holding `patterns` compiled, in order.
+    fn regexes<'a>(&mut self, patterns: impl IntoIterator<Item = &'a str>) -> String {
+        let name = format!("REGEXES_{}", self.declarations.len());
+        let items: Vec<String> = patterns
+            .into_iter()
+            .map(|pattern| format!("{pattern:?}"))
+            .collect();
+        self.declarations.push(format!(
+            "static {name}: LazyLock<Vec<Regex>> =\n    LazyLock::new(|| native::regexes(&[{}]));",
+            items.join(", ")
+        ));
+        self.has_regexes = true;
+        format!("&{name}")
+    }
 }
 
-/// Generate code for evaluating all assertions.
-fn generate_assertions_code(assertions: &[NamedAssertion]) -> Result<String, String> {
-    let mut code_lines = Vec::new();
-    code_lines.push("        let generated_assertions: Vec<NamedAssertion> = vec![".to_owned());
-    for assertion in assertions {
-        let name_code = match &assertion.name {
-            Some(n) => format!("Some({:?}.to_owned())", n),
-            None => "None".to_owned(),
-        };
-        let assertion_code = codegen_assertion(&assertion.assertion);
-        code_lines.push(format!(
-            "            NamedAssertion {{ name: {}, assertion: {} }},",
-            name_code, assertion_code
+/// Generate one evaluation step per assertion, in declaration order.
+fn generate_assertions_code(
+    assertions: &[NamedAssertion],
+    statics: &mut Statics,
+) -> Result<String, String> {
+    let mut code = String::new();
+    for (index, assertion) in assertions.iter().enumerate() {
+        let type_name = assertion_type_name(&assertion.assertion);
+        let name = assertion.name.as_deref().unwrap_or(type_name);
+        let outcome = codegen_outcome(&assertion.assertion, statics)?;
+        code.push_str(&format!(
+            r#"
+        // assertions[{index}]: {type_name}
+        let outcome = {outcome};
+        if !evaluation.record({name:?}, outcome, || {assertion}) {{
+            return evaluation.no_match();
+        }}
+"#,
+            assertion = codegen_assertion(&assertion.assertion),
         ));
     }
-    code_lines.push("        ];".to_owned());
-    code_lines.push(
-        "        let passed_assertions = evaluate_named_assertions_with_diagnose(&generated_assertions, doc, diagnose);".to_owned(),
-    );
-    code_lines.push(
-        "        let all_passed = passed_assertions.iter().all(|result| result.passed);".to_owned(),
-    );
-    code_lines.push("        let first_failure_reason = passed_assertions".to_owned());
-    code_lines.push("            .iter()".to_owned());
-    code_lines.push("            .find(|result| !result.passed)".to_owned());
-    code_lines.push("            .and_then(|result| result.detail.clone());".to_owned());
-    Ok(code_lines.join("\n"))
+    Ok(code)
+}
+
+/// Generate the expression evaluating one assertion to a `native::Outcome`.
+fn codegen_outcome(
+    assertion: &crate::dsl::assertions::Assertion,
+    statics: &mut Statics,
+) -> Result<String, String> {
+    use crate::dsl::assertions::Assertion;
+
+    /// Call `call` with the sheet, resolving `$binding` references first.
+    fn on_sheet(sheet: &str, call: impl Fn(&str) -> String) -> String {
+        if sheet.starts_with('$') {
+            format!(
+                "evaluation.sheet({sheet:?}).and_then(|sheet| {})",
+                call("&sheet")
+            )
+        } else {
+            call(&format!("{sheet:?}"))
+        }
+    }
+
+    fn opt<T: std::fmt::Display>(value: Option<T>) -> String {
+        codegen_option_display(value)
+    }
+
+    let code = match assertion {
+        Assertion::FilenameRegex { pattern } => {
+            format!("native::filename_regex(doc, {})", statics.regex(pattern))
+        }
+        Assertion::SheetExists(sheet) => {
+            on_sheet(sheet, |sheet| format!("native::sheet_exists(doc, {sheet})"))
+        }
+        Assertion::SheetNameRegex { pattern, bind } => {
+            let matched = format!("native::sheet_name_regex(doc, {})", statics.regex(pattern));
+            match bind {
+                Some(bind) => {
+                    format!("{matched}.and_then(|sheet| evaluation.bind({bind:?}, &sheet))")
+                }
+                None => format!("{matched}.map(|_| ())"),
+            }
+        }
+        Assertion::CellEq { sheet, cell, value } => {
+            let (row, column) = parse_cell_ref(cell)?;
+            on_sheet(sheet, |sheet| {
+                format!("native::cell_eq(doc, {sheet}, ({row}, {column}), {cell:?}, {value:?})")
+            })
+        }
+        Assertion::CellRegex {
+            sheet,
+            cell,
+            pattern,
+        } => {
+            let (row, column) = parse_cell_ref(cell)?;
+            let regex = statics.regex(pattern);
+            on_sheet(sheet, |sheet| {
+                format!("native::cell_regex(doc, {sheet}, ({row}, {column}), {cell:?}, {regex})")
+            })
+        }
+        Assertion::RangeNonNull { sheet, range } => {
+            let ((top, left), (bottom, right)) = parse_range_ref(range)?;
+            on_sheet(sheet, |sheet| {
+                format!(
+                    "native::range_non_null(doc, {sheet}, (({top}, {left}), ({bottom}, {right})), {range:?})"
+                )
+            })
+        }
+        Assertion::SheetMinRows { sheet, min_rows } => on_sheet(sheet, |sheet| {
+            format!("native::sheet_min_rows(doc, {sheet}, {min_rows})")
+        }),
+        Assertion::ColumnSearch {
+            sheet,
+            column,
+            row_range,
+            pattern,
+        } => {
+            let column_index = parse_column_ref(column)?;
+            let (start_row, end_row) = parse_row_range_ref(row_range)?;
+            let regex = statics.regex(pattern);
+            on_sheet(sheet, |sheet| {
+                format!(
+                    "native::column_search(doc, {sheet}, {column_index}, {column:?}, ({start_row}, {end_row}), {row_range:?}, {regex})"
+                )
+            })
+        }
+        Assertion::HeaderRowMatch {
+            sheet,
+            row_range,
+            min_match,
+            columns,
+        } => {
+            let (start_row, end_row) = parse_row_range_ref(row_range)?;
+            let patterns = statics.regexes(columns.iter().map(|column| column.pattern.as_str()));
+            on_sheet(sheet, |sheet| {
+                format!(
+                    "native::header_row_match(doc, {sheet}, ({start_row}, {end_row}), {row_range:?}, {min_match}, {patterns})"
+                )
+            })
+        }
+        Assertion::RangePopulated { .. }
+        | Assertion::SumEq { .. }
+        | Assertion::WithinTolerance { .. } => {
+            format!(
+                "native::Outcome::Err({:?}.to_owned())",
+                not_implemented(assertion)
+            )
+        }
+        Assertion::HeadingExists(text) => format!("native::heading_exists(doc, {text:?})"),
+        Assertion::HeadingRegex { pattern } => {
+            format!("native::heading_regex(doc, {})", statics.regex(pattern))
+        }
+        Assertion::HeadingLevel { level, pattern } => format!(
+            "native::heading_level(doc, {level}, {})",
+            statics.regex(pattern)
+        ),
+        Assertion::TextContains(text) => format!("native::text_contains(doc, {text:?})"),
+        Assertion::TextRegex { pattern } => {
+            format!("native::text_regex(doc, {})", statics.regex(pattern))
+        }
+        Assertion::TextNear {
+            anchor,
+            pattern,
+            within_chars,
+        } => format!(
+            "native::text_near(doc, {}, {}, {within_chars})",
+            statics.regex(anchor),
+            statics.regex(pattern)
+        ),
+        Assertion::SectionNonEmpty { heading } => {
+            format!("native::section_non_empty(doc, {})", statics.regex(heading))
+        }
+        Assertion::SectionMinLines { heading, min_lines } => format!(
+            "native::section_min_lines(doc, {}, {min_lines})",
+            statics.regex(heading)
+        ),
+        Assertion::TableExists { heading, index } => format!(
+            "native::table_exists(doc, {}, {})",
+            statics.regex(heading),
+            opt(*index)
+        ),
+        Assertion::TableColumns {
+            heading,
+            index,
+            patterns,
+        } => format!(
+            "native::table_columns(doc, {}, {}, {})",
+            statics.regex(heading),
+            opt(*index),
+            statics.regexes(patterns.iter().map(String::as_str))
+        ),
+        Assertion::TableShape {
+            heading,
+            index,
+            min_columns,
+            column_types,
+        } => format!(
+            "native::table_shape(doc, {}, {}, {min_columns}, &{column_types:?})",
+            statics.regex(heading),
+            opt(*index)
+        ),
+        Assertion::TableMinRows {
+            heading,
+            index,
+            min_rows,
+        } => format!(
+            "native::table_min_rows(doc, {}, {}, {min_rows})",
+            statics.regex(heading),
+            opt(*index)
+        ),
+        Assertion::HeaderTokenSearch {
+            page,
+            index,
+            tokens,
+            min_matches,
+            max_matches,
+        } => format!(
+            "native::header_token_search(doc, {}, {}, {}, {min_matches}, {})",
+            opt(*page),
+            opt(*index),
+            statics.regexes(tokens.iter().map(String::as_str)),
+            opt(*max_matches)
+        ),
+        Assertion::DominantColumnCount {
+            count,
+            tolerance,
+            sample_pages,
+        } => format!("native::dominant_column_count(doc, {count}, {tolerance}, {sample_pages})"),
+        Assertion::FullWidthRow { pattern, min_cells } => format!(
+            "native::full_width_row(doc, {}, {min_cells})",
+            statics.regex(pattern)
+        ),
+        Assertion::PageSectionCount { min, max } => format!(
+            "native::page_section_count(doc, {}, {})",
+            opt(*min),
+            opt(*max)
+        ),
+        Assertion::PageCount { min, max } => {
+            format!("native::page_count(doc, {}, {})", opt(*min), opt(*max))
+        }
+        Assertion::MetadataRegex { key, pattern } => format!(
+            "native::metadata_regex(doc, {key:?}, {})",
+            statics.regex(pattern)
+        ),
+    };
+    Ok(code)
 }
 
 /// Generate Rust source for a single Assertion enum variant with owned String fields.
@@ -479,26 +802,26 @@ fn codegen_assertion(assertion: &crate::dsl::assertions::Assertion) -> String {
     }
 }
 
-/// Generate code for content extraction.
-fn generate_extracted_code(extract: &[ExtractSection]) -> String {
+/// Generate the extraction expression, with the extract plan as a static.
+fn generate_extracted_code(extract: &[ExtractSection], statics: &mut Statics) -> String {
     if extract.is_empty() {
-        return "            None".to_string();
+        return "None".to_string();
     }
 
     let mut section_inits = Vec::new();
     for section in extract {
         section_inits.push(format!(
-            r#"                fingerprint::dsl::parser::ExtractSection {{
-                    name: {name}.to_owned(),
-                    r#type: {typ}.to_owned(),
-                    sheet: {sheet},
-                    range: {range},
-                    anchor_heading: {anchor_heading},
-                    index: {index},
-                    anchor: {anchor},
-                    pattern: {pattern},
-                    within_chars: {within_chars},
-                }}"#,
+            r#"        fingerprint::dsl::parser::ExtractSection {{
+            name: {name}.to_owned(),
+            r#type: {typ}.to_owned(),
+            sheet: {sheet},
+            range: {range},
+            anchor_heading: {anchor_heading},
+            index: {index},
+            anchor: {anchor},
+            pattern: {pattern},
+            within_chars: {within_chars},
+        }}"#,
             name = format_args!("{:?}", section.name),
             typ = format_args!("{:?}", section.r#type),
             sheet = codegen_option_string(section.sheet.as_deref()),
@@ -511,19 +834,18 @@ fn generate_extracted_code(extract: &[ExtractSection]) -> String {
         ));
     }
 
-    format!(
-        r#"            let sections = vec![
-{sections}
-            ];
-            match fingerprint::dsl::extract::extract(doc, &sections) {{
-                Ok(map) => Some(map),
-                Err(_) => None,
-            }}"#,
+    statics.declarations.push(format!(
+        r#"static EXTRACT: LazyLock<native::ExtractPlan> = LazyLock::new(|| {{
+    native::ExtractPlan::new(vec![
+{sections},
+    ])
+}});"#,
         sections = section_inits.join(",\n"),
-    )
+    ));
+    "EXTRACT.extract(doc)".to_owned()
 }
 
-/// Generate code for content hash computation.
+/// Generate the content hash expression over the extracted values.
 fn generate_content_hash_code(content_hash: &Option<ContentHashConfig>) -> String {
     match content_hash {
         Some(config) => {
@@ -533,12 +855,11 @@ fn generate_content_hash_code(content_hash: &Option<ContentHashConfig>) -> Strin
                 .map(|name| format!("{:?}.to_owned()", name))
                 .collect();
             format!(
-                r#"            let over = vec![{over}];
-            extracted.as_ref().map(|ext| fingerprint::dsl::content_hash::content_hash(ext, &over))"#,
+                "extracted\n            .as_ref()\n            .map(|ext| fingerprint::dsl::content_hash::content_hash(ext, &[{over}]))",
                 over = over_entries.join(", "),
             )
         }
-        None => "            None".to_string(),
+        None => "None".to_string(),
     }
 }
 
@@ -614,4 +935,89 @@ mod tests {
         assert!(generated.contains(r#"Some("cbre-appraisal.v1")"#));
         assert!(generated.contains(r#"parent: Some("cbre-appraisal.v1"),"#));
     }
+
+    #[test]
+    fn generate_rust_evaluates_assertions_without_the_interpreter() {
+        let mut definition = base_definition();
+        definition.assertions.push(NamedAssertion {
+            name: None,
+            assertion: Assertion::SectionNonEmpty {
+                heading: "(?i)cover".to_owned(),
+            },
+        });
+
+        let generated = generate_rust(&definition).expect("generated rust source");
+
+        assert!(!generated.contains("evaluate_named_assertions"));
+        assert_eq!(
+            generated.matches("LazyLock::new(|| native::regex(").count(),
+            1
+        );
+        assert!(generated.contains("native::heading_regex(doc, &REGEX_0)"));
+        assert!(generated.contains("native::section_non_empty(doc, &REGEX_0)"));
+        assert!(generated.contains(r#"evaluation.record("has_cover", outcome"#));
+        assert!(generated.contains(r#"evaluation.record("section_non_empty", outcome"#));
+        assert!(generated.contains("return evaluation.no_match();"));
+    }
+
+    #[test]
+    fn generate_rust_resolves_spreadsheet_references_at_compile_time() {
+        let mut definition = base_definition();
+        definition.format = "xlsx".to_owned();
+        definition.assertions = vec![
+            NamedAssertion {
+                name: None,
+                assertion: Assertion::SheetNameRegex {
+                    pattern: "(?i)rent".to_owned(),
+                    bind: Some("rent".to_owned()),
+                },
+            },
+            NamedAssertion {
+                name: None,
+                assertion: Assertion::CellEq {
+                    sheet: "$rent".to_owned(),
+                    cell: "B3".to_owned(),
+                    value: "Tenant".to_owned(),
+                },
+            },
+        ];
+
+        let generated = generate_rust(&definition).expect("generated rust source");
+
+        assert!(generated.contains(r#".and_then(|sheet| evaluation.bind("rent", &sheet))"#));
+        assert!(generated.contains(
+            r#"evaluation.sheet("$rent").and_then(|sheet| native::cell_eq(doc, &sheet, (2, 1), "B3", "Tenant"))"#
+        ));
+
+        definition.assertions[1].assertion = Assertion::CellEq {
+            sheet: "$rent".to_owned(),
+            cell: "3B".to_owned(),
+            value: "Tenant".to_owned(),
+        };
+        assert!(generate_rust(&definition).is_err());
+    }
+
+    #[test]
+    fn generate_rust_exports_plugin_abi() {
+        use crate::registry::plugin::{
+            ABI_VERSION_SYMBOL, EVALUATE_SYMBOL, FREE_SYMBOL, METADATA_SYMBOL,
+        };
+
+        let generated = generate_rust(&base_definition()).expect("generated rust source");
+
+        for symbol in [
+            ABI_VERSION_SYMBOL,
+            METADATA_SYMBOL,
+            EVALUATE_SYMBOL,
+            FREE_SYMBOL,
+        ] {
+            assert!(
+                generated.contains(&format!("extern \"C\" fn {symbol}(")),
+                "missing export {symbol}"
+            );
+        }
+        assert!(generated.contains("plugin::PLUGIN_ABI_VERSION"));
+        assert!(generated.contains("plugin::ffi::evaluate_export("));
+        assert!(generated.contains("plugin::ffi::free_export(response)"));
+    }
 }
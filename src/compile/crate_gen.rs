@@ -3,7 +3,8 @@ use crate::dsl::parser::FingerprintDefinition;
 use std::fs;
 use std::path::Path;
 
-/// Generate a complete Rust crate (Cargo.toml, src/lib.rs, fixtures/) from a DSL definition.
+/// Generate a complete Rust crate (Cargo.toml, src/lib.rs, tests/fixtures.rs,
+/// fixtures/) from a DSL definition.
 pub fn generate_crate(def: &FingerprintDefinition, out_dir: &Path) -> Result<(), String> {
     // Create output directory if it doesn't exist
     fs::create_dir_all(out_dir).map_err(|e| {
@@ -29,10 +30,19 @@ pub fn generate_crate(def: &FingerprintDefinition, out_dir: &Path) -> Result<(),
     fs::write(&lib_rs_path, rust_source)
         .map_err(|e| format!("Failed to write src/lib.rs: {}", e))?;
 
-    // Create fixtures/ directory stub
+    // Generate tests/fixtures.rs
+    let tests_dir = out_dir.join("tests");
+    fs::create_dir_all(&tests_dir)
+        .map_err(|e| format!("Failed to create tests directory: {}", e))?;
+    fs::write(tests_dir.join("fixtures.rs"), generate_fixtures_test(def))
+        .map_err(|e| format!("Failed to write tests/fixtures.rs: {}", e))?;
+
+    // Create fixtures/positive and fixtures/negative
     let fixtures_dir = out_dir.join("fixtures");
-    fs::create_dir_all(&fixtures_dir)
-        .map_err(|e| format!("Failed to create fixtures directory: {}", e))?;
+    for kind in ["positive", "negative"] {
+        fs::create_dir_all(fixtures_dir.join(kind))
+            .map_err(|e| format!("Failed to create fixtures directory: {}", e))?;
+    }
 
     // Create a placeholder README in fixtures/
     let fixtures_readme = generate_fixtures_readme(def);
@@ -79,14 +89,32 @@ fn generate_fixtures_readme(def: &FingerprintDefinition) -> String {
     format!(
         r#"# Fixtures for {}
 
-This directory is intended for test fixtures that demonstrate the fingerprint matching behavior.
+Test fixtures that demonstrate the fingerprint matching behavior. `cargo test`
+runs every fixture through the generated fingerprint (`tests/fixtures.rs`).
 
 ## Usage
 
-Place sample files that match this fingerprint in this directory:
+Place sample files in:
 - `positive/` - Files that should match the fingerprint
 - `negative/` - Files that should NOT match the fingerprint
 
+The file extension selects the document format, as in a manifest.
+
+## Expected output
+
+Next to a fixture, `<file>.expected.json` (for example `rent-roll.xlsx.expected.json`)
+holds the expected output. Each key is optional:
+
+```json
+{{
+  "content_hash": "blake3:...",
+  "extracted": {{ "section_name": "..." }}
+}}
+```
+
+`content_hash` and `extracted` are compared with the fingerprint result exactly,
+`null` included.
+
 ## Format
 
 This fingerprint is designed for `{}` format files.
@@ -101,8 +129,110 @@ This crate was generated from a DSL definition by fingerprint v{}.
     )
 }
 
+/// Generate tests/fixtures.rs, which runs the fixtures through the generated
+/// `Fingerprint` impl.
+fn generate_fixtures_test(def: &FingerprintDefinition) -> String {
+    format!(
+        r#"//! Fixture tests for {fingerprint_id}.
+//!
+//! Files in `fixtures/positive/` must match and files in `fixtures/negative/`
+//! must not. `<file>.expected.json` holds the expected `content_hash` and
+//! `extracted` values for a fixture.
+//!
+//! This file was auto-generated by fingerprint v{compiler_version}.
+//! DO NOT EDIT MANUALLY.
+
+use fingerprint::document::open_document_from_path;
+use fingerprint::{{Fingerprint, FingerprintResult}};
+use serde_json::Value;
+use std::fs;
+use std::path::{{Path, PathBuf}};
+use {lib_name}::GeneratedFingerprint;
+
+const EXPECTED_SUFFIX: &str = ".expected.json";
+
+fn fixtures(kind: &str) -> Vec<PathBuf> {{
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(kind);
+    let Ok(entries) = fs::read_dir(&dir) else {{
+        return Vec::new();
+    }};
+    let mut paths: Vec<PathBuf> = entries
+        .map(|entry| entry.expect("read fixtures entry").path())
+        .filter(|path| path.is_file())
+        .filter(|path| !path.to_string_lossy().ends_with(EXPECTED_SUFFIX))
+        .collect();
+    paths.sort();
+    paths
+}}
+
+fn evaluate(path: &Path) -> FingerprintResult {{
+    let doc = open_document_from_path(path)
+        .unwrap_or_else(|error| panic!("{{}}: failed to open: {{error}}", path.display()));
+    GeneratedFingerprint {{}}.fingerprint(&doc)
+}}
+
+fn check_expected_output(path: &Path, result: &FingerprintResult) {{
+    let mut expected_path = path.as_os_str().to_owned();
+    expected_path.push(EXPECTED_SUFFIX);
+    let Ok(expected) = fs::read_to_string(&expected_path) else {{
+        return;
+    }};
+    let expected: Value = serde_json::from_str(&expected)
+        .unwrap_or_else(|error| panic!("{{}}{{EXPECTED_SUFFIX}}: invalid JSON: {{error}}", path.display()));
+
+    if let Some(content_hash) = expected.get("content_hash") {{
+        assert_eq!(
+            &serde_json::to_value(&result.content_hash).expect("serialize content_hash"),
+            content_hash,
+            "{{}}: content_hash",
+            path.display()
+        );
+    }}
+    if let Some(extracted) = expected.get("extracted") {{
+        assert_eq!(
+            &serde_json::to_value(&result.extracted).expect("serialize extracted"),
+            extracted,
+            "{{}}: extracted",
+            path.display()
+        );
+    }}
+}}
+
+#[test]
+fn positive_fixtures_match() {{
+    for path in fixtures("positive") {{
+        let result = evaluate(&path);
+        assert!(
+            result.matched,
+            "{{}} should match {fingerprint_id}: {{:?}}",
+            path.display(),
+            result.reason
+        );
+        check_expected_output(&path, &result);
+    }}
+}}
+
+#[test]
+fn negative_fixtures_do_not_match() {{
+    for path in fixtures("negative") {{
+        let result = evaluate(&path);
+        assert!(
+            !result.matched,
+            "{{}} should not match {fingerprint_id}",
+            path.display()
+        );
+        check_expected_output(&path, &result);
+    }}
+}}
+"#,
+        fingerprint_id = def.fingerprint_id,
+        compiler_version = env!("CARGO_PKG_VERSION"),
+        lib_name = sanitize_crate_name(&def.fingerprint_id).replace('-', "_"),
+    )
+}
+
 /// Sanitize a fingerprint ID to be a valid Rust crate name.
-fn sanitize_crate_name(fingerprint_id: &str) -> String {
+pub(crate) fn sanitize_crate_name(fingerprint_id: &str) -> String {
     // Replace dots with hyphens and ensure it starts with a letter
     let sanitized = fingerprint_id
         .replace('.', "-")
@@ -207,5 +337,14 @@ mod tests {
         assert!(fixtures_dir.exists());
         let fixtures_readme = fixtures_dir.join("README.md");
         assert!(fixtures_readme.exists());
+        assert!(fixtures_dir.join("positive").is_dir());
+        assert!(fixtures_dir.join("negative").is_dir());
+
+        // Verify tests/fixtures.rs drives the generated fingerprint
+        let fixtures_test = std::fs::read_to_string(temp_dir.path().join("tests/fixtures.rs"))
+            .expect("read tests/fixtures.rs");
+        assert!(fixtures_test.contains("use test_v0::GeneratedFingerprint;"));
+        assert!(fixtures_test.contains("fn positive_fixtures_match()"));
+        assert!(fixtures_test.contains("fn negative_fixtures_do_not_match()"));
     }
 }
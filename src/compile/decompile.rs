@@ -0,0 +1,129 @@
+use crate::dsl::parser::FingerprintDefinition;
+use crate::dsl::plan::{definition_hash, source_hash};
+use crate::registry::Fingerprint;
+
+/// Return the definition embedded in a compiled fingerprint, once it is
+/// verified against the fingerprint's `source_hash`.
+///
+/// The embedded YAML must hash to `source_hash` as is, and again after it is
+/// parsed and re-serialized, so the printed file is the definition that was
+/// compiled.
+pub fn embedded_definition(fingerprint: &dyn Fingerprint) -> Result<&str, String> {
+    let id = fingerprint.id();
+    let yaml = fingerprint.definition_source().ok_or_else(|| {
+        format!("fingerprint '{id}' has no embedded definition; only compiled fingerprints can be decompiled")
+    })?;
+    let expected = fingerprint
+        .definition_hash()
+        .ok_or_else(|| format!("fingerprint '{id}' has no source_hash to verify against"))?;
+
+    let embedded = source_hash(yaml);
+    if embedded != expected {
+        return Err(format!(
+            "embedded definition of '{id}' hashes to {embedded}, but its source_hash is {expected}"
+        ));
+    }
+
+    let definition: FingerprintDefinition = serde_yaml::from_str(yaml)
+        .map_err(|error| format!("embedded definition of '{id}' does not parse: {error}"))?;
+    if definition.fingerprint_id != id {
+        return Err(format!(
+            "embedded definition is for '{}', not '{id}'",
+            definition.fingerprint_id
+        ));
+    }
+    let reserialized = definition_hash(&definition)?;
+    if reserialized != expected {
+        return Err(format!(
+            "re-serialized definition of '{id}' hashes to {reserialized}, but its source_hash is {expected}"
+        ));
+    }
+
+    Ok(yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::dsl::plan::canonical_definition;
+    use crate::registry::FingerprintResult;
+
+    struct Embedded {
+        id: &'static str,
+        hash: Option<String>,
+        source: Option<String>,
+    }
+
+    impl Fingerprint for Embedded {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn format(&self) -> &str {
+            "csv"
+        }
+
+        fn fingerprint(&self, _doc: &Document) -> FingerprintResult {
+            unreachable!("decompile never evaluates")
+        }
+
+        fn definition_hash(&self) -> Option<&str> {
+            self.hash.as_deref()
+        }
+
+        fn definition_source(&self) -> Option<&str> {
+            self.source.as_deref()
+        }
+    }
+
+    fn compiled(id: &'static str) -> Embedded {
+        let definition: FingerprintDefinition = serde_yaml::from_str(
+            "fingerprint_id: tape.v1\nformat: csv\nassertions:\n  - name: has_sheet\n    sheet_exists: csv\n",
+        )
+        .expect("parse definition");
+        let canonical = canonical_definition(&definition).expect("canonical definition");
+        Embedded {
+            id,
+            hash: Some(definition_hash(&definition).expect("definition hash")),
+            source: Some(canonical),
+        }
+    }
+
+    #[test]
+    fn embedded_definition_returns_verified_yaml() {
+        let fingerprint = compiled("tape.v1");
+
+        let yaml = embedded_definition(&fingerprint).expect("verified definition");
+
+        assert!(yaml.contains("fingerprint_id: tape.v1"));
+        assert!(yaml.contains("sheet_exists: csv"));
+    }
+
+    #[test]
+    fn embedded_definition_rejects_hash_mismatch() {
+        let mut fingerprint = compiled("tape.v1");
+        fingerprint.hash = Some("blake3:0000".to_owned());
+
+        let error = embedded_definition(&fingerprint).expect_err("hash mismatch");
+
+        assert!(
+            error.contains("but its source_hash is blake3:0000"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn embedded_definition_rejects_other_fingerprint_or_missing_source() {
+        let error = embedded_definition(&compiled("other.v1")).expect_err("wrong ID");
+        assert_eq!(
+            error,
+            "embedded definition is for 'tape.v1', not 'other.v1'"
+        );
+
+        let mut builtin = compiled("tape.v1");
+        builtin.source = None;
+        let error = embedded_definition(&builtin).expect_err("no embedded definition");
+        assert!(error.contains("only compiled fingerprints"), "{error}");
+    }
+}
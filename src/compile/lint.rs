@@ -0,0 +1,691 @@
+use crate::compile::validate::validate_definition_diagnostics;
+use crate::dsl::assertions::Assertion;
+use crate::dsl::diagnostics::Diagnostic;
+use crate::dsl::parser::{
+    ExtractSection, FingerprintDefinition, ParsedDefinition, parse_file, parse_sources_str,
+};
+use crate::dsl::resolve::resolve_definitions_with_origins;
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Output record version for `fingerprint lint`.
+pub const LINT_VERSION: &str = "lint.v0";
+
+/// How serious a lint finding is. Errors fail `fingerprint lint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One static-analysis finding for a fingerprint definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintFinding {
+    pub version: &'static str,
+    pub file: String,
+    pub fingerprint_id: String,
+    pub severity: Severity,
+    pub code: &'static str,
+    /// Definition path of the offending entry, e.g. `assertions[2].pattern`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub message: String,
+}
+
+/// Load definitions to lint from `.fp.yaml` files and directories of them.
+///
+/// Files in one directory are resolved together, as the registry does, so
+/// `extends` may cross files there.
+pub fn load_definitions(paths: &[PathBuf]) -> Result<Vec<ParsedDefinition>, String> {
+    let mut definitions = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            definitions.extend(load_dir(path)?);
+        } else {
+            definitions.extend(parse_file(path)?);
+        }
+    }
+    Ok(definitions)
+}
+
+fn load_dir(dir: &Path) -> Result<Vec<ParsedDefinition>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|error| format!("Failed reading '{}': {error}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.contains(".fp.") && (name.ends_with(".yaml") || name.ends_with(".yml"))
+                    })
+        })
+        .collect();
+    files.sort();
+
+    let mut read_file = |path: &str| {
+        fs::read_to_string(path).map_err(|error| format!("failed reading '{path}': {error}"))
+    };
+    let mut sources = Vec::new();
+    for file in &files {
+        let yaml = read_file(&file.display().to_string())?;
+        sources.extend(parse_sources_str(
+            &yaml,
+            &file.display().to_string(),
+            &mut read_file,
+        )?);
+    }
+    let resolved = resolve_definitions_with_origins(&sources, &mut read_file);
+    sources
+        .into_iter()
+        .zip(resolved)
+        .map(|(source, resolved)| {
+            let (definition, origins) = resolved?;
+            Ok(ParsedDefinition {
+                source,
+                definition,
+                origins,
+            })
+        })
+        .collect()
+}
+
+/// Lint a set of resolved definitions. Parent and sibling checks only see
+/// definitions in the same set.
+///
+/// Findings are ordered by input definition, then by check.
+pub fn lint_definitions(definitions: &[ParsedDefinition]) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let by_id: BTreeMap<&str, &FingerprintDefinition> = definitions
+        .iter()
+        .map(|parsed| {
+            (
+                parsed.definition.fingerprint_id.as_str(),
+                &parsed.definition,
+            )
+        })
+        .collect();
+
+    for (position, parsed) in definitions.iter().enumerate() {
+        let definition = &parsed.definition;
+        let mut report = |severity, code, location: Option<String>, message: String| {
+            findings.push(LintFinding {
+                version: LINT_VERSION,
+                file: parsed.source.origin.clone(),
+                fingerprint_id: definition.fingerprint_id.clone(),
+                severity,
+                code,
+                location,
+                message,
+            });
+        };
+
+        for diagnostic in validate_definition_diagnostics(definition) {
+            report(
+                Severity::Error,
+                "invalid_definition",
+                diagnostic_location(&diagnostic),
+                diagnostic.message,
+            );
+        }
+        check_validity_window(definition, &mut report);
+        check_empty_match_regexes(definition, &mut report);
+        check_sheet_bindings(definition, &mut report);
+        check_redundant_assertions(definition, &mut report);
+        check_content_hash_extracts(definition, &mut report);
+        if let Some(parent) = definition
+            .parent
+            .as_deref()
+            .and_then(|parent| by_id.get(parent))
+        {
+            check_implied_by_parent(definition, parent, &mut report);
+        }
+        check_duplicate_siblings(definitions, position, &mut report);
+    }
+
+    findings
+}
+
+/// Definition path of a validation diagnostic, e.g. `assertions[2].pattern`.
+fn diagnostic_location(diagnostic: &Diagnostic) -> Option<String> {
+    let mut location = diagnostic.section?.to_owned();
+    if let Some(index) = diagnostic.index {
+        location.push_str(&format!("[{index}]"));
+    }
+    if let Some(field) = &diagnostic.field {
+        location.push('.');
+        location.push_str(field);
+    }
+    Some(location)
+}
+
+type Report<'a> = dyn FnMut(Severity, &'static str, Option<String>, String) + 'a;
+
+fn check_validity_window(definition: &FingerprintDefinition, report: &mut Report<'_>) {
+    let parse = |field: &str, value: &Option<String>, report: &mut Report<'_>| {
+        let value = value.as_deref()?;
+        match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => Some(date),
+            Err(_) => {
+                report(
+                    Severity::Warning,
+                    "invalid_date",
+                    Some(field.to_owned()),
+                    format!("{field} '{value}' is not a YYYY-MM-DD date"),
+                );
+                None
+            }
+        }
+    };
+    let from = parse("valid_from", &definition.valid_from, report);
+    let until = parse("valid_until", &definition.valid_until, report);
+    if let (Some(from), Some(until)) = (from, until)
+        && from > until
+    {
+        report(
+            Severity::Error,
+            "inverted_validity_window",
+            Some("valid_from".to_owned()),
+            format!("valid_from {from} is after valid_until {until}"),
+        );
+    }
+}
+
+fn check_empty_match_regexes(definition: &FingerprintDefinition, report: &mut Report<'_>) {
+    let mut check = |location: String, pattern: &str| {
+        // Invalid patterns are reported by validation.
+        if Regex::new(pattern).is_ok_and(|regex| regex.is_match("")) {
+            report(
+                Severity::Warning,
+                "empty_match_regex",
+                Some(location),
+                format!(
+                    "pattern '{pattern}' can match the empty string, so it may pass without matching any content"
+                ),
+            );
+        }
+    };
+
+    for (index, named) in definition.assertions.iter().enumerate() {
+        for (field, pattern) in assertion_regexes(&named.assertion) {
+            check(format!("assertions[{index}].{field}"), pattern);
+        }
+    }
+    for (index, section) in definition.extract.iter().enumerate() {
+        for (field, pattern) in extract_regexes(section) {
+            check(format!("extract[{index}].{field}"), pattern);
+        }
+    }
+}
+
+fn check_sheet_bindings(definition: &FingerprintDefinition, report: &mut Report<'_>) {
+    let mut bound = HashSet::new();
+    for (index, named) in definition.assertions.iter().enumerate() {
+        if let Some(sheet) = assertion_sheet(&named.assertion)
+            && let Some(binding) = sheet.trim().strip_prefix('$')
+            && !bound.contains(binding.trim())
+        {
+            report(
+                Severity::Error,
+                "unbound_sheet_binding",
+                Some(format!("assertions[{index}].sheet")),
+                format!("sheet binding '{sheet}' is used before any sheet_name_regex binds it"),
+            );
+        }
+        if let Assertion::SheetNameRegex {
+            bind: Some(binding),
+            ..
+        } = &named.assertion
+        {
+            let binding = binding.trim();
+            bound.insert(binding.strip_prefix('$').unwrap_or(binding).trim());
+        }
+    }
+}
+
+fn check_redundant_assertions(definition: &FingerprintDefinition, report: &mut Report<'_>) {
+    let assertions = &definition.assertions;
+    for (index, named) in assertions.iter().enumerate() {
+        let implied_by = assertions.iter().enumerate().find(|(other, candidate)| {
+            let duplicate = *other < index && candidate.assertion == named.assertion;
+            duplicate || (*other != index && implies(&candidate.assertion, &named.assertion))
+        });
+        if let Some((other, candidate)) = implied_by {
+            report(
+                Severity::Warning,
+                "redundant_assertion",
+                Some(format!("assertions[{index}]")),
+                format!(
+                    "assertion '{}' is implied by assertions[{other}] '{}'",
+                    display_name(named.name.as_deref()),
+                    display_name(candidate.name.as_deref())
+                ),
+            );
+        }
+    }
+}
+
+fn check_content_hash_extracts(definition: &FingerprintDefinition, report: &mut Report<'_>) {
+    let Some(content_hash) = &definition.content_hash else {
+        return;
+    };
+    for (index, name) in content_hash.over.iter().enumerate() {
+        let Some(section) = definition
+            .extract
+            .iter()
+            .find(|section| &section.name == name)
+        else {
+            continue;
+        };
+        let guarded = definition
+            .assertions
+            .iter()
+            .any(|named| guarantees_extract(&named.assertion, section));
+        if !guarded {
+            report(
+                Severity::Warning,
+                "optional_hash_extract",
+                Some(format!("content_hash.over[{index}]")),
+                format!(
+                    "content_hash covers extract '{name}', but no assertion guarantees its target exists, so the hash may silently cover a missing section"
+                ),
+            );
+        }
+    }
+}
+
+fn check_implied_by_parent(
+    definition: &FingerprintDefinition,
+    parent: &FingerprintDefinition,
+    report: &mut Report<'_>,
+) {
+    for (index, named) in definition.assertions.iter().enumerate() {
+        if parent.assertions.iter().any(|candidate| {
+            candidate.assertion == named.assertion
+                || implies(&candidate.assertion, &named.assertion)
+        }) {
+            report(
+                Severity::Warning,
+                "implied_by_parent",
+                Some(format!("assertions[{index}]")),
+                format!(
+                    "assertion '{}' is already checked by parent '{}'",
+                    display_name(named.name.as_deref()),
+                    parent.fingerprint_id
+                ),
+            );
+        }
+    }
+}
+
+fn check_duplicate_siblings(
+    definitions: &[ParsedDefinition],
+    position: usize,
+    report: &mut Report<'_>,
+) {
+    let definition = &definitions[position].definition;
+    let Some(parent) = definition.parent.as_deref() else {
+        return;
+    };
+    let signature = assertion_signature(definition);
+    let earlier_twin = definitions[..position].iter().find(|sibling| {
+        sibling.definition.parent.as_deref() == Some(parent)
+            && assertion_signature(&sibling.definition) == signature
+    });
+    if let Some(twin) = earlier_twin {
+        report(
+            Severity::Warning,
+            "duplicate_sibling",
+            None,
+            format!(
+                "has the same assertions as sibling '{}' under parent '{parent}'; the first listed always wins",
+                twin.definition.fingerprint_id
+            ),
+        );
+    }
+}
+
+/// Assertions as an order- and name-independent set.
+fn assertion_signature(definition: &FingerprintDefinition) -> Vec<String> {
+    let mut signature: Vec<String> = definition
+        .assertions
+        .iter()
+        .filter_map(|named| serde_json::to_string(&named.assertion).ok())
+        .collect();
+    signature.sort();
+    signature.dedup();
+    signature
+}
+
+/// Whether `stronger` passing guarantees `weaker` passes.
+fn implies(stronger: &Assertion, weaker: &Assertion) -> bool {
+    match weaker {
+        Assertion::SheetExists(sheet) => {
+            !sheet.starts_with('$')
+                && !matches!(stronger, Assertion::SheetExists(_))
+                && assertion_sheet(stronger) == Some(sheet.as_str())
+        }
+        Assertion::SheetMinRows { sheet, min_rows } => matches!(
+            stronger,
+            Assertion::SheetMinRows { sheet: other, min_rows: other_rows }
+                if other == sheet && other_rows > min_rows
+        ),
+        Assertion::SectionNonEmpty { heading } => matches!(
+            stronger,
+            Assertion::SectionMinLines { heading: other, min_lines } if other == heading && *min_lines >= 1
+        ),
+        Assertion::SectionMinLines { heading, min_lines } => matches!(
+            stronger,
+            Assertion::SectionMinLines { heading: other, min_lines: other_lines }
+                if other == heading && other_lines > min_lines
+        ),
+        Assertion::TableExists { heading, index } => {
+            table_target(stronger).is_some_and(|(other, other_index)| {
+                other == heading && other_index == index.unwrap_or(0)
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Whether `assertion` passing means `section` extracts a value.
+fn guarantees_extract(assertion: &Assertion, section: &ExtractSection) -> bool {
+    match section.r#type.as_str() {
+        "range" => section.sheet.as_deref().is_some_and(|sheet| {
+            matches!(assertion, Assertion::SheetExists(other) if other == sheet)
+                || assertion_sheet(assertion) == Some(sheet)
+        }),
+        "table" => section.anchor_heading.as_deref().is_some_and(|heading| {
+            table_target(assertion).is_some_and(|(other, index)| {
+                other == heading && index == section.index.unwrap_or(0)
+            })
+        }),
+        "section" => section.anchor_heading.as_deref().is_some_and(|heading| {
+            matches!(
+                assertion,
+                Assertion::SectionNonEmpty { heading: other }
+                    | Assertion::SectionMinLines { heading: other, .. }
+                    | Assertion::HeadingRegex { pattern: other }
+                    if other == heading
+            )
+        }),
+        "text_match" => matches!(
+            assertion,
+            Assertion::TextNear { anchor, pattern, .. }
+                if Some(anchor) == section.anchor.as_ref() && Some(pattern) == section.pattern.as_ref()
+        ),
+        _ => false,
+    }
+}
+
+/// Heading pattern and table index an assertion requires to exist.
+fn table_target(assertion: &Assertion) -> Option<(&str, usize)> {
+    match assertion {
+        Assertion::TableExists { heading, index }
+        | Assertion::TableColumns { heading, index, .. }
+        | Assertion::TableShape { heading, index, .. }
+        | Assertion::TableMinRows { heading, index, .. } => {
+            Some((heading.as_str(), index.unwrap_or(0)))
+        }
+        _ => None,
+    }
+}
+
+/// Sheet an assertion reads, which must exist for it to pass.
+fn assertion_sheet(assertion: &Assertion) -> Option<&str> {
+    match assertion {
+        Assertion::SheetExists(sheet)
+        | Assertion::CellEq { sheet, .. }
+        | Assertion::CellRegex { sheet, .. }
+        | Assertion::RangeNonNull { sheet, .. }
+        | Assertion::RangePopulated { sheet, .. }
+        | Assertion::SheetMinRows { sheet, .. }
+        | Assertion::ColumnSearch { sheet, .. }
+        | Assertion::HeaderRowMatch { sheet, .. } => Some(sheet.as_str()),
+        _ => None,
+    }
+}
+
+fn assertion_regexes(assertion: &Assertion) -> Vec<(String, &str)> {
+    match assertion {
+        Assertion::FilenameRegex { pattern }
+        | Assertion::SheetNameRegex { pattern, .. }
+        | Assertion::CellRegex { pattern, .. }
+        | Assertion::ColumnSearch { pattern, .. }
+        | Assertion::HeadingRegex { pattern }
+        | Assertion::HeadingLevel { pattern, .. }
+        | Assertion::TextRegex { pattern }
+        | Assertion::FullWidthRow { pattern, .. }
+        | Assertion::MetadataRegex { pattern, .. } => vec![("pattern".to_owned(), pattern)],
+        Assertion::TextNear {
+            anchor, pattern, ..
+        } => vec![
+            ("anchor".to_owned(), anchor.as_str()),
+            ("pattern".to_owned(), pattern.as_str()),
+        ],
+        Assertion::HeaderRowMatch { columns, .. } => columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| (format!("columns[{index}].pattern"), column.pattern.as_str()))
+            .collect(),
+        Assertion::SectionNonEmpty { heading }
+        | Assertion::SectionMinLines { heading, .. }
+        | Assertion::TableExists { heading, .. }
+        | Assertion::TableShape { heading, .. }
+        | Assertion::TableMinRows { heading, .. } => vec![("heading".to_owned(), heading)],
+        Assertion::TableColumns {
+            heading, patterns, ..
+        } => std::iter::once(("heading".to_owned(), heading.as_str()))
+            .chain(
+                patterns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, pattern)| (format!("patterns[{index}]"), pattern.as_str())),
+            )
+            .collect(),
+        Assertion::HeaderTokenSearch { tokens, .. } => tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| (format!("tokens[{index}]"), token.as_str()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn extract_regexes(section: &ExtractSection) -> Vec<(String, &str)> {
+    let fields = match section.r#type.as_str() {
+        "section" | "table" => vec![("anchor_heading", &section.anchor_heading)],
+        "text_match" => vec![("anchor", &section.anchor), ("pattern", &section.pattern)],
+        _ => Vec::new(),
+    };
+    fields
+        .into_iter()
+        .filter_map(|(field, value)| Some((field.to_owned(), value.as_deref()?)))
+        .collect()
+}
+
+fn display_name(name: Option<&str>) -> &str {
+    name.unwrap_or("<unnamed>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::parser::parse_sources_str;
+    use crate::dsl::resolve::resolve_definitions_with_origins;
+
+    fn lint_yaml(yaml: &str) -> Vec<LintFinding> {
+        let mut read = |path: &str| Err(format!("unexpected read of '{path}'"));
+        let sources = parse_sources_str(yaml, "defs/lint.fp.yaml", &mut read).expect("parse");
+        let resolved = resolve_definitions_with_origins(&sources, &mut read);
+        let parsed: Vec<ParsedDefinition> = sources
+            .into_iter()
+            .zip(resolved)
+            .map(|(source, resolved)| {
+                let (definition, origins) = resolved.expect("resolve");
+                ParsedDefinition {
+                    source,
+                    definition,
+                    origins,
+                }
+            })
+            .collect();
+        lint_definitions(&parsed)
+    }
+
+    fn codes(findings: &[LintFinding]) -> Vec<(&str, &str, Option<&str>)> {
+        findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.fingerprint_id.as_str(),
+                    finding.code,
+                    finding.location.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clean_definition_has_no_findings() {
+        let findings = lint_yaml(
+            r#"
+fingerprint_id: clean.v1
+format: xlsx
+valid_from: "2024-01-01"
+valid_until: "2025-01-01"
+assertions:
+  - sheet_name_regex:
+      pattern: "(?i)^rent roll$"
+      bind: $rr
+  - sheet_min_rows:
+      sheet: $rr
+      min_rows: 5
+  - cell_eq:
+      sheet: "Summary"
+      cell: "A1"
+      value: "Total"
+extract:
+  - name: summary
+    type: range
+    sheet: "Summary"
+    range: "A1:B4"
+content_hash:
+  algorithm: blake3
+  over: [summary]
+"#,
+        );
+        assert_eq!(findings, Vec::new());
+    }
+
+    #[test]
+    fn flags_definition_level_problems() {
+        let findings = lint_yaml(
+            r#"
+fingerprint_id: noisy.v1
+format: xlsx
+valid_from: "2025-06-01"
+valid_until: "2024-06-01"
+assertions:
+  - sheet_min_rows:
+      sheet: $rr
+      min_rows: 1
+  - sheet_name_regex:
+      pattern: "(?i)rent.*"
+      bind: $rr
+  - filename_regex:
+      pattern: ".*"
+  - sheet_exists: "Summary"
+  - cell_eq:
+      sheet: "Summary"
+      cell: "A1"
+      value: "Total"
+  - sheet_min_rows:
+      sheet: "Summary"
+      min_rows: 2
+  - sheet_min_rows:
+      sheet: "Summary"
+      min_rows: 10
+extract:
+  - name: notes
+    type: range
+    sheet: "Notes"
+    range: "A1:A3"
+content_hash:
+  algorithm: blake3
+  over: [notes]
+"#,
+        );
+        assert_eq!(
+            codes(&findings),
+            vec![
+                ("noisy.v1", "inverted_validity_window", Some("valid_from")),
+                (
+                    "noisy.v1",
+                    "empty_match_regex",
+                    Some("assertions[2].pattern")
+                ),
+                (
+                    "noisy.v1",
+                    "unbound_sheet_binding",
+                    Some("assertions[0].sheet")
+                ),
+                ("noisy.v1", "redundant_assertion", Some("assertions[3]")),
+                ("noisy.v1", "redundant_assertion", Some("assertions[5]")),
+                (
+                    "noisy.v1",
+                    "optional_hash_extract",
+                    Some("content_hash.over[0]")
+                ),
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn flags_child_assertions_implied_by_parent_and_twin_siblings() {
+        let findings = lint_yaml(
+            r#"
+fingerprint_id: parent.v1
+format: markdown
+assertions:
+  - heading_regex:
+      pattern: "(?i)^schedule of investments"
+---
+fingerprint_id: child-a.v1
+format: markdown
+parent: parent.v1
+assertions:
+  - name: again
+    heading_regex:
+      pattern: "(?i)^schedule of investments"
+  - text_contains: "Level 3"
+---
+fingerprint_id: child-b.v1
+format: markdown
+parent: parent.v1
+assertions:
+  - text_contains: "Level 3"
+  - heading_regex:
+      pattern: "(?i)^schedule of investments"
+"#,
+        );
+        assert_eq!(
+            codes(&findings),
+            vec![
+                ("child-a.v1", "implied_by_parent", Some("assertions[0]")),
+                ("child-b.v1", "implied_by_parent", Some("assertions[1]")),
+                ("child-b.v1", "duplicate_sibling", None),
+            ]
+        );
+        assert!(findings[2].message.contains("sibling 'child-a.v1'"));
+    }
+}
@@ -1,4 +1,7 @@
+pub mod bundle;
 pub mod codegen;
 pub mod crate_gen;
+pub mod decompile;
+pub mod lint;
 pub mod schema;
 pub mod validate;
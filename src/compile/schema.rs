@@ -5,7 +5,9 @@ pub fn dsl_json_schema() -> String {
         "title": "Fingerprint DSL Definition",
         "type": "object",
         "additionalProperties": false,
-        "required": ["fingerprint_id", "format", "assertions"],
+        "required": ["fingerprint_id"],
+        "if": { "not": { "required": ["extends"] } },
+        "then": { "required": ["format", "assertions"] },
         "properties": {
             "fingerprint_id": {
                 "type": "string",
@@ -29,6 +31,16 @@ pub fn dsl_json_schema() -> String {
                 "type": "string",
                 "minLength": 1,
             },
+            "extends": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Fingerprint ID whose assertions, extract and content_hash are inherited; assertions and extract sections with the same name are overridden.",
+            },
+            "include": {
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 },
+                "description": "Assertion library files (relative paths) whose assertions are prepended.",
+            },
             "assertions": {
                 "type": "array",
                 "items": { "$ref": "#/$defs/namedAssertion" },
@@ -693,10 +705,17 @@ mod tests {
         let required = parsed["required"]
             .as_array()
             .expect("required should be an array");
+        let required_without_extends = parsed["then"]["required"]
+            .as_array()
+            .expect("conditional required should be an array");
 
         assert!(required.contains(&Value::String("fingerprint_id".to_owned())));
-        assert!(required.contains(&Value::String("format".to_owned())));
-        assert!(required.contains(&Value::String("assertions".to_owned())));
+        assert_eq!(
+            parsed["if"]["not"]["required"][0],
+            Value::String("extends".to_owned())
+        );
+        assert!(required_without_extends.contains(&Value::String("format".to_owned())));
+        assert!(required_without_extends.contains(&Value::String("assertions".to_owned())));
     }
 
     #[test]
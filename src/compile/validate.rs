@@ -1,21 +1,83 @@
 use crate::dsl::assertions::{Assertion, NamedAssertion};
+use crate::dsl::diagnostics::{
+    Diagnostic, E_FORMAT_MISMATCH, E_INVALID_ASSERTION, E_INVALID_CONTENT_HASH, E_INVALID_EXTRACT,
+    E_INVALID_PATTERN, E_UNSUPPORTED_FORMAT,
+};
 use crate::dsl::parser::{ContentHashConfig, ExtractSection, FingerprintDefinition};
+use crate::dsl::plan::pattern_errors;
 use std::collections::BTreeSet;
 
 const SUPPORTED_FORMATS: &[&str] = &["xlsx", "csv", "pdf", "markdown", "text", "html"];
 const SUPPORTED_EXTRACT_TYPES: &[&str] = &["range", "table", "section", "text_match"];
 
+/// Validate a definition, stopping at the first problem.
 pub fn validate_definition(definition: &FingerprintDefinition) -> Result<(), String> {
-    validate_format(&definition.format)?;
+    match validate_definition_diagnostics(definition)
+        .into_iter()
+        .next()
+    {
+        Some(diagnostic) => Err(diagnostic.message),
+        None => Ok(()),
+    }
+}
+
+/// Validate a definition and collect every problem, each tagged with the
+/// section, entry and field it belongs to. Source positions are left for the
+/// caller to fill in.
+pub fn validate_definition_diagnostics(definition: &FingerprintDefinition) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let Err(error) = validate_format(&definition.format) {
+        diagnostics.push(Diagnostic {
+            section: Some("format"),
+            ..Diagnostic::new(E_UNSUPPORTED_FORMAT, error)
+        });
+    }
+
+    for (index, assertion) in definition.assertions.iter().enumerate() {
+        let result = match html_only_assertion(&assertion.assertion) {
+            Some(name) => require_html_format(name, &definition.format)
+                .map_err(|error| (E_FORMAT_MISMATCH, error)),
+            None => Ok(()),
+        }
+        .and_then(|()| validate_assertion(assertion).map_err(|error| (E_INVALID_ASSERTION, error)));
+        if let Err((code, error)) = result {
+            diagnostics.push(Diagnostic::new(code, error).at(
+                "assertions",
+                index,
+                assertion.name.as_deref(),
+            ));
+        }
+    }
 
-    for assertion in &definition.assertions {
-        validate_assertion(&definition.format, assertion)?;
+    for (index, section) in definition.extract.iter().enumerate() {
+        if let Err(error) = validate_extract_section(section) {
+            diagnostics.push(Diagnostic::new(E_INVALID_EXTRACT, error).at(
+                "extract",
+                index,
+                Some(&section.name),
+            ));
+        }
     }
 
-    validate_extract_sections(&definition.extract)?;
-    validate_content_hash(&definition.extract, definition.content_hash.as_ref())?;
+    validate_content_hash(
+        &definition.extract,
+        definition.content_hash.as_ref(),
+        &mut diagnostics,
+    );
+
+    for error in pattern_errors(definition) {
+        diagnostics.push(
+            Diagnostic::new(E_INVALID_PATTERN, error.to_string())
+                .at(error.section, error.index, Some(&error.name))
+                .with_field(error.field),
+        );
+    }
 
-    Ok(())
+    for diagnostic in &mut diagnostics {
+        diagnostic.fingerprint_id = Some(definition.fingerprint_id.clone());
+    }
+    diagnostics
 }
 
 fn validate_format(format: &str) -> Result<(), String> {
@@ -29,7 +91,18 @@ fn validate_format(format: &str) -> Result<(), String> {
     }
 }
 
-fn validate_assertion(format: &str, assertion: &NamedAssertion) -> Result<(), String> {
+/// DSL name of assertions that only apply to HTML documents.
+fn html_only_assertion(assertion: &Assertion) -> Option<&'static str> {
+    match assertion {
+        Assertion::HeaderTokenSearch { .. } => Some("header_token_search"),
+        Assertion::DominantColumnCount { .. } => Some("dominant_column_count"),
+        Assertion::FullWidthRow { .. } => Some("full_width_row"),
+        Assertion::PageSectionCount { .. } => Some("page_section_count"),
+        _ => None,
+    }
+}
+
+fn validate_assertion(assertion: &NamedAssertion) -> Result<(), String> {
     match &assertion.assertion {
         Assertion::HeaderTokenSearch {
             page,
@@ -38,7 +111,6 @@ fn validate_assertion(format: &str, assertion: &NamedAssertion) -> Result<(), St
             max_matches,
             ..
         } => {
-            require_html_format("header_token_search", format)?;
             if matches!(page, Some(0)) {
                 return Err("header_token_search.page must be >= 1".to_owned());
             }
@@ -74,7 +146,6 @@ fn validate_assertion(format: &str, assertion: &NamedAssertion) -> Result<(), St
             sample_pages,
             ..
         } => {
-            require_html_format("dominant_column_count", format)?;
             if *count == 0 {
                 return Err("dominant_column_count.count must be >= 1".to_owned());
             }
@@ -83,7 +154,6 @@ fn validate_assertion(format: &str, assertion: &NamedAssertion) -> Result<(), St
             }
         }
         Assertion::FullWidthRow { pattern, min_cells } => {
-            require_html_format("full_width_row", format)?;
             if pattern.trim().is_empty() {
                 return Err("full_width_row.pattern must not be empty".to_owned());
             }
@@ -92,7 +162,6 @@ fn validate_assertion(format: &str, assertion: &NamedAssertion) -> Result<(), St
             }
         }
         Assertion::PageSectionCount { min, max } => {
-            require_html_format("page_section_count", format)?;
             validate_bounds("page_section_count", *min, *max)?;
         }
         Assertion::PageCount { min, max } => {
@@ -128,37 +197,35 @@ fn validate_bounds(name: &str, min: Option<u64>, max: Option<u64>) -> Result<(),
     Ok(())
 }
 
-fn validate_extract_sections(extract: &[ExtractSection]) -> Result<(), String> {
-    for section in extract {
-        if section.name.trim().is_empty() {
-            return Err("extract.name must not be empty".to_owned());
-        }
+fn validate_extract_section(section: &ExtractSection) -> Result<(), String> {
+    if section.name.trim().is_empty() {
+        return Err("extract.name must not be empty".to_owned());
+    }
 
-        match section.r#type.as_str() {
-            "range" => {
-                require_extract_field(section, section.sheet.as_ref(), "sheet")?;
-                require_extract_field(section, section.range.as_ref(), "range")?;
-            }
-            "table" | "section" => {
-                require_extract_field(section, section.anchor_heading.as_ref(), "anchor_heading")?;
-            }
-            "text_match" => {
-                require_extract_field(section, section.anchor.as_ref(), "anchor")?;
-                require_extract_field(section, section.pattern.as_ref(), "pattern")?;
-                if section.within_chars.is_none() {
-                    return Err(format!(
-                        "extract '{}' of type '{}' requires field 'within_chars'",
-                        section.name, section.r#type
-                    ));
-                }
-            }
-            other => {
+    match section.r#type.as_str() {
+        "range" => {
+            require_extract_field(section, section.sheet.as_ref(), "sheet")?;
+            require_extract_field(section, section.range.as_ref(), "range")?;
+        }
+        "table" | "section" => {
+            require_extract_field(section, section.anchor_heading.as_ref(), "anchor_heading")?;
+        }
+        "text_match" => {
+            require_extract_field(section, section.anchor.as_ref(), "anchor")?;
+            require_extract_field(section, section.pattern.as_ref(), "pattern")?;
+            if section.within_chars.is_none() {
                 return Err(format!(
-                    "unsupported extract type '{other}'; supported extract types are {}",
-                    SUPPORTED_EXTRACT_TYPES.join(", ")
+                    "extract '{}' of type '{}' requires field 'within_chars'",
+                    section.name, section.r#type
                 ));
             }
         }
+        other => {
+            return Err(format!(
+                "unsupported extract type '{other}'; supported extract types are {}",
+                SUPPORTED_EXTRACT_TYPES.join(", ")
+            ));
+        }
     }
 
     Ok(())
@@ -182,35 +249,48 @@ fn require_extract_field(
 fn validate_content_hash(
     extract: &[ExtractSection],
     content_hash: Option<&ContentHashConfig>,
-) -> Result<(), String> {
+    diagnostics: &mut Vec<Diagnostic>,
+) {
     let Some(content_hash) = content_hash else {
-        return Ok(());
+        return;
+    };
+    let mut report = |field: String, error: String| {
+        diagnostics.push(Diagnostic {
+            section: Some("content_hash"),
+            field: Some(field),
+            ..Diagnostic::new(E_INVALID_CONTENT_HASH, error)
+        });
     };
 
     if content_hash.algorithm != "blake3" {
-        return Err(format!(
-            "unsupported content_hash.algorithm '{}'; supported algorithms are blake3",
-            content_hash.algorithm
-        ));
+        report(
+            "algorithm".to_owned(),
+            format!(
+                "unsupported content_hash.algorithm '{}'; supported algorithms are blake3",
+                content_hash.algorithm
+            ),
+        );
     }
 
     if content_hash.over.is_empty() {
-        return Err("content_hash.over must contain at least one extract name".to_owned());
+        report(
+            "over".to_owned(),
+            "content_hash.over must contain at least one extract name".to_owned(),
+        );
     }
 
     let extract_names: BTreeSet<&str> = extract
         .iter()
         .map(|section| section.name.as_str())
         .collect();
-    for extract_name in &content_hash.over {
+    for (index, extract_name) in content_hash.over.iter().enumerate() {
         if !extract_names.contains(extract_name.as_str()) {
-            return Err(format!(
-                "content_hash.over references unknown extract '{extract_name}'"
-            ));
+            report(
+                format!("over[{index}]"),
+                format!("content_hash.over references unknown extract '{extract_name}'"),
+            );
         }
     }
-
-    Ok(())
 }
 
 #[cfg(test)]
@@ -338,4 +418,88 @@ mod tests {
         let error = validate_definition(&definition).expect_err("missing bounds should fail");
         assert!(error.contains("requires at least one of 'min' or 'max'"));
     }
+
+    #[test]
+    fn validate_definition_rejects_uncompilable_regex_with_position() {
+        let mut definition = base_html_definition();
+        definition.assertions[0].assertion = Assertion::HeaderTokenSearch {
+            page: None,
+            index: None,
+            tokens: vec!["Issuer".to_owned(), "(unclosed".to_owned()],
+            min_matches: 1,
+            max_matches: None,
+        };
+
+        let error = validate_definition(&definition).expect_err("invalid regex should fail");
+        assert!(error.contains("assertions[0]"));
+        assert!(error.contains("field 'tokens[1]'"));
+        assert!(error.contains("invalid regex '(unclosed'"));
+    }
+
+    #[test]
+    fn validate_definition_diagnostics_collects_every_problem() {
+        let mut definition = base_html_definition();
+        definition.format = "markdown".to_owned();
+        definition.assertions[3].assertion = Assertion::PageSectionCount {
+            min: None,
+            max: None,
+        };
+        definition.assertions.push(NamedAssertion {
+            name: Some("bad_heading".to_owned()),
+            assertion: Assertion::HeadingRegex {
+                pattern: "(unclosed".to_owned(),
+            },
+        });
+        definition.content_hash = Some(ContentHashConfig {
+            algorithm: "sha1".to_owned(),
+            over: vec!["schedule_table".to_owned(), "missing".to_owned()],
+        });
+
+        let diagnostics = validate_definition_diagnostics(&definition);
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.code,
+                    diagnostic.section,
+                    diagnostic.index,
+                    diagnostic.field.as_deref(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (E_FORMAT_MISMATCH, Some("assertions"), Some(0), None),
+                (E_FORMAT_MISMATCH, Some("assertions"), Some(1), None),
+                (E_FORMAT_MISMATCH, Some("assertions"), Some(2), None),
+                (E_FORMAT_MISMATCH, Some("assertions"), Some(3), None),
+                (
+                    E_INVALID_CONTENT_HASH,
+                    Some("content_hash"),
+                    None,
+                    Some("algorithm")
+                ),
+                (
+                    E_INVALID_CONTENT_HASH,
+                    Some("content_hash"),
+                    None,
+                    Some("over[1]")
+                ),
+                (
+                    E_INVALID_PATTERN,
+                    Some("assertions"),
+                    Some(4),
+                    Some("pattern")
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[6].name.as_deref(), Some("bad_heading"));
+        assert!(
+            diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.fingerprint_id.as_deref() == Some("compile-html.v1"))
+        );
+    }
 }
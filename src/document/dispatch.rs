@@ -1,6 +1,6 @@
 use crate::document::{
-    CsvDocument, Document, HtmlDocument, MarkdownDocument, PdfDocument, RawDocument, TextDocument,
-    XlsxDocument,
+    CsvDocument, Document, DocumentKind, HtmlDocument, MarkdownDocument, PdfDocument, RawDocument,
+    TextDocument, XlsxDocument,
 };
 use std::path::Path;
 
@@ -15,27 +15,25 @@ pub fn open_document_with_text_path(
     extension: &str,
     text_path: Option<&Path>,
 ) -> Result<Document, String> {
-    let extension = extension.to_lowercase();
-
-    match extension.as_str() {
-        "xlsx" | "xls" => Ok(Document::Xlsx(XlsxDocument::open(path)?)),
-        "csv" => Ok(Document::Csv(CsvDocument {
+    match document_kind_for_extension(extension) {
+        DocumentKind::Xlsx => Ok(Document::Xlsx(XlsxDocument::open(path)?)),
+        DocumentKind::Csv => Ok(Document::Csv(CsvDocument {
             path: path.to_path_buf(),
         })),
-        "pdf" => Ok(Document::Pdf(PdfDocument::open(path, text_path)?)),
-        "html" | "htm" => {
+        DocumentKind::Pdf => Ok(Document::Pdf(PdfDocument::open(path, text_path)?)),
+        DocumentKind::Html => {
             let doc = HtmlDocument::open(path)?;
             Ok(Document::Html(doc))
         }
-        "md" | "markdown" => {
+        DocumentKind::Markdown => {
             let doc = MarkdownDocument::open(path)?;
             Ok(Document::Markdown(doc))
         }
-        "txt" | "text" => {
+        DocumentKind::Text => {
             let doc = TextDocument::open(path)?;
             Ok(Document::Text(doc))
         }
-        _ => {
+        DocumentKind::Unknown => {
             // Fallback to raw bytes for unknown extensions
             let doc = RawDocument::open(path)?;
             Ok(Document::Unknown(doc))
@@ -43,6 +41,19 @@ pub fn open_document_with_text_path(
     }
 }
 
+/// Document variant that format dispatch selects for an extension.
+pub fn document_kind_for_extension(extension: &str) -> DocumentKind {
+    match extension.to_lowercase().as_str() {
+        "xlsx" | "xls" => DocumentKind::Xlsx,
+        "csv" => DocumentKind::Csv,
+        "pdf" => DocumentKind::Pdf,
+        "html" | "htm" => DocumentKind::Html,
+        "md" | "markdown" => DocumentKind::Markdown,
+        "txt" | "text" => DocumentKind::Text,
+        _ => DocumentKind::Unknown,
+    }
+}
+
 /// Open a document using format dispatch from file extension inference.
 pub fn open_document_from_path(path: &Path) -> Result<Document, String> {
     open_document_from_path_with_text_path(path, None)
@@ -8,8 +8,8 @@ pub mod text;
 pub mod xlsx;
 
 pub use dispatch::{
-    open_document, open_document_from_path, open_document_from_path_with_text_path,
-    open_document_with_text_path,
+    document_kind_for_extension, open_document, open_document_from_path,
+    open_document_from_path_with_text_path, open_document_with_text_path,
 };
 pub use html::HtmlDocument;
 pub use markdown::{Heading, MarkdownDocument, Section, Table};
@@ -30,6 +30,32 @@ pub enum Document {
     Unknown(RawDocument),
 }
 
+/// Document variant selected by format dispatch, known before the file is opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Xlsx,
+    Csv,
+    Pdf,
+    Html,
+    Markdown,
+    Text,
+    Unknown,
+}
+
+impl DocumentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DocumentKind::Xlsx => "xlsx",
+            DocumentKind::Csv => "csv",
+            DocumentKind::Pdf => "pdf",
+            DocumentKind::Html => "html",
+            DocumentKind::Markdown => "markdown",
+            DocumentKind::Text => "text",
+            DocumentKind::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct StructuredDocument<'a> {
     pub normalized: &'a str,
@@ -39,6 +65,18 @@ pub struct StructuredDocument<'a> {
 }
 
 impl Document {
+    pub fn kind(&self) -> DocumentKind {
+        match self {
+            Document::Xlsx(_) => DocumentKind::Xlsx,
+            Document::Csv(_) => DocumentKind::Csv,
+            Document::Pdf(_) => DocumentKind::Pdf,
+            Document::Html(_) => DocumentKind::Html,
+            Document::Markdown(_) => DocumentKind::Markdown,
+            Document::Text(_) => DocumentKind::Text,
+            Document::Unknown(_) => DocumentKind::Unknown,
+        }
+    }
+
     pub fn path(&self) -> &Path {
         match self {
             Document::Xlsx(d) => &d.path,
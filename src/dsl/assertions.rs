@@ -1,16 +1,18 @@
 use crate::document::{Document, HtmlDocument, StructuredDocument, Table};
+use crate::dsl::plan::{CompiledPatterns, cached_regex};
 use crate::registry::AssertionResult;
 use calamine::{Reader, open_workbook_auto};
 use chrono::NaiveDate;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-type CellRef = (usize, usize);
-type CellRange = (CellRef, CellRef);
+pub(crate) type CellRef = (usize, usize);
+pub(crate) type CellRange = (CellRef, CellRef);
 static DIAGNOSE_MODE: AtomicUsize = AtomicUsize::new(0);
 
 /// Enable or disable diagnostic context mode for assertion evaluation.
@@ -169,8 +171,9 @@ pub struct ColumnPattern {
 }
 
 #[derive(Debug, Default, Clone)]
-struct EvaluationContext {
+pub(crate) struct EvaluationContext<'a> {
     sheet_bindings: HashMap<String, String>,
+    plan: Option<&'a CompiledPatterns>,
 }
 
 /// A DSL assertion entry with an optional human-readable name.
@@ -205,7 +208,7 @@ fn evaluate_named_with_diagnose_and_context(
     named_assertion: &NamedAssertion,
     doc: &Document,
     diagnose: bool,
-    context: &mut EvaluationContext,
+    context: &mut EvaluationContext<'_>,
 ) -> AssertionResult {
     let mut result =
         evaluate_with_diagnose_and_context(&named_assertion.assertion, doc, diagnose, context);
@@ -232,7 +235,29 @@ pub fn evaluate_named_assertions_with_diagnose(
     doc: &Document,
     diagnose: bool,
 ) -> Vec<AssertionResult> {
-    let mut context = EvaluationContext::default();
+    evaluate_named_assertions_in_context(assertions, doc, diagnose, EvaluationContext::default())
+}
+
+/// Evaluate assertions in declaration order, reusing precompiled patterns.
+pub fn evaluate_named_assertions_with_plan(
+    assertions: &[NamedAssertion],
+    doc: &Document,
+    diagnose: bool,
+    plan: &CompiledPatterns,
+) -> Vec<AssertionResult> {
+    let context = EvaluationContext {
+        plan: Some(plan),
+        ..EvaluationContext::default()
+    };
+    evaluate_named_assertions_in_context(assertions, doc, diagnose, context)
+}
+
+fn evaluate_named_assertions_in_context(
+    assertions: &[NamedAssertion],
+    doc: &Document,
+    diagnose: bool,
+    mut context: EvaluationContext<'_>,
+) -> Vec<AssertionResult> {
     let mut results = Vec::with_capacity(assertions.len());
     for assertion in assertions {
         let result =
@@ -265,17 +290,21 @@ fn evaluate_with_diagnose_and_context(
     assertion: &Assertion,
     doc: &Document,
     diagnose: bool,
-    context: &mut EvaluationContext,
+    context: &mut EvaluationContext<'_>,
 ) -> AssertionResult {
     let name = assertion_type_name(assertion).to_owned();
+    let plan = context.plan;
     let result = if is_content_assertion(assertion) {
-        evaluate_content_assertion(assertion, doc)
+        evaluate_content_assertion(assertion, doc, plan)
     } else {
         match assertion {
-            Assertion::FilenameRegex { pattern } => evaluate_filename_regex(doc, pattern),
+            Assertion::FilenameRegex { pattern } => {
+                cached_regex(plan, pattern).and_then(|regex| evaluate_filename_regex(doc, &regex))
+            }
             Assertion::SheetExists(sheet) => resolve_sheet_name(sheet, context)
                 .and_then(|resolved| evaluate_sheet_exists(doc, &resolved)),
-            Assertion::SheetNameRegex { pattern, bind } => evaluate_sheet_name_regex(doc, pattern)
+            Assertion::SheetNameRegex { pattern, bind } => cached_regex(plan, pattern)
+                .and_then(|regex| evaluate_sheet_name_regex(doc, &regex))
                 .and_then(|matched_sheet| {
                     if let Some(bind_name) = bind {
                         bind_sheet_name(context, bind_name, &matched_sheet)?;
@@ -283,15 +312,24 @@ fn evaluate_with_diagnose_and_context(
                     Ok(())
                 }),
             Assertion::CellEq { sheet, cell, value } => resolve_sheet_name(sheet, context)
-                .and_then(|resolved| evaluate_cell_eq(doc, &resolved, cell, value)),
+                .and_then(|resolved| {
+                    let cell_ref = cached_cell_ref(plan, cell)?;
+                    evaluate_cell_eq(doc, &resolved, cell_ref, cell, value)
+                }),
             Assertion::CellRegex {
                 sheet,
                 cell,
                 pattern,
-            } => resolve_sheet_name(sheet, context)
-                .and_then(|resolved| evaluate_cell_regex(doc, &resolved, cell, pattern)),
+            } => resolve_sheet_name(sheet, context).and_then(|resolved| {
+                let cell_ref = cached_cell_ref(plan, cell)?;
+                let regex = cached_regex(plan, pattern)?;
+                evaluate_cell_regex(doc, &resolved, cell_ref, cell, &regex)
+            }),
             Assertion::RangeNonNull { sheet, range } => resolve_sheet_name(sheet, context)
-                .and_then(|resolved| evaluate_range_non_null(doc, &resolved, range)),
+                .and_then(|resolved| {
+                    let range_ref = cached_range_ref(plan, range)?;
+                    evaluate_range_non_null(doc, &resolved, range_ref, range)
+                }),
             Assertion::SheetMinRows { sheet, min_rows } => resolve_sheet_name(sheet, context)
                 .and_then(|resolved| evaluate_sheet_min_rows(doc, &resolved, *min_rows)),
             Assertion::ColumnSearch {
@@ -300,7 +338,18 @@ fn evaluate_with_diagnose_and_context(
                 row_range,
                 pattern,
             } => resolve_sheet_name(sheet, context).and_then(|resolved| {
-                evaluate_column_search(doc, &resolved, column, row_range, pattern)
+                let column_index = cached_column_ref(plan, column)?;
+                let rows = cached_row_range_ref(plan, row_range)?;
+                let regex = cached_regex(plan, pattern)?;
+                evaluate_column_search(
+                    doc,
+                    &resolved,
+                    column_index,
+                    column,
+                    rows,
+                    row_range,
+                    &regex,
+                )
             }),
             Assertion::HeaderRowMatch {
                 sheet,
@@ -308,14 +357,17 @@ fn evaluate_with_diagnose_and_context(
                 min_match,
                 columns,
             } => resolve_sheet_name(sheet, context).and_then(|resolved| {
-                evaluate_header_row_match(doc, &resolved, row_range, *min_match, columns)
+                if columns.is_empty() {
+                    return Err("header_row_match requires at least one column pattern".to_owned());
+                }
+                let rows = cached_row_range_ref(plan, row_range)?;
+                let patterns = compile_column_patterns(columns, plan)?;
+                evaluate_header_row_match(doc, &resolved, rows, row_range, *min_match, &patterns)
             }),
             Assertion::PageCount { min, max } => evaluate_page_count(doc, *min, *max),
-            Assertion::MetadataRegex { key, pattern } => evaluate_metadata_regex(doc, key, pattern),
-            _ => Err(format!(
-                "assertion '{}' is not implemented in v0.1",
-                assertion_type_name(assertion)
-            )),
+            Assertion::MetadataRegex { key, pattern } => cached_regex(plan, pattern)
+                .and_then(|regex| evaluate_metadata_regex(doc, key, &regex)),
+            _ => Err(not_implemented(assertion)),
         }
     };
 
@@ -342,6 +394,37 @@ fn evaluate_with_diagnose_and_context(
     }
 }
 
+fn cached_cell_ref(plan: Option<&CompiledPatterns>, cell: &str) -> Result<CellRef, String> {
+    match plan.and_then(|plan| plan.cell_ref(cell)) {
+        Some(cell_ref) => Ok(cell_ref),
+        None => parse_cell_ref(cell),
+    }
+}
+
+fn cached_range_ref(plan: Option<&CompiledPatterns>, range: &str) -> Result<CellRange, String> {
+    match plan.and_then(|plan| plan.range_ref(range)) {
+        Some(range_ref) => Ok(range_ref),
+        None => parse_range_ref(range),
+    }
+}
+
+fn cached_column_ref(plan: Option<&CompiledPatterns>, column: &str) -> Result<usize, String> {
+    match plan.and_then(|plan| plan.column_ref(column)) {
+        Some(column_index) => Ok(column_index),
+        None => parse_column_ref(column),
+    }
+}
+
+fn cached_row_range_ref(
+    plan: Option<&CompiledPatterns>,
+    row_range: &str,
+) -> Result<(usize, usize), String> {
+    match plan.and_then(|plan| plan.row_range_ref(row_range)) {
+        Some(rows) => Ok(rows),
+        None => parse_row_range_ref(row_range),
+    }
+}
+
 /// Adapter used by generated crates.
 pub fn evaluate_assertion(
     doc: &Document,
@@ -362,8 +445,8 @@ fn normalize_binding_name(value: &str) -> Result<String, String> {
     Ok(normalized.to_owned())
 }
 
-fn bind_sheet_name(
-    context: &mut EvaluationContext,
+pub(crate) fn bind_sheet_name(
+    context: &mut EvaluationContext<'_>,
     binding: &str,
     sheet_name: &str,
 ) -> Result<(), String> {
@@ -372,7 +455,10 @@ fn bind_sheet_name(
     Ok(())
 }
 
-fn resolve_sheet_name(sheet: &str, context: &EvaluationContext) -> Result<String, String> {
+pub(crate) fn resolve_sheet_name(
+    sheet: &str,
+    context: &EvaluationContext<'_>,
+) -> Result<String, String> {
     if !sheet.starts_with('$') {
         return Ok(sheet.to_owned());
     }
@@ -385,10 +471,10 @@ fn resolve_sheet_name(sheet: &str, context: &EvaluationContext) -> Result<String
         .ok_or_else(|| format!("sheet binding '{sheet}' was not found"))
 }
 
-fn diagnostic_context(
+pub(crate) fn diagnostic_context(
     assertion: &Assertion,
     doc: &Document,
-    context: &EvaluationContext,
+    context: &EvaluationContext<'_>,
 ) -> Option<Value> {
     match assertion {
         Assertion::HeadingExists(heading) => heading_diagnostic_context(doc, heading),
@@ -401,12 +487,14 @@ fn diagnostic_context(
             anchor,
             pattern,
             within_chars,
-        } => text_near_diagnostic_context(doc, anchor, pattern, *within_chars),
+        } => text_near_diagnostic_context(doc, anchor, pattern, *within_chars, context.plan),
         Assertion::TableExists { heading, .. }
         | Assertion::TableColumns { heading, .. }
-        | Assertion::TableShape { heading, .. } => table_diagnostic_context(doc, heading),
+        | Assertion::TableShape { heading, .. } => {
+            table_diagnostic_context(doc, heading, context.plan)
+        }
         Assertion::SectionNonEmpty { heading } | Assertion::SectionMinLines { heading, .. } => {
-            section_diagnostic_context(doc, heading)
+            section_diagnostic_context(doc, heading, context.plan)
         }
         Assertion::HeaderTokenSearch {
             page,
@@ -421,6 +509,7 @@ fn diagnostic_context(
             tokens,
             *min_matches,
             *max_matches,
+            context.plan,
         ),
         Assertion::DominantColumnCount {
             count,
@@ -428,7 +517,7 @@ fn diagnostic_context(
             sample_pages,
         } => dominant_column_count_diagnostic_context(doc, *count, *tolerance, *sample_pages),
         Assertion::FullWidthRow { pattern, min_cells } => {
-            full_width_row_diagnostic_context(doc, pattern, *min_cells)
+            full_width_row_diagnostic_context(doc, pattern, *min_cells, context.plan)
         }
         Assertion::PageSectionCount { min, max } => {
             page_section_count_diagnostic_context(doc, *min, *max)
@@ -457,7 +546,7 @@ fn column_search_diagnostic_context(
     column: &str,
     row_range: &str,
     pattern: &str,
-    context: &EvaluationContext,
+    context: &EvaluationContext<'_>,
 ) -> Option<Value> {
     let resolved_sheet = resolve_sheet_name(sheet, context).ok()?;
     let column_index = parse_column_ref(column).ok()?;
@@ -507,11 +596,11 @@ fn header_row_match_diagnostic_context(
     row_range: &str,
     min_match: u64,
     columns: &[ColumnPattern],
-    context: &EvaluationContext,
+    context: &EvaluationContext<'_>,
 ) -> Option<Value> {
     let resolved_sheet = resolve_sheet_name(sheet, context).ok()?;
     let (start_row, end_row) = parse_row_range_ref(row_range).ok()?;
-    let patterns = compile_column_patterns(columns).ok()?;
+    let patterns = compile_column_patterns(columns, context.plan).ok()?;
     let rows = spreadsheet_rows(doc, &resolved_sheet).ok()?;
     let (best_row, best_match_count, best_pattern_indexes) =
         best_header_row_match(&rows, start_row, end_row, &patterns);
@@ -559,10 +648,11 @@ fn text_near_diagnostic_context(
     anchor_pattern: &str,
     value_pattern: &str,
     within_chars: u32,
+    plan: Option<&CompiledPatterns>,
 ) -> Option<Value> {
     let source = content_source_text(doc)?;
-    let anchor_regex = Regex::new(anchor_pattern).ok()?;
-    let value_regex = Regex::new(value_pattern).ok()?;
+    let anchor_regex = cached_regex(plan, anchor_pattern).ok()?;
+    let value_regex = cached_regex(plan, value_pattern).ok()?;
 
     let anchors: Vec<_> = anchor_regex.find_iter(source).collect();
     let values: Vec<_> = value_regex.find_iter(source).collect();
@@ -594,9 +684,13 @@ fn text_near_diagnostic_context(
     }))
 }
 
-fn table_diagnostic_context(doc: &Document, heading_pattern: &str) -> Option<Value> {
+fn table_diagnostic_context(
+    doc: &Document,
+    heading_pattern: &str,
+    plan: Option<&CompiledPatterns>,
+) -> Option<Value> {
     let md_doc = get_content_document(doc).ok()?;
-    let heading_regex = Regex::new(heading_pattern).ok()?;
+    let heading_regex = cached_regex(plan, heading_pattern).ok()?;
     let heading_found = md_doc
         .headings
         .iter()
@@ -628,9 +722,13 @@ fn table_diagnostic_context(doc: &Document, heading_pattern: &str) -> Option<Val
     }))
 }
 
-fn section_diagnostic_context(doc: &Document, heading_pattern: &str) -> Option<Value> {
+fn section_diagnostic_context(
+    doc: &Document,
+    heading_pattern: &str,
+    plan: Option<&CompiledPatterns>,
+) -> Option<Value> {
     let md_doc = get_content_document(doc).ok()?;
-    let heading_regex = Regex::new(heading_pattern).ok()?;
+    let heading_regex = cached_regex(plan, heading_pattern).ok()?;
 
     let section = md_doc.sections.iter().find(|section| {
         section
@@ -660,9 +758,10 @@ fn header_token_search_diagnostic_context(
     tokens: &[String],
     min_matches: u64,
     max_matches: Option<u64>,
+    plan: Option<&CompiledPatterns>,
 ) -> Option<Value> {
     let html = get_html_document(doc).ok()?;
-    let token_regexes = compile_header_token_regexes(tokens).ok()?;
+    let token_regexes = compile_header_token_regexes(tokens, plan).ok()?;
     let tables = html_tables_for_filter(html, page);
     let scanned_tables: Vec<Value> = tables
         .iter()
@@ -723,9 +822,10 @@ fn full_width_row_diagnostic_context(
     doc: &Document,
     pattern: &str,
     min_cells: usize,
+    plan: Option<&CompiledPatterns>,
 ) -> Option<Value> {
     let html = get_html_document(doc).ok()?;
-    let regex = Regex::new(pattern).ok()?;
+    let regex = cached_regex(plan, pattern).ok()?;
     let candidates: Vec<Value> = html
         .tables
         .iter()
@@ -934,63 +1034,102 @@ fn is_content_assertion(assertion: &Assertion) -> bool {
     )
 }
 
-fn evaluate_content_assertion(assertion: &Assertion, doc: &Document) -> Result<(), String> {
+fn evaluate_content_assertion(
+    assertion: &Assertion,
+    doc: &Document,
+    plan: Option<&CompiledPatterns>,
+) -> Result<(), String> {
     match assertion {
         Assertion::HeadingExists(text) => evaluate_heading_exists(doc, text),
-        Assertion::HeadingRegex { pattern } => evaluate_heading_regex(doc, pattern),
-        Assertion::HeadingLevel { level, pattern } => evaluate_heading_level(doc, *level, pattern),
+        Assertion::HeadingRegex { pattern } => {
+            cached_regex(plan, pattern).and_then(|regex| evaluate_heading_regex(doc, &regex))
+        }
+        Assertion::HeadingLevel { level, pattern } => cached_regex(plan, pattern)
+            .and_then(|regex| evaluate_heading_level(doc, *level, &regex)),
         Assertion::TextContains(text) => evaluate_text_contains(doc, text),
-        Assertion::TextRegex { pattern } => evaluate_text_regex(doc, pattern),
+        Assertion::TextRegex { pattern } => {
+            cached_regex(plan, pattern).and_then(|regex| evaluate_text_regex(doc, &regex))
+        }
         Assertion::TextNear {
             anchor,
             pattern,
             within_chars,
-        } => evaluate_text_near(doc, anchor, pattern, *within_chars),
-        Assertion::SectionNonEmpty { heading } => evaluate_section_non_empty(doc, heading),
-        Assertion::SectionMinLines { heading, min_lines } => {
-            evaluate_section_min_lines(doc, heading, *min_lines)
+        } => {
+            let anchor_regex = cached_regex(plan, anchor)?;
+            let value_regex = cached_regex(plan, pattern)?;
+            evaluate_text_near(doc, &anchor_regex, &value_regex, *within_chars)
+        }
+        Assertion::SectionNonEmpty { heading } => {
+            cached_regex(plan, heading).and_then(|regex| evaluate_section_non_empty(doc, &regex))
+        }
+        Assertion::SectionMinLines { heading, min_lines } => cached_regex(plan, heading)
+            .and_then(|regex| evaluate_section_min_lines(doc, &regex, *min_lines)),
+        Assertion::TableExists { heading, index } => {
+            cached_regex(plan, heading).and_then(|regex| evaluate_table_exists(doc, &regex, *index))
         }
-        Assertion::TableExists { heading, index } => evaluate_table_exists(doc, heading, *index),
         Assertion::TableColumns {
             heading,
             index,
             patterns,
-        } => evaluate_table_columns(doc, heading, *index, patterns),
+        } => {
+            let heading_regex = cached_regex(plan, heading)?;
+            let patterns = patterns
+                .iter()
+                .map(|pattern| cached_regex(plan, pattern).map(Cow::into_owned))
+                .collect::<Result<Vec<_>, _>>()?;
+            evaluate_table_columns(doc, &heading_regex, *index, &patterns)
+        }
         Assertion::TableShape {
             heading,
             index,
             min_columns,
             column_types,
-        } => evaluate_table_shape(doc, heading, *index, *min_columns, column_types),
+        } => cached_regex(plan, heading).and_then(|regex| {
+            evaluate_table_shape(doc, &regex, *index, *min_columns, column_types)
+        }),
         Assertion::TableMinRows {
             heading,
             index,
             min_rows,
-        } => evaluate_table_min_rows(doc, heading, *index, *min_rows),
+        } => cached_regex(plan, heading)
+            .and_then(|regex| evaluate_table_min_rows(doc, &regex, *index, *min_rows)),
         Assertion::HeaderTokenSearch {
             page,
             index,
             tokens,
             min_matches,
             max_matches,
-        } => evaluate_header_token_search(doc, *page, *index, tokens, *min_matches, *max_matches),
+        } => compile_header_token_regexes(tokens, plan).and_then(|token_regexes| {
+            evaluate_header_token_search(
+                doc,
+                *page,
+                *index,
+                &token_regexes,
+                *min_matches,
+                *max_matches,
+            )
+        }),
         Assertion::DominantColumnCount {
             count,
             tolerance,
             sample_pages,
         } => evaluate_dominant_column_count(doc, *count, *tolerance, *sample_pages),
-        Assertion::FullWidthRow { pattern, min_cells } => {
-            evaluate_full_width_row(doc, pattern, *min_cells)
-        }
+        Assertion::FullWidthRow { pattern, min_cells } => cached_regex(plan, pattern)
+            .and_then(|regex| evaluate_full_width_row(doc, &regex, *min_cells)),
         Assertion::PageSectionCount { min, max } => evaluate_page_section_count(doc, *min, *max),
-        _ => Err(format!(
-            "assertion '{}' is not implemented in v0.1",
-            assertion_type_name(assertion)
-        )),
+        _ => Err(not_implemented(assertion)),
     }
 }
 
-fn assertion_type_name(assertion: &Assertion) -> &'static str {
+/// Refusal detail for assertion types the evaluator does not implement.
+pub(crate) fn not_implemented(assertion: &Assertion) -> String {
+    format!(
+        "assertion '{}' is not implemented in v0.1",
+        assertion_type_name(assertion)
+    )
+}
+
+pub(crate) fn assertion_type_name(assertion: &Assertion) -> &'static str {
     match assertion {
         Assertion::FilenameRegex { .. } => "filename_regex",
         Assertion::SheetExists(_) => "sheet_exists",
@@ -1025,9 +1164,7 @@ fn assertion_type_name(assertion: &Assertion) -> &'static str {
     }
 }
 
-fn evaluate_filename_regex(doc: &Document, pattern: &str) -> Result<(), String> {
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
+pub(crate) fn evaluate_filename_regex(doc: &Document, regex: &Regex) -> Result<(), String> {
     let file_name = doc
         .path()
         .file_name()
@@ -1043,12 +1180,13 @@ fn evaluate_filename_regex(doc: &Document, pattern: &str) -> Result<(), String>
     } else {
         Err(format!(
             "filename '{}' does not match pattern '{}'",
-            file_name, pattern
+            file_name,
+            regex.as_str()
         ))
     }
 }
 
-fn evaluate_sheet_exists(doc: &Document, sheet: &str) -> Result<(), String> {
+pub(crate) fn evaluate_sheet_exists(doc: &Document, sheet: &str) -> Result<(), String> {
     match doc {
         Document::Xlsx(xlsx) => {
             let workbook = open_workbook_auto(&xlsx.path).map_err(|error| {
@@ -1074,10 +1212,7 @@ fn evaluate_sheet_exists(doc: &Document, sheet: &str) -> Result<(), String> {
     }
 }
 
-fn evaluate_sheet_name_regex(doc: &Document, pattern: &str) -> Result<String, String> {
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
-
+pub(crate) fn evaluate_sheet_name_regex(doc: &Document, regex: &Regex) -> Result<String, String> {
     match doc {
         Document::Xlsx(xlsx) => {
             let workbook = open_workbook_auto(&xlsx.path).map_err(|error| {
@@ -1091,7 +1226,10 @@ fn evaluate_sheet_name_regex(doc: &Document, pattern: &str) -> Result<String, St
             {
                 Ok(matched)
             } else {
-                Err(format!("no sheet name matched pattern '{}'", pattern))
+                Err(format!(
+                    "no sheet name matched pattern '{}'",
+                    regex.as_str()
+                ))
             }
         }
         Document::Csv(csv) => {
@@ -1104,7 +1242,7 @@ fn evaluate_sheet_name_regex(doc: &Document, pattern: &str) -> Result<String, St
             } else {
                 Err(format!(
                     "no csv virtual sheet name matched pattern '{}'",
-                    pattern
+                    regex.as_str()
                 ))
             }
         }
@@ -1112,8 +1250,13 @@ fn evaluate_sheet_name_regex(doc: &Document, pattern: &str) -> Result<String, St
     }
 }
 
-fn evaluate_cell_eq(doc: &Document, sheet: &str, cell: &str, value: &str) -> Result<(), String> {
-    let cell_ref = parse_cell_ref(cell)?;
+pub(crate) fn evaluate_cell_eq(
+    doc: &Document,
+    sheet: &str,
+    cell_ref: CellRef,
+    cell: &str,
+    value: &str,
+) -> Result<(), String> {
     let actual = spreadsheet_cell_value(doc, sheet, cell_ref)?;
     match actual {
         Some(actual) if actual == value => Ok(()),
@@ -1124,27 +1267,30 @@ fn evaluate_cell_eq(doc: &Document, sheet: &str, cell: &str, value: &str) -> Res
     }
 }
 
-fn evaluate_cell_regex(
+pub(crate) fn evaluate_cell_regex(
     doc: &Document,
     sheet: &str,
+    cell_ref: CellRef,
     cell: &str,
-    pattern: &str,
+    regex: &Regex,
 ) -> Result<(), String> {
-    let cell_ref = parse_cell_ref(cell)?;
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
     let actual = spreadsheet_cell_value(doc, sheet, cell_ref)?;
     match actual {
         Some(value) if regex.is_match(&value) => Ok(()),
         Some(value) => Err(format!(
-            "cell {cell} value '{value}' did not match pattern '{pattern}'"
+            "cell {cell} value '{value}' did not match pattern '{}'",
+            regex.as_str()
         )),
         None => Err(format!("cell {cell} is empty or missing")),
     }
 }
 
-fn evaluate_range_non_null(doc: &Document, sheet: &str, range: &str) -> Result<(), String> {
-    let (start, end) = parse_range_ref(range)?;
+pub(crate) fn evaluate_range_non_null(
+    doc: &Document,
+    sheet: &str,
+    (start, end): CellRange,
+    range: &str,
+) -> Result<(), String> {
     for row in start.0..=end.0 {
         for col in start.1..=end.1 {
             let value = spreadsheet_cell_value(doc, sheet, (row, col))?;
@@ -1159,7 +1305,11 @@ fn evaluate_range_non_null(doc: &Document, sheet: &str, range: &str) -> Result<(
     Ok(())
 }
 
-fn evaluate_sheet_min_rows(doc: &Document, sheet: &str, min_rows: u64) -> Result<(), String> {
+pub(crate) fn evaluate_sheet_min_rows(
+    doc: &Document,
+    sheet: &str,
+    min_rows: u64,
+) -> Result<(), String> {
     let row_count = spreadsheet_non_empty_row_count(doc, sheet)?;
     if row_count as u64 >= min_rows {
         Ok(())
@@ -1170,17 +1320,15 @@ fn evaluate_sheet_min_rows(doc: &Document, sheet: &str, min_rows: u64) -> Result
     }
 }
 
-fn evaluate_column_search(
+pub(crate) fn evaluate_column_search(
     doc: &Document,
     sheet: &str,
+    column_index: usize,
     column: &str,
+    (start_row, end_row): (usize, usize),
     row_range: &str,
-    pattern: &str,
+    regex: &Regex,
 ) -> Result<(), String> {
-    let column_index = parse_column_ref(column)?;
-    let (start_row, end_row) = parse_row_range_ref(row_range)?;
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
     let rows = spreadsheet_rows(doc, sheet)?;
 
     for row_index in start_row..=end_row {
@@ -1192,25 +1340,25 @@ fn evaluate_column_search(
     }
 
     Err(format!(
-        "no cell in column {column} rows {row_range} matched pattern '{pattern}'"
+        "no cell in column {column} rows {row_range} matched pattern '{}'",
+        regex.as_str()
     ))
 }
 
-fn evaluate_header_row_match(
+pub(crate) fn evaluate_header_row_match(
     doc: &Document,
     sheet: &str,
+    (start_row, end_row): (usize, usize),
     row_range: &str,
     min_match: u64,
-    columns: &[ColumnPattern],
+    patterns: &[Regex],
 ) -> Result<(), String> {
-    if columns.is_empty() {
+    if patterns.is_empty() {
         return Err("header_row_match requires at least one column pattern".to_owned());
     }
 
-    let (start_row, end_row) = parse_row_range_ref(row_range)?;
-    let patterns = compile_column_patterns(columns)?;
     let rows = spreadsheet_rows(doc, sheet)?;
-    let (best_row, best_count, _) = best_header_row_match(&rows, start_row, end_row, &patterns);
+    let (best_row, best_count, _) = best_header_row_match(&rows, start_row, end_row, patterns);
 
     if best_count as u64 >= min_match {
         return Ok(());
@@ -1224,11 +1372,17 @@ fn evaluate_header_row_match(
     ))
 }
 
-fn compile_column_patterns(columns: &[ColumnPattern]) -> Result<Vec<Regex>, String> {
+fn compile_column_patterns(
+    columns: &[ColumnPattern],
+    plan: Option<&CompiledPatterns>,
+) -> Result<Vec<Regex>, String> {
     columns
         .iter()
         .enumerate()
         .map(|(index, column)| {
+            if let Some(regex) = plan.and_then(|plan| plan.regex(&column.pattern)) {
+                return Ok(regex.clone());
+            }
             Regex::new(&column.pattern).map_err(|error| {
                 format!(
                     "invalid regex for header_row_match columns[{index}] '{}': {error}",
@@ -1367,7 +1521,7 @@ fn spreadsheet_rows(doc: &Document, sheet: &str) -> Result<Vec<Vec<String>>, Str
     }
 }
 
-fn parse_cell_ref(cell: &str) -> Result<CellRef, String> {
+pub(crate) fn parse_cell_ref(cell: &str) -> Result<CellRef, String> {
     let mut letters = String::new();
     let mut digits = String::new();
 
@@ -1407,7 +1561,7 @@ fn parse_cell_ref(cell: &str) -> Result<CellRef, String> {
     Ok((row - 1, column - 1))
 }
 
-fn parse_range_ref(range: &str) -> Result<CellRange, String> {
+pub(crate) fn parse_range_ref(range: &str) -> Result<CellRange, String> {
     let (left, right) = range
         .split_once(':')
         .ok_or_else(|| format!("invalid range reference '{range}'"))?;
@@ -1420,7 +1574,7 @@ fn parse_range_ref(range: &str) -> Result<CellRange, String> {
     ))
 }
 
-fn parse_column_ref(column: &str) -> Result<usize, String> {
+pub(crate) fn parse_column_ref(column: &str) -> Result<usize, String> {
     let trimmed = column.trim();
     if trimmed.is_empty() {
         return Err("column reference cannot be empty".to_owned());
@@ -1438,7 +1592,7 @@ fn parse_column_ref(column: &str) -> Result<usize, String> {
     Ok(parsed - 1)
 }
 
-fn parse_row_range_ref(row_range: &str) -> Result<(usize, usize), String> {
+pub(crate) fn parse_row_range_ref(row_range: &str) -> Result<(usize, usize), String> {
     let (start, end) = row_range
         .split_once(':')
         .ok_or_else(|| format!("invalid row_range '{row_range}'"))?;
@@ -1515,7 +1669,11 @@ fn load_csv_rows(path: &Path) -> Result<Vec<Vec<String>>, String> {
     Ok(rows)
 }
 
-fn evaluate_page_count(doc: &Document, min: Option<u64>, max: Option<u64>) -> Result<(), String> {
+pub(crate) fn evaluate_page_count(
+    doc: &Document,
+    min: Option<u64>,
+    max: Option<u64>,
+) -> Result<(), String> {
     let pdf = match doc {
         Document::Pdf(pdf) => pdf,
         _ => return Err("pdf structural assertion requires pdf format".to_owned()),
@@ -1543,13 +1701,15 @@ fn evaluate_page_count(doc: &Document, min: Option<u64>, max: Option<u64>) -> Re
     Ok(())
 }
 
-fn evaluate_metadata_regex(doc: &Document, key: &str, pattern: &str) -> Result<(), String> {
+pub(crate) fn evaluate_metadata_regex(
+    doc: &Document,
+    key: &str,
+    regex: &Regex,
+) -> Result<(), String> {
     let pdf = match doc {
         Document::Pdf(pdf) => pdf,
         _ => return Err("pdf structural assertion requires pdf format".to_owned()),
     };
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
 
     let pdf_document = lopdf::Document::load(&pdf.path)
         .map_err(|error| format!("failed reading pdf '{}': {error}", pdf.path.display()))?;
@@ -1574,7 +1734,8 @@ fn evaluate_metadata_regex(doc: &Document, key: &str, pattern: &str) -> Result<(
         Ok(())
     } else {
         Err(format!(
-            "pdf metadata key '{key}' value '{value}' does not match '{pattern}'"
+            "pdf metadata key '{key}' value '{value}' does not match '{}'",
+            regex.as_str()
         ))
     }
 }
@@ -1628,7 +1789,7 @@ fn pdf_object_as_string(
 
 // Content assertion implementations with format-aware dispatch
 
-fn evaluate_heading_exists(doc: &Document, text: &str) -> Result<(), String> {
+pub(crate) fn evaluate_heading_exists(doc: &Document, text: &str) -> Result<(), String> {
     let md_doc = get_content_document(doc)?;
     let found = md_doc.headings.iter().any(|h| h.text == text);
     if found {
@@ -1638,22 +1799,22 @@ fn evaluate_heading_exists(doc: &Document, text: &str) -> Result<(), String> {
     }
 }
 
-fn evaluate_heading_regex(doc: &Document, pattern: &str) -> Result<(), String> {
+pub(crate) fn evaluate_heading_regex(doc: &Document, regex: &Regex) -> Result<(), String> {
     let md_doc = get_content_document(doc)?;
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
     let found = md_doc.headings.iter().any(|h| regex.is_match(&h.text));
     if found {
         Ok(())
     } else {
-        Err(format!("No heading matches pattern '{}'", pattern))
+        Err(format!("No heading matches pattern '{}'", regex.as_str()))
     }
 }
 
-fn evaluate_heading_level(doc: &Document, level: u8, pattern: &str) -> Result<(), String> {
+pub(crate) fn evaluate_heading_level(
+    doc: &Document,
+    level: u8,
+    regex: &Regex,
+) -> Result<(), String> {
     let md_doc = get_content_document(doc)?;
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
     let found = md_doc
         .headings
         .iter()
@@ -1662,12 +1823,13 @@ fn evaluate_heading_level(doc: &Document, level: u8, pattern: &str) -> Result<()
         Ok(())
     } else {
         Err(format!(
-            "No level-{level} heading matches pattern '{pattern}'"
+            "No level-{level} heading matches pattern '{}'",
+            regex.as_str()
         ))
     }
 }
 
-fn evaluate_text_contains(doc: &Document, text: &str) -> Result<(), String> {
+pub(crate) fn evaluate_text_contains(doc: &Document, text: &str) -> Result<(), String> {
     let source = text_source_for_assertion(doc, "text_contains")?;
     if source.contains(text) {
         Ok(())
@@ -1676,28 +1838,26 @@ fn evaluate_text_contains(doc: &Document, text: &str) -> Result<(), String> {
     }
 }
 
-fn evaluate_text_regex(doc: &Document, pattern: &str) -> Result<(), String> {
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
-
+pub(crate) fn evaluate_text_regex(doc: &Document, regex: &Regex) -> Result<(), String> {
     let source = text_source_for_assertion(doc, "text_regex")?;
     if regex.is_match(source) {
         Ok(())
     } else {
-        Err(format!("Pattern '{}' not found in document", pattern))
+        Err(format!(
+            "Pattern '{}' not found in document",
+            regex.as_str()
+        ))
     }
 }
 
-fn evaluate_text_near(
+pub(crate) fn evaluate_text_near(
     doc: &Document,
-    anchor_pattern: &str,
-    pattern: &str,
+    anchor_regex: &Regex,
+    value_regex: &Regex,
     within_chars: u32,
 ) -> Result<(), String> {
-    let anchor_regex = Regex::new(anchor_pattern)
-        .map_err(|error| format!("invalid regex '{}': {error}", anchor_pattern))?;
-    let value_regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
+    let anchor_pattern = anchor_regex.as_str();
+    let pattern = value_regex.as_str();
     let source = text_source_for_assertion(doc, "text_near")?;
 
     let anchors: Vec<_> = anchor_regex.find_iter(source).collect();
@@ -1779,10 +1939,11 @@ fn text_source_for_assertion<'a>(
     }
 }
 
-fn evaluate_section_non_empty(doc: &Document, heading_pattern: &str) -> Result<(), String> {
+pub(crate) fn evaluate_section_non_empty(
+    doc: &Document,
+    heading_regex: &Regex,
+) -> Result<(), String> {
     let md_doc = get_content_document(doc)?;
-    let heading_regex = Regex::new(heading_pattern)
-        .map_err(|error| format!("invalid regex '{heading_pattern}': {error}"))?;
 
     // Find the section with matching heading
     let section = md_doc.sections.iter().find(|section| {
@@ -1804,19 +1965,17 @@ fn evaluate_section_non_empty(doc: &Document, heading_pattern: &str) -> Result<(
         }
         None => Err(format!(
             "heading not found: no section matches '{}'",
-            heading_pattern
+            heading_regex.as_str()
         )),
     }
 }
 
-fn evaluate_section_min_lines(
+pub(crate) fn evaluate_section_min_lines(
     doc: &Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     min_lines: u64,
 ) -> Result<(), String> {
     let md_doc = get_content_document(doc)?;
-    let heading_regex = Regex::new(heading_pattern)
-        .map_err(|error| format!("invalid regex '{heading_pattern}': {error}"))?;
 
     // Find the section with matching heading
     let section = md_doc.sections.iter().find(|section| {
@@ -1843,7 +2002,7 @@ fn evaluate_section_min_lines(
         }
         None => Err(format!(
             "heading not found: no section matches '{}'",
-            heading_pattern
+            heading_regex.as_str()
         )),
     }
 }
@@ -1856,22 +2015,22 @@ fn section_body_lines(section: &crate::document::markdown::Section) -> impl Iter
     lines
 }
 
-fn evaluate_table_exists(
+pub(crate) fn evaluate_table_exists(
     doc: &Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     index: Option<usize>,
 ) -> Result<(), String> {
-    let _ = find_table(doc, heading_pattern, index)?;
+    let _ = find_table(doc, heading_regex, index)?;
     Ok(())
 }
 
-fn evaluate_table_columns(
+pub(crate) fn evaluate_table_columns(
     doc: &Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     index: Option<usize>,
-    patterns: &[String],
+    patterns: &[Regex],
 ) -> Result<(), String> {
-    let table = find_table(doc, heading_pattern, index)?;
+    let table = find_table(doc, heading_regex, index)?;
     if table.headers.len() < patterns.len() {
         return Err(format!(
             "table has {} columns but {} patterns were provided",
@@ -1880,28 +2039,28 @@ fn evaluate_table_columns(
         ));
     }
 
-    for (column_index, pattern) in patterns.iter().enumerate() {
-        let regex =
-            Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
+    for (column_index, regex) in patterns.iter().enumerate() {
         let header = &table.headers[column_index];
         if !regex.is_match(header) {
             return Err(format!(
                 "column {} header '{}' does not match '{}'",
-                column_index, header, pattern
+                column_index,
+                header,
+                regex.as_str()
             ));
         }
     }
     Ok(())
 }
 
-fn evaluate_table_shape(
+pub(crate) fn evaluate_table_shape<S: AsRef<str>>(
     doc: &Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     index: Option<usize>,
     min_columns: usize,
-    column_types: &[String],
+    column_types: &[S],
 ) -> Result<(), String> {
-    let table = find_table(doc, heading_pattern, index)?;
+    let table = find_table(doc, heading_regex, index)?;
     if table.headers.len() < min_columns {
         return Err(format!(
             "table has {} columns, expected at least {}",
@@ -1919,6 +2078,7 @@ fn evaluate_table_shape(
     }
 
     for (column_index, expected_type) in column_types.iter().enumerate() {
+        let expected_type = expected_type.as_ref();
         let inferred = infer_column_type(table, column_index);
         if !column_type_matches(expected_type, &inferred) {
             return Err(format!(
@@ -1931,13 +2091,13 @@ fn evaluate_table_shape(
     Ok(())
 }
 
-fn evaluate_table_min_rows(
+pub(crate) fn evaluate_table_min_rows(
     doc: &Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     index: Option<usize>,
     min_rows: u64,
 ) -> Result<(), String> {
-    let table = find_table(doc, heading_pattern, index)?;
+    let table = find_table(doc, heading_regex, index)?;
     let row_count = table.rows.len() as u64;
     if row_count >= min_rows {
         Ok(())
@@ -1949,16 +2109,16 @@ fn evaluate_table_min_rows(
     }
 }
 
-fn evaluate_header_token_search(
+pub(crate) fn evaluate_header_token_search(
     doc: &Document,
     page: Option<u32>,
     index: Option<usize>,
-    tokens: &[String],
+    token_regexes: &[Regex],
     min_matches: u64,
     max_matches: Option<u64>,
 ) -> Result<(), String> {
     let html = get_html_document(doc)?;
-    if tokens.is_empty() {
+    if token_regexes.is_empty() {
         return Err("header_token_search requires at least one token".to_owned());
     }
     if let Some(max_matches) = max_matches
@@ -1967,11 +2127,10 @@ fn evaluate_header_token_search(
         return Err("header_token_search max_matches cannot be less than min_matches".to_owned());
     }
 
-    let token_regexes = compile_header_token_regexes(tokens)?;
     let tables = select_html_tables(html, page, index)?;
     let best_match_count = tables
         .iter()
-        .map(|table| matched_header_tokens(table, &token_regexes).len() as u64)
+        .map(|table| matched_header_tokens(table, token_regexes).len() as u64)
         .max()
         .unwrap_or(0);
 
@@ -1991,7 +2150,7 @@ fn evaluate_header_token_search(
     Ok(())
 }
 
-fn evaluate_dominant_column_count(
+pub(crate) fn evaluate_dominant_column_count(
     doc: &Document,
     count: usize,
     tolerance: usize,
@@ -2026,10 +2185,12 @@ fn evaluate_dominant_column_count(
     }
 }
 
-fn evaluate_full_width_row(doc: &Document, pattern: &str, min_cells: usize) -> Result<(), String> {
+pub(crate) fn evaluate_full_width_row(
+    doc: &Document,
+    regex: &Regex,
+    min_cells: usize,
+) -> Result<(), String> {
     let html = get_html_document(doc)?;
-    let regex =
-        Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
 
     let found = html.tables.iter().any(|table| {
         table.rows.iter().any(|row| {
@@ -2042,12 +2203,13 @@ fn evaluate_full_width_row(doc: &Document, pattern: &str, min_cells: usize) -> R
         Ok(())
     } else {
         Err(format!(
-            "no full-width row matched '{pattern}' with min_cells {min_cells}"
+            "no full-width row matched '{}' with min_cells {min_cells}",
+            regex.as_str()
         ))
     }
 }
 
-fn evaluate_page_section_count(
+pub(crate) fn evaluate_page_section_count(
     doc: &Document,
     min: Option<u64>,
     max: Option<u64>,
@@ -2119,22 +2281,21 @@ fn dominant_column_tables(html: &HtmlDocument, sample_pages: u32) -> Vec<&Table>
         .collect()
 }
 
-fn compile_header_token_regexes(tokens: &[String]) -> Result<Vec<(String, Regex)>, String> {
+fn compile_header_token_regexes(
+    tokens: &[String],
+    plan: Option<&CompiledPatterns>,
+) -> Result<Vec<Regex>, String> {
     tokens
         .iter()
-        .map(|token| {
-            Regex::new(token)
-                .map(|regex| (token.clone(), regex))
-                .map_err(|error| format!("invalid regex '{token}': {error}"))
-        })
+        .map(|token| cached_regex(plan, token).map(Cow::into_owned))
         .collect()
 }
 
-fn matched_header_tokens(table: &Table, token_regexes: &[(String, Regex)]) -> Vec<String> {
+fn matched_header_tokens(table: &Table, token_regexes: &[Regex]) -> Vec<String> {
     token_regexes
         .iter()
-        .filter(|(_, regex)| table.headers.iter().any(|header| regex.is_match(header)))
-        .map(|(token, _)| token.clone())
+        .filter(|regex| table.headers.iter().any(|header| regex.is_match(header)))
+        .map(|regex| regex.as_str().to_owned())
         .collect()
 }
 
@@ -2174,12 +2335,10 @@ fn full_width_row_text(row: &[String]) -> Option<String> {
 
 fn find_table<'a>(
     doc: &'a Document,
-    heading_pattern: &str,
+    heading_regex: &Regex,
     index: Option<usize>,
 ) -> Result<&'a crate::document::markdown::Table, String> {
     let md_doc = get_content_document(doc)?;
-    let heading_regex = Regex::new(heading_pattern)
-        .map_err(|error| format!("invalid regex '{heading_pattern}': {error}"))?;
     let expected_index = index.unwrap_or(0);
 
     let matching_tables: Vec<&crate::document::markdown::Table> = md_doc
@@ -2196,7 +2355,8 @@ fn find_table<'a>(
     matching_tables.get(expected_index).copied().ok_or_else(|| {
         format!(
             "table not found for heading '{}' at index {}",
-            heading_pattern, expected_index
+            heading_regex.as_str(),
+            expected_index
         )
     })
 }
@@ -2474,7 +2634,8 @@ mod tests {
     #[test]
     fn sheet_name_regex_returns_first_match_when_multiple_names_match() {
         let doc = csv_document("a,b\nx,y\n");
-        let matched = evaluate_sheet_name_regex(&doc, "(?i)sheet1|csv").expect("sheet name match");
+        let regex = Regex::new("(?i)sheet1|csv").expect("valid regex");
+        let matched = evaluate_sheet_name_regex(&doc, &regex).expect("sheet name match");
         assert_eq!(matched, "Sheet1");
     }
 
@@ -0,0 +1,614 @@
+use crate::dsl::assertions::{Assertion, NamedAssertion};
+use crate::dsl::parser::ExtractSection;
+use crate::dsl::resolve::{DefinitionOrigins, DefinitionSource, SourceDocument};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Diagnostic codes. Parse codes match the compile refusal codes.
+pub const E_INVALID_YAML: &str = "E_INVALID_YAML";
+pub const E_MISSING_FIELD: &str = "E_MISSING_FIELD";
+pub const E_UNKNOWN_ASSERTION: &str = "E_UNKNOWN_ASSERTION";
+pub const E_UNSUPPORTED_FORMAT: &str = "E_UNSUPPORTED_FORMAT";
+pub const E_FORMAT_MISMATCH: &str = "E_FORMAT_MISMATCH";
+pub const E_INVALID_ASSERTION: &str = "E_INVALID_ASSERTION";
+pub const E_INVALID_EXTRACT: &str = "E_INVALID_EXTRACT";
+pub const E_INVALID_CONTENT_HASH: &str = "E_INVALID_CONTENT_HASH";
+pub const E_INVALID_PATTERN: &str = "E_INVALID_PATTERN";
+/// Set codes, for definitions compiled together into one crate.
+pub const E_DUPLICATE_ID: &str = "E_DUPLICATE_ID";
+pub const E_ORPHAN_PARENT: &str = "E_ORPHAN_PARENT";
+pub const E_PARENT_CYCLE: &str = "E_PARENT_CYCLE";
+
+/// One problem found in a fingerprint definition.
+///
+/// `section`, `index`, `name` and `field` locate the problem within the
+/// definition; `file`, `line` and `column` (1-based) locate it in the source
+/// when it is known.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint_id: Option<String>,
+    /// Definition section: `fingerprint_id`, `format`, `parent`, `assertions`,
+    /// `extract` or `content_hash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// Assertion or extract name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Field within the entry, e.g. `pattern` or `columns[2].pattern`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Attach the definition entry the problem belongs to.
+    pub fn at(mut self, section: &'static str, index: usize, name: Option<&str>) -> Self {
+        self.section = Some(section);
+        self.index = Some(index);
+        self.name = name.map(str::to_owned);
+        self
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// Fill in file, line and column from the source text of `file`, where
+    /// `document` and the diagnostic's `index` are local to that file.
+    pub fn locate(&mut self, file: &str, map: &SourceMap<'_>, document: usize) {
+        self.file = Some(file.to_owned());
+        if self.line.is_some() {
+            return;
+        }
+        let position = match (self.section, self.index) {
+            (Some(section), Some(index)) => {
+                map.entry(document, section, index, self.field.as_deref())
+            }
+            (Some(section), None) => map.key(document, section),
+            _ => None,
+        }
+        .or_else(|| map.document_start(document));
+        if let Some((line, column)) = position {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+    }
+}
+
+/// Locate diagnostics raised on a resolved definition.
+///
+/// Assertion and extract entries are followed back through `extends` and
+/// `include` to the file, document and local index that wrote them, and
+/// `index` is rewritten to that local index; anything else is placed in the
+/// definition's own document.
+pub fn locate_resolved(
+    diagnostics: &mut [Diagnostic],
+    source: &SourceDocument,
+    origins: &DefinitionOrigins,
+) {
+    let mut files: HashMap<&str, String> = HashMap::new();
+    for diagnostic in diagnostics {
+        let origin = diagnostic
+            .section
+            .zip(diagnostic.index)
+            .and_then(|(section, index)| origins.entry(section, index));
+        let (file, document) = match origin {
+            Some(origin) => {
+                diagnostic.index = Some(origin.index);
+                (origin.file.as_str(), origin.document)
+            }
+            None => (source.origin.as_str(), source.document),
+        };
+        let yaml = files
+            .entry(file)
+            .or_insert_with(|| std::fs::read_to_string(file).unwrap_or_default());
+        diagnostic.locate(file, &SourceMap::new(yaml), document);
+    }
+}
+
+/// Line index over block-style YAML, used to place diagnostics for entries
+/// that serde_yaml does not report positions for.
+///
+/// Document numbers count non-empty documents, matching the order in which
+/// definitions are read from the file.
+pub struct SourceMap<'a> {
+    lines: Vec<&'a str>,
+    documents: Vec<(usize, usize)>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(yaml: &'a str) -> Self {
+        let lines: Vec<&str> = yaml.lines().collect();
+        let mut documents = Vec::new();
+        let mut start = 0;
+        for (index, line) in lines.iter().enumerate() {
+            if is_document_separator(line) {
+                push_document(&lines, start, index, &mut documents);
+                start = index + 1;
+            }
+        }
+        push_document(&lines, start, lines.len(), &mut documents);
+        Self { lines, documents }
+    }
+
+    /// First content line of a document.
+    pub fn document_start(&self, document: usize) -> Option<(u64, u64)> {
+        let (start, end) = *self.documents.get(document)?;
+        (start..end)
+            .find(|index| is_content(self.lines[*index]))
+            .map(|index| self.position(index))
+    }
+
+    /// Line of a top-level key in a document.
+    pub fn key(&self, document: usize, key: &str) -> Option<(u64, u64)> {
+        self.key_line(document, key)
+            .map(|index| self.position(index))
+    }
+
+    /// Line of the `index`th entry of a top-level list, or of `field` inside it.
+    pub fn entry(
+        &self,
+        document: usize,
+        section: &str,
+        index: usize,
+        field: Option<&str>,
+    ) -> Option<(u64, u64)> {
+        let key_line = self.key_line(document, section)?;
+        let (_, end) = self.documents[document];
+        let items = self.list_items(key_line, end);
+        let Some(&item_start) = items.get(index) else {
+            return Some(self.position(key_line));
+        };
+        let item_end = items
+            .get(index + 1)
+            .copied()
+            .unwrap_or_else(|| self.section_end(key_line, end));
+
+        let field_key = field.map(|field| {
+            let last = field.rsplit('.').next().unwrap_or(field);
+            let key = last.split('[').next().unwrap_or(last);
+            format!("{key}:")
+        });
+        if let Some(field_key) = field_key
+            && let Some(line) = (item_start..item_end).find(|line| {
+                let trimmed = self.lines[*line].trim_start();
+                let trimmed = trimmed.strip_prefix("- ").unwrap_or(trimmed);
+                trimmed.starts_with(&field_key)
+            })
+        {
+            return Some(self.position(line));
+        }
+        Some(self.position(item_start))
+    }
+
+    fn key_line(&self, document: usize, key: &str) -> Option<usize> {
+        let (start, end) = *self.documents.get(document)?;
+        let prefix = format!("{key}:");
+        (start..end).find(|index| self.lines[*index].starts_with(&prefix))
+    }
+
+    /// End of the block under a top-level key: the next top-level key.
+    fn section_end(&self, key_line: usize, end: usize) -> usize {
+        (key_line + 1..end)
+            .find(|index| {
+                let line = self.lines[*index];
+                is_content(line) && !line.starts_with(' ') && !line.starts_with('-')
+            })
+            .unwrap_or(end)
+    }
+
+    fn list_items(&self, key_line: usize, end: usize) -> Vec<usize> {
+        let section_end = self.section_end(key_line, end);
+        let mut indent = None;
+        let mut items = Vec::new();
+        for index in key_line + 1..section_end {
+            let line = self.lines[index];
+            let trimmed = line.trim_start();
+            if !(trimmed.starts_with("- ") || trimmed == "-") {
+                continue;
+            }
+            let line_indent = line.len() - trimmed.len();
+            match indent {
+                None => {
+                    indent = Some(line_indent);
+                    items.push(index);
+                }
+                Some(indent) if indent == line_indent => items.push(index),
+                Some(_) => {}
+            }
+        }
+        items
+    }
+
+    fn position(&self, index: usize) -> (u64, u64) {
+        let line = self.lines[index];
+        let column = line.len() - line.trim_start().len() + 1;
+        (index as u64 + 1, column as u64)
+    }
+}
+
+fn is_document_separator(line: &str) -> bool {
+    line == "---" || line.starts_with("--- ") || line.trim_end() == "---"
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+fn push_document(lines: &[&str], start: usize, end: usize, documents: &mut Vec<(usize, usize)>) {
+    if lines[start..end].iter().any(|line| is_content(line)) {
+        documents.push((start, end));
+    }
+}
+
+/// A definition document with every field kept as raw YAML, so each entry
+/// can be checked on its own.
+#[derive(Debug, Deserialize)]
+struct LooseDefinition {
+    fingerprint_id: Option<Value>,
+    format: Option<Value>,
+    extends: Option<Value>,
+    #[serde(default)]
+    include: Vec<Value>,
+    assertions: Option<Vec<Value>>,
+    extract: Option<Vec<Value>>,
+}
+
+/// Collect every parse problem in a YAML stream of definitions.
+///
+/// Unlike `parse_sources_str`, which stops at the first error, each assertion
+/// and extract entry is checked separately. Instantiation documents are
+/// skipped; their problems surface when they are expanded.
+pub fn parse_diagnostics(yaml: &str, origin: &str) -> Vec<Diagnostic> {
+    let map = SourceMap::new(yaml);
+    let mut diagnostics = Vec::new();
+    let mut document = 0;
+
+    for raw in serde_yaml::Deserializer::from_str(yaml) {
+        let value = match Option::<Value>::deserialize(raw) {
+            Ok(Some(value)) => value,
+            Ok(None) => continue,
+            Err(error) => {
+                diagnostics.push(from_yaml_error(E_INVALID_YAML, &error, origin));
+                break;
+            }
+        };
+        for mut diagnostic in document_diagnostics(&value) {
+            diagnostic.locate(origin, &map, document);
+            diagnostics.push(diagnostic);
+        }
+        document += 1;
+    }
+    diagnostics
+}
+
+fn document_diagnostics(value: &Value) -> Vec<Diagnostic> {
+    if value.get("template").is_some() {
+        return Vec::new();
+    }
+
+    let loose = match LooseDefinition::deserialize(value) {
+        Ok(loose) => loose,
+        Err(error) => return vec![Diagnostic::new(E_INVALID_YAML, error.to_string())],
+    };
+    let fingerprint_id = loose
+        .fingerprint_id
+        .as_ref()
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+    let mut diagnostics = Vec::new();
+
+    let inherits = loose.extends.is_some();
+    let mut require = |present: bool, field: &'static str| {
+        if !present {
+            let mut diagnostic =
+                Diagnostic::new(E_MISSING_FIELD, format!("missing field `{field}`"))
+                    .with_field(field);
+            diagnostic.section = (field != "fingerprint_id").then_some(field);
+            diagnostics.push(diagnostic);
+        }
+    };
+    require(loose.fingerprint_id.is_some(), "fingerprint_id");
+    require(loose.format.is_some() || inherits, "format");
+    require(
+        loose.assertions.is_some() || inherits || !loose.include.is_empty(),
+        "assertions",
+    );
+
+    for (index, entry) in loose.assertions.iter().flatten().enumerate() {
+        if let Err(error) = NamedAssertion::deserialize(entry) {
+            let code = if names_assertion_type(entry) {
+                E_INVALID_YAML
+            } else {
+                E_UNKNOWN_ASSERTION
+            };
+            diagnostics.push(Diagnostic::new(code, error.to_string()).at(
+                "assertions",
+                index,
+                entry_name(entry),
+            ));
+        }
+    }
+    for (index, entry) in loose.extract.iter().flatten().enumerate() {
+        if let Err(error) = ExtractSection::deserialize(entry) {
+            diagnostics.push(Diagnostic::new(E_INVALID_YAML, error.to_string()).at(
+                "extract",
+                index,
+                entry_name(entry),
+            ));
+        }
+    }
+
+    // Anything else wrong with the document, such as a mistyped scalar field.
+    if diagnostics.is_empty()
+        && let Err(error) = DefinitionSource::deserialize(value)
+    {
+        diagnostics.push(Diagnostic::new(E_INVALID_YAML, error.to_string()));
+    }
+
+    for diagnostic in &mut diagnostics {
+        diagnostic.fingerprint_id = fingerprint_id.clone();
+    }
+    diagnostics
+}
+
+fn entry_name(entry: &Value) -> Option<&str> {
+    entry.get("name").and_then(Value::as_str)
+}
+
+/// Whether an assertion entry has a key naming a known assertion type.
+fn names_assertion_type(entry: &Value) -> bool {
+    let types = assertion_types();
+    match entry {
+        Value::Mapping(mapping) => mapping
+            .keys()
+            .filter_map(Value::as_str)
+            .any(|key| types.contains(&key)),
+        Value::String(key) => types.contains(&key.as_str()),
+        _ => false,
+    }
+}
+
+/// Assertion type keys, as `Assertion` declares its variants to serde.
+fn assertion_types() -> &'static [&'static str] {
+    static TYPES: OnceLock<&'static [&'static str]> = OnceLock::new();
+    TYPES.get_or_init(|| {
+        let mut variants: &'static [&'static str] = &[];
+        let _ = Assertion::deserialize(VariantNames(&mut variants));
+        variants
+    })
+}
+
+/// Deserializer that only records the variant names an enum asks for.
+struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> de::Deserializer<'de> for VariantNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected an enum"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = variants;
+        Err(de::Error::custom("variant names recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Convert a serde_yaml error, keeping its position.
+pub fn from_yaml_error(code: &'static str, error: &serde_yaml::Error, file: &str) -> Diagnostic {
+    let mut diagnostic = Diagnostic::new(code, error.to_string());
+    diagnostic.file = Some(file.to_owned());
+    if let Some(location) = error.location() {
+        diagnostic.line = Some(location.line() as u64);
+        diagnostic.column = Some(location.column() as u64);
+    }
+    diagnostic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_DEFINITIONS: &str = r#"# leading comment
+fingerprint_id: first.v1
+format: csv
+assertions:
+  - sheet_exists: "csv"
+  - name: rows
+    sheet_min_rows:
+      sheet: "csv"
+      min_rows: 1
+---
+fingerprint_id: second.v1
+format: html
+assertions:
+- html_magic: true
+- name: bad_rows
+  sheet_min_rows:
+    sheet: "csv"
+    min_rows: many
+extract:
+  - name: summary
+    type: range
+"#;
+
+    #[test]
+    fn source_map_places_entries_and_fields() {
+        let map = SourceMap::new(TWO_DEFINITIONS);
+
+        assert_eq!(map.document_start(0), Some((2, 1)));
+        assert_eq!(map.document_start(1), Some((11, 1)));
+        assert_eq!(map.key(1, "extract"), Some((19, 1)));
+        assert_eq!(map.entry(0, "assertions", 1, None), Some((6, 3)));
+        assert_eq!(
+            map.entry(0, "assertions", 1, Some("min_rows")),
+            Some((9, 7))
+        );
+        assert_eq!(map.entry(1, "assertions", 1, None), Some((15, 1)));
+    }
+
+    #[test]
+    fn parse_diagnostics_reports_every_bad_entry() {
+        let diagnostics = parse_diagnostics(TWO_DEFINITIONS, "defs/two.fp.yaml");
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.code,
+                    diagnostic.fingerprint_id.as_deref(),
+                    diagnostic.index,
+                    diagnostic.name.as_deref(),
+                    diagnostic.line,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    E_UNKNOWN_ASSERTION,
+                    Some("second.v1"),
+                    Some(0),
+                    None,
+                    Some(14)
+                ),
+                (
+                    E_INVALID_YAML,
+                    Some("second.v1"),
+                    Some(1),
+                    Some("bad_rows"),
+                    Some(15)
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].file.as_deref(), Some("defs/two.fp.yaml"));
+    }
+
+    #[test]
+    fn parse_diagnostics_reports_missing_fields_and_syntax_errors() {
+        let diagnostics = parse_diagnostics("fingerprint_id: lonely.v1\n", "lonely.fp.yaml");
+        let missing: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.field.as_deref()))
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                (E_MISSING_FIELD, Some("format")),
+                (E_MISSING_FIELD, Some("assertions")),
+            ]
+        );
+
+        let diagnostics = parse_diagnostics("fingerprint_id: [unclosed\n", "broken.fp.yaml");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, E_INVALID_YAML);
+        assert!(diagnostics[0].line.is_some());
+    }
+
+    #[test]
+    fn locate_resolved_follows_entries_to_their_base_and_library() {
+        use crate::compile::validate::validate_definition_diagnostics;
+        use crate::dsl::parser::parse_selected;
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        std::fs::write(
+            dir.path().join("lib.fpl.yaml"),
+            "assertions:\n  - name: lib_bad\n    filename_regex:\n      pattern: \"[\"\n",
+        )
+        .expect("write library");
+        let definitions = r#"fingerprint_id: base.v1
+format: csv
+assertions:
+  - sheet_exists: "csv"
+  - name: base_bad
+    filename_regex:
+      pattern: "("
+---
+fingerprint_id: child.v1
+extends: base.v1
+include:
+  - lib.fpl.yaml
+assertions:
+  - name: extra
+    sheet_exists: "other"
+"#;
+        let path = dir.path().join("defs.fp.yaml");
+        std::fs::write(&path, definitions).expect("write definitions");
+
+        let parsed = parse_selected(&path, Some("child.v1")).expect("parse child");
+        let mut diagnostics: Vec<_> = validate_definition_diagnostics(&parsed.definition)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code == E_INVALID_PATTERN)
+            .collect();
+        locate_resolved(&mut diagnostics, &parsed.source, &parsed.origins);
+
+        let library = dir.path().join("lib.fpl.yaml").display().to_string();
+        let located: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.name.as_deref(),
+                    diagnostic.file.clone(),
+                    diagnostic.index,
+                    diagnostic.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                (
+                    Some("base_bad"),
+                    Some(path.display().to_string()),
+                    Some(1),
+                    Some(7)
+                ),
+                (Some("lib_bad"), Some(library), Some(0), Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn assertion_types_come_from_the_assertion_enum() {
+        let types = assertion_types();
+        assert!(types.contains(&"sheet_exists"));
+        assert!(types.contains(&"filename_regex"));
+        assert!(!types.contains(&"html_magic"));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::document::{Document, StructuredDocument};
 use crate::dsl::parser::ExtractSection;
+use crate::dsl::plan::{CompiledPatterns, cached_regex};
 use calamine::{Reader, open_workbook_auto};
-use regex::Regex;
 use serde_json::Value;
 use serde_json::json;
 use std::collections::HashMap;
@@ -14,11 +14,28 @@ type CellRange = (CellRef, CellRef);
 pub fn extract(
     doc: &Document,
     sections: &[ExtractSection],
+) -> Result<HashMap<String, Value>, String> {
+    extract_sections(doc, sections, None)
+}
+
+/// Extract content sections, reusing regexes and ranges from a compiled plan.
+pub fn extract_with_plan(
+    doc: &Document,
+    sections: &[ExtractSection],
+    plan: &CompiledPatterns,
+) -> Result<HashMap<String, Value>, String> {
+    extract_sections(doc, sections, Some(plan))
+}
+
+fn extract_sections(
+    doc: &Document,
+    sections: &[ExtractSection],
+    plan: Option<&CompiledPatterns>,
 ) -> Result<HashMap<String, Value>, String> {
     let mut extracted = HashMap::new();
 
     for section in sections {
-        let maybe_value = extract_one(doc, section)
+        let maybe_value = extract_one(doc, section, plan)
             .map_err(|error| format!("extract section '{}': {error}", section.name))?;
         if let Some(value) = maybe_value {
             extracted.insert(section.name.clone(), value);
@@ -28,17 +45,25 @@ pub fn extract(
     Ok(extracted)
 }
 
-fn extract_one(doc: &Document, section: &ExtractSection) -> Result<Option<Value>, String> {
+fn extract_one(
+    doc: &Document,
+    section: &ExtractSection,
+    plan: Option<&CompiledPatterns>,
+) -> Result<Option<Value>, String> {
     match section.r#type.as_str() {
-        "range" => extract_range(doc, section),
-        "section" => extract_section(doc, section),
-        "table" => extract_table(doc, section),
-        "text_match" => extract_text_match(doc, section),
+        "range" => extract_range(doc, section, plan),
+        "section" => extract_section(doc, section, plan),
+        "table" => extract_table(doc, section, plan),
+        "text_match" => extract_text_match(doc, section, plan),
         other => Err(format!("unsupported extract type '{other}'")),
     }
 }
 
-fn extract_range(doc: &Document, section: &ExtractSection) -> Result<Option<Value>, String> {
+fn extract_range(
+    doc: &Document,
+    section: &ExtractSection,
+    plan: Option<&CompiledPatterns>,
+) -> Result<Option<Value>, String> {
     let sheet = section
         .sheet
         .as_deref()
@@ -47,7 +72,10 @@ fn extract_range(doc: &Document, section: &ExtractSection) -> Result<Option<Valu
         .range
         .as_deref()
         .ok_or_else(|| "range extract requires 'range'".to_owned())?;
-    let (start, end) = parse_range_ref(range_str)?;
+    let (start, end) = match plan.and_then(|plan| plan.range_ref(range_str)) {
+        Some(range) => range,
+        None => parse_range_ref(range_str)?,
+    };
 
     match doc {
         Document::Csv(csv) => {
@@ -83,13 +111,16 @@ fn extract_range(doc: &Document, section: &ExtractSection) -> Result<Option<Valu
     }
 }
 
-fn extract_section(doc: &Document, section: &ExtractSection) -> Result<Option<Value>, String> {
+fn extract_section(
+    doc: &Document,
+    section: &ExtractSection,
+    plan: Option<&CompiledPatterns>,
+) -> Result<Option<Value>, String> {
     let pattern = section
         .anchor_heading
         .as_deref()
         .ok_or_else(|| "section extract requires 'anchor_heading'".to_owned())?;
-    let heading_regex =
-        Regex::new(pattern).map_err(|error| format!("invalid anchor_heading regex: {error}"))?;
+    let heading_regex = cached_regex(plan, pattern)?;
     let content_doc = content_document(doc);
 
     let Some(content_doc) = content_doc else {
@@ -119,14 +150,17 @@ fn extract_section(doc: &Document, section: &ExtractSection) -> Result<Option<Va
     })))
 }
 
-fn extract_table(doc: &Document, section: &ExtractSection) -> Result<Option<Value>, String> {
+fn extract_table(
+    doc: &Document,
+    section: &ExtractSection,
+    plan: Option<&CompiledPatterns>,
+) -> Result<Option<Value>, String> {
     let pattern = section
         .anchor_heading
         .as_deref()
         .ok_or_else(|| "table extract requires 'anchor_heading'".to_owned())?;
     let index = section.index.unwrap_or(0);
-    let heading_regex =
-        Regex::new(pattern).map_err(|error| format!("invalid anchor_heading regex: {error}"))?;
+    let heading_regex = cached_regex(plan, pattern)?;
     let content_doc = content_document(doc);
 
     let Some(content_doc) = content_doc else {
@@ -158,7 +192,11 @@ fn extract_table(doc: &Document, section: &ExtractSection) -> Result<Option<Valu
     })))
 }
 
-fn extract_text_match(doc: &Document, section: &ExtractSection) -> Result<Option<Value>, String> {
+fn extract_text_match(
+    doc: &Document,
+    section: &ExtractSection,
+    plan: Option<&CompiledPatterns>,
+) -> Result<Option<Value>, String> {
     let anchor_pattern = section
         .anchor
         .as_deref()
@@ -171,10 +209,8 @@ fn extract_text_match(doc: &Document, section: &ExtractSection) -> Result<Option
         .within_chars
         .ok_or_else(|| "text_match extract requires 'within_chars'".to_owned())?;
 
-    let anchor_regex =
-        Regex::new(anchor_pattern).map_err(|error| format!("invalid anchor regex: {error}"))?;
-    let value_regex =
-        Regex::new(pattern).map_err(|error| format!("invalid pattern regex: {error}"))?;
+    let anchor_regex = cached_regex(plan, anchor_pattern)?;
+    let value_regex = cached_regex(plan, pattern)?;
     let Some(text) = content_text(doc) else {
         return Ok(None);
     };
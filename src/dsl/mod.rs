@@ -1,7 +1,13 @@
 pub mod assertions;
 pub mod content_hash;
+pub mod diagnostics;
 pub mod extract;
+pub mod native;
+pub mod pack;
 pub mod parser;
+pub mod plan;
+pub mod resolve;
+pub mod template;
 
 pub use assertions::{Assertion, NamedAssertion};
 pub use parser::{ContentHashConfig, ExtractSection, FingerprintDefinition};
@@ -0,0 +1,318 @@
+//! Evaluation primitives for compiled fingerprint crates.
+//!
+//! `fingerprint compile` emits one call per assertion into these functions,
+//! with regexes compiled once into statics and spreadsheet references parsed
+//! at compile time. They share their implementation with the DSL
+//! interpreter, so a compiled fingerprint reports the same
+//! `FingerprintResult` as the definition it was generated from.
+
+use crate::document::Document;
+use crate::dsl::assertions::{
+    self, Assertion, EvaluationContext, bind_sheet_name, diagnostic_context, resolve_sheet_name,
+};
+use crate::dsl::extract::extract_with_plan;
+use crate::dsl::parser::ExtractSection;
+use crate::dsl::plan::{CompiledPatterns, compile_extract_patterns};
+use crate::registry::{AssertionResult, FingerprintResult};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub use regex::Regex;
+
+/// Result of a single assertion: `Err` carries the failure detail.
+pub type Outcome = Result<(), String>;
+
+/// Compile a pattern that `fingerprint compile` already validated.
+pub fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap_or_else(|error| panic!("invalid regex '{pattern}': {error}"))
+}
+
+/// Compile a list of validated patterns, in order.
+pub fn regexes(patterns: &[&str]) -> Vec<Regex> {
+    patterns.iter().copied().map(regex).collect()
+}
+
+/// Assertion results of one compiled fingerprint evaluation, with the sheet
+/// bindings made so far.
+pub struct Evaluation<'a> {
+    doc: &'a Document,
+    diagnose: bool,
+    context: EvaluationContext<'static>,
+    results: Vec<AssertionResult>,
+}
+
+impl<'a> Evaluation<'a> {
+    pub fn new(doc: &'a Document, diagnose: bool) -> Self {
+        Self {
+            doc,
+            diagnose,
+            context: EvaluationContext::default(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Resolve a `$binding` sheet reference made by an earlier assertion.
+    pub fn sheet(&self, sheet: &str) -> Result<String, String> {
+        resolve_sheet_name(sheet, &self.context)
+    }
+
+    /// Bind a matched sheet name for later `$binding` references.
+    pub fn bind(&mut self, binding: &str, sheet: &str) -> Outcome {
+        bind_sheet_name(&mut self.context, binding, sheet)
+    }
+
+    /// Record an assertion outcome. Returns whether evaluation continues:
+    /// outside diagnose mode, the first failure ends it.
+    ///
+    /// `assertion` is only built for failures in diagnose mode, to attach the
+    /// same diagnostic context as the interpreter.
+    pub fn record(
+        &mut self,
+        name: &str,
+        outcome: Outcome,
+        assertion: impl FnOnce() -> Assertion,
+    ) -> bool {
+        let result = match outcome {
+            Ok(()) => AssertionResult {
+                name: name.to_owned(),
+                passed: true,
+                detail: None,
+                context: None,
+            },
+            Err(detail) => AssertionResult {
+                name: name.to_owned(),
+                passed: false,
+                detail: Some(detail),
+                context: if self.diagnose {
+                    diagnostic_context(&assertion(), self.doc, &self.context)
+                } else {
+                    None
+                },
+            },
+        };
+        let passed = result.passed;
+        self.results.push(result);
+        passed || self.diagnose
+    }
+
+    /// Non-matching result carrying the assertions evaluated so far.
+    pub fn no_match(self) -> FingerprintResult {
+        let reason = self
+            .results
+            .iter()
+            .find(|result| !result.passed)
+            .and_then(|result| result.detail.clone());
+        FingerprintResult {
+            matched: false,
+            reason,
+            assertions: self.results,
+            extracted: None,
+            content_hash: None,
+        }
+    }
+
+    /// Assertion results when every assertion passed, else the non-matching
+    /// result.
+    pub fn finish(self) -> Result<Vec<AssertionResult>, FingerprintResult> {
+        if self.results.iter().all(|result| result.passed) {
+            Ok(self.results)
+        } else {
+            Err(self.no_match())
+        }
+    }
+}
+
+/// Extract sections with their patterns compiled once.
+pub struct ExtractPlan {
+    sections: Vec<ExtractSection>,
+    patterns: CompiledPatterns,
+}
+
+impl ExtractPlan {
+    /// Compile sections that `fingerprint compile` already validated.
+    pub fn new(sections: Vec<ExtractSection>) -> Self {
+        let patterns =
+            compile_extract_patterns(&sections).unwrap_or_else(|error| panic!("{error}"));
+        Self { sections, patterns }
+    }
+
+    /// Extracted values, or `None` when extraction fails.
+    pub fn extract(&self, doc: &Document) -> Option<HashMap<String, Value>> {
+        extract_with_plan(doc, &self.sections, &self.patterns).ok()
+    }
+}
+
+pub fn filename_regex(doc: &Document, regex: &Regex) -> Outcome {
+    assertions::evaluate_filename_regex(doc, regex)
+}
+
+pub fn sheet_exists(doc: &Document, sheet: &str) -> Outcome {
+    assertions::evaluate_sheet_exists(doc, sheet)
+}
+
+/// Name of the first sheet matching `regex`.
+pub fn sheet_name_regex(doc: &Document, regex: &Regex) -> Result<String, String> {
+    assertions::evaluate_sheet_name_regex(doc, regex)
+}
+
+/// `cell_ref` is the zero-based (row, column) of `cell`.
+pub fn cell_eq(
+    doc: &Document,
+    sheet: &str,
+    cell_ref: (usize, usize),
+    cell: &str,
+    value: &str,
+) -> Outcome {
+    assertions::evaluate_cell_eq(doc, sheet, cell_ref, cell, value)
+}
+
+/// `cell_ref` is the zero-based (row, column) of `cell`.
+pub fn cell_regex(
+    doc: &Document,
+    sheet: &str,
+    cell_ref: (usize, usize),
+    cell: &str,
+    regex: &Regex,
+) -> Outcome {
+    assertions::evaluate_cell_regex(doc, sheet, cell_ref, cell, regex)
+}
+
+/// `range_ref` holds the zero-based top-left and bottom-right cells of `range`.
+pub fn range_non_null(
+    doc: &Document,
+    sheet: &str,
+    range_ref: ((usize, usize), (usize, usize)),
+    range: &str,
+) -> Outcome {
+    assertions::evaluate_range_non_null(doc, sheet, range_ref, range)
+}
+
+pub fn sheet_min_rows(doc: &Document, sheet: &str, min_rows: u64) -> Outcome {
+    assertions::evaluate_sheet_min_rows(doc, sheet, min_rows)
+}
+
+/// `column_index` and `rows` are the zero-based forms of `column` and
+/// `row_range`.
+pub fn column_search(
+    doc: &Document,
+    sheet: &str,
+    column_index: usize,
+    column: &str,
+    rows: (usize, usize),
+    row_range: &str,
+    regex: &Regex,
+) -> Outcome {
+    assertions::evaluate_column_search(doc, sheet, column_index, column, rows, row_range, regex)
+}
+
+/// `rows` is the zero-based form of `row_range`.
+pub fn header_row_match(
+    doc: &Document,
+    sheet: &str,
+    rows: (usize, usize),
+    row_range: &str,
+    min_match: u64,
+    columns: &[Regex],
+) -> Outcome {
+    assertions::evaluate_header_row_match(doc, sheet, rows, row_range, min_match, columns)
+}
+
+pub fn page_count(doc: &Document, min: Option<u64>, max: Option<u64>) -> Outcome {
+    assertions::evaluate_page_count(doc, min, max)
+}
+
+pub fn metadata_regex(doc: &Document, key: &str, regex: &Regex) -> Outcome {
+    assertions::evaluate_metadata_regex(doc, key, regex)
+}
+
+pub fn heading_exists(doc: &Document, text: &str) -> Outcome {
+    assertions::evaluate_heading_exists(doc, text)
+}
+
+pub fn heading_regex(doc: &Document, regex: &Regex) -> Outcome {
+    assertions::evaluate_heading_regex(doc, regex)
+}
+
+pub fn heading_level(doc: &Document, level: u8, regex: &Regex) -> Outcome {
+    assertions::evaluate_heading_level(doc, level, regex)
+}
+
+pub fn text_contains(doc: &Document, text: &str) -> Outcome {
+    assertions::evaluate_text_contains(doc, text)
+}
+
+pub fn text_regex(doc: &Document, regex: &Regex) -> Outcome {
+    assertions::evaluate_text_regex(doc, regex)
+}
+
+pub fn text_near(doc: &Document, anchor: &Regex, regex: &Regex, within_chars: u32) -> Outcome {
+    assertions::evaluate_text_near(doc, anchor, regex, within_chars)
+}
+
+pub fn section_non_empty(doc: &Document, heading: &Regex) -> Outcome {
+    assertions::evaluate_section_non_empty(doc, heading)
+}
+
+pub fn section_min_lines(doc: &Document, heading: &Regex, min_lines: u64) -> Outcome {
+    assertions::evaluate_section_min_lines(doc, heading, min_lines)
+}
+
+pub fn table_exists(doc: &Document, heading: &Regex, index: Option<usize>) -> Outcome {
+    assertions::evaluate_table_exists(doc, heading, index)
+}
+
+pub fn table_columns(
+    doc: &Document,
+    heading: &Regex,
+    index: Option<usize>,
+    columns: &[Regex],
+) -> Outcome {
+    assertions::evaluate_table_columns(doc, heading, index, columns)
+}
+
+pub fn table_shape(
+    doc: &Document,
+    heading: &Regex,
+    index: Option<usize>,
+    min_columns: usize,
+    column_types: &[&str],
+) -> Outcome {
+    assertions::evaluate_table_shape(doc, heading, index, min_columns, column_types)
+}
+
+pub fn table_min_rows(
+    doc: &Document,
+    heading: &Regex,
+    index: Option<usize>,
+    min_rows: u64,
+) -> Outcome {
+    assertions::evaluate_table_min_rows(doc, heading, index, min_rows)
+}
+
+pub fn header_token_search(
+    doc: &Document,
+    page: Option<u32>,
+    index: Option<usize>,
+    tokens: &[Regex],
+    min_matches: u64,
+    max_matches: Option<u64>,
+) -> Outcome {
+    assertions::evaluate_header_token_search(doc, page, index, tokens, min_matches, max_matches)
+}
+
+pub fn dominant_column_count(
+    doc: &Document,
+    count: usize,
+    tolerance: usize,
+    sample_pages: u32,
+) -> Outcome {
+    assertions::evaluate_dominant_column_count(doc, count, tolerance, sample_pages)
+}
+
+pub fn full_width_row(doc: &Document, regex: &Regex, min_cells: usize) -> Outcome {
+    assertions::evaluate_full_width_row(doc, regex, min_cells)
+}
+
+pub fn page_section_count(doc: &Document, min: Option<u64>, max: Option<u64>) -> Outcome {
+    assertions::evaluate_page_section_count(doc, min, max)
+}
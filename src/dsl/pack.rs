@@ -0,0 +1,439 @@
+use crate::dsl::parser::{FingerprintDefinition, parse_sources_str};
+use crate::dsl::resolve::resolve_definitions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// File name of the manifest at the root of every pack.
+pub const PACK_MANIFEST: &str = "pack.yaml";
+
+/// Largest file read from a pack archive: 16 MiB.
+pub const MAX_PACK_FILE_BYTES: u64 = 16 << 20;
+
+/// `pack.yaml`: identity, version and provenance of a fingerprint pack.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PackManifest {
+    pub pack: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<PackProvenance>,
+    /// Definition files relative to the pack root, each holding one or more definitions.
+    pub definitions: Vec<String>,
+}
+
+/// Where a pack came from.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PackProvenance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+/// A loaded pack: its manifest and every definition it lists, in manifest order.
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub definitions: Vec<PackDefinition>,
+    /// Assertion libraries and templates the definitions read, relative to the pack root.
+    pub includes: Vec<String>,
+}
+
+/// One definition from a pack, with the file it was read from.
+#[derive(Debug, Clone)]
+pub struct PackDefinition {
+    pub file: String,
+    /// Template the definition was instantiated from, relative to the pack root.
+    pub template: Option<String>,
+    pub definition: FingerprintDefinition,
+}
+
+/// Read-only view over pack contents, either an unpacked directory or a ZIP archive.
+trait PackSource {
+    fn read_file(&mut self, relative: &str) -> Result<String, String>;
+}
+
+struct DirSource<'a> {
+    root: &'a Path,
+}
+
+impl PackSource for DirSource<'_> {
+    fn read_file(&mut self, relative: &str) -> Result<String, String> {
+        let path = self.root.join(relative);
+        fs::read_to_string(&path)
+            .map_err(|error| format!("failed to read '{}': {error}", path.display()))
+    }
+}
+
+struct ZipSource {
+    archive: zip::ZipArchive<fs::File>,
+    origin: String,
+}
+
+impl PackSource for ZipSource {
+    fn read_file(&mut self, relative: &str) -> Result<String, String> {
+        let mut entry = self.archive.by_name(relative).map_err(|error| {
+            format!(
+                "failed to read '{relative}' from '{}': {error}",
+                self.origin
+            )
+        })?;
+        // Declared entry sizes are not trusted; the read itself is bounded.
+        let mut contents = String::new();
+        (&mut entry)
+            .take(MAX_PACK_FILE_BYTES + 1)
+            .read_to_string(&mut contents)
+            .map_err(|error| {
+                format!(
+                    "failed to read '{relative}' from '{}': {error}",
+                    self.origin
+                )
+            })?;
+        if contents.len() as u64 > MAX_PACK_FILE_BYTES {
+            return Err(format!(
+                "'{relative}' in '{}' exceeds {MAX_PACK_FILE_BYTES} bytes",
+                self.origin
+            ));
+        }
+        Ok(contents)
+    }
+}
+
+/// Load a pack from a directory or a ZIP archive containing `pack.yaml` at its root.
+pub fn load_pack(path: &Path) -> Result<Pack, String> {
+    let mut source = open_source(path)?;
+    load_from_source(source.as_mut(), path)
+}
+
+fn open_source(path: &Path) -> Result<Box<dyn PackSource + '_>, String> {
+    if path.is_dir() {
+        return Ok(Box::new(DirSource { root: path }));
+    }
+
+    let file = fs::File::open(path)
+        .map_err(|error| format!("failed to open pack '{}': {error}", path.display()))?;
+    let archive = zip::ZipArchive::new(file).map_err(|error| {
+        format!(
+            "'{}' is neither a pack directory nor a ZIP archive: {error}",
+            path.display()
+        )
+    })?;
+    Ok(Box::new(ZipSource {
+        archive,
+        origin: path.display().to_string(),
+    }))
+}
+
+fn load_from_source(source: &mut dyn PackSource, path: &Path) -> Result<Pack, String> {
+    let manifest_yaml = source.read_file(PACK_MANIFEST)?;
+    let manifest: PackManifest = serde_yaml::from_str(&manifest_yaml).map_err(|error| {
+        format!(
+            "failed parsing '{}/{PACK_MANIFEST}': {error}",
+            path.display()
+        )
+    })?;
+    validate_manifest(&manifest)?;
+
+    let mut files = Vec::new();
+    for file in &manifest.definitions {
+        files.push((file, source.read_file(file)?));
+    }
+
+    // Included libraries and templates are read through the pack and
+    // installed with it.
+    let mut includes = Vec::new();
+    let mut read_include = |path: &str| {
+        if !is_contained_path(path) {
+            return Err(format!("include '{path}' must stay inside the pack"));
+        }
+        let contents = source.read_file(path)?;
+        if !includes.iter().any(|seen| seen == path) {
+            includes.push(path.to_owned());
+        }
+        Ok(contents)
+    };
+
+    let mut sources = Vec::new();
+    for (file, yaml) in &files {
+        sources.extend(parse_sources_str(yaml, file, &mut read_include)?);
+    }
+
+    let mut seen_ids = HashSet::new();
+    for entry in &sources {
+        if !seen_ids.insert(entry.definition.fingerprint_id.as_str()) {
+            return Err(format!(
+                "pack '{}' defines '{}' more than once",
+                manifest.pack, entry.definition.fingerprint_id
+            ));
+        }
+    }
+
+    let resolved = resolve_definitions(&sources, &mut read_include);
+
+    let mut definitions = Vec::new();
+    for (entry, definition) in sources.iter().zip(resolved) {
+        definitions.push(PackDefinition {
+            file: entry.origin.clone(),
+            template: entry.template.clone(),
+            definition: definition?,
+        });
+    }
+
+    Ok(Pack {
+        manifest,
+        definitions,
+        includes,
+    })
+}
+
+fn is_contained_path(relative: &str) -> bool {
+    !relative.is_empty()
+        && Path::new(relative)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn validate_manifest(manifest: &PackManifest) -> Result<(), String> {
+    let valid_name = !manifest.pack.is_empty()
+        && manifest
+            .pack
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        && !manifest.pack.starts_with('.');
+    if !valid_name {
+        return Err(format!(
+            "pack name '{}' must contain only letters, digits, '-', '_' or '.'",
+            manifest.pack
+        ));
+    }
+    if manifest.version.trim().is_empty() {
+        return Err(format!("pack '{}' is missing a version", manifest.pack));
+    }
+    if manifest.definitions.is_empty() {
+        return Err(format!("pack '{}' lists no definitions", manifest.pack));
+    }
+    for file in &manifest.definitions {
+        if !is_contained_path(file) {
+            return Err(format!(
+                "pack '{}' definition path '{file}' must be relative and stay inside the pack",
+                manifest.pack
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Install a pack into `definitions_dir/<pack>/` as a unit.
+///
+/// Every definition is validated before anything is written; an existing
+/// install of the same pack is moved aside only once the new copy is complete,
+/// swapped out by rename, and deleted after the new copy is in place.
+pub fn install_pack(source: &Path, definitions_dir: &Path) -> Result<Pack, String> {
+    let pack = load_pack(source)?;
+    for entry in &pack.definitions {
+        crate::compile::validate::validate_definition(&entry.definition)
+            .map_err(|error| format!("{}: {error}", entry.file))?;
+    }
+
+    fs::create_dir_all(definitions_dir).map_err(|error| {
+        format!(
+            "failed to create definitions directory '{}': {error}",
+            definitions_dir.display()
+        )
+    })?;
+    let target = definitions_dir.join(&pack.manifest.pack);
+    let staging = definitions_dir.join(format!(".{}.installing", pack.manifest.pack));
+    let replaced = definitions_dir.join(format!(".{}.replaced", pack.manifest.pack));
+    for leftover in [&staging, &replaced] {
+        if leftover.exists() {
+            fs::remove_dir_all(leftover)
+                .map_err(|error| format!("failed to clear '{}': {error}", leftover.display()))?;
+        }
+    }
+
+    let staged = stage_pack(source, &pack, &staging);
+    if let Err(error) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(error);
+    }
+
+    let had_previous = target.exists();
+    if had_previous {
+        fs::rename(&target, &replaced).map_err(|error| {
+            let _ = fs::remove_dir_all(&staging);
+            format!(
+                "failed to replace installed pack '{}': {error}",
+                target.display()
+            )
+        })?;
+    }
+    if let Err(error) = fs::rename(&staging, &target) {
+        if had_previous {
+            let _ = fs::rename(&replaced, &target);
+        }
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!(
+            "failed to install pack '{}': {error}",
+            target.display()
+        ));
+    }
+    if had_previous {
+        let _ = fs::remove_dir_all(&replaced);
+    }
+
+    Ok(pack)
+}
+
+fn stage_pack(source: &Path, pack: &Pack, staging: &Path) -> Result<(), String> {
+    let mut reader = open_source(source)?;
+    let files = std::iter::once(PACK_MANIFEST)
+        .chain(pack.manifest.definitions.iter().map(String::as_str))
+        .chain(pack.includes.iter().map(String::as_str));
+    for relative in files {
+        let contents = reader.read_file(relative)?;
+        let destination: PathBuf = staging.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("failed to create '{}': {error}", parent.display()))?;
+        }
+        fs::write(&destination, contents)
+            .map_err(|error| format!("failed to write '{}': {error}", destination.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const FAMILY_YAML: &str = r#"
+fingerprint_id: family.v1
+format: csv
+include:
+  - ../shared/csv.fpl.yaml
+assertions: []
+---
+fingerprint_id: family-child.v1
+extends: family.v1
+parent: family.v1
+assertions:
+  - sheet_min_rows:
+      sheet: "Sheet1"
+      min_rows: 1
+"#;
+
+    const LIBRARY_YAML: &str = "assertions:\n  - sheet_exists: \"csv\"\n";
+
+    const MANIFEST_YAML: &str = r#"
+pack: cmbs-family
+version: 1.2.0
+provenance:
+  author: data-team
+definitions:
+  - defs/family.fp.yaml
+"#;
+
+    fn write_pack_dir(root: &Path) {
+        fs::create_dir_all(root.join("defs")).expect("create defs dir");
+        fs::write(root.join(PACK_MANIFEST), MANIFEST_YAML).expect("write manifest");
+        fs::write(root.join("defs/family.fp.yaml"), FAMILY_YAML).expect("write definitions");
+        fs::create_dir_all(root.join("shared")).expect("create shared dir");
+        fs::write(root.join("shared/csv.fpl.yaml"), LIBRARY_YAML).expect("write library");
+    }
+
+    #[test]
+    fn loads_pack_directory_with_multi_document_definitions() {
+        let dir = tempdir().expect("create pack dir");
+        write_pack_dir(dir.path());
+
+        let pack = load_pack(dir.path()).expect("load pack");
+        assert_eq!(pack.manifest.pack, "cmbs-family");
+        assert_eq!(pack.manifest.version, "1.2.0");
+        assert_eq!(
+            pack.manifest
+                .provenance
+                .as_ref()
+                .and_then(|provenance| provenance.author.as_deref()),
+            Some("data-team")
+        );
+        let ids: Vec<&str> = pack
+            .definitions
+            .iter()
+            .map(|entry| entry.definition.fingerprint_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["family.v1", "family-child.v1"]);
+        assert_eq!(pack.includes, vec!["shared/csv.fpl.yaml".to_owned()]);
+        assert_eq!(pack.definitions[1].definition.format, "csv");
+        assert_eq!(pack.definitions[1].definition.assertions.len(), 2);
+    }
+
+    #[test]
+    fn installs_zip_archive_as_a_unit() {
+        let work = tempdir().expect("create work dir");
+        let archive_path = work.path().join("cmbs-family.zip");
+        {
+            let mut zip = zip::ZipWriter::new(fs::File::create(&archive_path).expect("create zip"));
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file(PACK_MANIFEST, options)
+                .expect("start manifest");
+            zip.write_all(MANIFEST_YAML.as_bytes())
+                .expect("write manifest");
+            zip.start_file("defs/family.fp.yaml", options)
+                .expect("start definitions");
+            zip.write_all(FAMILY_YAML.as_bytes())
+                .expect("write definitions");
+            zip.start_file("shared/csv.fpl.yaml", options)
+                .expect("start library");
+            zip.write_all(LIBRARY_YAML.as_bytes())
+                .expect("write library");
+            zip.finish().expect("finish zip");
+        }
+        let definitions_dir = work.path().join("definitions");
+
+        let pack = install_pack(&archive_path, &definitions_dir).expect("install pack");
+        assert_eq!(pack.definitions.len(), 2);
+        let installed = definitions_dir.join("cmbs-family");
+        assert!(installed.join(PACK_MANIFEST).is_file());
+        assert!(installed.join("defs/family.fp.yaml").is_file());
+        assert!(installed.join("shared/csv.fpl.yaml").is_file());
+        assert_eq!(
+            load_pack(&installed)
+                .expect("reload installed pack")
+                .manifest,
+            pack.manifest
+        );
+
+        install_pack(&archive_path, &definitions_dir).expect("reinstall pack");
+        let names: Vec<String> = fs::read_dir(&definitions_dir)
+            .expect("list definitions dir")
+            .map(|entry| {
+                entry
+                    .expect("read entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(names, ["cmbs-family"]);
+        assert!(installed.join(PACK_MANIFEST).is_file());
+    }
+
+    #[test]
+    fn rejects_paths_escaping_the_pack() {
+        let dir = tempdir().expect("create pack dir");
+        fs::write(
+            dir.path().join(PACK_MANIFEST),
+            "pack: bad\nversion: 0.1.0\ndefinitions:\n  - ../outside.fp.yaml\n",
+        )
+        .expect("write manifest");
+
+        let error = load_pack(dir.path()).expect_err("escaping path should be rejected");
+        assert!(error.contains("must be relative and stay inside the pack"));
+    }
+}
@@ -1,4 +1,9 @@
 use crate::dsl::assertions::NamedAssertion;
+use crate::dsl::resolve::{
+    DefinitionOrigins, DefinitionSource, IncludeReader, SourceDocument, resolve_definitions,
+    resolve_definitions_with_origins,
+};
+use crate::dsl::template::{TemplateInstances, instantiate};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -40,17 +45,159 @@ pub struct ContentHashConfig {
     pub over: Vec<String>,
 }
 
-/// Parse a `.fp.yaml` file into a fingerprint definition.
+/// A resolved definition and the source document it came from.
+#[derive(Debug, Clone)]
+pub struct ParsedDefinition {
+    pub source: SourceDocument,
+    pub definition: FingerprintDefinition,
+    /// Where each resolved assertion and extract entry was written.
+    pub origins: DefinitionOrigins,
+}
+
+/// Parse a `.fp.yaml` file that holds exactly one fingerprint definition.
 pub fn parse(path: &Path) -> Result<FingerprintDefinition, String> {
+    parse_selected(path, None).map(|parsed| parsed.definition)
+}
+
+/// Parse one definition from a `.fp.yaml` file: the one with `fingerprint_id`
+/// when given, otherwise the file's only definition.
+pub fn parse_selected(
+    path: &Path,
+    fingerprint_id: Option<&str>,
+) -> Result<ParsedDefinition, String> {
+    let mut definitions = parse_file(path)?;
+    if let Some(fingerprint_id) = fingerprint_id {
+        return definitions
+            .into_iter()
+            .find(|parsed| parsed.definition.fingerprint_id == fingerprint_id)
+            .ok_or_else(|| {
+                format!(
+                    "Failed parsing '{}': no fingerprint definition with id '{fingerprint_id}'",
+                    path.display()
+                )
+            });
+    }
+    match definitions.len() {
+        1 => Ok(definitions.remove(0)),
+        0 => Err(format!(
+            "Failed parsing '{}': no fingerprint definition found",
+            path.display()
+        )),
+        count => Err(format!(
+            "Failed parsing '{}': expected one fingerprint definition, found {count}",
+            path.display()
+        )),
+    }
+}
+
+/// Parse every `---`-separated fingerprint definition in a `.fp.yaml` file.
+pub fn parse_all(path: &Path) -> Result<Vec<FingerprintDefinition>, String> {
+    Ok(parse_file(path)?
+        .into_iter()
+        .map(|parsed| parsed.definition)
+        .collect())
+}
+
+/// Parse and resolve every definition in a `.fp.yaml` file, keeping the
+/// source document of each.
+pub fn parse_file(path: &Path) -> Result<Vec<ParsedDefinition>, String> {
     let yaml = fs::read_to_string(path)
         .map_err(|error| format!("Failed reading '{}': {error}", path.display()))?;
-    let mut definition: FingerprintDefinition = serde_yaml::from_str(&yaml)
-        .map_err(|error| format!("Failed parsing '{}': {error}", path.display()))?;
-    auto_name_assertions(&mut definition.assertions);
-    Ok(definition)
+    let origin = path.display().to_string();
+    let mut read_file = |path: &str| {
+        fs::read_to_string(path).map_err(|error| format!("failed reading '{path}': {error}"))
+    };
+    let sources = parse_sources_str(&yaml, &origin, &mut read_file)?;
+    let resolved = resolve_definitions_with_origins(&sources, &mut read_file);
+    sources
+        .into_iter()
+        .zip(resolved)
+        .map(|(source, resolved)| {
+            let (definition, origins) = resolved?;
+            Ok(ParsedDefinition {
+                source,
+                definition,
+                origins,
+            })
+        })
+        .collect()
+}
+
+/// Parse a YAML stream of fingerprint definitions; `origin` is the file path
+/// used in errors and as the base for `include` and template paths.
+///
+/// `extends` resolves against other definitions in the same stream.
+pub fn parse_all_str(yaml: &str, origin: &str) -> Result<Vec<FingerprintDefinition>, String> {
+    let mut read_file = |path: &str| {
+        fs::read_to_string(path).map_err(|error| format!("failed reading '{path}': {error}"))
+    };
+    let sources = parse_sources_str(yaml, origin, &mut read_file)?;
+    resolve_definitions(&sources, &mut read_file)
+        .into_iter()
+        .collect()
+}
+
+/// Parse a YAML stream into definitions as written, without resolving
+/// `extends` or `include`.
+///
+/// Documents with a `template` key are instantiation documents and expand
+/// into one definition per instance, reading the template through
+/// `read_file`. Empty documents (such as a leading or trailing `---`) are
+/// ignored.
+pub fn parse_sources_str(
+    yaml: &str,
+    origin: &str,
+    read_file: &mut IncludeReader<'_>,
+) -> Result<Vec<SourceDocument>, String> {
+    let document_error = |index: usize, error: serde_yaml::Error| {
+        if index == 0 {
+            format!("Failed parsing '{origin}': {error}")
+        } else {
+            format!("Failed parsing '{origin}' document {}: {error}", index + 1)
+        }
+    };
+
+    // Classify documents first so each can then be deserialized into its own
+    // type, keeping line numbers in errors.
+    let mut is_instantiation = Vec::new();
+    for (index, document) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+        let value = Option::<serde_yaml::Value>::deserialize(document)
+            .map_err(|error| document_error(index, error))?;
+        is_instantiation.push(
+            value
+                .as_ref()
+                .and_then(|value| value.get("template"))
+                .is_some(),
+        );
+    }
+
+    let mut sources = Vec::new();
+    let mut non_empty = 0;
+    for (index, document) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+        if is_instantiation[index] {
+            let instances = TemplateInstances::deserialize(document)
+                .map_err(|error| document_error(index, error))?;
+            sources.extend(instantiate(origin, non_empty, &instances, read_file)?);
+            non_empty += 1;
+            continue;
+        }
+
+        let parsed = Option::<DefinitionSource>::deserialize(document)
+            .map_err(|error| document_error(index, error))?;
+        if let Some(definition) = parsed {
+            sources.push(SourceDocument {
+                origin: origin.to_owned(),
+                document: non_empty,
+                template: None,
+                definition,
+            });
+            non_empty += 1;
+        }
+    }
+    Ok(sources)
 }
 
-fn auto_name_assertions(assertions: &mut [NamedAssertion]) {
+pub(crate) fn auto_name_assertions(assertions: &mut [NamedAssertion]) {
     let mut seen: HashMap<String, usize> = HashMap::new();
 
     for assertion in assertions {
@@ -598,6 +745,46 @@ assertions:
         }
     }
 
+    #[test]
+    fn parse_all_reads_multi_document_streams() {
+        let yaml = r#"
+---
+fingerprint_id: family.v1
+format: csv
+assertions:
+  - filename_regex:
+      pattern: "(?i).*\\.csv$"
+---
+fingerprint_id: family-child.v1
+format: csv
+parent: family.v1
+assertions:
+  - sheet_exists: "csv"
+---
+"#;
+        let mut file = NamedTempFile::new().expect("create temp file");
+        std::io::Write::write_all(&mut file, yaml.as_bytes()).expect("write yaml");
+        std::io::Write::flush(&mut file).expect("flush yaml");
+
+        let definitions = parse_all(file.path()).expect("parse multi-document yaml");
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[1].parent.as_deref(), Some("family.v1"));
+        assert_eq!(
+            definitions[1].assertions[0].name.as_deref(),
+            Some("sheet_exists__csv")
+        );
+
+        let error = parse(file.path()).expect_err("single-definition parse should refuse streams");
+        assert!(error.contains("expected one fingerprint definition, found 2"));
+
+        let error = parse_all_str(
+            "fingerprint_id: a\nformat: csv\nassertions: []\n---\nformat: csv\n",
+            "inline",
+        )
+        .expect_err("second document is incomplete");
+        assert!(error.contains("document 2"));
+    }
+
     #[test]
     fn parse_rejects_unknown_html_assertion_keys() {
         let yaml = r#"
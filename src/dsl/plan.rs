@@ -0,0 +1,459 @@
+use crate::dsl::assertions::{
+    Assertion, CellRange, CellRef, parse_cell_ref, parse_column_ref, parse_range_ref,
+    parse_row_range_ref,
+};
+use crate::dsl::parser::{ExtractSection, FingerprintDefinition};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Regexes and spreadsheet references parsed once from a fingerprint definition.
+///
+/// Lookups are keyed by the literal DSL string, so evaluation can reuse the
+/// parsed value for every record and thread without re-parsing.
+#[derive(Debug, Clone, Default)]
+pub struct CompiledPatterns {
+    regexes: HashMap<String, Regex>,
+    cells: HashMap<String, CellRef>,
+    ranges: HashMap<String, CellRange>,
+    columns: HashMap<String, usize>,
+    row_ranges: HashMap<String, (usize, usize)>,
+}
+
+impl CompiledPatterns {
+    /// Resolve a precompiled regex by its source pattern.
+    pub fn regex(&self, pattern: &str) -> Option<&Regex> {
+        self.regexes.get(pattern)
+    }
+
+    pub(crate) fn cell_ref(&self, cell: &str) -> Option<CellRef> {
+        self.cells.get(cell).copied()
+    }
+
+    pub(crate) fn range_ref(&self, range: &str) -> Option<CellRange> {
+        self.ranges.get(range).copied()
+    }
+
+    pub(crate) fn column_ref(&self, column: &str) -> Option<usize> {
+        self.columns.get(column).copied()
+    }
+
+    pub(crate) fn row_range_ref(&self, row_range: &str) -> Option<(usize, usize)> {
+        self.row_ranges.get(row_range).copied()
+    }
+
+    /// Number of distinct regexes held by the plan.
+    pub fn regex_count(&self) -> usize {
+        self.regexes.len()
+    }
+
+    fn add_regex(&mut self, pattern: &str) -> Result<(), String> {
+        if self.regexes.contains_key(pattern) {
+            return Ok(());
+        }
+        let regex =
+            Regex::new(pattern).map_err(|error| format!("invalid regex '{pattern}': {error}"))?;
+        self.regexes.insert(pattern.to_owned(), regex);
+        Ok(())
+    }
+
+    fn add_cell(&mut self, cell: &str) -> Result<(), String> {
+        let parsed = parse_cell_ref(cell)?;
+        self.cells.insert(cell.to_owned(), parsed);
+        Ok(())
+    }
+
+    fn add_range(&mut self, range: &str) -> Result<(), String> {
+        let parsed = parse_range_ref(range)?;
+        self.ranges.insert(range.to_owned(), parsed);
+        Ok(())
+    }
+
+    fn add_column(&mut self, column: &str) -> Result<(), String> {
+        let parsed = parse_column_ref(column)?;
+        self.columns.insert(column.to_owned(), parsed);
+        Ok(())
+    }
+
+    fn add_row_range(&mut self, row_range: &str) -> Result<(), String> {
+        let parsed = parse_row_range_ref(row_range)?;
+        self.row_ranges.insert(row_range.to_owned(), parsed);
+        Ok(())
+    }
+}
+
+/// The plan's compiled regex for `pattern`, or `pattern` compiled now when
+/// there is no plan or it does not hold the pattern.
+pub(crate) fn cached_regex<'a>(
+    plan: Option<&'a CompiledPatterns>,
+    pattern: &str,
+) -> Result<Cow<'a, Regex>, String> {
+    match plan.and_then(|plan| plan.regex(pattern)) {
+        Some(regex) => Ok(Cow::Borrowed(regex)),
+        None => Regex::new(pattern)
+            .map(Cow::Owned)
+            .map_err(|error| format!("invalid regex '{pattern}': {error}")),
+    }
+}
+
+/// A fingerprint definition paired with its precompiled evaluation plan.
+#[derive(Debug, Clone)]
+pub struct CompiledDefinition {
+    definition: FingerprintDefinition,
+    patterns: CompiledPatterns,
+}
+
+impl CompiledDefinition {
+    /// Compile every regex, cell reference and range in a definition.
+    pub fn compile(definition: FingerprintDefinition) -> Result<Self, PlanError> {
+        let patterns = compile_patterns(&definition)?;
+        Ok(Self {
+            definition,
+            patterns,
+        })
+    }
+
+    pub fn definition(&self) -> &FingerprintDefinition {
+        &self.definition
+    }
+
+    pub fn patterns(&self) -> &CompiledPatterns {
+        &self.patterns
+    }
+}
+
+/// A definition entry that could not be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanError {
+    pub fingerprint_id: String,
+    /// Definition section holding the entry: `assertions` or `extract`.
+    pub section: &'static str,
+    /// Zero-based position of the entry within its section.
+    pub index: usize,
+    /// Assertion or extract name.
+    pub name: String,
+    /// Field that failed to compile, e.g. `pattern` or `columns[2].pattern`.
+    pub field: String,
+    pub error: String,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}] '{}' field '{}': {}",
+            self.fingerprint_id, self.section, self.index, self.name, self.field, self.error
+        )
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+/// Compute the deterministic `source_hash` of a fingerprint definition.
+pub fn definition_hash(definition: &FingerprintDefinition) -> Result<String, String> {
+    Ok(source_hash(&canonical_definition(definition)?))
+}
+
+/// Serialize a definition to the canonical YAML that `source_hash` covers.
+pub fn canonical_definition(definition: &FingerprintDefinition) -> Result<String, String> {
+    // Serialize to a canonical form (sorted keys) for consistent hashing
+    serde_yaml::to_string(definition)
+        .map_err(|e| format!("Failed to serialize definition for hashing: {}", e))
+}
+
+/// `source_hash` of canonical definition YAML.
+pub fn source_hash(canonical: &str) -> String {
+    format!("blake3:{}", blake3::hash(canonical.as_bytes()).to_hex())
+}
+
+/// Compile the regexes and spreadsheet references used by a definition.
+pub fn compile_patterns(definition: &FingerprintDefinition) -> Result<CompiledPatterns, PlanError> {
+    let (patterns, errors) = compile_all(definition);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(patterns),
+    }
+}
+
+/// Compile the regexes and ranges used by extract sections alone.
+pub(crate) fn compile_extract_patterns(
+    sections: &[ExtractSection],
+) -> Result<CompiledPatterns, String> {
+    let mut patterns = CompiledPatterns::default();
+    for section in sections {
+        compile_extract(&mut patterns, section).map_err(|(field, error)| {
+            format!("extract '{}' field '{field}': {error}", section.name)
+        })?;
+    }
+    Ok(patterns)
+}
+
+/// Every assertion and extract entry that fails to compile, in definition order.
+pub fn pattern_errors(definition: &FingerprintDefinition) -> Vec<PlanError> {
+    compile_all(definition).1
+}
+
+fn compile_all(definition: &FingerprintDefinition) -> (CompiledPatterns, Vec<PlanError>) {
+    let mut patterns = CompiledPatterns::default();
+    let mut errors = Vec::new();
+
+    for (index, named) in definition.assertions.iter().enumerate() {
+        if let Err((field, error)) = compile_assertion(&mut patterns, &named.assertion) {
+            errors.push(PlanError {
+                fingerprint_id: definition.fingerprint_id.clone(),
+                section: "assertions",
+                index,
+                name: named.name.clone().unwrap_or_default(),
+                field,
+                error,
+            });
+        }
+    }
+
+    for (index, section) in definition.extract.iter().enumerate() {
+        if let Err((field, error)) = compile_extract(&mut patterns, section) {
+            errors.push(PlanError {
+                fingerprint_id: definition.fingerprint_id.clone(),
+                section: "extract",
+                index,
+                name: section.name.clone(),
+                field,
+                error,
+            });
+        }
+    }
+
+    (patterns, errors)
+}
+
+type FieldError = (String, String);
+
+fn field(name: &str) -> impl Fn(String) -> FieldError + '_ {
+    move |error| (name.to_owned(), error)
+}
+
+fn compile_assertion(
+    patterns: &mut CompiledPatterns,
+    assertion: &Assertion,
+) -> Result<(), FieldError> {
+    match assertion {
+        Assertion::FilenameRegex { pattern }
+        | Assertion::SheetNameRegex { pattern, .. }
+        | Assertion::HeadingRegex { pattern }
+        | Assertion::HeadingLevel { pattern, .. }
+        | Assertion::TextRegex { pattern }
+        | Assertion::FullWidthRow { pattern, .. }
+        | Assertion::MetadataRegex { pattern, .. } => {
+            patterns.add_regex(pattern).map_err(field("pattern"))?;
+        }
+        Assertion::CellEq { cell, .. } => {
+            patterns.add_cell(cell).map_err(field("cell"))?;
+        }
+        Assertion::CellRegex { cell, pattern, .. } => {
+            patterns.add_cell(cell).map_err(field("cell"))?;
+            patterns.add_regex(pattern).map_err(field("pattern"))?;
+        }
+        Assertion::RangeNonNull { range, .. } => {
+            patterns.add_range(range).map_err(field("range"))?;
+        }
+        Assertion::ColumnSearch {
+            column,
+            row_range,
+            pattern,
+            ..
+        } => {
+            patterns.add_column(column).map_err(field("column"))?;
+            patterns
+                .add_row_range(row_range)
+                .map_err(field("row_range"))?;
+            patterns.add_regex(pattern).map_err(field("pattern"))?;
+        }
+        Assertion::HeaderRowMatch {
+            row_range, columns, ..
+        } => {
+            patterns
+                .add_row_range(row_range)
+                .map_err(field("row_range"))?;
+            for (index, column) in columns.iter().enumerate() {
+                patterns
+                    .add_regex(&column.pattern)
+                    .map_err(|error| (format!("columns[{index}].pattern"), error))?;
+            }
+        }
+        Assertion::TextNear {
+            anchor, pattern, ..
+        } => {
+            patterns.add_regex(anchor).map_err(field("anchor"))?;
+            patterns.add_regex(pattern).map_err(field("pattern"))?;
+        }
+        Assertion::SectionNonEmpty { heading }
+        | Assertion::SectionMinLines { heading, .. }
+        | Assertion::TableExists { heading, .. }
+        | Assertion::TableShape { heading, .. }
+        | Assertion::TableMinRows { heading, .. } => {
+            patterns.add_regex(heading).map_err(field("heading"))?;
+        }
+        Assertion::TableColumns {
+            heading,
+            patterns: column_patterns,
+            ..
+        } => {
+            patterns.add_regex(heading).map_err(field("heading"))?;
+            for (index, pattern) in column_patterns.iter().enumerate() {
+                patterns
+                    .add_regex(pattern)
+                    .map_err(|error| (format!("patterns[{index}]"), error))?;
+            }
+        }
+        Assertion::HeaderTokenSearch { tokens, .. } => {
+            for (index, token) in tokens.iter().enumerate() {
+                patterns
+                    .add_regex(token)
+                    .map_err(|error| (format!("tokens[{index}]"), error))?;
+            }
+        }
+        Assertion::SheetExists(_)
+        | Assertion::RangePopulated { .. }
+        | Assertion::SheetMinRows { .. }
+        | Assertion::SumEq { .. }
+        | Assertion::WithinTolerance { .. }
+        | Assertion::HeadingExists(_)
+        | Assertion::TextContains(_)
+        | Assertion::DominantColumnCount { .. }
+        | Assertion::PageSectionCount { .. }
+        | Assertion::PageCount { .. } => {}
+    }
+
+    Ok(())
+}
+
+fn compile_extract(
+    patterns: &mut CompiledPatterns,
+    section: &ExtractSection,
+) -> Result<(), FieldError> {
+    match section.r#type.as_str() {
+        "range" => {
+            if let Some(range) = &section.range {
+                patterns.add_range(range).map_err(field("range"))?;
+            }
+        }
+        "section" | "table" => {
+            if let Some(anchor_heading) = &section.anchor_heading {
+                patterns
+                    .add_regex(anchor_heading)
+                    .map_err(field("anchor_heading"))?;
+            }
+        }
+        "text_match" => {
+            if let Some(anchor) = &section.anchor {
+                patterns.add_regex(anchor).map_err(field("anchor"))?;
+            }
+            if let Some(pattern) = &section.pattern {
+                patterns.add_regex(pattern).map_err(field("pattern"))?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::assertions::NamedAssertion;
+
+    fn definition(assertions: Vec<NamedAssertion>) -> FingerprintDefinition {
+        FingerprintDefinition {
+            fingerprint_id: "plan-test.v1".to_owned(),
+            format: "csv".to_owned(),
+            valid_from: None,
+            valid_until: None,
+            parent: None,
+            assertions,
+            extract: vec![],
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn compile_caches_regexes_and_cell_references() {
+        let plan = CompiledDefinition::compile(definition(vec![
+            NamedAssertion {
+                name: Some("name_check".to_owned()),
+                assertion: Assertion::FilenameRegex {
+                    pattern: "(?i)\\.csv$".to_owned(),
+                },
+            },
+            NamedAssertion {
+                name: Some("title".to_owned()),
+                assertion: Assertion::CellRegex {
+                    sheet: "Sheet1".to_owned(),
+                    cell: "B2".to_owned(),
+                    pattern: "(?i)\\.csv$".to_owned(),
+                },
+            },
+        ]))
+        .expect("definition should compile");
+
+        assert_eq!(plan.patterns().regex_count(), 1);
+        assert!(plan.patterns().regex("(?i)\\.csv$").is_some());
+        assert_eq!(plan.patterns().cell_ref("B2"), Some((1, 1)));
+    }
+
+    #[test]
+    fn compile_reports_assertion_name_position_and_field() {
+        let error = CompiledDefinition::compile(definition(vec![
+            NamedAssertion {
+                name: Some("ok".to_owned()),
+                assertion: Assertion::TextContains("hello".to_owned()),
+            },
+            NamedAssertion {
+                name: Some("broken_header".to_owned()),
+                assertion: Assertion::HeaderRowMatch {
+                    sheet: "Sheet1".to_owned(),
+                    row_range: "1:5".to_owned(),
+                    min_match: 1,
+                    columns: vec![
+                        crate::dsl::assertions::ColumnPattern {
+                            pattern: "ok".to_owned(),
+                        },
+                        crate::dsl::assertions::ColumnPattern {
+                            pattern: "(unclosed".to_owned(),
+                        },
+                    ],
+                },
+            },
+        ]))
+        .expect_err("invalid regex should be refused");
+
+        assert_eq!(error.section, "assertions");
+        assert_eq!(error.index, 1);
+        assert_eq!(error.name, "broken_header");
+        assert_eq!(error.field, "columns[1].pattern");
+        assert!(error.error.contains("invalid regex '(unclosed'"));
+    }
+
+    #[test]
+    fn compile_rejects_invalid_extract_range() {
+        let mut def = definition(vec![]);
+        def.extract.push(ExtractSection {
+            name: "totals".to_owned(),
+            r#type: "range".to_owned(),
+            anchor_heading: None,
+            index: None,
+            anchor: None,
+            pattern: None,
+            within_chars: None,
+            sheet: Some("Sheet1".to_owned()),
+            range: Some("A1-B2".to_owned()),
+        });
+
+        let error = CompiledDefinition::compile(def).expect_err("invalid range should be refused");
+        assert_eq!(error.section, "extract");
+        assert_eq!(error.index, 0);
+        assert_eq!(error.name, "totals");
+        assert_eq!(error.field, "range");
+    }
+}
@@ -0,0 +1,516 @@
+use crate::dsl::assertions::NamedAssertion;
+use crate::dsl::parser::{
+    ContentHashConfig, ExtractSection, FingerprintDefinition, auto_name_assertions,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A definition as written, before `extends` and `include` are resolved.
+///
+/// `format` and `assertions` may be omitted when they are inherited.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct DefinitionSource {
+    pub fingerprint_id: String,
+    pub format: Option<String>,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+    pub parent: Option<String>,
+    /// Fingerprint ID whose assertions, extract and content_hash are inherited.
+    pub extends: Option<String>,
+    /// Assertion library files, relative to the file that includes them.
+    #[serde(default)]
+    pub include: Vec<String>,
+    pub assertions: Option<Vec<NamedAssertion>>,
+    pub extract: Option<Vec<ExtractSection>>,
+    pub content_hash: Option<ContentHashConfig>,
+}
+
+/// A parsed definition together with the file it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceDocument {
+    pub origin: String,
+    /// Position of the defining document among the non-empty documents of `origin`.
+    pub document: usize,
+    /// Template this definition was instantiated from, if any.
+    pub template: Option<String>,
+    pub definition: DefinitionSource,
+}
+
+impl SourceDocument {
+    /// Prefix `message` with where this definition came from; template
+    /// instances name both the instance file and the template.
+    pub fn context(&self, message: &str) -> String {
+        let id = &self.definition.fingerprint_id;
+        match &self.template {
+            Some(template) => format!(
+                "'{}' instance '{id}' of template '{template}': {message}",
+                self.origin
+            ),
+            None => format!("'{}': {id}: {message}", self.origin),
+        }
+    }
+
+    /// File that `include` paths in this definition are relative to.
+    fn include_base(&self) -> &str {
+        self.template.as_deref().unwrap_or(&self.origin)
+    }
+}
+
+/// Where a resolved assertion or extract entry was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryOrigin {
+    pub file: String,
+    /// Position of the writing document among the non-empty documents of `file`.
+    pub document: usize,
+    /// Position of the entry within that document's section.
+    pub index: usize,
+}
+
+/// Origins of a resolved definition's entries, parallel to its `assertions`
+/// and `extract`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefinitionOrigins {
+    pub assertions: Vec<EntryOrigin>,
+    pub extract: Vec<EntryOrigin>,
+}
+
+impl DefinitionOrigins {
+    /// Origin of the resolved entry at `index` of `section`.
+    pub fn entry(&self, section: &str, index: usize) -> Option<&EntryOrigin> {
+        match section {
+            "assertions" => self.assertions.get(index),
+            "extract" => self.extract.get(index),
+            _ => None,
+        }
+    }
+}
+
+type Resolved = Result<(FingerprintDefinition, DefinitionOrigins), String>;
+
+/// A shared assertion library referenced through `include`.
+#[derive(Debug, Deserialize)]
+struct AssertionLibrary {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    assertions: Vec<NamedAssertion>,
+}
+
+/// Reads an included library or template by its resolved path.
+pub type IncludeReader<'a> = dyn FnMut(&str) -> Result<String, String> + 'a;
+
+/// Resolve `extends` and `include` across one set of definitions (a file, a
+/// pack, or a definitions directory) into flat definitions.
+///
+/// Results are returned in source order so callers can report failures per
+/// definition. A definition inherits from its `extends` target first; its own
+/// assertions and extract sections then replace inherited ones with the same
+/// name, and anything new is appended.
+pub fn resolve_definitions(
+    sources: &[SourceDocument],
+    read_include: &mut IncludeReader<'_>,
+) -> Vec<Result<FingerprintDefinition, String>> {
+    resolve_definitions_with_origins(sources, read_include)
+        .into_iter()
+        .map(|resolved| resolved.map(|(definition, _)| definition))
+        .collect()
+}
+
+/// [`resolve_definitions`], also returning where each resolved entry was
+/// written so diagnostics can point at the right file.
+pub fn resolve_definitions_with_origins(
+    sources: &[SourceDocument],
+    read_include: &mut IncludeReader<'_>,
+) -> Vec<Resolved> {
+    let mut resolver = Resolver {
+        sources,
+        by_id: HashMap::new(),
+        resolved: vec![None; sources.len()],
+        read_include,
+    };
+    for (index, source) in sources.iter().enumerate() {
+        resolver
+            .by_id
+            .entry(source.definition.fingerprint_id.as_str())
+            .or_insert(index);
+    }
+
+    (0..sources.len())
+        .map(|index| resolver.resolve(index, &mut Vec::new()))
+        .collect()
+}
+
+struct Resolver<'s, 'r, 'a> {
+    sources: &'s [SourceDocument],
+    by_id: HashMap<&'s str, usize>,
+    resolved: Vec<Option<Resolved>>,
+    read_include: &'r mut IncludeReader<'a>,
+}
+
+impl Resolver<'_, '_, '_> {
+    fn resolve(&mut self, index: usize, chain: &mut Vec<usize>) -> Resolved {
+        if let Some(resolved) = &self.resolved[index] {
+            return resolved.clone();
+        }
+
+        let source = &self.sources[index];
+        let id = &source.definition.fingerprint_id;
+        if let Some(position) = chain.iter().position(|entry| *entry == index) {
+            let cycle: Vec<&str> = chain[position..]
+                .iter()
+                .map(|entry| self.sources[*entry].definition.fingerprint_id.as_str())
+                .chain(std::iter::once(id.as_str()))
+                .collect();
+            return Err(format!(
+                "Failed parsing {}",
+                source.context(&format!("extends cycle {}", cycle.join(" -> ")))
+            ));
+        }
+
+        chain.push(index);
+        let result = self.resolve_uncached(index, chain);
+        chain.pop();
+        self.resolved[index] = Some(result.clone());
+        result
+    }
+
+    fn resolve_uncached(&mut self, index: usize, chain: &mut Vec<usize>) -> Resolved {
+        let source = &self.sources[index];
+        let definition = &source.definition;
+        let id = &definition.fingerprint_id;
+        let fail = |message: String| format!("Failed parsing {}", source.context(&message));
+
+        let base = match &definition.extends {
+            Some(target) => {
+                let Some(&target_index) = self.by_id.get(target.as_str()) else {
+                    return Err(fail(format!("extends unknown definition '{target}'")));
+                };
+                Some(self.resolve(target_index, chain)?)
+            }
+            None => None,
+        };
+
+        let mut own_assertions = Vec::new();
+        let mut own_origins = Vec::new();
+        let mut include_chain = vec![source.include_base().to_owned()];
+        for include in &definition.include {
+            load_library(
+                source.include_base(),
+                include,
+                self.read_include,
+                &mut include_chain,
+                &mut own_assertions,
+                &mut own_origins,
+            )
+            .map_err(fail)?;
+        }
+        let own_origin = |index| EntryOrigin {
+            file: source.origin.clone(),
+            document: source.document,
+            index,
+        };
+        for (local, assertion) in definition.assertions.iter().flatten().enumerate() {
+            own_assertions.push(assertion.clone());
+            own_origins.push(own_origin(local));
+        }
+        auto_name_assertions(&mut own_assertions);
+
+        let format = match (&definition.format, &base) {
+            (Some(format), _) => format.clone(),
+            (None, Some(base)) => base.format.clone(),
+            (None, None) => return Err(fail("missing field `format`".to_owned())),
+        };
+        if base.is_none() && definition.assertions.is_none() && definition.include.is_empty() {
+            return Err(fail("missing field `assertions`".to_owned()));
+        }
+
+        let (mut assertions, mut extract, inherited_hash, mut origins) = match base {
+            Some((base, origins)) => (base.assertions, base.extract, base.content_hash, origins),
+            None => (Vec::new(), Vec::new(), None, DefinitionOrigins::default()),
+        };
+        for (assertion, origin) in own_assertions.into_iter().zip(own_origins) {
+            match assertions
+                .iter()
+                .position(|existing| existing.name.is_some() && existing.name == assertion.name)
+            {
+                Some(position) => {
+                    assertions[position] = assertion;
+                    origins.assertions[position] = origin;
+                }
+                None => {
+                    assertions.push(assertion);
+                    origins.assertions.push(origin);
+                }
+            }
+        }
+        for (local, section) in definition.extract.iter().flatten().enumerate() {
+            let origin = own_origin(local);
+            match extract
+                .iter()
+                .position(|existing| existing.name == section.name)
+            {
+                Some(position) => {
+                    extract[position] = section.clone();
+                    origins.extract[position] = origin;
+                }
+                None => {
+                    extract.push(section.clone());
+                    origins.extract.push(origin);
+                }
+            }
+        }
+
+        let resolved = FingerprintDefinition {
+            fingerprint_id: id.clone(),
+            format,
+            valid_from: definition.valid_from.clone(),
+            valid_until: definition.valid_until.clone(),
+            parent: definition.parent.clone(),
+            assertions,
+            extract,
+            content_hash: definition.content_hash.clone().or(inherited_hash),
+        };
+        Ok((resolved, origins))
+    }
+}
+
+/// Append the assertions of `include` (and anything it includes, first) to
+/// `out`, and where each was written to `origins`.
+fn load_library(
+    including_file: &str,
+    include: &str,
+    read_include: &mut IncludeReader<'_>,
+    chain: &mut Vec<String>,
+    out: &mut Vec<NamedAssertion>,
+    origins: &mut Vec<EntryOrigin>,
+) -> Result<(), String> {
+    let path = resolve_include_path(including_file, include);
+    if let Some(position) = chain.iter().position(|entry| *entry == path) {
+        let mut cycle = chain[position..].to_vec();
+        cycle.push(path);
+        return Err(format!("include cycle {}", cycle.join(" -> ")));
+    }
+
+    let yaml = read_include(&path)?;
+    let library: AssertionLibrary = serde_yaml::from_str(&yaml)
+        .map_err(|error| format!("failed parsing include '{path}': {error}"))?;
+
+    chain.push(path.clone());
+    for nested in &library.include {
+        load_library(&path, nested, read_include, chain, out, origins)?;
+    }
+    chain.pop();
+    origins.extend((0..library.assertions.len()).map(|index| EntryOrigin {
+        file: path.clone(),
+        document: 0,
+        index,
+    }));
+    out.extend(library.assertions);
+    Ok(())
+}
+
+/// Join `include` onto the directory of `including_file`, folding `.` and `..`.
+pub fn resolve_include_path(including_file: &str, include: &str) -> String {
+    let joined = Path::new(including_file)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(include);
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let can_pop = matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                );
+                if can_pop {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::parser::parse_sources_str;
+
+    fn resolve_yaml(
+        yaml: &str,
+        files: &[(&str, &str)],
+    ) -> Vec<Result<FingerprintDefinition, String>> {
+        let mut read = |path: &str| {
+            files
+                .iter()
+                .find(|(name, _)| *name == path)
+                .map(|(_, contents)| (*contents).to_owned())
+                .ok_or_else(|| format!("missing include '{path}'"))
+        };
+        let sources =
+            parse_sources_str(yaml, "defs/family.fp.yaml", &mut read).expect("parse sources");
+        resolve_definitions(&sources, &mut read)
+    }
+
+    fn assertion_names(definition: &FingerprintDefinition) -> Vec<&str> {
+        definition
+            .assertions
+            .iter()
+            .filter_map(|assertion| assertion.name.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn extends_inherits_and_overrides_by_name() {
+        let yaml = r#"
+fingerprint_id: child.v1
+extends: parent.v1
+parent: parent.v1
+assertions:
+  - name: layout
+    sheet_min_rows:
+      sheet: "Sheet1"
+      min_rows: 5
+  - name: child_only
+    sheet_exists: "csv"
+extract:
+  - name: body
+    type: range
+    sheet: "Sheet1"
+    range: "A1:B2"
+---
+fingerprint_id: parent.v1
+format: csv
+assertions:
+  - name: extension
+    filename_regex:
+      pattern: "(?i)\\.csv$"
+  - name: layout
+    sheet_min_rows:
+      sheet: "Sheet1"
+      min_rows: 1
+extract:
+  - name: body
+    type: range
+    sheet: "Sheet1"
+    range: "A1:A1"
+content_hash:
+  algorithm: blake3
+  over: [body]
+"#;
+        let resolved = resolve_yaml(yaml, &[]);
+        let child = resolved[0].as_ref().expect("child resolves");
+
+        assert_eq!(child.format, "csv");
+        assert_eq!(child.parent.as_deref(), Some("parent.v1"));
+        assert_eq!(
+            assertion_names(child),
+            vec!["extension", "layout", "child_only"]
+        );
+        assert!(matches!(
+            child.assertions[1].assertion,
+            crate::dsl::assertions::Assertion::SheetMinRows { min_rows: 5, .. }
+        ));
+        assert_eq!(child.extract.len(), 1);
+        assert_eq!(child.extract[0].range.as_deref(), Some("A1:B2"));
+        assert!(child.content_hash.is_some());
+    }
+
+    #[test]
+    fn definition_hash_covers_inherited_content() {
+        let child = "fingerprint_id: child.v1\nextends: base.v1\n";
+        let base_v1 =
+            "fingerprint_id: base.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n";
+        let base_v2 =
+            "fingerprint_id: base.v1\nformat: csv\nassertions:\n  - sheet_exists: \"Sheet1\"\n";
+
+        let hash_with = |base: &str| {
+            let resolved = resolve_yaml(&format!("{child}---\n{base}"), &[]);
+            let child = resolved[0].as_ref().expect("child resolves");
+            crate::dsl::plan::definition_hash(child).expect("hash resolved child")
+        };
+
+        assert_ne!(hash_with(base_v1), hash_with(base_v2));
+    }
+
+    #[test]
+    fn include_expands_nested_libraries_relative_to_the_including_file() {
+        let yaml = r#"
+fingerprint_id: soi.v1
+format: html
+include:
+  - ../shared/headers.fpl.yaml
+assertions:
+  - heading_exists: "Schedule of Investments"
+"#;
+        let files = [
+            (
+                "shared/headers.fpl.yaml",
+                "include:\n  - base.fpl.yaml\nassertions:\n  - name: soi_headers\n    header_token_search:\n      tokens: [\"(?i)cost\"]\n      min_matches: 1\n",
+            ),
+            (
+                "shared/base.fpl.yaml",
+                "assertions:\n  - name: has_tables\n    table_exists:\n      heading: \"(?i)schedule\"\n",
+            ),
+        ];
+
+        let resolved = resolve_yaml(yaml, &files);
+        let definition = resolved[0].as_ref().expect("definition resolves");
+        assert_eq!(
+            assertion_names(definition),
+            vec![
+                "has_tables",
+                "soi_headers",
+                "heading_exists__schedule_of_investme"
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_extends_and_include_cycles() {
+        let yaml = r#"
+fingerprint_id: a.v1
+extends: b.v1
+---
+fingerprint_id: b.v1
+extends: a.v1
+---
+fingerprint_id: c.v1
+format: csv
+include: [loop.fpl.yaml]
+---
+fingerprint_id: d.v1
+extends: missing.v1
+"#;
+        let files = [("defs/loop.fpl.yaml", "include: [loop.fpl.yaml]\n")];
+        let resolved = resolve_yaml(yaml, &files);
+
+        let error = resolved[0].as_ref().expect_err("cycle should fail");
+        assert!(
+            error.contains("extends cycle a.v1 -> b.v1 -> a.v1"),
+            "{error}"
+        );
+        let error = resolved[2].as_ref().expect_err("include cycle should fail");
+        assert!(error.contains("include cycle"), "{error}");
+        let error = resolved[3].as_ref().expect_err("unknown base should fail");
+        assert!(error.contains("extends unknown definition 'missing.v1'"));
+    }
+
+    #[test]
+    fn resolve_include_path_folds_parent_components() {
+        assert_eq!(
+            resolve_include_path("defs/family.fp.yaml", "../shared/a.yaml"),
+            "shared/a.yaml"
+        );
+        assert_eq!(
+            resolve_include_path("family.fp.yaml", "../a.yaml"),
+            "../a.yaml"
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use crate::dsl::resolve::{DefinitionSource, IncludeReader, SourceDocument, resolve_include_path};
+use regex::Regex;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+/// A `.fpt.yaml` template: a definition body with `{{name}}` placeholders and
+/// the typed parameters that fill them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateDefinition {
+    pub parameters: BTreeMap<String, TemplateParameter>,
+    /// Definition fields other than `fingerprint_id`, which each instance supplies.
+    pub definition: Mapping,
+}
+
+/// A declared template parameter.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateParameter {
+    #[serde(rename = "type")]
+    pub kind: ParameterKind,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+/// Value types a template parameter can accept.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterKind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// A string that must compile as a regular expression.
+    Regex,
+}
+
+impl ParameterKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Regex => "regex",
+        }
+    }
+}
+
+/// An instantiation document: the template to use, relative to the file that
+/// holds this document, and the instances to produce from it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateInstances {
+    pub template: String,
+    pub instances: Vec<TemplateInstance>,
+}
+
+/// One concrete definition produced from a template.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateInstance {
+    pub fingerprint_id: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, Value>,
+}
+
+/// Expand an instantiation document found in `origin` into one definition per
+/// instance.
+///
+/// Each returned document keeps `origin` as the instance file and records the
+/// template path, so `include` inside the template resolves next to the
+/// template and errors name both files.
+pub fn instantiate(
+    origin: &str,
+    document: usize,
+    instances: &TemplateInstances,
+    read_file: &mut IncludeReader<'_>,
+) -> Result<Vec<SourceDocument>, String> {
+    let template_path = resolve_include_path(origin, &instances.template);
+    let yaml =
+        read_file(&template_path).map_err(|error| format!("Failed parsing '{origin}': {error}"))?;
+    let template: TemplateDefinition = serde_yaml::from_str(&yaml)
+        .map_err(|error| format!("Failed parsing template '{template_path}': {error}"))?;
+    check_template(&template)
+        .map_err(|error| format!("Failed parsing template '{template_path}': {error}"))?;
+
+    let mut documents = Vec::with_capacity(instances.instances.len());
+    for instance in &instances.instances {
+        let fail = |message: String| {
+            format!(
+                "Failed parsing '{origin}' instance '{}' of template '{template_path}': {message}",
+                instance.fingerprint_id
+            )
+        };
+
+        let bindings = bind_parameters(&template.parameters, &instance.params).map_err(fail)?;
+        let mut body = Value::Mapping(template.definition.clone());
+        substitute(&mut body, &bindings).map_err(fail)?;
+        if let Value::Mapping(body) = &mut body {
+            body.insert(
+                Value::String("fingerprint_id".to_owned()),
+                Value::String(instance.fingerprint_id.clone()),
+            );
+        }
+        let definition: DefinitionSource =
+            serde_yaml::from_value(body).map_err(|error| fail(error.to_string()))?;
+
+        documents.push(SourceDocument {
+            origin: origin.to_owned(),
+            document,
+            template: Some(template_path.clone()),
+            definition,
+        });
+    }
+    Ok(documents)
+}
+
+/// Refuse templates whose body sets `fingerprint_id`, references undeclared
+/// parameters, or declares defaults of the wrong type.
+fn check_template(template: &TemplateDefinition) -> Result<(), String> {
+    if template.definition.contains_key("fingerprint_id") {
+        return Err(
+            "template definition must not set fingerprint_id; each instance provides its own"
+                .to_owned(),
+        );
+    }
+
+    for (name, parameter) in &template.parameters {
+        if let Some(default) = &parameter.default {
+            check_type(name, parameter.kind, default)?;
+        }
+    }
+
+    let mut names = Vec::new();
+    collect_placeholders(&Value::Mapping(template.definition.clone()), &mut names);
+    for name in names {
+        if !template.parameters.contains_key(&name) {
+            return Err(format!(
+                "placeholder '{{{{{name}}}}}' does not name a declared parameter"
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn bind_parameters(
+    parameters: &BTreeMap<String, TemplateParameter>,
+    params: &BTreeMap<String, Value>,
+) -> Result<BTreeMap<String, Value>, String> {
+    if let Some(unknown) = params.keys().find(|name| !parameters.contains_key(*name)) {
+        return Err(format!("unknown parameter '{unknown}'"));
+    }
+
+    let mut bindings = BTreeMap::new();
+    for (name, parameter) in parameters {
+        let value = match (params.get(name), &parameter.default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default,
+            (None, None) => return Err(format!("missing parameter '{name}'")),
+        };
+        check_type(name, parameter.kind, value)?;
+        bindings.insert(name.clone(), value.clone());
+    }
+    Ok(bindings)
+}
+
+fn check_type(name: &str, kind: ParameterKind, value: &Value) -> Result<(), String> {
+    let matches = match kind {
+        ParameterKind::String | ParameterKind::Regex => value.is_string(),
+        ParameterKind::Integer => value.is_i64() || value.is_u64(),
+        ParameterKind::Number => value.is_number(),
+        ParameterKind::Boolean => value.is_bool(),
+    };
+    if !matches {
+        return Err(format!(
+            "parameter '{name}' expects {}, got {}",
+            kind.as_str(),
+            value_kind(value)
+        ));
+    }
+
+    if kind == ParameterKind::Regex
+        && let Some(pattern) = value.as_str()
+    {
+        Regex::new(pattern)
+            .map_err(|error| format!("parameter '{name}' is not a valid regex: {error}"))?;
+    }
+    Ok(())
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "sequence",
+        Value::Mapping(_) => "mapping",
+        Value::Tagged(_) => "tagged value",
+    }
+}
+
+/// Replace placeholders in every string value. A string that is exactly one
+/// placeholder takes the parameter's typed value, so `min_rows: "{{rows}}"`
+/// becomes an integer; placeholders inside longer strings are interpolated.
+fn substitute(value: &mut Value, bindings: &BTreeMap<String, Value>) -> Result<(), String> {
+    match value {
+        Value::String(text) => {
+            *value = substitute_text(text, bindings)?;
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                substitute(item, bindings)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                substitute(item, bindings)?;
+            }
+        }
+        Value::Tagged(tagged) => substitute(&mut tagged.value, bindings)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+fn substitute_text(text: &str, bindings: &BTreeMap<String, Value>) -> Result<Value, String> {
+    let lookup = |name: &str| {
+        bindings
+            .get(name)
+            .ok_or_else(|| format!("placeholder '{{{{{name}}}}}' is not bound"))
+    };
+
+    let pieces = split_placeholders(text);
+    if let [Piece::Placeholder(name)] = pieces.as_slice() {
+        return lookup(name).cloned();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for piece in pieces {
+        match piece {
+            Piece::Literal(literal) => out.push_str(literal),
+            Piece::Placeholder(name) => match lookup(name)? {
+                Value::String(value) => out.push_str(value),
+                Value::Bool(value) => out.push_str(&value.to_string()),
+                Value::Number(value) => out.push_str(&value.to_string()),
+                other => {
+                    return Err(format!(
+                        "parameter '{name}' holds a {} and cannot be interpolated",
+                        value_kind(other)
+                    ));
+                }
+            },
+        }
+    }
+    Ok(Value::String(out))
+}
+
+fn collect_placeholders(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            for piece in split_placeholders(text) {
+                if let Piece::Placeholder(name) = piece {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                collect_placeholders(item, names);
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping {
+                collect_placeholders(item, names);
+            }
+        }
+        Value::Tagged(tagged) => collect_placeholders(&tagged.value, names),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Piece<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split `text` into literal runs and `{{ name }}` placeholders.
+fn split_placeholders(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        if start > 0 {
+            pieces.push(Piece::Literal(&rest[..start]));
+        }
+        pieces.push(Piece::Placeholder(
+            rest[start + 2..start + 2 + length].trim(),
+        ));
+        rest = &rest[start + 4 + length..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Literal(rest));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::assertions::Assertion;
+    use crate::dsl::parser::parse_sources_str;
+    use crate::dsl::resolve::resolve_definitions;
+
+    const TEMPLATE: &str = r#"
+parameters:
+  manager:
+    type: string
+  sheet:
+    type: string
+    default: "Rent Roll"
+  min_rows:
+    type: integer
+    default: 10
+  sponsor:
+    type: regex
+definition:
+  format: xlsx
+  include: [shared.fpl.yaml]
+  assertions:
+    - name: has_sheet
+      sheet_exists: "{{ sheet }}"
+    - sheet_min_rows:
+        sheet: "{{sheet}}"
+        min_rows: "{{min_rows}}"
+    - name: sponsor
+      cell_regex:
+        sheet: "{{sheet}}"
+        cell: "A1"
+        pattern: "^{{sponsor}} Rent Roll$"
+"#;
+
+    const LIBRARY: &str = "assertions:\n  - name: is_workbook\n    sheet_exists: \"Summary\"\n";
+
+    fn expand(instances: &str) -> Result<Vec<SourceDocument>, String> {
+        let mut read = |path: &str| match path {
+            "defs/templates/rent-roll.fpt.yaml" => Ok(TEMPLATE.to_owned()),
+            "defs/templates/shared.fpl.yaml" => Ok(LIBRARY.to_owned()),
+            other => Err(format!("no such file '{other}'")),
+        };
+        parse_sources_str(instances, "defs/rent-rolls.fp.yaml", &mut read)
+    }
+
+    #[test]
+    fn instances_expand_into_distinct_typed_definitions() {
+        let sources = expand(
+            r#"
+template: templates/rent-roll.fpt.yaml
+instances:
+  - fingerprint_id: rent-roll-acme.v1
+    params: { manager: Acme, sponsor: "Acme( Holdings)?" }
+  - fingerprint_id: rent-roll-birch.v1
+    params: { manager: Birch, sponsor: Birch, sheet: RR, min_rows: 3 }
+"#,
+        )
+        .expect("expand instances");
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources[1].template.as_deref(),
+            Some("defs/templates/rent-roll.fpt.yaml")
+        );
+
+        let mut read = |path: &str| match path {
+            "defs/templates/shared.fpl.yaml" => Ok(LIBRARY.to_owned()),
+            other => Err(format!("no such file '{other}'")),
+        };
+        let resolved: Vec<_> = resolve_definitions(&sources, &mut read)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .expect("resolve instances");
+
+        let birch = &resolved[1];
+        assert_eq!(birch.fingerprint_id, "rent-roll-birch.v1");
+        assert_eq!(birch.assertions.len(), 4);
+        assert_eq!(birch.assertions[0].name.as_deref(), Some("is_workbook"));
+        assert!(matches!(
+            &birch.assertions[2].assertion,
+            Assertion::SheetMinRows { sheet, min_rows: 3 } if sheet == "RR"
+        ));
+        assert!(matches!(
+            &resolved[0].assertions[3].assertion,
+            Assertion::CellRegex { pattern, .. } if pattern == "^Acme( Holdings)? Rent Roll$"
+        ));
+    }
+
+    #[test]
+    fn instance_errors_name_the_instance_and_the_template() {
+        let error = expand(
+            "template: templates/rent-roll.fpt.yaml\ninstances:\n  - fingerprint_id: rent-roll-acme.v1\n    params: { manager: Acme, sponsor: Acme, min_rows: many }\n",
+        )
+        .expect_err("wrong parameter type should fail");
+        assert_eq!(
+            error,
+            "Failed parsing 'defs/rent-rolls.fp.yaml' instance 'rent-roll-acme.v1' of template 'defs/templates/rent-roll.fpt.yaml': parameter 'min_rows' expects integer, got string"
+        );
+
+        let error = expand(
+            "template: templates/rent-roll.fpt.yaml\ninstances:\n  - fingerprint_id: rent-roll-acme.v1\n    params: { manager: Acme }\n",
+        )
+        .expect_err("missing parameter should fail");
+        assert!(error.ends_with("missing parameter 'sponsor'"), "{error}");
+
+        let error = expand(
+            "template: templates/rent-roll.fpt.yaml\ninstances:\n  - fingerprint_id: rent-roll-acme.v1\n    params: { manager: Acme, sponsor: \"(\" }\n",
+        )
+        .expect_err("invalid regex parameter should fail");
+        assert!(
+            error.contains("parameter 'sponsor' is not a valid regex"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn template_placeholders_must_name_declared_parameters() {
+        let template: TemplateDefinition = serde_yaml::from_str(
+            "parameters: {}\ndefinition:\n  format: csv\n  assertions:\n    - sheet_exists: \"{{sheet}}\"\n",
+        )
+        .expect("parse template");
+
+        let error = check_template(&template).expect_err("undeclared placeholder should fail");
+        assert_eq!(
+            error,
+            "placeholder '{{sheet}}' does not name a declared parameter"
+        );
+    }
+
+    #[test]
+    fn split_placeholders_keeps_literal_braces() {
+        assert_eq!(
+            split_placeholders("a{2}{{ x }}b"),
+            vec![
+                Piece::Literal("a{2}"),
+                Piece::Placeholder("x"),
+                Piece::Literal("b")
+            ]
+        );
+        assert_eq!(split_placeholders("{{x"), vec![Piece::Literal("{{x")]);
+    }
+}
@@ -1,6 +1,7 @@
 #![forbid(unsafe_code)]
 #![recursion_limit = "512"]
 
+pub mod cache;
 pub mod cli;
 pub mod compile;
 pub mod document;
@@ -20,6 +21,13 @@ pub use registry::{Fingerprint, FingerprintResult};
 
 use clap::{Parser, error::ErrorKind};
 
+/// Run the fingerprint CLI with a set of compiled fingerprints statically
+/// linked in. Used by the `main` of binaries built with `compile --binary`.
+pub fn run_with_compiled(set: registry::compiled::CompiledSet) -> u8 {
+    registry::compiled::install(set);
+    run()
+}
+
 struct DiagnoseModeGuard;
 
 impl DiagnoseModeGuard {
@@ -73,27 +81,75 @@ pub fn run() -> u8 {
             yaml,
             out,
             check,
+            fingerprint_id,
+            binary,
+            crate_name,
             schema,
         }) => {
             if schema {
                 println!("{}", compile::schema::dsl_json_schema());
                 0
-            } else if let Some(yaml_path) = yaml.as_deref() {
-                match handle_compile_command(yaml_path, out.as_deref(), check) {
+            } else if yaml.is_empty() {
+                eprintln!("Error: compile requires a YAML path or --schema");
+                2
+            } else if binary || is_definition_set(&yaml) {
+                if fingerprint_id.is_some() {
+                    eprintln!("Error: --fp selects from one YAML file, not a set of definitions");
+                    return 2;
+                }
+                let mode = match (out.as_deref(), check, binary) {
+                    (_, true, _) => SetCompileMode::Check,
+                    (Some(out_dir), false, true) => SetCompileMode::Binary(out_dir),
+                    (Some(out_dir), false, false) => SetCompileMode::Library(out_dir),
+                    (None, false, _) => {
+                        eprintln!(
+                            "Error: compiling a set of definitions requires --out or --check"
+                        );
+                        return 2;
+                    }
+                };
+                match handle_compile_set_command(&yaml, mode, crate_name.as_deref()) {
+                    Ok(()) => 0,
+                    Err(refusal) => {
+                        output_compile_command_refusal(&refusal);
+                        2
+                    }
+                }
+            } else {
+                match handle_compile_command(
+                    &yaml[0],
+                    fingerprint_id.as_deref(),
+                    out.as_deref(),
+                    check,
+                ) {
                     Ok(()) => 0, // Success
                     Err(refusal) => {
                         output_compile_command_refusal(&refusal);
                         2 // Refusal
                     }
                 }
-            } else {
-                eprintln!("Error: compile requires a YAML path or --schema");
-                2
             }
         }
+        Some(Command::Decompile {
+            fingerprint_id,
+            out,
+        }) => handle_decompile_command(&fingerprint_id, out.as_deref()),
+        Some(Command::Lint { paths }) => handle_lint_command(&paths),
         Some(Command::Witness { action }) => handle_witness_command(action),
-        Some(Command::StructCheck { rules, input }) => {
-            handle_struct_check_command(&rules, input.as_deref())
+        Some(Command::Pack { action }) => handle_pack_command(action),
+        Some(Command::Cache { action }) => handle_cache_command(action),
+        Some(Command::StructCheck {
+            rules,
+            input,
+            dir,
+            follow_symlinks,
+            no_ignore,
+        }) => {
+            let walk_options = struct_check::walk::WalkOptions {
+                follow_symlinks,
+                no_ignore,
+            };
+            handle_struct_check_command(&rules, input.as_deref(), dir.as_deref(), walk_options)
         }
         Some(Command::Infer {
             dir,
@@ -176,7 +232,17 @@ where
 fn is_subcommand_token(arg: &std::ffi::OsStr) -> bool {
     matches!(
         arg.to_str(),
-        Some("compile" | "witness" | "infer" | "infer-schema" | "struct-check")
+        Some(
+            "compile"
+                | "decompile"
+                | "lint"
+                | "witness"
+                | "pack"
+                | "cache"
+                | "infer"
+                | "infer-schema"
+                | "struct-check"
+        )
     )
 }
 
@@ -192,12 +258,6 @@ fn handle_display_mode(mode: DisplayMode) -> u8 {
     }
 }
 
-fn serialize_records_to_jsonl_bytes(records: &[serde_json::Value]) -> Result<Vec<u8>, String> {
-    let mut output = Vec::new();
-    output::jsonl::write_jsonl(&mut output, records)?;
-    Ok(output)
-}
-
 fn serialize_refusal_envelope_bytes<T: serde::Serialize>(refusal: &T) -> Result<Vec<u8>, String> {
     let mut output = serde_json::to_vec(refusal)
         .map_err(|error| format!("failed to serialize refusal envelope: {error}"))?;
@@ -261,13 +321,71 @@ fn describe_run_input(input_path: Option<&std::path::Path>) -> witness::record::
     }
 }
 
+type RunOutputWriter =
+    output::jsonl::HashingJsonlWriter<std::io::BufWriter<Box<dyn std::io::Write>>>;
+
+/// Open run-mode output: stdout, a fresh `--output` file, or the verified
+/// prefix of an interrupted run's output when resuming.
+fn open_run_output(
+    output_path: Option<&std::path::Path>,
+    resume_from: Option<&pipeline::checkpoint::Checkpoint>,
+) -> Result<RunOutputWriter, String> {
+    use output::jsonl::HashingJsonlWriter;
+    use std::io::BufWriter;
+
+    let Some(path) = output_path else {
+        return Ok(HashingJsonlWriter::new(BufWriter::new(Box::new(
+            std::io::stdout(),
+        ))));
+    };
+
+    match resume_from {
+        Some(checkpoint) => {
+            let (file, hasher) = pipeline::checkpoint::reopen_output(path, checkpoint)?;
+            Ok(HashingJsonlWriter::resume(
+                BufWriter::new(Box::new(file)),
+                hasher,
+                checkpoint.output_bytes,
+            ))
+        }
+        None => {
+            let file = std::fs::File::create(path).map_err(|error| {
+                format!("failed to create output '{}': {error}", path.display())
+            })?;
+            Ok(HashingJsonlWriter::new(BufWriter::new(Box::new(file))))
+        }
+    }
+}
+
+/// Flush emitted output and record how far the run has progressed.
+fn save_run_checkpoint(
+    path: &std::path::Path,
+    template: &pipeline::checkpoint::Checkpoint,
+    writer: &mut RunOutputWriter,
+    next_record: u64,
+    partial: bool,
+) -> Result<(), String> {
+    writer.flush()?;
+    pipeline::checkpoint::Checkpoint {
+        next_record,
+        output_bytes: writer.bytes_written(),
+        output_hash: writer.current_hash(),
+        partial,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        ..template.clone()
+    }
+    .save(path)
+}
+
 fn append_run_mode_witness(
     cli: &cli::Cli,
     normalized_jobs: usize,
     outcome: cli::exit::Outcome,
-    output_bytes: &[u8],
+    output_hash: String,
+    classifications: Option<witness::classification::Spool>,
 ) {
     use progress::reporter::report_warning;
+    use witness::classification;
     use witness::ledger::{append, ledger_path};
     use witness::record::WitnessRecord;
 
@@ -283,7 +401,12 @@ fn append_run_mode_witness(
         serde_json::json!({
             "fingerprints": cli.fingerprints,
             "jobs": normalized_jobs,
-            "input": cli.input.as_ref().map(|path| path.display().to_string())
+            "input": cli.input.as_ref().map(|path| path.display().to_string()),
+            "output": cli.output.as_ref().map(|path| path.display().to_string()),
+            "diagnose": cli.diagnose,
+            "max_file_size": cli.max_file_size,
+            "max_decompressed_size": cli.max_decompressed_size,
+            "timeout": cli.timeout
         }),
         match outcome {
             cli::exit::Outcome::AllMatched => "ALL_MATCHED",
@@ -292,13 +415,17 @@ fn append_run_mode_witness(
         }
         .to_owned(),
         outcome.exit_code(),
-        format!("blake3:{}", blake3::hash(output_bytes).to_hex()),
+        output_hash,
         chrono::Utc::now().to_rfc3339(),
     );
 
     match witness_record {
         Ok(record) => {
-            if let Err(error) = append(&ledger_path, &record) {
+            let appended = append(&ledger_path, &record).and_then(|run| match classifications {
+                Some(spool) => classification::append(&ledger_path, &run, spool),
+                None => Ok(()),
+            });
+            if let Err(error) = appended {
                 if cli.progress {
                     report_warning(
                         &ledger_path.display().to_string(),
@@ -322,6 +449,27 @@ fn append_run_mode_witness(
     }
 }
 
+/// Write a run-mode refusal where run output goes: `--output` when set, stdout
+/// otherwise. Under `--resume` the refusal is appended after the existing
+/// output, which a later resume truncates back to the checkpointed prefix.
+fn write_run_mode_refusal(cli: &cli::Cli, bytes: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+
+    let Some(path) = cli.output.as_deref() else {
+        return write_stdout_bytes(bytes);
+    };
+    let mut options = std::fs::OpenOptions::new();
+    if cli.resume {
+        options.create(true).append(true);
+    } else {
+        options.create(true).write(true).truncate(true);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|error| format!("failed to write output '{}': {error}", path.display()))
+}
+
 fn emit_run_mode_refusal(cli: &cli::Cli, refusal: &refusal::codes::RefusalEnvelope) -> u8 {
     let normalized_jobs = normalize_run_jobs(cli.jobs);
     let output_bytes = match serialize_refusal_envelope_bytes(refusal) {
@@ -332,7 +480,7 @@ fn emit_run_mode_refusal(cli: &cli::Cli, refusal: &refusal::codes::RefusalEnvelo
         }
     };
 
-    if let Err(error) = write_stdout_bytes(&output_bytes) {
+    if let Err(error) = write_run_mode_refusal(cli, &output_bytes) {
         eprintln!("Error writing refusal output: {}", error);
         return 2;
     }
@@ -341,7 +489,8 @@ fn emit_run_mode_refusal(cli: &cli::Cli, refusal: &refusal::codes::RefusalEnvelo
         cli,
         normalized_jobs,
         cli::exit::Outcome::Refusal,
-        &output_bytes,
+        format!("blake3:{}", blake3::hash(&output_bytes).to_hex()),
+        None,
     );
     2
 }
@@ -358,108 +507,128 @@ fn output_compile_command_refusal(refusal: &CompileCommandRefusal) {
     }
 }
 
-fn extract_compile_line_number(error: &str) -> u64 {
-    let Some((_, suffix)) = error.rsplit_once(" line ") else {
-        return 1;
+/// Build the refusal for a definition that failed to parse.
+///
+/// The file is re-read so every bad entry is reported with its position, not
+/// just the first error serde_yaml stopped at. Errors the per-entry pass does
+/// not cover, such as unresolved `extends`, fall back to the parser's message.
+fn compile_parse_refusal(
+    yaml_path: &std::path::Path,
+    error: String,
+) -> refusal::codes::CompileRefusalEnvelope {
+    use dsl::diagnostics::{
+        Diagnostic, E_INVALID_YAML, E_MISSING_FIELD, E_UNKNOWN_ASSERTION, parse_diagnostics,
     };
-    let digits: String = suffix
-        .chars()
-        .take_while(|character| character.is_ascii_digit())
-        .collect();
-    digits.parse::<u64>().unwrap_or(1)
-}
-
-fn extract_compile_missing_field(error: &str) -> Option<String> {
-    let marker = "missing field `";
-    let (_, suffix) = error.split_once(marker)?;
-    let (field, _) = suffix.split_once('`')?;
-    Some(field.to_owned())
-}
-
-fn compile_parse_refusal(error: String) -> refusal::codes::CompileRefusalEnvelope {
     use refusal::codes::{BadInputDetail, CompileRefusalCode, build_compile_envelope};
 
-    let line = extract_compile_line_number(&error);
-    if let Some(missing_field) = extract_compile_missing_field(&error) {
-        return build_compile_envelope(
-            CompileRefusalCode::MissingField,
-            "Missing required field in fingerprint definition",
-            BadInputDetail {
-                line,
-                error: Some(error),
-                missing_field: Some(missing_field),
-                version: None,
-            },
-            Some("Add the required field and rerun fingerprint compile".to_owned()),
-        );
+    let file = yaml_path.display().to_string();
+    let mut diagnostics = std::fs::read_to_string(yaml_path)
+        .map(|yaml| parse_diagnostics(&yaml, &file))
+        .unwrap_or_default();
+    if diagnostics.is_empty() {
+        diagnostics.push(Diagnostic {
+            file: Some(file),
+            ..Diagnostic::new(E_INVALID_YAML, error.clone())
+        });
     }
 
-    let code =
-        if error.contains("no variant of enum Assertion") || error.contains("unknown variant") {
-            CompileRefusalCode::UnknownAssertion
-        } else {
-            CompileRefusalCode::InvalidYaml
-        };
-    let message = match code {
-        CompileRefusalCode::UnknownAssertion => "Unsupported assertion in fingerprint definition",
-        CompileRefusalCode::InvalidYaml => "Failed to parse fingerprint definition",
-        CompileRefusalCode::MissingField => unreachable!("handled above"),
-    };
-    let next_command = match code {
-        CompileRefusalCode::UnknownAssertion => {
-            Some("Check supported assertion types above".to_owned())
-        }
-        CompileRefusalCode::InvalidYaml => Some("Check YAML syntax and schema".to_owned()),
-        CompileRefusalCode::MissingField => unreachable!("handled above"),
+    let first = &diagnostics[0];
+    let (code, message, next_command) = match first.code {
+        E_MISSING_FIELD => (
+            CompileRefusalCode::MissingField,
+            "Missing required field in fingerprint definition",
+            "Add the required field and rerun fingerprint compile",
+        ),
+        E_UNKNOWN_ASSERTION => (
+            CompileRefusalCode::UnknownAssertion,
+            "Unsupported assertion in fingerprint definition",
+            "Check supported assertion types above",
+        ),
+        _ => (
+            CompileRefusalCode::InvalidYaml,
+            "Failed to parse fingerprint definition",
+            "Check YAML syntax and schema",
+        ),
     };
+    let missing_field = (code == CompileRefusalCode::MissingField)
+        .then(|| first.field.clone())
+        .flatten();
 
     build_compile_envelope(
         code,
         message,
         BadInputDetail {
-            line,
+            line: first.line.unwrap_or(1),
             error: Some(error),
-            missing_field: None,
+            missing_field,
             version: None,
         },
-        next_command,
+        Some(next_command.to_owned()),
     )
+    .with_diagnostics(&diagnostics)
 }
 
-fn compile_validation_refusal(error: String) -> refusal::codes::CompileRefusalEnvelope {
+fn compile_validation_refusal(
+    diagnostics: &[dsl::diagnostics::Diagnostic],
+    error: String,
+) -> refusal::codes::CompileRefusalEnvelope {
     use refusal::codes::{BadInputDetail, CompileRefusalCode, build_compile_envelope};
 
     build_compile_envelope(
         CompileRefusalCode::InvalidYaml,
         "Fingerprint definition failed validation",
         BadInputDetail {
-            line: 1,
+            line: diagnostics
+                .first()
+                .and_then(|diagnostic| diagnostic.line)
+                .unwrap_or(1),
             error: Some(error),
             missing_field: None,
             version: None,
         },
         Some("Fix the fingerprint definition and rerun fingerprint compile".to_owned()),
     )
+    .with_diagnostics(diagnostics)
 }
 
 /// Handle the compile subcommand.
 #[allow(clippy::result_large_err)]
 fn handle_compile_command(
     yaml_path: &std::path::Path,
+    fingerprint_id: Option<&str>,
     out_dir: Option<&std::path::Path>,
     check: bool,
 ) -> Result<(), CompileCommandRefusal> {
     use compile::crate_gen::generate_crate;
-    use compile::validate::validate_definition;
-    use dsl::parser::parse;
+    use compile::validate::validate_definition_diagnostics;
+    use dsl::diagnostics::locate_resolved;
+    use dsl::parser::parse_selected;
     use refusal::codes::{BadInputDetail, RefusalCode, RefusalDetail, build_envelope};
 
-    let def = parse(yaml_path)
-        .map_err(compile_parse_refusal)
-        .map_err(CompileCommandRefusal::Compile)?;
-    validate_definition(&def)
-        .map_err(compile_validation_refusal)
+    let parsed = parse_selected(yaml_path, fingerprint_id)
+        .map_err(|error| compile_parse_refusal(yaml_path, error))
         .map_err(CompileCommandRefusal::Compile)?;
+    let def = parsed.definition;
+    let mut diagnostics = validate_definition_diagnostics(&def);
+    if let Some(first) = diagnostics.first() {
+        // Instance errors name both the instance file and its template; their
+        // entries live in the template, so no line is reported.
+        let error = match parsed.source.template {
+            Some(_) => parsed.source.context(&first.message),
+            None => first.message.clone(),
+        };
+        if parsed.source.template.is_some() {
+            for diagnostic in &mut diagnostics {
+                diagnostic.file = Some(parsed.source.origin.clone());
+            }
+        } else {
+            locate_resolved(&mut diagnostics, &parsed.source, &parsed.origins);
+        }
+        return Err(CompileCommandRefusal::Compile(compile_validation_refusal(
+            &diagnostics,
+            error,
+        )));
+    }
 
     if check {
         println!("✓ {} is valid", yaml_path.display());
@@ -507,6 +676,123 @@ fn handle_compile_command(
     Ok(())
 }
 
+/// Whether compile inputs form a set compiled into one crate: several
+/// paths, a directory or a pack.
+fn is_definition_set(paths: &[std::path::PathBuf]) -> bool {
+    match paths {
+        [path] => path.is_dir() || compile::bundle::is_pack(path),
+        _ => true,
+    }
+}
+
+/// What `compile` does with a set of definitions.
+enum SetCompileMode<'a> {
+    /// `--check`: validate the set only.
+    Check,
+    /// `--out`: a library crate with `register_all()`.
+    Library(&'a std::path::Path),
+    /// `--binary --out`: a crate building a `fingerprint` binary.
+    Binary(&'a std::path::Path),
+}
+
+/// Handle `compile` over several files, directories or packs: validate every
+/// definition and the set's parent/child relationships, then generate one
+/// crate with a module per fingerprint.
+fn handle_compile_set_command(
+    paths: &[std::path::PathBuf],
+    mode: SetCompileMode<'_>,
+    crate_name: Option<&str>,
+) -> Result<(), CompileCommandRefusal> {
+    use compile::bundle::{
+        check_definition_set, generate_binary_crate, generate_set_crate, load_bundle_input,
+    };
+    use compile::validate::validate_definition_diagnostics;
+    use refusal::codes::{BadInputDetail, RefusalCode, RefusalDetail, build_envelope};
+
+    let bad_input = |message: &str, error: String, next_command: &str| {
+        CompileCommandRefusal::Run(build_envelope(
+            RefusalCode::BadInput,
+            message,
+            RefusalDetail::BadInput(BadInputDetail {
+                line: 0,
+                error: Some(error),
+                missing_field: None,
+                version: None,
+            }),
+            Some(next_command.to_owned()),
+        ))
+    };
+
+    let input = load_bundle_input(paths).map_err(|error| {
+        bad_input(
+            "Failed to load fingerprint definitions",
+            error,
+            "Check definition files and packs",
+        )
+    })?;
+
+    let mut diagnostics = Vec::new();
+    for entry in &input.definitions {
+        for mut diagnostic in validate_definition_diagnostics(&entry.definition) {
+            diagnostic.file = Some(entry.origin.clone());
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics.extend(check_definition_set(&input.definitions));
+    if let Some(first) = diagnostics.first() {
+        let error = format!("{}: {}", entry_label(first), first.message);
+        return Err(CompileCommandRefusal::Compile(compile_validation_refusal(
+            &diagnostics,
+            error,
+        )));
+    }
+
+    let (default_name, version) = input.identity();
+    let crate_name = crate_name.map_or(default_name, str::to_owned);
+    let definitions: Vec<_> = input
+        .definitions
+        .into_iter()
+        .map(|entry| entry.definition)
+        .collect();
+    let (generated, out_dir) = match mode {
+        SetCompileMode::Check => {
+            println!("✓ {} definitions are valid", definitions.len());
+            return Ok(());
+        }
+        SetCompileMode::Library(out_dir) => (
+            generate_set_crate(&definitions, &crate_name, &version, out_dir),
+            out_dir,
+        ),
+        SetCompileMode::Binary(out_dir) => (
+            generate_binary_crate(&definitions, &crate_name, &version, out_dir),
+            out_dir,
+        ),
+    };
+    generated.map_err(|error| {
+        bad_input(
+            "Failed to generate crate",
+            error,
+            "Check output directory permissions",
+        )
+    })?;
+
+    println!(
+        "✓ Generated {crate_name} {version} ({} fingerprints) in {}",
+        definitions.len(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+fn entry_label(diagnostic: &dsl::diagnostics::Diagnostic) -> String {
+    match (&diagnostic.file, &diagnostic.fingerprint_id) {
+        (Some(file), Some(fingerprint_id)) => format!("{file} ({fingerprint_id})"),
+        (Some(file), None) => file.clone(),
+        (None, Some(fingerprint_id)) => fingerprint_id.clone(),
+        (None, None) => "definition".to_owned(),
+    }
+}
+
 /// Handle --describe flag: print compiled operator.json and exit.
 fn handle_describe() -> u8 {
     use std::io::Write;
@@ -552,7 +838,7 @@ fn handle_schema() -> u8 {
 
 /// Handle --list flag: list available fingerprints and exit.
 fn handle_list() -> u8 {
-    match build_registry() {
+    match build_registry(&[]) {
         Ok(registry) => {
             let fingerprints = registry.list();
             for fp in fingerprints {
@@ -567,17 +853,149 @@ fn handle_list() -> u8 {
     }
 }
 
+/// Handle the pack subcommand.
+fn handle_pack_command(action: cli::PackAction) -> u8 {
+    use cli::PackAction;
+    use dsl::pack::install_pack;
+    use registry::installed::definitions_dir;
+
+    match action {
+        PackAction::Install { path } => {
+            let definitions_dir = definitions_dir();
+            match install_pack(&path, &definitions_dir) {
+                Ok(pack) => {
+                    println!(
+                        "installed pack {} {} ({} definitions) to {}",
+                        pack.manifest.pack,
+                        pack.manifest.version,
+                        pack.definitions.len(),
+                        definitions_dir.join(&pack.manifest.pack).display()
+                    );
+                    0
+                }
+                Err(error) => {
+                    eprintln!("Error installing pack: {}", error);
+                    2
+                }
+            }
+        }
+    }
+}
+
+/// Handle the cache subcommand.
+fn handle_cache_command(action: cli::CacheAction) -> u8 {
+    use cache::EvalCache;
+    use cache::store::{InvalidateFilter, default_cache_dir};
+    use cli::CacheAction;
+
+    let open =
+        |dir: Option<std::path::PathBuf>| EvalCache::new(dir.unwrap_or_else(default_cache_dir));
+
+    match action {
+        CacheAction::Inspect { cache_dir, json } => {
+            let cache = open(cache_dir);
+            match cache.inspect() {
+                Ok(stats) => {
+                    if json {
+                        match serde_json::to_string(&stats) {
+                            Ok(stats_json) => println!("{}", stats_json),
+                            Err(error) => {
+                                eprintln!("Error serializing cache stats: {}", error);
+                                return 2;
+                            }
+                        }
+                    } else {
+                        println!("cache_dir: {}", stats.cache_dir);
+                        println!("entries: {}", stats.entries);
+                        println!("bytes: {}", stats.bytes);
+                        println!("unreadable: {}", stats.unreadable);
+                        for (fingerprint_id, count) in &stats.fingerprints {
+                            println!("fingerprint {}: {}", fingerprint_id, count);
+                        }
+                        for (tool_version, count) in &stats.tool_versions {
+                            println!("tool_version {}: {}", tool_version, count);
+                        }
+                        if let (Some(oldest), Some(newest)) = (&stats.oldest, &stats.newest) {
+                            println!("oldest: {}", oldest);
+                            println!("newest: {}", newest);
+                        }
+                    }
+                    0
+                }
+                Err(error) => {
+                    eprintln!("Error inspecting cache: {}", error);
+                    2
+                }
+            }
+        }
+        CacheAction::Prune { cache_dir, before } => {
+            let cache = open(cache_dir);
+            match cache.prune(env!("CARGO_PKG_VERSION"), before.as_deref()) {
+                Ok(removed) => {
+                    println!("removed {} cache entries", removed);
+                    0
+                }
+                Err(error) => {
+                    eprintln!("Error pruning cache: {}", error);
+                    2
+                }
+            }
+        }
+        CacheAction::Invalidate {
+            cache_dir,
+            fingerprint_id,
+            bytes_hash,
+            definition_hash,
+            all,
+        } => {
+            let filter = InvalidateFilter {
+                fingerprint_id,
+                bytes_hash,
+                definition_hash,
+            };
+            if !all && filter == InvalidateFilter::default() {
+                eprintln!(
+                    "Error: cache invalidate requires --fp, --bytes-hash, --definition-hash, or --all"
+                );
+                return 2;
+            }
+
+            let cache = open(cache_dir);
+            match cache.invalidate(&filter) {
+                Ok(removed) => {
+                    println!("removed {} cache entries", removed);
+                    0
+                }
+                Err(error) => {
+                    eprintln!("Error invalidating cache: {}", error);
+                    2
+                }
+            }
+        }
+    }
+}
+
 /// Handle witness subcommands.
 fn handle_witness_command(action: cli::WitnessAction) -> u8 {
     use cli::WitnessAction;
-    use serde_json::json;
+    use serde_json::{Value, json};
     use witness::{ledger::ledger_path, query};
 
     let ledger_path = ledger_path();
 
     match action {
-        WitnessAction::Query { filters, json } => match query::query(&ledger_path, &filters) {
-            Ok(records) => {
+        WitnessAction::Query {
+            filters,
+            fields,
+            json,
+        } => match query::query(&ledger_path, &filters) {
+            Ok(mut records) => {
+                if !fields.is_empty() {
+                    records = records
+                        .iter()
+                        .map(|record| query::project(record, &fields))
+                        .collect();
+                }
                 if json {
                     match serde_json::to_string(&records) {
                         Ok(json_output) => println!("{}", json_output),
@@ -619,8 +1037,15 @@ fn handle_witness_command(action: cli::WitnessAction) -> u8 {
                 2
             }
         },
-        WitnessAction::Last { filters, json } => match query::last(&ledger_path, &filters) {
-            Ok(Some(record)) => {
+        WitnessAction::Last {
+            filters,
+            fields,
+            json,
+        } => match query::last(&ledger_path, &filters) {
+            Ok(Some(mut record)) => {
+                if !fields.is_empty() {
+                    record = query::project(&record, &fields);
+                }
                 match serde_json::to_string(&record) {
                     Ok(record_json) => println!("{}", record_json),
                     Err(error) => {
@@ -650,7 +1075,39 @@ fn handle_witness_command(action: cli::WitnessAction) -> u8 {
                 2
             }
         },
-        WitnessAction::Count { filters, json } => match query::count(&ledger_path, &filters) {
+        WitnessAction::Count {
+            filters,
+            group_by,
+            json,
+        } if !group_by.is_empty() => match query::count_by(&ledger_path, &filters, &group_by) {
+            Ok(groups) => {
+                let total: u64 = groups
+                    .iter()
+                    .filter_map(|group| group["count"].as_u64())
+                    .sum();
+                if json {
+                    println!("{}", json!({ "count": total, "groups": groups }));
+                } else {
+                    for group in &groups {
+                        let row: Vec<String> = group_by
+                            .iter()
+                            .map(|key| match &group[key] {
+                                Value::String(value) => value.clone(),
+                                value => value.to_string(),
+                            })
+                            .chain([group["count"].to_string()])
+                            .collect();
+                        println!("{}", row.join("\t"));
+                    }
+                }
+                if total == 0 { 1 } else { 0 }
+            }
+            Err(error) => {
+                eprintln!("Error querying witness: {}", error);
+                2
+            }
+        },
+        WitnessAction::Count { filters, json, .. } => match query::count(&ledger_path, &filters) {
             Ok(count) => {
                 if json {
                     println!("{}", json!({ "count": count }));
@@ -664,6 +1121,231 @@ fn handle_witness_command(action: cli::WitnessAction) -> u8 {
                 2
             }
         },
+        WitnessAction::Verify { json } => match witness::verify::verify(&ledger_path) {
+            Ok(report) => {
+                if json {
+                    match serde_json::to_string(&report) {
+                        Ok(report_json) => println!("{report_json}"),
+                        Err(error) => {
+                            eprintln!("Error serializing verify report: {error}");
+                            return 2;
+                        }
+                    }
+                } else if let Some(chain_break) = &report.chain_break {
+                    println!(
+                        "Witness ledger broken at '{}' line {} ({}): {}",
+                        chain_break.path.display(),
+                        chain_break.line,
+                        chain_break.id.as_deref().unwrap_or("no id"),
+                        chain_break.reason
+                    );
+                } else {
+                    println!(
+                        "Witness ledger intact: {} records ({} before chaining), head {}",
+                        report.records,
+                        report.legacy,
+                        report.head.as_deref().unwrap_or("none")
+                    );
+                }
+                if report.is_intact() { 0 } else { 1 }
+            }
+            Err(error) => {
+                eprintln!("Error verifying witness: {error}");
+                2
+            }
+        },
+        WitnessAction::Replay { id, expected, json } => {
+            let report = witness::replay::find_record(&ledger_path, &id).and_then(|record| {
+                let binary = std::env::current_exe()
+                    .map_err(|error| format!("failed to locate this executable: {error}"))?;
+                witness::replay::replay(
+                    &record,
+                    &binary,
+                    &current_binary_hash(),
+                    expected.as_deref(),
+                )
+            });
+            match report {
+                Ok(report) => {
+                    if json {
+                        match serde_json::to_string(&report) {
+                            Ok(report_json) => println!("{report_json}"),
+                            Err(error) => {
+                                eprintln!("Error serializing replay report: {error}");
+                                return 2;
+                            }
+                        }
+                    } else {
+                        print_replay_report(&report);
+                    }
+                    if report.matches { 0 } else { 1 }
+                }
+                Err(error) => {
+                    eprintln!("Error replaying witness: {error}");
+                    2
+                }
+            }
+        }
+        WitnessAction::History { bytes_hash, json } => {
+            match witness::classification::history(&ledger_path, &bytes_hash) {
+                Ok(entries) => {
+                    if json {
+                        match serde_json::to_string(&entries) {
+                            Ok(entries_json) => println!("{entries_json}"),
+                            Err(error) => {
+                                eprintln!("Error serializing witness classifications: {error}");
+                                return 2;
+                            }
+                        }
+                    } else if entries.is_empty() {
+                        eprintln!("No witness classifications found for {bytes_hash}");
+                    } else {
+                        for entry in &entries {
+                            let classification = &entry.classification;
+                            println!(
+                                "{}\t{}\t{}\t{}\t{}",
+                                entry.ts,
+                                classification.fingerprint_id.as_deref().unwrap_or("-"),
+                                classification
+                                    .child_fingerprint_id
+                                    .as_deref()
+                                    .or(classification.child_route.as_deref())
+                                    .unwrap_or("-"),
+                                classification.content_hash.as_deref().unwrap_or("-"),
+                                entry.run
+                            );
+                        }
+                    }
+                    if entries.is_empty() { 1 } else { 0 }
+                }
+                Err(error) => {
+                    eprintln!("Error querying witness: {error}");
+                    2
+                }
+            }
+        }
+    }
+}
+
+fn print_replay_report(report: &witness::replay::ReplayReport) {
+    if !report.binary_matches {
+        println!("Note: this binary differs from the one that made the recording");
+    }
+    for input in report.inputs.iter().filter(|input| !input.matches) {
+        if let Some(note) = &input.note {
+            println!("Input not verified: {} ({note})", input.path);
+            continue;
+        }
+        println!(
+            "Input changed: {} (recorded {}, now {})",
+            input.path,
+            input.recorded_hash.as_deref().unwrap_or("no hash"),
+            input.current_hash.as_deref().unwrap_or("unreadable")
+        );
+    }
+    if let Some(replayed) = &report.replayed {
+        if report.matches {
+            println!(
+                "Replayed {}: output hash matches ({})",
+                report.id, replayed.output_hash
+            );
+        } else {
+            println!(
+                "Replayed {}: output hash differs (recorded {}, replayed {}; exit code {} vs {})",
+                report.id,
+                report.recorded.output_hash,
+                replayed.output_hash,
+                report.recorded.exit_code,
+                replayed.exit_code
+            );
+        }
+    }
+    for difference in report.differences.iter().flatten() {
+        if difference.fields.is_empty() {
+            println!("  line {}: {}", difference.line, difference.kind);
+        } else {
+            println!(
+                "  line {}: {} ({})",
+                difference.line,
+                difference.kind,
+                difference.fields.join(", ")
+            );
+        }
+    }
+    if let Some(note) = &report.note {
+        println!("{note}");
+    }
+}
+
+/// Handle the decompile subcommand: print the definition embedded in a
+/// compiled fingerprint, after verifying it against its `source_hash`.
+fn handle_decompile_command(fingerprint_id: &str, out: Option<&std::path::Path>) -> u8 {
+    let registry = match build_registry(&[fingerprint_id.to_owned()]) {
+        Ok(registry) => registry,
+        Err(refusal) => {
+            output_refusal_envelope(&refusal);
+            return 2;
+        }
+    };
+    let Some(fingerprint) = registry.get(fingerprint_id) else {
+        eprintln!("Error: unknown fingerprint '{fingerprint_id}'");
+        return 2;
+    };
+    let yaml = match compile::decompile::embedded_definition(fingerprint) {
+        Ok(yaml) => yaml,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            return 2;
+        }
+    };
+
+    match out {
+        Some(path) => {
+            if let Err(error) = std::fs::write(path, yaml) {
+                eprintln!("Error: failed to write '{}': {error}", path.display());
+                return 2;
+            }
+            0
+        }
+        None => {
+            print!("{yaml}");
+            0
+        }
+    }
+}
+
+/// Handle the lint subcommand: emit `lint.v0` findings as JSONL.
+///
+/// Exits 1 when any finding is an error, 2 when definitions fail to load.
+fn handle_lint_command(paths: &[std::path::PathBuf]) -> u8 {
+    use compile::lint::{Severity, lint_definitions, load_definitions};
+
+    let definitions = match load_definitions(paths) {
+        Ok(definitions) => definitions,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            return 2;
+        }
+    };
+
+    let findings = lint_definitions(&definitions);
+    for finding in &findings {
+        match serde_json::to_string(finding) {
+            Ok(json) => println!("{json}"),
+            Err(error) => {
+                eprintln!("Error: failed to serialize lint finding: {error}");
+                return 2;
+            }
+        }
+    }
+
+    if findings
+        .iter()
+        .any(|finding| finding.severity == Severity::Error)
+    {
+        1
+    } else {
+        0
     }
 }
 
@@ -671,11 +1353,14 @@ fn handle_witness_command(action: cli::WitnessAction) -> u8 {
 fn handle_struct_check_command(
     rules_path: &std::path::Path,
     input_path: Option<&std::path::Path>,
+    dir: Option<&std::path::Path>,
+    walk_options: struct_check::walk::WalkOptions,
 ) -> u8 {
     use std::fs::File;
     use std::io::{self, BufReader};
-    use struct_check::checker::{check_groups, read_vacuum_records};
+    use struct_check::checker::{CheckSummary, check_groups, read_vacuum_records};
     use struct_check::rules::parse_rules_file;
+    use struct_check::walk::walk_directory;
 
     // Load rules file
     let rules_file = match parse_rules_file(rules_path) {
@@ -686,9 +1371,25 @@ fn handle_struct_check_command(
         }
     };
 
-    // Read vacuum.v0 JSONL from stdin or file
-    let groups = match input_path {
-        Some(path) => {
+    // Walk the directory, or read vacuum.v0 or fingerprint.v0 JSONL from
+    // stdin or file
+    let require_fingerprints = rules_file.requires_fingerprints();
+    let groups = match (dir, input_path) {
+        (Some(dir), _) => {
+            if require_fingerprints {
+                eprintln!(
+                    "Error: rules require fingerprint IDs, which --dir cannot provide; pipe fingerprint.v0 records instead"
+                );
+                return 2;
+            }
+            walk_directory(dir, walk_options).map(|(groups, warnings)| {
+                for warning in warnings {
+                    eprintln!("Warning: skipping {warning}");
+                }
+                groups
+            })
+        }
+        (None, Some(path)) => {
             let file = match File::open(path) {
                 Ok(f) => f,
                 Err(error) => {
@@ -700,12 +1401,12 @@ fn handle_struct_check_command(
                 }
             };
             let mut reader = BufReader::new(file);
-            read_vacuum_records(&mut reader)
+            read_vacuum_records(&mut reader, require_fingerprints)
         }
-        None => {
+        (None, None) => {
             let stdin = io::stdin();
             let mut reader = stdin.lock();
-            read_vacuum_records(&mut reader)
+            read_vacuum_records(&mut reader, require_fingerprints)
         }
     };
 
@@ -719,11 +1420,22 @@ fn handle_struct_check_command(
 
     // Check groups against rules
     let version = env!("CARGO_PKG_VERSION");
-    let (records, all_complete) = check_groups(&groups, &rules_file.rules, version);
+    let (records, all_complete) = match check_groups(&groups, &rules_file.rules, version) {
+        Ok(checked) => checked,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            return 2;
+        }
+    };
 
-    // Emit struct-check.v0 JSONL to stdout
-    for record in &records {
-        match serde_json::to_string(record) {
+    // Emit struct-check.v0 JSONL to stdout, then the summary
+    let summary = CheckSummary::new(&rules_file.rules, &records, version);
+    let lines = records
+        .iter()
+        .map(serde_json::to_string)
+        .chain(std::iter::once(serde_json::to_string(&summary)));
+    for line in lines {
+        match line {
             Ok(json) => println!("{json}"),
             Err(error) => {
                 eprintln!("Error: failed to serialize output record: {error}");
@@ -737,11 +1449,16 @@ fn handle_struct_check_command(
 
 /// Handle default run mode (fingerprint processing).
 fn handle_run_mode(cli: cli::Cli) -> u8 {
+    use cache::{EvalCache, store::default_cache_dir};
     use cli::exit::Outcome;
-    use pipeline::enricher::enrich_record_with_fingerprints;
-    use pipeline::parallel::process_parallel_for_each;
+    use pipeline::checkpoint::{CHECKPOINT_FORMAT, CHECKPOINT_INTERVAL, Checkpoint};
+    use pipeline::enricher::enrich_record_with_limits;
+    use pipeline::limits::RecordLimits;
+    use pipeline::parallel::try_process_parallel_for_each;
     use progress::reporter::{ProgressEvent, report_progress};
+    use std::sync::Arc;
     use std::time::Instant;
+    use witness::classification::Classification;
 
     // Validate fingerprint IDs provided
     if cli.fingerprints.is_empty() {
@@ -750,7 +1467,7 @@ fn handle_run_mode(cli: cli::Cli) -> u8 {
     }
 
     // Build registry
-    let registry = match build_registry() {
+    let registry = match build_registry(&cli.fingerprints) {
         Ok(reg) => reg,
         Err(refusal) => return emit_run_mode_refusal(&cli, &refusal),
     };
@@ -773,27 +1490,136 @@ fn handle_run_mode(cli: cli::Cli) -> u8 {
         Err(error) => return emit_run_mode_refusal(&cli, &build_bad_input_refusal(error)),
     };
 
+    let limits = RecordLimits::from_cli(cli.max_file_size, cli.max_decompressed_size, cli.timeout);
+    let use_cache = cli.cache || cli.cache_dir.is_some();
+    let checkpoint_template = match cli.checkpoint.as_ref() {
+        Some(_) => match describe_run_input(cli.input.as_deref()).hash {
+            Some(input_hash) => Some(Checkpoint {
+                format: CHECKPOINT_FORMAT.to_owned(),
+                input_hash,
+                fingerprints: cli.fingerprints.clone(),
+                diagnose: cli.diagnose,
+                max_file_bytes: limits.max_file_bytes,
+                max_decompressed_bytes: limits.max_decompressed_bytes,
+                timeout_secs: limits.timeout.map(|timeout| timeout.as_secs()),
+                cache: use_cache,
+                next_record: 0,
+                output_bytes: 0,
+                output_hash: String::new(),
+                partial: false,
+                updated_at: String::new(),
+            }),
+            None => {
+                eprintln!("Error: --checkpoint requires a readable INPUT file");
+                return 2;
+            }
+        },
+        None => None,
+    };
+    let resume_from = match (cli.resume, cli.checkpoint.as_deref(), &checkpoint_template) {
+        (true, Some(path), Some(template)) => match Checkpoint::load(path)
+            .and_then(|checkpoint| checkpoint.ensure_matches(template).map(|()| checkpoint))
+        {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(error) => {
+                let refusal = build_resume_refusal(error);
+                return emit_run_mode_refusal(&cli, &refusal);
+            }
+        },
+        _ => None,
+    };
+
     let _diagnose_guard = DiagnoseModeGuard::new(cli.diagnose);
     let normalized_jobs = normalize_run_jobs(cli.jobs);
+    let registry = Arc::new(registry);
+    let fingerprint_ids: Arc<[String]> = cli.fingerprints.clone().into();
+    let cache = use_cache.then(|| {
+        Arc::new(EvalCache::new(
+            cli.cache_dir.clone().unwrap_or_else(default_cache_dir),
+        ))
+    });
 
-    // Process records through enrichment pipeline
+    // Process records through enrichment pipeline, streaming output in input order
     let total_records = u64::try_from(records.len()).unwrap_or(u64::MAX);
     let started_at = Instant::now();
-    let mut enriched_records = Vec::with_capacity(records.len());
-    let mut outcome = Outcome::AllMatched;
-    let mut processed_records = 0u64;
+    let mut writer = match open_run_output(cli.output.as_deref(), resume_from.as_ref()) {
+        Ok(writer) => writer,
+        Err(error) if resume_from.is_some() => {
+            return emit_run_mode_refusal(&cli, &build_resume_refusal(error));
+        }
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return 2;
+        }
+    };
+    let mut outcome = if resume_from
+        .as_ref()
+        .is_some_and(|checkpoint| checkpoint.partial)
+    {
+        Outcome::Partial
+    } else {
+        Outcome::AllMatched
+    };
+    let mut processed_records = resume_from
+        .as_ref()
+        .map_or(0, |checkpoint| checkpoint.next_record);
+    let skip_records = usize::try_from(processed_records).unwrap_or(usize::MAX);
+    let checkpoint_target = cli.checkpoint.as_deref().zip(checkpoint_template.as_ref());
+    // With --witness-records, classifications are spooled next to the ledger
+    // and linked to the run's witness record once the run ends. A run that
+    // is interrupted writes no witness record, so the records it processed
+    // are never classified, even after `--resume` finishes the output.
+    let mut classifications = if cli.witness_records && !cli.no_witness {
+        match witness::classification::Spool::create(&witness::ledger::ledger_path()) {
+            Ok(spool) => Some(spool),
+            Err(error) => {
+                eprintln!("Warning: Failed to record witness classifications: {error}");
+                None
+            }
+        }
+    } else {
+        None
+    };
 
-    process_parallel_for_each(
-        records,
+    let streamed = try_process_parallel_for_each(
+        records.into_iter().skip(skip_records),
         normalized_jobs,
-        |record| enrich_record_with_fingerprints(&record, &registry, &cli.fingerprints),
+        |record| {
+            enrich_record_with_limits(
+                &record,
+                &registry,
+                &fingerprint_ids,
+                &limits,
+                cache.as_ref(),
+            )
+        },
         |_index, enriched| {
+            let enriched = enriched?;
             if record_requires_partial_outcome(&enriched) {
                 outcome = Outcome::Partial;
             }
 
-            enriched_records.push(enriched);
+            writer.write_record(&enriched)?;
             processed_records = processed_records.saturating_add(1);
+            if let Some(spool) = classifications.as_mut()
+                && let Some(classification) = Classification::from_output(&enriched)
+                && let Err(error) = spool.push(&classification)
+            {
+                eprintln!("Warning: Failed to record witness classifications: {error}");
+                classifications = None;
+            }
+
+            if let Some((path, template)) = checkpoint_target
+                && processed_records % CHECKPOINT_INTERVAL == 0
+            {
+                save_run_checkpoint(
+                    path,
+                    template,
+                    &mut writer,
+                    processed_records,
+                    outcome == Outcome::Partial,
+                )?;
+            }
 
             if cli.progress {
                 let percent = if total_records == 0 {
@@ -812,23 +1638,29 @@ fn handle_run_mode(cli: cli::Cli) -> u8 {
                     elapsed_ms,
                 });
             }
+            Ok::<(), String>(())
         },
     );
 
-    let output_bytes = match serialize_records_to_jsonl_bytes(&enriched_records) {
-        Ok(bytes) => bytes,
+    let finished = streamed.and_then(|()| match checkpoint_target {
+        Some((path, template)) => save_run_checkpoint(
+            path,
+            template,
+            &mut writer,
+            processed_records,
+            outcome == Outcome::Partial,
+        ),
+        None => Ok(()),
+    });
+    let output_hash = match finished.and_then(|()| writer.finish()) {
+        Ok(hash) => hash,
         Err(error) => {
-            eprintln!("Error serializing output: {}", error);
+            eprintln!("Error: {}", error);
             return 2;
         }
     };
 
-    if let Err(error) = write_stdout_bytes(&output_bytes) {
-        eprintln!("Error writing output: {}", error);
-        return 2;
-    }
-
-    append_run_mode_witness(&cli, normalized_jobs, outcome, &output_bytes);
+    append_run_mode_witness(&cli, normalized_jobs, outcome, output_hash, classifications);
 
     outcome.exit_code()
 }
@@ -884,9 +1716,13 @@ fn load_trust_file(path: &std::path::Path, entries: &mut Vec<String>) {
     entries.extend(config.trust);
 }
 
-/// Build the fingerprint registry with builtin fingerprints.
+/// Build the fingerprint registry with builtin fingerprints. `selected` holds
+/// the fingerprint IDs the caller will run; an installed definition that fails
+/// to compile refuses the build only when it is selected.
 #[allow(clippy::result_large_err)]
-fn build_registry() -> Result<registry::FingerprintRegistry, refusal::codes::RefusalEnvelope> {
+fn build_registry(
+    selected: &[String],
+) -> Result<registry::FingerprintRegistry, refusal::codes::RefusalEnvelope> {
     use refusal::codes::{RefusalCode, RefusalDetail, build_envelope};
     use registry::core::RegistryValidationError;
     use registry::{FingerprintRegistry, builtin::register_builtins};
@@ -907,13 +1743,53 @@ fn build_registry() -> Result<registry::FingerprintRegistry, refusal::codes::Ref
         registry.register_with_info(builtin, info);
     }
 
-    // Discover and register installed fingerprint definitions
-    for (installed, info) in registry::installed::discover_installed() {
+    // Register fingerprints statically linked into this binary
+    for (compiled, info) in registry::compiled::compiled_fingerprints() {
+        registry.register_with_info(compiled, info);
+    }
+
+    // Discover, compile, and register installed fingerprint definitions. A
+    // definition that fails to compile refuses only runs that select it.
+    let (installed, invalid) = registry::installed::discover_installed();
+    for invalid in invalid {
+        if !selected.contains(&invalid.error.fingerprint_id) {
+            eprintln!("Warning: skipping invalid definition {invalid}");
+            continue;
+        }
+        let next_command = format!(
+            "Fix {}[{}] '{}' in {}",
+            invalid.error.section,
+            invalid.error.index,
+            invalid.error.name,
+            invalid.path.display()
+        );
+        return Err(build_envelope(
+            RefusalCode::InvalidDefinition,
+            "Installed fingerprint definition failed to compile",
+            RefusalDetail::InvalidDefinition(refusal::codes::InvalidDefinitionDetail {
+                path: invalid.path.display().to_string(),
+                fingerprint_id: invalid.error.fingerprint_id,
+                section: invalid.error.section.to_owned(),
+                index: invalid.error.index,
+                name: invalid.error.name,
+                field: invalid.error.field,
+                error: invalid.error.error,
+            }),
+            Some(next_command),
+        ));
+    }
+    for (installed, info) in installed {
         registry.register_with_info(installed, info);
     }
 
-    // Validate registry (check for duplicates and trust policy)
+    // Load compiled fingerprint crates from FINGERPRINT_PATH; only trusted
+    // libraries are loaded at all
     let allowlist = load_trust_config();
+    for (plugin, info) in registry::plugin::discover_plugins(&allowlist) {
+        registry.register_with_info(plugin, info);
+    }
+
+    // Validate registry (check for duplicates and trust policy)
     registry
         .validate(&allowlist)
         .map_err(|validation_error| match validation_error {
@@ -1073,6 +1949,22 @@ fn build_bad_input_refusal(error: impl Into<String>) -> refusal::codes::RefusalE
     )
 }
 
+fn build_resume_refusal(error: impl Into<String>) -> refusal::codes::RefusalEnvelope {
+    use refusal::codes::{BadInputDetail, RefusalCode, RefusalDetail, build_envelope};
+
+    build_envelope(
+        RefusalCode::BadInput,
+        "Cannot resume from checkpoint",
+        RefusalDetail::BadInput(BadInputDetail {
+            line: 0,
+            error: Some(error.into()),
+            missing_field: None,
+            version: None,
+        }),
+        Some("Rerun with the checkpointed settings, or without --resume".to_owned()),
+    )
+}
+
 /// Output a refusal envelope to stdout.
 fn output_refusal_envelope<T: serde::Serialize>(refusal: &T) {
     if let Ok(json) = serde_json::to_string(refusal) {
@@ -16,9 +16,75 @@ pub fn write_jsonl(out: &mut dyn Write, records: &[Value]) -> Result<(), String>
     Ok(())
 }
 
+/// Streams JSONL records to an output while hashing every emitted byte.
+///
+/// Run mode writes each record as soon as it is ready instead of buffering the
+/// whole output; the hash matches `blake3` over the equivalent buffered bytes.
+pub struct HashingJsonlWriter<W: Write> {
+    out: W,
+    hasher: blake3::Hasher,
+    bytes_written: u64,
+    line: Vec<u8>,
+}
+
+impl<W: Write> HashingJsonlWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self::resume(out, blake3::Hasher::new(), 0)
+    }
+
+    /// Continue a stream whose first `bytes_written` bytes were already hashed into `hasher`.
+    pub fn resume(out: W, hasher: blake3::Hasher, bytes_written: u64) -> Self {
+        Self {
+            out,
+            hasher,
+            bytes_written,
+            line: Vec::new(),
+        }
+    }
+
+    /// Total bytes emitted, including any resumed prefix.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// `blake3:`-prefixed hash of every byte emitted so far.
+    pub fn current_hash(&self) -> String {
+        format!("blake3:{}", self.hasher.finalize().to_hex())
+    }
+
+    /// Flush buffered output through to the underlying writer.
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.out
+            .flush()
+            .map_err(|error| format!("failed to flush JSONL output: {error}"))
+    }
+
+    /// Serialize one record as a JSONL line and write it through.
+    pub fn write_record(&mut self, record: &Value) -> Result<(), String> {
+        self.line.clear();
+        serde_json::to_writer(&mut self.line, record)
+            .map_err(|error| format!("failed to serialize JSON record: {error}"))?;
+        self.line.push(b'\n');
+        self.out
+            .write_all(&self.line)
+            .map_err(|error| format!("failed to write JSONL output: {error}"))?;
+        self.hasher.update(&self.line);
+        self.bytes_written = self
+            .bytes_written
+            .saturating_add(u64::try_from(self.line.len()).unwrap_or(u64::MAX));
+        Ok(())
+    }
+
+    /// Flush the output and return the `blake3:`-prefixed hash of all written bytes.
+    pub fn finish(mut self) -> Result<String, String> {
+        self.flush()?;
+        Ok(self.current_hash())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::write_jsonl;
+    use super::{HashingJsonlWriter, write_jsonl};
     use serde_json::json;
     use std::io::{Cursor, Error, ErrorKind, Write};
 
@@ -81,4 +147,41 @@ mod tests {
         let error = write_jsonl(&mut writer, &[json!({"path": "a.xlsx"})]).expect_err("fail");
         assert!(error.contains("failed to serialize JSON record"));
     }
+
+    #[test]
+    fn hashing_writer_matches_buffered_output_hash() {
+        let records = vec![json!({"path": "a.xlsx"}), json!({"path": "b.xlsx"})];
+        let mut buffered = Cursor::new(Vec::new());
+        write_jsonl(&mut buffered, &records).expect("write buffered");
+        let buffered = buffered.into_inner();
+
+        let mut streamed = Vec::new();
+        let mut writer = HashingJsonlWriter::new(&mut streamed);
+        for record in &records {
+            writer.write_record(record).expect("write streamed record");
+        }
+        let hash = writer.finish().expect("finish stream");
+
+        assert_eq!(streamed, buffered);
+        assert_eq!(hash, format!("blake3:{}", blake3::hash(&buffered).to_hex()));
+    }
+
+    #[test]
+    fn resumed_hashing_writer_continues_prefix_hash() {
+        let prefix = b"{\"path\":\"a.xlsx\"}\n";
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(prefix);
+
+        let mut rest = Vec::new();
+        let mut writer = HashingJsonlWriter::resume(&mut rest, hasher, prefix.len() as u64);
+        writer
+            .write_record(&json!({"path": "b.xlsx"}))
+            .expect("write resumed record");
+        assert_eq!(writer.bytes_written(), 36);
+        let hash = writer.finish().expect("finish stream");
+
+        let mut full = prefix.to_vec();
+        full.extend_from_slice(&rest);
+        assert_eq!(hash, format!("blake3:{}", blake3::hash(&full).to_hex()));
+    }
 }
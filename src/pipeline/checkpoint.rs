@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// On-disk format tag written into every checkpoint.
+pub const CHECKPOINT_FORMAT: &str = "fingerprint-checkpoint.v0";
+
+/// Number of emitted records between checkpoint writes.
+pub const CHECKPOINT_INTERVAL: u64 = 256;
+
+/// Progress of a run-mode job, recorded so an interrupted run can resume.
+///
+/// `next_record` is the index of the first input record not yet emitted;
+/// `output_bytes` and `output_hash` describe the output prefix written so far.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub format: String,
+    pub input_hash: String,
+    pub fingerprints: Vec<String>,
+    pub diagnose: bool,
+    /// Resource limits in effect; `None` when the guard is disabled.
+    pub max_file_bytes: Option<u64>,
+    pub max_decompressed_bytes: Option<u64>,
+    pub timeout_secs: Option<u64>,
+    /// Whether the run reads and writes the evaluation cache.
+    pub cache: bool,
+    pub next_record: u64,
+    pub output_bytes: u64,
+    pub output_hash: String,
+    pub partial: bool,
+    pub updated_at: String,
+}
+
+impl Checkpoint {
+    /// Load a checkpoint written by a previous run.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read(path)
+            .map_err(|error| format!("failed to read checkpoint '{}': {error}", path.display()))?;
+        let checkpoint: Self = serde_json::from_slice(&contents)
+            .map_err(|error| format!("failed to parse checkpoint '{}': {error}", path.display()))?;
+        if checkpoint.format != CHECKPOINT_FORMAT {
+            return Err(format!(
+                "checkpoint '{}' has unsupported format '{}'",
+                path.display(),
+                checkpoint.format
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Atomically replace the checkpoint file.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = serde_json::to_vec(self)
+            .map_err(|error| format!("failed to serialize checkpoint: {error}"))?;
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = Path::new(&temp_name);
+        fs::write(temp_path, bytes).map_err(|error| {
+            format!(
+                "failed to write checkpoint '{}': {error}",
+                temp_path.display()
+            )
+        })?;
+        fs::rename(temp_path, path)
+            .map_err(|error| format!("failed to commit checkpoint '{}': {error}", path.display()))
+    }
+
+    /// Refuse to resume when the input or run configuration changed.
+    pub fn ensure_matches(&self, expected: &Checkpoint) -> Result<(), String> {
+        if self.input_hash != expected.input_hash {
+            return Err(format!(
+                "checkpoint was recorded for input {} but input is {}",
+                self.input_hash, expected.input_hash
+            ));
+        }
+        if self.fingerprints != expected.fingerprints {
+            return Err(format!(
+                "checkpoint was recorded for fingerprints [{}] but run requests [{}]",
+                self.fingerprints.join(", "),
+                expected.fingerprints.join(", ")
+            ));
+        }
+        if self.diagnose != expected.diagnose {
+            return Err("checkpoint was recorded with a different --diagnose setting".to_owned());
+        }
+        if self.max_file_bytes != expected.max_file_bytes
+            || self.max_decompressed_bytes != expected.max_decompressed_bytes
+            || self.timeout_secs != expected.timeout_secs
+        {
+            return Err(
+                "checkpoint was recorded with different --max-file-size, --max-decompressed-size or --timeout limits"
+                    .to_owned(),
+            );
+        }
+        if self.cache != expected.cache {
+            return Err("checkpoint was recorded with a different --cache setting".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Reopen the output of an interrupted run for appending.
+///
+/// The first `output_bytes` bytes must hash to the checkpoint's `output_hash`;
+/// anything written after the checkpoint is truncated. Returns the file
+/// positioned at the end of the verified prefix and a hasher seeded with it.
+pub fn reopen_output(
+    path: &Path,
+    checkpoint: &Checkpoint,
+) -> Result<(File, blake3::Hasher), String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|error| format!("failed to open output '{}': {error}", path.display()))?;
+
+    let mut hasher = blake3::Hasher::new();
+    let copied = std::io::copy(&mut (&mut file).take(checkpoint.output_bytes), &mut hasher)
+        .map_err(|error| format!("failed to read output '{}': {error}", path.display()))?;
+    if copied != checkpoint.output_bytes {
+        return Err(format!(
+            "output '{}' has {copied} bytes but checkpoint recorded {}",
+            path.display(),
+            checkpoint.output_bytes
+        ));
+    }
+    let prefix_hash = format!("blake3:{}", hasher.finalize().to_hex());
+    if prefix_hash != checkpoint.output_hash {
+        return Err(format!(
+            "output '{}' does not match checkpoint hash {}",
+            path.display(),
+            checkpoint.output_hash
+        ));
+    }
+
+    file.set_len(checkpoint.output_bytes)
+        .map_err(|error| format!("failed to truncate output '{}': {error}", path.display()))?;
+    file.seek(SeekFrom::Start(checkpoint.output_bytes))
+        .map_err(|error| format!("failed to seek output '{}': {error}", path.display()))?;
+    Ok((file, hasher))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn checkpoint_for(prefix: &[u8]) -> Checkpoint {
+        Checkpoint {
+            format: CHECKPOINT_FORMAT.to_owned(),
+            input_hash: "blake3:input".to_owned(),
+            fingerprints: vec!["csv.v0".to_owned()],
+            diagnose: false,
+            max_file_bytes: Some(1 << 30),
+            max_decompressed_bytes: Some(4 << 30),
+            timeout_secs: Some(300),
+            cache: false,
+            next_record: 1,
+            output_bytes: u64::try_from(prefix.len()).expect("prefix length"),
+            output_hash: format!("blake3:{}", blake3::hash(prefix).to_hex()),
+            partial: false,
+            updated_at: "2026-01-01T00:00:00Z".to_owned(),
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempdir().expect("create temp dir");
+        let path = dir.path().join("run.checkpoint");
+        let checkpoint = checkpoint_for(b"{}\n");
+
+        checkpoint.save(&path).expect("save checkpoint");
+        assert_eq!(
+            Checkpoint::load(&path).expect("load checkpoint"),
+            checkpoint
+        );
+        assert!(checkpoint.ensure_matches(&checkpoint).is_ok());
+        for expected in [
+            Checkpoint {
+                input_hash: "blake3:other".to_owned(),
+                ..checkpoint.clone()
+            },
+            Checkpoint {
+                timeout_secs: None,
+                ..checkpoint.clone()
+            },
+            Checkpoint {
+                max_decompressed_bytes: Some(1024),
+                ..checkpoint.clone()
+            },
+            Checkpoint {
+                cache: true,
+                ..checkpoint.clone()
+            },
+        ] {
+            assert!(checkpoint.ensure_matches(&expected).is_err());
+        }
+    }
+
+    #[test]
+    fn reopen_output_truncates_after_verified_prefix() {
+        let dir = tempdir().expect("create temp dir");
+        let path = dir.path().join("out.jsonl");
+        fs::write(&path, b"{\"a\":1}\n{\"b\":").expect("write partial output");
+        let checkpoint = checkpoint_for(b"{\"a\":1}\n");
+
+        let (mut file, mut hasher) = reopen_output(&path, &checkpoint).expect("reopen output");
+        file.write_all(b"{\"b\":2}\n").expect("append record");
+        hasher.update(b"{\"b\":2}\n");
+
+        let contents = fs::read(&path).expect("read output");
+        assert_eq!(contents, b"{\"a\":1}\n{\"b\":2}\n");
+        assert_eq!(hasher.finalize(), blake3::hash(&contents));
+    }
+
+    #[test]
+    fn reopen_output_rejects_modified_prefix() {
+        let dir = tempdir().expect("create temp dir");
+        let path = dir.path().join("out.jsonl");
+        fs::write(&path, b"{\"a\":2}\n").expect("write output");
+
+        let error = reopen_output(&path, &checkpoint_for(b"{\"a\":1}\n"))
+            .expect_err("modified prefix should be rejected");
+        assert!(error.contains("does not match checkpoint hash"));
+    }
+}
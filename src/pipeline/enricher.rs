@@ -1,10 +1,21 @@
-use crate::document::{CsvDocument, Document, dispatch::open_document_with_text_path};
+use crate::cache::{CacheKey, EvalCache};
+use crate::document::{
+    CsvDocument, Document, DocumentKind, dispatch::document_kind_for_extension,
+    dispatch::open_document_with_text_path,
+};
 use crate::dsl::assertions::diagnose_mode;
+use crate::pipeline::limits::{
+    GuardFailure, RecordLimits, SizeViolation, check_decompressed_size, check_file_size,
+    run_guarded,
+};
 use crate::progress::reporter::{report_warning, report_warning_code};
 use crate::refusal::codes::{BadInputDetail, RefusalCode, RefusalDetail, build_envelope};
-use crate::registry::{AssertionResult, FingerprintInfo, FingerprintRegistry, FingerprintResult};
+use crate::registry::{
+    AssertionResult, Fingerprint, FingerprintInfo, FingerprintRegistry, FingerprintResult,
+};
 use serde_json::{Map, Value, json};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Warning structure for `_warnings` array in JSONL records.
 #[derive(Debug, Clone, serde::Serialize)]
@@ -90,6 +101,17 @@ pub fn enrich_record_with_fingerprints(
     record: &Value,
     registry: &FingerprintRegistry,
     fingerprint_ids: &[String],
+) -> Value {
+    enrich_record_with_cache(record, registry, fingerprint_ids, None)
+}
+
+/// Enrich a record, serving fingerprint results from `cache` when every
+/// candidate evaluation is already stored and recording fresh results otherwise.
+fn enrich_record_with_cache(
+    record: &Value,
+    registry: &FingerprintRegistry,
+    fingerprint_ids: &[String],
+    cache: Option<&EvalCache>,
 ) -> Value {
     if !record.is_object() {
         return create_bad_input_refusal(
@@ -142,6 +164,38 @@ pub fn enrich_record_with_fingerprints(
     }
 
     let path = Path::new(path_str);
+    let csv_text_fallback = should_try_csv_text_fallback(&extension, registry, fingerprint_ids);
+    let record_cache =
+        cache.and_then(|cache| RecordCache::for_record(cache, record, path_str, text_path));
+
+    if let Some(record_cache) = record_cache.as_ref() {
+        let mut hits = 0usize;
+        let cached = evaluate_fingerprints(
+            document_kind_for_extension(&extension),
+            csv_text_fallback,
+            registry,
+            fingerprint_ids,
+            &mut |fingerprint, info, kind| {
+                let result = record_cache
+                    .lookup(fingerprint, info, kind)
+                    .ok_or(CacheMiss)?;
+                hits += 1;
+                Ok(result)
+            },
+        );
+        // A record with no cacheable candidates still has to be opened so that
+        // parse failures surface exactly as they would in an uncached run.
+        if let Ok(fingerprint_value) = cached
+            && hits > 0
+        {
+            enriched_obj.insert(
+                "fingerprint".to_owned(),
+                fingerprint_value.unwrap_or(Value::Null),
+            );
+            return Value::Object(enriched_obj.clone());
+        }
+    }
+
     let document = match open_document_with_text_path(path, &extension, text_path) {
         Ok(document) => document,
         Err(error) => {
@@ -160,19 +214,29 @@ pub fn enrich_record_with_fingerprints(
 
     maybe_emit_sparse_text_warning(path_str, &document);
 
-    let fingerprint_value = if should_try_csv_text_fallback(&extension, registry, fingerprint_ids) {
-        let csv_document = Document::Csv(CsvDocument {
+    let csv_document = csv_text_fallback.then(|| {
+        Document::Csv(CsvDocument {
             path: path.to_path_buf(),
-        });
-        evaluate_fingerprints_with_csv_text_fallback(
-            &document,
-            &csv_document,
-            registry,
-            fingerprint_ids,
-        )
-    } else {
-        evaluate_fingerprints(&document, registry, fingerprint_ids)
-    };
+        })
+    });
+    let fingerprint_value = evaluate_fingerprints(
+        document.kind(),
+        csv_text_fallback,
+        registry,
+        fingerprint_ids,
+        &mut |fingerprint, info, kind| {
+            let target = match csv_document.as_ref() {
+                Some(csv_document) if kind == DocumentKind::Csv => csv_document,
+                _ => &document,
+            };
+            let result = fingerprint.fingerprint(target);
+            if let Some(record_cache) = record_cache.as_ref() {
+                record_cache.store(fingerprint, info, kind, &result);
+            }
+            Ok(result)
+        },
+    )
+    .unwrap_or(None);
 
     enriched_obj.insert(
         "fingerprint".to_owned(),
@@ -182,6 +246,128 @@ pub fn enrich_record_with_fingerprints(
     Value::Object(enriched_obj.clone())
 }
 
+/// Enrich a record under per-record resource guards.
+///
+/// Files over the size limits are skipped with `E_TOO_LARGE` before they are
+/// parsed; the file size is checked up front and the decompressed size inside
+/// the guard, since measuring it inflates the archive. Evaluation that
+/// overruns the timeout or panics is skipped with `E_TIMEOUT` or `E_CRASH`
+/// instead of ending the run. Returns an error, which ends the run, once too
+/// many timed-out evaluations are still running.
+pub fn enrich_record_with_limits(
+    record: &Value,
+    registry: &Arc<FingerprintRegistry>,
+    fingerprint_ids: &Arc<[String]>,
+    limits: &RecordLimits,
+    cache: Option<&Arc<EvalCache>>,
+) -> Result<Value, String> {
+    let path_str = record.get("path").and_then(Value::as_str).unwrap_or("");
+    let size_checked = record.is_object() && !is_skipped_record(record) && !path_str.is_empty();
+    if size_checked && let Err(violation) = check_file_size(Path::new(path_str), limits) {
+        return Ok(create_too_large_skip(record, path_str, &violation));
+    }
+
+    let decompressed_check =
+        size_checked.then(|| (PathBuf::from(path_str), extract_extension(record, path_str)));
+    let task_record = record.clone();
+    let task_registry = Arc::clone(registry);
+    let task_ids = Arc::clone(fingerprint_ids);
+    let task_cache = cache.map(Arc::clone);
+    let task_limits = *limits;
+    let outcome = run_guarded(limits.timeout, move || -> Result<Value, SizeViolation> {
+        if let Some((path, extension)) = decompressed_check {
+            check_decompressed_size(&path, &extension, &task_limits)?;
+        }
+        Ok(enrich_record_with_cache(
+            &task_record,
+            &task_registry,
+            &task_ids,
+            task_cache.as_deref(),
+        ))
+    });
+
+    match outcome {
+        Ok(Ok(enriched)) => Ok(enriched),
+        Ok(Err(violation)) => Ok(create_too_large_skip(record, path_str, &violation)),
+        Err(GuardFailure::Timeout(timeout)) => {
+            let timeout_ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+            let message = format!("evaluation exceeded timeout of {timeout_ms} ms");
+            let warning = Warning::new(
+                "E_TIMEOUT",
+                message.clone(),
+                json!({
+                    "path": path_str,
+                    "timeout_ms": timeout_ms,
+                }),
+            );
+            report_warning_code(path_str, Some("E_TIMEOUT"), &format!("skipped: {message}"));
+            Ok(create_guarded_skip(record, warning))
+        }
+        Err(GuardFailure::Crash(error)) => {
+            let warning = Warning::new(
+                "E_CRASH",
+                format!("evaluation crashed: {error}"),
+                json!({
+                    "path": path_str,
+                    "error": error,
+                }),
+            );
+            report_warning_code(
+                path_str,
+                Some("E_CRASH"),
+                &format!("skipped: evaluation crashed: {error}"),
+            );
+            Ok(create_guarded_skip(record, warning))
+        }
+        Err(GuardFailure::WorkersExhausted(abandoned)) => Err(format!(
+            "stopping run at '{path_str}': {abandoned} timed-out evaluations are still running"
+        )),
+    }
+}
+
+fn create_too_large_skip(record: &Value, path_str: &str, violation: &SizeViolation) -> Value {
+    let message = format!(
+        "{} {} bytes exceeds limit of {} bytes",
+        violation.limit.replace('_', " "),
+        violation.size_bytes,
+        violation.limit_bytes
+    );
+    let warning = Warning::new(
+        "E_TOO_LARGE",
+        message.clone(),
+        json!({
+            "path": path_str,
+            "limit": violation.limit,
+            "size_bytes": violation.size_bytes,
+            "limit_bytes": violation.limit_bytes,
+        }),
+    );
+    report_warning_code(
+        path_str,
+        Some("E_TOO_LARGE"),
+        &format!("skipped: {message}"),
+    );
+    create_guarded_skip(record, warning)
+}
+
+fn create_guarded_skip(record: &Value, warning: Warning) -> Value {
+    let mut enriched = record.clone();
+    let Some(enriched_obj) = enriched.as_object_mut() else {
+        return create_bad_input_refusal(
+            0,
+            "Record is not a JSON object",
+            None,
+            Some("record must be a JSON object".to_owned()),
+        );
+    };
+    enriched_obj.insert(
+        "version".to_owned(),
+        Value::String("fingerprint.v0".to_owned()),
+    );
+    update_tool_versions(enriched_obj);
+    create_skipped_record_with_warning(enriched_obj, warning)
+}
+
 fn extract_extension(record: &Value, path_str: &str) -> String {
     if let Some(extension) = record.get("extension").and_then(Value::as_str) {
         return extension
@@ -198,81 +384,20 @@ fn extract_extension(record: &Value, path_str: &str) -> String {
         .to_string()
 }
 
-fn evaluate_fingerprints(
-    document: &Document,
-    registry: &FingerprintRegistry,
-    fingerprint_ids: &[String],
-) -> Option<Value> {
-    let diagnose = diagnose_mode();
-    let mut last_attempt: Option<Value> = None;
-    let mut attempts = Vec::new();
-
-    for (index, fingerprint_id) in fingerprint_ids.iter().enumerate() {
-        let Some(fingerprint_info) = registry.info_for(fingerprint_id) else {
-            continue;
-        };
-        if fingerprint_info.parent.is_some() {
-            continue;
-        }
-
-        let Some(fingerprint) = registry.get(fingerprint_id) else {
-            continue;
-        };
-        if !format_matches(fingerprint.format(), document) {
-            continue;
-        }
-
-        let result = fingerprint.fingerprint(document);
-        if diagnose {
-            attempts.push(build_diagnostic_attempt(fingerprint.id(), &result));
-        }
-        let mut payload =
-            build_fingerprint_payload(fingerprint.id(), Some(fingerprint_info), &result);
-
-        if result.matched {
-            if diagnose {
-                attach_run_diagnostics(
-                    &mut payload,
-                    &attempts,
-                    false,
-                    remaining_root_candidate_ids(document, registry, fingerprint_ids, index + 1),
-                );
-            }
-            let child_evaluation =
-                evaluate_children(document, registry, fingerprint_ids, fingerprint.id());
-            if let Some(parent_payload) = payload.as_object_mut() {
-                if !child_evaluation.children.is_empty() {
-                    parent_payload.insert(
-                        "children".to_owned(),
-                        Value::Array(child_evaluation.children),
-                    );
-                }
-                if let Some(routing) = child_evaluation.routing {
-                    parent_payload.insert(
-                        "child_routing".to_owned(),
-                        serde_json::to_value(routing)
-                            .expect("child routing summary should serialize"),
-                    );
-                }
-            }
-            return Some(payload);
-        }
-        last_attempt = Some(payload);
-    }
-
-    if diagnose && let Some(payload) = last_attempt.as_mut() {
-        attach_run_diagnostics(payload, &attempts, true, Vec::new());
-    }
+/// Marker for an evaluation that the cache could not answer.
+struct CacheMiss;
 
-    last_attempt
-}
+/// Produces the result of one fingerprint against the record's document of the given kind.
+type Evaluator<'a> = dyn FnMut(&dyn Fingerprint, &FingerprintInfo, DocumentKind) -> Result<FingerprintResult, CacheMiss>
+    + 'a;
 
-fn evaluate_fingerprints_with_csv_text_fallback(
-    primary_document: &Document,
-    csv_document: &Document,
+fn evaluate_fingerprints(
+    primary_kind: DocumentKind,
+    csv_text_fallback: bool,
     registry: &FingerprintRegistry,
     fingerprint_ids: &[String],
-) -> Option<Value> {
+    evaluate: &mut Evaluator<'_>,
+) -> Result<Option<Value>, CacheMiss> {
     let diagnose = diagnose_mode();
     let mut last_attempt: Option<Value> = None;
     let mut attempts = Vec::new();
@@ -288,16 +413,12 @@ fn evaluate_fingerprints_with_csv_text_fallback(
         let Some(fingerprint) = registry.get(fingerprint_id) else {
             continue;
         };
-        let document = if fingerprint.format().eq_ignore_ascii_case("csv") {
-            csv_document
-        } else {
-            primary_document
-        };
-        if !format_matches(fingerprint.format(), document) {
+        let kind = candidate_kind(fingerprint.format(), primary_kind, csv_text_fallback);
+        if !format_matches(fingerprint.format(), kind) {
             continue;
         }
 
-        let result = fingerprint.fingerprint(document);
+        let result = evaluate(fingerprint, fingerprint_info, kind)?;
         if diagnose {
             attempts.push(build_diagnostic_attempt(fingerprint.id(), &result));
         }
@@ -310,9 +431,9 @@ fn evaluate_fingerprints_with_csv_text_fallback(
                     &mut payload,
                     &attempts,
                     false,
-                    remaining_root_candidate_ids_with_csv_text_fallback(
-                        primary_document,
-                        csv_document,
+                    remaining_root_candidate_ids(
+                        primary_kind,
+                        csv_text_fallback,
                         registry,
                         fingerprint_ids,
                         index + 1,
@@ -320,7 +441,7 @@ fn evaluate_fingerprints_with_csv_text_fallback(
                 );
             }
             let child_evaluation =
-                evaluate_children(document, registry, fingerprint_ids, fingerprint.id());
+                evaluate_children(kind, registry, fingerprint_ids, fingerprint.id(), evaluate)?;
             if let Some(parent_payload) = payload.as_object_mut() {
                 if !child_evaluation.children.is_empty() {
                     parent_payload.insert(
@@ -336,7 +457,7 @@ fn evaluate_fingerprints_with_csv_text_fallback(
                     );
                 }
             }
-            return Some(payload);
+            return Ok(Some(payload));
         }
         last_attempt = Some(payload);
     }
@@ -345,7 +466,21 @@ fn evaluate_fingerprints_with_csv_text_fallback(
         attach_run_diagnostics(payload, &attempts, true, Vec::new());
     }
 
-    last_attempt
+    Ok(last_attempt)
+}
+
+/// Document kind a fingerprint is evaluated against; csv fingerprints read
+/// delimited text records through the csv view.
+fn candidate_kind(
+    fingerprint_format: &str,
+    primary_kind: DocumentKind,
+    csv_text_fallback: bool,
+) -> DocumentKind {
+    if csv_text_fallback && fingerprint_format.eq_ignore_ascii_case("csv") {
+        DocumentKind::Csv
+    } else {
+        primary_kind
+    }
 }
 
 fn should_try_csv_text_fallback(
@@ -371,39 +506,13 @@ fn should_try_csv_text_fallback(
     })
 }
 
-fn remaining_root_candidate_ids_with_csv_text_fallback(
-    primary_document: &Document,
-    csv_document: &Document,
-    registry: &FingerprintRegistry,
-    fingerprint_ids: &[String],
-    start_index: usize,
-) -> Vec<String> {
-    fingerprint_ids
-        .iter()
-        .skip(start_index)
-        .filter_map(|fingerprint_id| {
-            let info = registry.info_for(fingerprint_id)?;
-            if info.parent.is_some() {
-                return None;
-            }
-
-            let fingerprint = registry.get(fingerprint_id)?;
-            let document = if fingerprint.format().eq_ignore_ascii_case("csv") {
-                csv_document
-            } else {
-                primary_document
-            };
-            format_matches(fingerprint.format(), document).then(|| fingerprint.id().to_owned())
-        })
-        .collect()
-}
-
 fn evaluate_children(
-    document: &Document,
+    kind: DocumentKind,
     registry: &FingerprintRegistry,
     fingerprint_ids: &[String],
     parent_id: &str,
-) -> ChildEvaluation {
+    evaluate: &mut Evaluator<'_>,
+) -> Result<ChildEvaluation, CacheMiss> {
     let mut children = Vec::new();
     let mut matched_child_ids = Vec::new();
 
@@ -418,11 +527,11 @@ fn evaluate_children(
         let Some(child_fingerprint) = registry.get(child_id) else {
             continue;
         };
-        if !format_matches(child_fingerprint.format(), document) {
+        if !format_matches(child_fingerprint.format(), kind) {
             continue;
         }
 
-        let child_result = child_fingerprint.fingerprint(document);
+        let child_result = evaluate(child_fingerprint, child_info, kind)?;
         if child_result.matched {
             matched_child_ids.push(child_fingerprint.id().to_owned());
         }
@@ -449,21 +558,21 @@ fn evaluate_children(
         })
     };
 
-    ChildEvaluation { children, routing }
+    Ok(ChildEvaluation { children, routing })
 }
 
-fn format_matches(fingerprint_format: &str, document: &Document) -> bool {
-    match document {
-        Document::Xlsx(_) => fingerprint_format.eq_ignore_ascii_case("xlsx"),
-        Document::Csv(_) => fingerprint_format.eq_ignore_ascii_case("csv"),
-        Document::Pdf(_) => fingerprint_format.eq_ignore_ascii_case("pdf"),
-        Document::Html(_) => fingerprint_format.eq_ignore_ascii_case("html"),
-        Document::Markdown(_) => {
+fn format_matches(fingerprint_format: &str, kind: DocumentKind) -> bool {
+    match kind {
+        DocumentKind::Xlsx => fingerprint_format.eq_ignore_ascii_case("xlsx"),
+        DocumentKind::Csv => fingerprint_format.eq_ignore_ascii_case("csv"),
+        DocumentKind::Pdf => fingerprint_format.eq_ignore_ascii_case("pdf"),
+        DocumentKind::Html => fingerprint_format.eq_ignore_ascii_case("html"),
+        DocumentKind::Markdown => {
             fingerprint_format.eq_ignore_ascii_case("markdown")
                 || fingerprint_format.eq_ignore_ascii_case("md")
         }
-        Document::Text(_) => fingerprint_format.eq_ignore_ascii_case("text"),
-        Document::Unknown(_) => false,
+        DocumentKind::Text => fingerprint_format.eq_ignore_ascii_case("text"),
+        DocumentKind::Unknown => false,
     }
 }
 
@@ -542,7 +651,8 @@ fn attach_run_diagnostics(
 }
 
 fn remaining_root_candidate_ids(
-    document: &Document,
+    primary_kind: DocumentKind,
+    csv_text_fallback: bool,
     registry: &FingerprintRegistry,
     fingerprint_ids: &[String],
     start_index: usize,
@@ -557,11 +667,113 @@ fn remaining_root_candidate_ids(
             }
 
             let fingerprint = registry.get(fingerprint_id)?;
-            format_matches(fingerprint.format(), document).then(|| fingerprint.id().to_owned())
+            let kind = candidate_kind(fingerprint.format(), primary_kind, csv_text_fallback);
+            format_matches(fingerprint.format(), kind).then(|| fingerprint.id().to_owned())
         })
         .collect()
 }
 
+/// Cache lookups and stores for one manifest record.
+struct RecordCache<'a> {
+    cache: &'a EvalCache,
+    path: &'a str,
+    bytes_hash: &'a str,
+    diagnose: bool,
+    text_path_hash: Option<String>,
+    file_size: u64,
+    modified_ns: Option<u64>,
+}
+
+impl<'a> RecordCache<'a> {
+    /// Returns `None` when the record cannot be keyed, e.g. an unreadable `text_path`
+    /// or a file that cannot be stat'ed; the uncached path then reports the failure.
+    fn for_record(
+        cache: &'a EvalCache,
+        record: &'a Value,
+        path: &'a str,
+        text_path: Option<&Path>,
+    ) -> Option<Self> {
+        let bytes_hash = record.get("bytes_hash").and_then(Value::as_str)?;
+        let metadata = std::fs::metadata(path).ok().filter(|meta| meta.is_file())?;
+        let modified_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|since| u64::try_from(since.as_nanos()).ok());
+        let text_path_hash = match text_path {
+            Some(text_path) => {
+                let bytes = std::fs::read(text_path).ok()?;
+                Some(format!("blake3:{}", blake3::hash(&bytes).to_hex()))
+            }
+            None => None,
+        };
+        Some(Self {
+            cache,
+            path,
+            bytes_hash,
+            diagnose: diagnose_mode(),
+            text_path_hash,
+            file_size: metadata.len(),
+            modified_ns,
+        })
+    }
+
+    /// Builtin fingerprints are pinned by the tool version; DSL fingerprints by
+    /// their definition hash. Compiled and plugin fingerprints also carry their
+    /// provider and compiler version, since a rebuild changes their code
+    /// without changing the definition. Anything else is never cached.
+    fn key(
+        &self,
+        fingerprint: &dyn Fingerprint,
+        info: &FingerprintInfo,
+        kind: DocumentKind,
+    ) -> Option<CacheKey> {
+        let definition_hash = match fingerprint.definition_hash() {
+            Some(hash) if info.source.starts_with("installed:") => hash.to_owned(),
+            Some(hash) => format!("{hash}@{}@{}", info.source, info.version),
+            None if info.source.starts_with("builtin") => {
+                format!("{}@{}", info.source, info.version)
+            }
+            None => return None,
+        };
+        Some(CacheKey {
+            bytes_hash: self.bytes_hash.to_owned(),
+            fingerprint_id: fingerprint.id().to_owned(),
+            definition_hash,
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            document_kind: kind.as_str().to_owned(),
+            diagnose: self.diagnose,
+            text_path_hash: self.text_path_hash.clone(),
+            file_size: self.file_size,
+            modified_ns: self.modified_ns,
+        })
+    }
+
+    fn lookup(
+        &self,
+        fingerprint: &dyn Fingerprint,
+        info: &FingerprintInfo,
+        kind: DocumentKind,
+    ) -> Option<FingerprintResult> {
+        self.cache.get(&self.key(fingerprint, info, kind)?)
+    }
+
+    fn store(
+        &self,
+        fingerprint: &dyn Fingerprint,
+        info: &FingerprintInfo,
+        kind: DocumentKind,
+        result: &FingerprintResult,
+    ) {
+        let Some(key) = self.key(fingerprint, info, kind) else {
+            return;
+        };
+        if let Err(error) = self.cache.put(&key, result) {
+            report_warning(self.path, &format!("cache write failed: {error}"));
+        }
+    }
+}
+
 fn maybe_emit_sparse_text_warning(path: &str, document: &Document) {
     let Document::Pdf(pdf) = document else {
         return;
@@ -674,9 +886,14 @@ fn create_bad_input_refusal(
 
 #[cfg(test)]
 mod tests {
-    use super::{enrich_record, enrich_record_with_fingerprints, sparse_text_warning_message};
-    use crate::document::Document;
+    use super::{
+        RecordCache, enrich_record, enrich_record_with_fingerprints, enrich_record_with_limits,
+        sparse_text_warning_message,
+    };
+    use crate::cache::EvalCache;
+    use crate::document::{Document, DocumentKind};
     use crate::dsl::assertions::set_diagnose_mode;
+    use crate::pipeline::limits::RecordLimits;
     use crate::registry::{
         AssertionResult, Fingerprint, FingerprintInfo, FingerprintRegistry, FingerprintResult,
     };
@@ -815,6 +1032,199 @@ mod tests {
         assert_eq!(output["_warnings"][0]["code"], "E_PARSE");
     }
 
+    #[test]
+    fn oversized_files_are_skipped_with_too_large_warning() {
+        let temp_file = NamedTempFile::with_suffix(".txt").expect("create text temp file");
+        fs::write(temp_file.path(), "x".repeat(64)).expect("write text file");
+        let input = json!({
+            "version": "hash.v0",
+            "path": temp_file.path().display().to_string(),
+            "extension": ".txt",
+            "bytes_hash": "blake3:abc"
+        });
+        let limits = RecordLimits {
+            max_file_bytes: Some(16),
+            ..RecordLimits::unlimited()
+        };
+
+        let output = enrich_record_with_limits(
+            &input,
+            &Arc::new(FingerprintRegistry::new()),
+            &Arc::from(Vec::<String>::new()),
+            &limits,
+            None,
+        )
+        .expect("oversized file is skipped, not fatal");
+        assert_eq!(output["_skipped"], true);
+        assert_eq!(output["version"], "fingerprint.v0");
+        assert_eq!(output["fingerprint"], Value::Null);
+        assert_eq!(output["_warnings"][0]["code"], "E_TOO_LARGE");
+        assert_eq!(output["_warnings"][0]["detail"]["size_bytes"], 64);
+        assert_eq!(output["_warnings"][0]["detail"]["limit_bytes"], 16);
+    }
+
+    #[test]
+    fn oversized_archives_are_measured_under_the_guard() {
+        use std::io::Write;
+
+        let temp_file = NamedTempFile::with_suffix(".xlsx").expect("create xlsx temp file");
+        {
+            let mut zip =
+                zip::ZipWriter::new(fs::File::create(temp_file.path()).expect("open zip"));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("xl/workbook.xml", options)
+                .expect("start zip entry");
+            zip.write_all(&[b'x'; 4096]).expect("write zip entry");
+            zip.finish().expect("finish zip");
+        }
+        let input = json!({
+            "version": "hash.v0",
+            "path": temp_file.path().display().to_string(),
+            "extension": ".xlsx",
+            "bytes_hash": "blake3:abc"
+        });
+        let limits = RecordLimits {
+            max_decompressed_bytes: Some(1024),
+            timeout: Some(std::time::Duration::from_secs(5)),
+            ..RecordLimits::unlimited()
+        };
+
+        let output = enrich_record_with_limits(
+            &input,
+            &Arc::new(FingerprintRegistry::new()),
+            &Arc::from(Vec::<String>::new()),
+            &limits,
+            None,
+        )
+        .expect("oversized archive is skipped, not fatal");
+        assert_eq!(output["_skipped"], true);
+        assert_eq!(output["fingerprint"], Value::Null);
+        assert_eq!(output["_warnings"][0]["code"], "E_TOO_LARGE");
+        assert_eq!(
+            output["_warnings"][0]["detail"]["limit"],
+            "decompressed_size"
+        );
+        assert_eq!(output["_warnings"][0]["detail"]["size_bytes"], 1025);
+        assert_eq!(output["_warnings"][0]["detail"]["limit_bytes"], 1024);
+    }
+
+    #[test]
+    fn cache_keys_pin_native_fingerprints_to_provider_and_compiler_version() {
+        struct DefinedFingerprint;
+
+        impl Fingerprint for DefinedFingerprint {
+            fn id(&self) -> &str {
+                "defined.v1"
+            }
+
+            fn format(&self) -> &str {
+                "csv"
+            }
+
+            fn definition_hash(&self) -> Option<&str> {
+                Some("blake3:def")
+            }
+
+            fn fingerprint(&self, _doc: &Document) -> FingerprintResult {
+                unreachable!("keys are built without evaluating")
+            }
+        }
+
+        let cache_dir = tempfile::tempdir().expect("create cache dir");
+        let cache = EvalCache::new(cache_dir.path());
+        let record_cache = RecordCache {
+            cache: &cache,
+            path: "tape.csv",
+            bytes_hash: "blake3:abc",
+            diagnose: false,
+            text_path_hash: None,
+            file_size: 8,
+            modified_ns: None,
+        };
+        let info = |source: &str, version: &str| FingerprintInfo {
+            id: "defined.v1".to_owned(),
+            crate_name: "fingerprint-defined".to_owned(),
+            version: version.to_owned(),
+            source: source.to_owned(),
+            format: "csv".to_owned(),
+            parent: None,
+        };
+        let definition_hash = |info: &FingerprintInfo| {
+            record_cache
+                .key(&DefinedFingerprint, info, DocumentKind::Csv)
+                .expect("fingerprint is cacheable")
+                .definition_hash
+        };
+
+        assert_eq!(
+            definition_hash(&info("installed:defined.v1", "0.6.2")),
+            "blake3:def"
+        );
+        let plugin = definition_hash(&info("plugin:/opt/plugins/libdefined.so", "0.6.2"));
+        assert_eq!(plugin, "blake3:def@plugin:/opt/plugins/libdefined.so@0.6.2");
+        assert_ne!(
+            plugin,
+            definition_hash(&info("plugin:/opt/plugins/libdefined.so", "0.6.3"))
+        );
+        assert_ne!(
+            definition_hash(&info("compiled:fingerprint-defined@1.0.0", "0.6.2")),
+            definition_hash(&info("compiled:fingerprint-defined@1.0.1", "0.6.2"))
+        );
+    }
+
+    #[test]
+    fn panicking_fingerprints_are_skipped_with_crash_warning() {
+        struct PanickingFingerprint;
+
+        impl Fingerprint for PanickingFingerprint {
+            fn id(&self) -> &str {
+                "panics.v0"
+            }
+
+            fn format(&self) -> &str {
+                "text"
+            }
+
+            fn fingerprint(&self, _doc: &Document) -> FingerprintResult {
+                panic!("parser blew up");
+            }
+        }
+
+        let temp_file = NamedTempFile::with_suffix(".txt").expect("create text temp file");
+        fs::write(temp_file.path(), "hello world").expect("write text file");
+        let input = json!({
+            "version": "hash.v0",
+            "path": temp_file.path().display().to_string(),
+            "extension": ".txt",
+            "bytes_hash": "blake3:abc"
+        });
+        let mut registry = FingerprintRegistry::new();
+        registry.register_with_info(
+            Box::new(PanickingFingerprint),
+            FingerprintInfo {
+                id: "panics.v0".to_owned(),
+                crate_name: "fingerprint-panics".to_owned(),
+                version: "0.1.0".to_owned(),
+                source: "builtin:panics".to_owned(),
+                format: "text".to_owned(),
+                parent: None,
+            },
+        );
+
+        let output = enrich_record_with_limits(
+            &input,
+            &Arc::new(registry),
+            &Arc::from(vec!["panics.v0".to_owned()]),
+            &RecordLimits::default(),
+            None,
+        )
+        .expect("crash is skipped, not fatal");
+        assert_eq!(output["_skipped"], true);
+        assert_eq!(output["_warnings"][0]["code"], "E_CRASH");
+        assert_eq!(output["_warnings"][0]["detail"]["error"], "parser blew up");
+    }
+
     #[test]
     fn first_matching_fingerprint_wins() {
         let temp_file = NamedTempFile::with_suffix(".txt").expect("create text temp file");
@@ -0,0 +1,372 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+/// Default maximum input file size: 1 GiB.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 1 << 30;
+/// Default maximum decompressed size for ZIP-based formats: 4 GiB.
+pub const DEFAULT_MAX_DECOMPRESSED_BYTES: u64 = 4 << 30;
+/// Default wall-clock budget for evaluating one record.
+pub const DEFAULT_RECORD_TIMEOUT: Duration = Duration::from_secs(300);
+/// Timed-out record workers that may still be running before a further
+/// timeout ends the run.
+pub const MAX_ABANDONED_WORKERS: usize = 4;
+
+/// Resource guards applied to every record in run mode.
+///
+/// `None` disables the corresponding guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordLimits {
+    pub max_file_bytes: Option<u64>,
+    pub max_decompressed_bytes: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RecordLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: Some(DEFAULT_MAX_FILE_BYTES),
+            max_decompressed_bytes: Some(DEFAULT_MAX_DECOMPRESSED_BYTES),
+            timeout: Some(DEFAULT_RECORD_TIMEOUT),
+        }
+    }
+}
+
+impl RecordLimits {
+    /// Limits with every guard disabled.
+    pub fn unlimited() -> Self {
+        Self {
+            max_file_bytes: None,
+            max_decompressed_bytes: None,
+            timeout: None,
+        }
+    }
+
+    /// Build limits from CLI values, where `0` disables a guard and `None` keeps the default.
+    pub fn from_cli(
+        max_file_size: Option<u64>,
+        max_decompressed_size: Option<u64>,
+        timeout_secs: Option<u64>,
+    ) -> Self {
+        let defaults = Self::default();
+        let resolve = |value: Option<u64>, default: Option<u64>| match value {
+            Some(0) => None,
+            Some(value) => Some(value),
+            None => default,
+        };
+        Self {
+            max_file_bytes: resolve(max_file_size, defaults.max_file_bytes),
+            max_decompressed_bytes: resolve(max_decompressed_size, defaults.max_decompressed_bytes),
+            timeout: resolve(
+                timeout_secs,
+                defaults.timeout.map(|timeout| timeout.as_secs()),
+            )
+            .map(Duration::from_secs),
+        }
+    }
+}
+
+/// A size guard tripped before the document was parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeViolation {
+    /// `file_size` or `decompressed_size`.
+    pub limit: &'static str,
+    pub size_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+/// Failure of a guarded evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardFailure {
+    /// Evaluation exceeded the wall-clock budget.
+    Timeout(Duration),
+    /// Evaluation panicked; carries the panic message.
+    Crash(String),
+    /// `MAX_ABANDONED_WORKERS` timed-out workers are still running, so no
+    /// further record can be evaluated safely.
+    WorkersExhausted(usize),
+}
+
+/// Check the input file size against `max_file_bytes`.
+///
+/// Reads only metadata, so it runs before the record is handed to a worker.
+pub fn check_file_size(path: &Path, limits: &RecordLimits) -> Result<(), SizeViolation> {
+    if let Some(limit_bytes) = limits.max_file_bytes
+        && let Ok(metadata) = std::fs::metadata(path)
+        && metadata.len() > limit_bytes
+    {
+        return Err(SizeViolation {
+            limit: "file_size",
+            size_bytes: metadata.len(),
+            limit_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Check the decompressed size of ZIP-based formats against
+/// `max_decompressed_bytes`.
+///
+/// This inflates the archive, so callers run it under [`run_guarded`] to bound
+/// its time and isolate panics in the ZIP reader.
+pub fn check_decompressed_size(
+    path: &Path,
+    extension: &str,
+    limits: &RecordLimits,
+) -> Result<(), SizeViolation> {
+    if let Some(limit_bytes) = limits.max_decompressed_bytes
+        && is_zip_based_extension(extension)
+        && let Some(size_bytes) = decompressed_size_over(path, limit_bytes)
+    {
+        return Err(SizeViolation {
+            limit: "decompressed_size",
+            size_bytes,
+            limit_bytes,
+        });
+    }
+    Ok(())
+}
+
+fn is_zip_based_extension(extension: &str) -> bool {
+    matches!(
+        extension.to_ascii_lowercase().as_str(),
+        "xlsx" | "xlsm" | "docx" | "pptx" | "ods"
+    )
+}
+
+/// Decompress a ZIP archive's entries until more than `limit_bytes` have been
+/// read, returning the bytes read when the limit is exceeded.
+///
+/// Sizes declared in the central directory are not trusted; each entry is read
+/// through a `Read::take` bounded by the remaining budget. Returns `None` when
+/// the archive is within the limit or is not a readable ZIP archive; the format
+/// reader reports the latter case as a parse error.
+fn decompressed_size_over(path: &Path, limit_bytes: u64) -> Option<u64> {
+    let file = File::open(path).ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+    let mut total = 0u64;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).ok()?;
+        let budget = (limit_bytes - total).saturating_add(1);
+        total += io::copy(&mut entry.take(budget), &mut io::sink()).ok()?;
+        if total > limit_bytes {
+            return Some(total);
+        }
+    }
+    None
+}
+
+/// A task handed to a record worker.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Long-lived helper thread that runs guarded tasks for one calling thread, so
+/// a run does not spawn a thread per record.
+struct RecordWorker {
+    jobs: mpsc::Sender<Job>,
+    abandoned: Arc<AtomicBool>,
+}
+
+impl RecordWorker {
+    fn spawn() -> io::Result<Self> {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let worker_abandoned = Arc::clone(&abandoned);
+        thread::Builder::new()
+            .name("fingerprint-record".to_owned())
+            .spawn(move || {
+                for job in receiver {
+                    job();
+                }
+                if worker_abandoned.load(Ordering::SeqCst) {
+                    ABANDONED_WORKERS.fetch_sub(1, Ordering::SeqCst);
+                }
+            })?;
+        Ok(Self { jobs, abandoned })
+    }
+
+    /// Give up on the worker's current task. The thread exits, and stops
+    /// counting as abandoned, once that task finally returns.
+    fn abandon(self) -> usize {
+        self.abandoned.store(true, Ordering::SeqCst);
+        ABANDONED_WORKERS.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// Timed-out record workers whose task is still running.
+static ABANDONED_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static RECORD_WORKER: RefCell<Option<RecordWorker>> = const { RefCell::new(None) };
+}
+
+/// Run `task` with panic isolation and an optional wall-clock timeout.
+///
+/// With a timeout, the task runs on the calling thread's record worker. A task
+/// that overruns is abandoned (Rust threads cannot be cancelled), its result
+/// discarded, and the next task gets a fresh worker. Once
+/// `MAX_ABANDONED_WORKERS` abandoned workers are still running, the timeout
+/// that reached the cap and every later task fail with
+/// [`GuardFailure::WorkersExhausted`], which ends the run.
+pub fn run_guarded<T, F>(timeout: Option<Duration>, task: F) -> Result<T, GuardFailure>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return panic::catch_unwind(AssertUnwindSafe(task))
+            .map_err(|payload| GuardFailure::Crash(panic_message(payload.as_ref())));
+    };
+
+    let abandoned = ABANDONED_WORKERS.load(Ordering::SeqCst);
+    if abandoned >= MAX_ABANDONED_WORKERS {
+        return Err(GuardFailure::WorkersExhausted(abandoned));
+    }
+
+    let (result_tx, result_rx) = mpsc::channel();
+    let job: Job = Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(task))
+            .map_err(|payload| panic_message(payload.as_ref()));
+        let _ = result_tx.send(result);
+    });
+    let dispatched = RECORD_WORKER.with_borrow_mut(|worker| {
+        let worker = match worker {
+            Some(worker) => worker,
+            None => worker.insert(RecordWorker::spawn()?),
+        };
+        worker
+            .jobs
+            .send(job)
+            .map_err(|_| io::Error::other("record worker is not running"))
+    });
+    if let Err(error) = dispatched {
+        RECORD_WORKER.set(None);
+        return Err(GuardFailure::Crash(format!(
+            "failed to start record worker: {error}"
+        )));
+    }
+
+    match result_rx.recv_timeout(timeout) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(message)) => Err(GuardFailure::Crash(message)),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            let abandoned = RECORD_WORKER.take().map_or(
+                ABANDONED_WORKERS.load(Ordering::SeqCst),
+                RecordWorker::abandon,
+            );
+            if abandoned >= MAX_ABANDONED_WORKERS {
+                Err(GuardFailure::WorkersExhausted(abandoned))
+            } else {
+                Err(GuardFailure::Timeout(timeout))
+            }
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            RECORD_WORKER.set(None);
+            Err(GuardFailure::Crash(
+                "record worker exited without a result".to_owned(),
+            ))
+        }
+    }
+}
+
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with non-string payload".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn from_cli_keeps_defaults_and_zero_disables() {
+        let limits = RecordLimits::from_cli(None, Some(0), Some(5));
+        assert_eq!(limits.max_file_bytes, Some(DEFAULT_MAX_FILE_BYTES));
+        assert_eq!(limits.max_decompressed_bytes, None);
+        assert_eq!(limits.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn rejects_files_over_the_size_limit() {
+        let mut file = NamedTempFile::with_suffix(".csv").expect("create temp file");
+        file.write_all(&[b'a'; 64]).expect("write bytes");
+        let limits = RecordLimits {
+            max_file_bytes: Some(16),
+            ..RecordLimits::unlimited()
+        };
+
+        let violation =
+            check_file_size(file.path(), &limits).expect_err("file should be too large");
+        assert_eq!(violation.limit, "file_size");
+        assert_eq!(violation.size_bytes, 64);
+        assert_eq!(violation.limit_bytes, 16);
+    }
+
+    #[test]
+    fn rejects_zip_archives_over_the_decompressed_limit() {
+        let file = NamedTempFile::with_suffix(".xlsx").expect("create temp file");
+        {
+            let mut zip = zip::ZipWriter::new(File::create(file.path()).expect("open zip"));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("xl/workbook.xml", options)
+                .expect("start zip entry");
+            zip.write_all(&[b'x'; 4096]).expect("write zip entry");
+            zip.finish().expect("finish zip");
+        }
+        let limits = RecordLimits {
+            max_decompressed_bytes: Some(1024),
+            ..RecordLimits::unlimited()
+        };
+
+        let violation = check_decompressed_size(file.path(), "xlsx", &limits)
+            .expect_err("archive should exceed decompressed limit");
+        assert_eq!(violation.limit, "decompressed_size");
+        assert_eq!(violation.size_bytes, 1025);
+        let within = RecordLimits {
+            max_decompressed_bytes: Some(4096),
+            ..RecordLimits::unlimited()
+        };
+        assert!(check_decompressed_size(file.path(), "xlsx", &within).is_ok());
+        assert!(check_decompressed_size(file.path(), "csv", &limits).is_ok());
+    }
+
+    #[test]
+    fn run_guarded_isolates_panics() {
+        let failure = run_guarded(None, || -> u32 { panic!("corrupt input") })
+            .expect_err("panic should be isolated");
+        assert_eq!(failure, GuardFailure::Crash("corrupt input".to_owned()));
+
+        let failure = run_guarded(Some(Duration::from_secs(5)), || -> u32 {
+            panic!("{}", "threaded panic")
+        })
+        .expect_err("panic should be isolated on worker thread");
+        assert_eq!(failure, GuardFailure::Crash("threaded panic".to_owned()));
+    }
+
+    #[test]
+    fn run_guarded_times_out_slow_tasks() {
+        let timeout = Duration::from_millis(20);
+        let failure = run_guarded(Some(timeout), || {
+            thread::sleep(Duration::from_millis(500));
+            1u32
+        })
+        .expect_err("slow task should time out");
+        assert_eq!(failure, GuardFailure::Timeout(timeout));
+
+        assert_eq!(run_guarded(Some(Duration::from_secs(5)), || 7u32), Ok(7));
+        assert_eq!(run_guarded(Some(Duration::from_secs(5)), || 8u32), Ok(8));
+    }
+}
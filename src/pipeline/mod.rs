@@ -1,3 +1,5 @@
+pub mod checkpoint;
 pub mod enricher;
+pub mod limits;
 pub mod parallel;
 pub mod reader;
@@ -1,7 +1,9 @@
 use crate::registry::FingerprintRegistry;
 use serde_json::Value;
 use std::collections::BTreeMap;
-use std::sync::mpsc;
+use std::convert::Infallible;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, mpsc};
 use std::thread;
 
 /// Process records in parallel with bounded reorder buffer, emitting in input order.
@@ -24,58 +26,140 @@ where
     ordered
 }
 
-pub fn process_parallel_for_each<F, E>(records: Vec<Value>, jobs: usize, process: F, mut emit: E)
+/// Process records on a persistent worker pool, emitting results in input order.
+///
+/// See [`try_process_parallel_for_each`] for scheduling and memory bounds.
+pub fn process_parallel_for_each<I, F, E>(records: I, jobs: usize, process: F, mut emit: E)
 where
+    I: IntoIterator<Item = Value>,
     F: Fn(Value) -> Value + Sync,
     E: FnMut(usize, Value),
+{
+    let result: Result<(), Infallible> =
+        try_process_parallel_for_each(records, jobs, process, |index, record| {
+            emit(index, record);
+            Ok(())
+        });
+    match result {
+        Ok(()) => {}
+        Err(never) => match never {},
+    }
+}
+
+/// Process records on a persistent worker pool with a bounded reorder buffer.
+///
+/// `jobs` workers pull records from a shared queue as soon as they are idle, so
+/// one slow record only occupies its own worker. At most `2 * jobs` records are
+/// dispatched but not yet emitted; once that window is full, dispatch waits for
+/// the head-of-line record to be emitted. `emit` runs on the calling thread in
+/// input order, so a slow sink (e.g. a blocked stdout pipe) holds back dispatch
+/// instead of growing memory. An `emit` error stops dispatch and is returned
+/// after in-flight workers drain.
+pub fn try_process_parallel_for_each<I, T, F, E, Err>(
+    records: I,
+    jobs: usize,
+    process: F,
+    mut emit: E,
+) -> Result<(), Err>
+where
+    I: IntoIterator<Item = Value>,
+    T: Send,
+    F: Fn(Value) -> T + Sync,
+    E: FnMut(usize, T) -> Result<(), Err>,
 {
     let worker_count = jobs.max(1);
-    let in_flight_limit = worker_count.saturating_mul(2).max(1);
+    let window = worker_count.saturating_mul(2).max(1);
     let mut indexed = records.into_iter().enumerate();
 
-    while let Some(first) = indexed.next() {
-        let mut batch = Vec::with_capacity(in_flight_limit);
-        batch.push(first);
-        for _ in 1..in_flight_limit {
-            if let Some(next) = indexed.next() {
-                batch.push(next);
-            } else {
-                break;
-            }
-        }
-        if worker_count == 1 {
-            for (index, record) in batch {
-                emit(index, process(record));
-            }
-            continue;
+    if worker_count == 1 {
+        for (index, record) in indexed {
+            emit(index, process(record))?;
         }
+        return Ok(());
+    }
 
-        let (result_tx, result_rx) = mpsc::channel::<(usize, Value)>();
-        thread::scope(|scope| {
-            for (index, record) in batch {
-                let result_tx = result_tx.clone();
-                let process = &process;
-                scope.spawn(move || {
-                    let processed = process(record);
-                    let _ = result_tx.send((index, processed));
-                });
-            }
-        });
+    let (work_tx, work_rx) = mpsc::sync_channel::<(usize, Value)>(window);
+    let work_rx = Mutex::new(work_rx);
+    let (result_tx, result_rx) = mpsc::channel::<(usize, thread::Result<T>)>();
+
+    let (result, panic) = thread::scope(|scope| {
+        for _ in 0..worker_count {
+            let work_rx = &work_rx;
+            let result_tx = result_tx.clone();
+            let process = &process;
+            scope.spawn(move || {
+                loop {
+                    let next = match work_rx.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(poisoned) => poisoned.into_inner().recv(),
+                    };
+                    let Ok((index, record)) = next else {
+                        break;
+                    };
+                    let processed = panic::catch_unwind(AssertUnwindSafe(|| process(record)));
+                    if result_tx.send((index, processed)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
         drop(result_tx);
 
         let mut pending = BTreeMap::new();
-        for (index, record) in result_rx {
-            pending.insert(index, record);
-        }
-        for (index, record) in pending {
-            emit(index, record);
-        }
+        let mut next_emit = 0usize;
+        let mut dispatched = 0usize;
+        let mut exhausted = false;
+
+        let outcome = 'schedule: loop {
+            while !exhausted && dispatched - next_emit < window {
+                match indexed.next() {
+                    Some(job) => {
+                        if work_tx.send(job).is_err() {
+                            exhausted = true;
+                        } else {
+                            dispatched += 1;
+                        }
+                    }
+                    None => exhausted = true,
+                }
+            }
+            if next_emit == dispatched {
+                break (Ok(()), None);
+            }
+
+            let Ok((index, processed)) = result_rx.recv() else {
+                break (Ok(()), None);
+            };
+            match processed {
+                Ok(record) => {
+                    pending.insert(index, record);
+                }
+                Err(payload) => break (Ok(()), Some(payload)),
+            }
+
+            while let Some(record) = pending.remove(&next_emit) {
+                if let Err(error) = emit(next_emit, record) {
+                    break 'schedule (Err(error), None);
+                }
+                next_emit += 1;
+            }
+        };
+        drop(work_tx);
+        outcome
+    });
+
+    if let Some(payload) = panic {
+        panic::resume_unwind(payload);
     }
+    result
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{process_parallel, process_parallel_for_each, process_parallel_with};
+    use super::{
+        process_parallel, process_parallel_for_each, process_parallel_with,
+        try_process_parallel_for_each,
+    };
     use crate::registry::FingerprintRegistry;
     use serde_json::{Value, json};
     use std::sync::{
@@ -180,4 +264,86 @@ mod tests {
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn runs_at_most_jobs_records_concurrently() {
+        let jobs = 3usize;
+        let current = AtomicUsize::new(0);
+        let observed_max = AtomicUsize::new(0);
+
+        let _ = process_parallel_with(sample_records(30), jobs, |record| {
+            let active = current.fetch_add(1, Ordering::SeqCst) + 1;
+            observed_max.fetch_max(active, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            current.fetch_sub(1, Ordering::SeqCst);
+            record
+        });
+
+        assert!(observed_max.load(Ordering::SeqCst) <= jobs);
+    }
+
+    #[test]
+    fn bounds_dispatch_ahead_of_emission_to_reorder_window() {
+        let jobs = 2usize;
+        let emitted = AtomicUsize::new(0);
+        let max_ahead = AtomicUsize::new(0);
+
+        process_parallel_for_each(
+            sample_records(20),
+            jobs,
+            |record| {
+                let seq =
+                    usize::try_from(record["seq"].as_u64().expect("u64 seq")).expect("fits usize");
+                if seq == 0 {
+                    thread::sleep(Duration::from_millis(30));
+                }
+                let ahead = seq - emitted.load(Ordering::SeqCst);
+                max_ahead.fetch_max(ahead, Ordering::SeqCst);
+                record
+            },
+            |_index, _record| {
+                thread::sleep(Duration::from_millis(1));
+                emitted.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+
+        assert!(max_ahead.load(Ordering::SeqCst) < jobs * 2);
+    }
+
+    #[test]
+    fn stops_dispatch_when_emit_fails() {
+        let processed = AtomicUsize::new(0);
+        let mut emitted = Vec::new();
+
+        let result = try_process_parallel_for_each(
+            sample_records(100),
+            2,
+            |record| {
+                processed.fetch_add(1, Ordering::SeqCst);
+                record
+            },
+            |index, _record| {
+                if index == 3 {
+                    return Err("stdout closed");
+                }
+                emitted.push(index);
+                Ok(())
+            },
+        );
+
+        assert_eq!(result, Err("stdout closed"));
+        assert_eq!(emitted, vec![0, 1, 2]);
+        assert!(processed.load(Ordering::SeqCst) < 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "worker failure")]
+    fn propagates_worker_panics_without_deadlock() {
+        let _ = process_parallel_with(sample_records(8), 2, |record| {
+            if record["seq"] == 2 {
+                panic!("worker failure");
+            }
+            record
+        });
+    }
 }
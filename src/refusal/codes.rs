@@ -3,6 +3,8 @@ use std::fmt;
 use serde::Serialize;
 use serde_json::Value;
 
+use crate::dsl::diagnostics::Diagnostic;
+
 /// Run-mode refusal codes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum RefusalCode {
@@ -21,6 +23,9 @@ pub enum RefusalCode {
     /// Child fingerprint references a parent not loaded in --fp.
     #[serde(rename = "E_ORPHAN_CHILD")]
     OrphanChild,
+    /// Installed definition contains a regex or reference that does not compile.
+    #[serde(rename = "E_INVALID_DEFINITION")]
+    InvalidDefinition,
 }
 
 /// Compile-mode refusal codes.
@@ -45,6 +50,7 @@ impl fmt::Display for RefusalCode {
             Self::DuplicateFpId => "Duplicate fingerprint ID discovered",
             Self::UntrustedFp => "Fingerprint provider not allowlisted",
             Self::OrphanChild => "Child fingerprint references unloaded parent",
+            Self::InvalidDefinition => "Installed fingerprint definition failed to compile",
         };
 
         f.write_str(message)
@@ -88,6 +94,17 @@ pub struct OrphanChildDetail {
     pub loaded: Vec<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidDefinitionDetail {
+    pub path: String,
+    pub fingerprint_id: String,
+    pub section: String,
+    pub index: usize,
+    pub name: String,
+    pub field: String,
+    pub error: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(untagged)]
 pub enum RefusalDetail {
@@ -96,6 +113,7 @@ pub enum RefusalDetail {
     DuplicateFpId(DuplicateFpIdDetail),
     UntrustedFp(UntrustedFpDetail),
     OrphanChild(OrphanChildDetail),
+    InvalidDefinition(InvalidDefinitionDetail),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -128,6 +146,20 @@ pub struct CompileRefusalBody {
     pub next_command: Option<String>,
 }
 
+impl CompileRefusalEnvelope {
+    /// Attach every diagnostic found in the definition to the refusal detail.
+    pub fn with_diagnostics(mut self, diagnostics: &[Diagnostic]) -> Self {
+        if let Value::Object(detail) = &mut self.refusal.detail {
+            detail.insert(
+                "diagnostics".to_owned(),
+                serde_json::to_value(diagnostics)
+                    .expect("diagnostic serialization should never fail"),
+            );
+        }
+        self
+    }
+}
+
 pub fn build_envelope(
     code: RefusalCode,
     message: impl Into<String>,
@@ -193,6 +225,10 @@ mod tests {
             serde_json::to_value(RefusalCode::OrphanChild).expect("serialize code"),
             json!("E_ORPHAN_CHILD")
         );
+        assert_eq!(
+            serde_json::to_value(RefusalCode::InvalidDefinition).expect("serialize code"),
+            json!("E_INVALID_DEFINITION")
+        );
     }
 
     #[test]
@@ -329,6 +365,28 @@ mod tests {
                 "loaded": ["csv.v0", "xlsx.v0"]
             })
         );
+
+        assert_eq!(
+            serde_json::to_value(RefusalDetail::InvalidDefinition(InvalidDefinitionDetail {
+                path: "/defs/rent-roll.fp.yaml".to_owned(),
+                fingerprint_id: "rent-roll.v1".to_owned(),
+                section: "assertions".to_owned(),
+                index: 2,
+                name: "title_cell".to_owned(),
+                field: "pattern".to_owned(),
+                error: "invalid regex '(unclosed': regex parse error".to_owned(),
+            }))
+            .expect("serialize detail"),
+            json!({
+                "path": "/defs/rent-roll.fp.yaml",
+                "fingerprint_id": "rent-roll.v1",
+                "section": "assertions",
+                "index": 2,
+                "name": "title_cell",
+                "field": "pattern",
+                "error": "invalid regex '(unclosed': regex parse error"
+            })
+        );
     }
 
     #[test]
@@ -0,0 +1,78 @@
+use crate::registry::core::{Fingerprint, FingerprintInfo};
+use crate::registry::installed::DiscoveredFingerprint;
+use std::sync::OnceLock;
+
+/// Fingerprints statically linked into a custom `fingerprint` binary.
+///
+/// Generated by `fingerprint compile --binary`; the binary's `main` hands it
+/// to `run_with_compiled`, and every fingerprint is registered next to the
+/// builtins with source `compiled:<crate>@<version>`, which the trust config
+/// must allowlist.
+#[derive(Clone, Copy)]
+pub struct CompiledSet {
+    pub crate_name: &'static str,
+    pub version: &'static str,
+    pub register: fn() -> Vec<Box<dyn Fingerprint>>,
+}
+
+impl CompiledSet {
+    /// Provider source recorded for every fingerprint in the set.
+    pub fn source(&self) -> String {
+        format!("compiled:{}@{}", self.crate_name, self.version)
+    }
+}
+
+static COMPILED: OnceLock<CompiledSet> = OnceLock::new();
+
+/// Install the set registered by `build_registry`. Only the first call wins.
+pub fn install(set: CompiledSet) {
+    let _ = COMPILED.set(set);
+}
+
+/// Fingerprints of the installed set, with registry metadata.
+pub fn compiled_fingerprints() -> Vec<DiscoveredFingerprint> {
+    COMPILED.get().map(set_fingerprints).unwrap_or_default()
+}
+
+fn set_fingerprints(set: &CompiledSet) -> Vec<DiscoveredFingerprint> {
+    let source = set.source();
+    (set.register)()
+        .into_iter()
+        .map(|fingerprint| {
+            let info = FingerprintInfo {
+                id: fingerprint.id().to_owned(),
+                crate_name: set.crate_name.to_owned(),
+                version: set.version.to_owned(),
+                source: source.clone(),
+                format: fingerprint.format().to_owned(),
+                parent: fingerprint.parent().map(ToOwned::to_owned),
+            };
+            (fingerprint, info)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::builtin::register_builtins;
+
+    #[test]
+    fn set_fingerprints_report_compiled_source() {
+        let set = CompiledSet {
+            crate_name: "fingerprint-cmbs",
+            version: "2.1.0",
+            register: register_builtins,
+        };
+
+        let fingerprints = set_fingerprints(&set);
+
+        assert_eq!(fingerprints.len(), register_builtins().len());
+        for (fingerprint, info) in &fingerprints {
+            assert_eq!(info.id, fingerprint.id());
+            assert_eq!(info.crate_name, "fingerprint-cmbs");
+            assert_eq!(info.version, "2.1.0");
+            assert_eq!(info.source, "compiled:fingerprint-cmbs@2.1.0");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::document::Document;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
@@ -19,10 +19,26 @@ pub trait Fingerprint: Send + Sync {
 
     /// Test a document against this fingerprint definition.
     fn fingerprint(&self, doc: &Document) -> FingerprintResult;
+
+    /// Hash of the definition this fingerprint evaluates, when it has one.
+    ///
+    /// DSL-backed fingerprints return their `source_hash`; the evaluation
+    /// cache uses it to invalidate results when a definition changes.
+    fn definition_hash(&self) -> Option<&str> {
+        None
+    }
+
+    /// Canonical YAML of the definition, when embedded by `fingerprint compile`.
+    ///
+    /// `fingerprint decompile` prints it after checking it against
+    /// `definition_hash`.
+    fn definition_source(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Result of testing a document against a fingerprint.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FingerprintResult {
     pub matched: bool,
     pub reason: Option<String>,
@@ -32,7 +48,7 @@ pub struct FingerprintResult {
 }
 
 /// Result of evaluating a single assertion.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssertionResult {
     pub name: String,
     pub passed: bool,
@@ -147,6 +163,9 @@ impl FingerprintRegistry {
     }
 
     /// Validate trust policy for non-builtin providers.
+    ///
+    /// Only builtins are trusted implicitly; every other provider, including
+    /// fingerprints statically compiled into the binary, must be allowlisted.
     pub fn validate_trust(&self, allowlist: &[String]) -> Result<(), RegistryValidationError> {
         for entry in &self.fingerprints {
             if is_trusted_source(&entry.info.source, allowlist) {
@@ -227,15 +246,18 @@ impl fmt::Display for RegistryValidationError {
 impl std::error::Error for RegistryValidationError {}
 
 fn is_trusted_source(source: &str, allowlist: &[String]) -> bool {
-    source == "builtin"
-        || source.starts_with("builtin:")
-        || allowlist.iter().any(|entry| {
-            if let Some(prefix) = entry.strip_suffix('*') {
-                source.starts_with(prefix)
-            } else {
-                entry == source
-            }
-        })
+    source == "builtin" || source.starts_with("builtin:") || is_allowlisted(source, allowlist)
+}
+
+/// Whether a trust entry names `source`, exactly or by a `*`-suffixed prefix.
+pub(crate) fn is_allowlisted(source: &str, allowlist: &[String]) -> bool {
+    allowlist.iter().any(|entry| {
+        if let Some(prefix) = entry.strip_suffix('*') {
+            source.starts_with(prefix)
+        } else {
+            entry == source
+        }
+    })
 }
 
 #[cfg(test)]
@@ -393,9 +415,28 @@ mod tests {
             },
         );
 
+        registry.register_with_info(
+            Box::new(TestFingerprint {
+                id: "cmbs-setup.v1",
+                format: "csv",
+                parent: None,
+            }),
+            FingerprintInfo {
+                id: "cmbs-setup.v1".to_owned(),
+                crate_name: "fingerprint-cmbs".to_owned(),
+                version: "2.1.0".to_owned(),
+                source: "compiled:fingerprint-cmbs@2.1.0".to_owned(),
+                format: "csv".to_owned(),
+                parent: None,
+            },
+        );
+
         registry
-            .validate_trust(&["crate:fingerprint-argus".to_owned()])
-            .expect("allowlisted external crate should pass");
+            .validate_trust(&[
+                "crate:fingerprint-argus".to_owned(),
+                "compiled:fingerprint-cmbs@*".to_owned(),
+            ])
+            .expect("allowlisted providers should pass");
     }
 
     #[test]
@@ -429,6 +470,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_trust_requires_compiled_sources_to_be_allowlisted() {
+        let mut registry = FingerprintRegistry::new();
+        registry.register_with_info(
+            Box::new(TestFingerprint {
+                id: "cmbs-setup.v1",
+                format: "csv",
+                parent: None,
+            }),
+            FingerprintInfo {
+                id: "cmbs-setup.v1".to_owned(),
+                crate_name: "fingerprint-cmbs".to_owned(),
+                version: "2.1.0".to_owned(),
+                source: "compiled:fingerprint-cmbs@2.1.0".to_owned(),
+                format: "csv".to_owned(),
+                parent: None,
+            },
+        );
+
+        assert_eq!(
+            registry
+                .validate_trust(&["compiled:fingerprint-other@*".to_owned()])
+                .expect_err("compiled sources are not implicitly trusted"),
+            RegistryValidationError::UntrustedFp {
+                fingerprint_id: "cmbs-setup.v1".to_owned(),
+                provider: "compiled:fingerprint-cmbs@2.1.0".to_owned(),
+                policy: "allowlist_required".to_owned(),
+            }
+        );
+    }
+
     #[test]
     fn register_propagates_trait_parent_metadata() {
         let mut registry = FingerprintRegistry::new();
@@ -1,32 +1,70 @@
 use crate::document::Document;
-use crate::dsl::assertions::evaluate_named_assertions;
+use crate::dsl::assertions::{diagnose_mode, evaluate_named_assertions_with_plan};
 use crate::dsl::content_hash::content_hash;
-use crate::dsl::extract::extract;
-use crate::dsl::parser::FingerprintDefinition;
+use crate::dsl::extract::extract_with_plan;
+use crate::dsl::pack::{PACK_MANIFEST, load_pack};
+use crate::dsl::parser::{FingerprintDefinition, parse_sources_str};
+use crate::dsl::plan::{CompiledDefinition, PlanError, definition_hash};
+use crate::dsl::resolve::resolve_definitions;
 use crate::registry::core::{Fingerprint, FingerprintInfo, FingerprintResult};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 
-/// A fingerprint backed by a parsed DSL definition, evaluated at runtime.
+/// A fingerprint backed by a DSL definition compiled once at discovery time.
+///
+/// The compiled plan is shared by every record and worker thread.
 struct DslFingerprint {
-    def: FingerprintDefinition,
+    plan: CompiledDefinition,
+    source_hash: Option<String>,
+}
+
+/// An installed definition that parsed but could not be compiled into a plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidInstalledDefinition {
+    pub path: PathBuf,
+    /// Template the definition was instantiated from, if any.
+    pub template: Option<PathBuf>,
+    pub error: PlanError,
+}
+
+impl fmt::Display for InvalidInstalledDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.template {
+            Some(template) => write!(
+                f,
+                "{} (template {}): {}",
+                self.path.display(),
+                template.display(),
+                self.error
+            ),
+            None => write!(f, "{}: {}", self.path.display(), self.error),
+        }
+    }
 }
 
 impl Fingerprint for DslFingerprint {
     fn id(&self) -> &str {
-        &self.def.fingerprint_id
+        &self.plan.definition().fingerprint_id
     }
 
     fn format(&self) -> &str {
-        &self.def.format
+        &self.plan.definition().format
     }
 
     fn parent(&self) -> Option<&str> {
-        self.def.parent.as_deref()
+        self.plan.definition().parent.as_deref()
+    }
+
+    fn definition_hash(&self) -> Option<&str> {
+        self.source_hash.as_deref()
     }
 
     fn fingerprint(&self, doc: &Document) -> FingerprintResult {
-        let assertion_results = evaluate_named_assertions(&self.def.assertions, doc);
+        let def = self.plan.definition();
+        let patterns = self.plan.patterns();
+        let assertion_results =
+            evaluate_named_assertions_with_plan(&def.assertions, doc, diagnose_mode(), patterns);
 
         let all_passed = assertion_results.iter().all(|r| r.passed);
         let first_failure_reason = assertion_results
@@ -35,14 +73,14 @@ impl Fingerprint for DslFingerprint {
             .and_then(|r| r.detail.clone());
 
         let extracted: Option<HashMap<String, serde_json::Value>> =
-            if all_passed && !self.def.extract.is_empty() {
-                extract(doc, &self.def.extract).ok()
+            if all_passed && !def.extract.is_empty() {
+                extract_with_plan(doc, &def.extract, patterns).ok()
             } else {
                 None
             };
 
         let content_hash_value = if all_passed {
-            if let Some(ref config) = self.def.content_hash {
+            if let Some(ref config) = def.content_hash {
                 extracted
                     .as_ref()
                     .map(|ext| content_hash(ext, &config.over))
@@ -64,7 +102,7 @@ impl Fingerprint for DslFingerprint {
 }
 
 /// Default directory for installed fingerprint definitions.
-fn definitions_dir() -> PathBuf {
+pub fn definitions_dir() -> PathBuf {
     if let Ok(dir) = std::env::var("FINGERPRINT_DEFINITIONS") {
         return PathBuf::from(dir);
     }
@@ -77,29 +115,50 @@ fn definitions_dir() -> PathBuf {
 
 /// Discover installed fingerprint definitions from `~/.fingerprint/definitions/`.
 ///
-/// Scans the definitions directory for `.fp.yaml` files, parses each one,
-/// and returns fingerprint implementations with metadata.
+/// Scans the definitions directory for `.fp.yaml` files and installed pack
+/// directories (subdirectories holding a `pack.yaml`), parses every definition,
+/// compiles it into an evaluation plan, and returns fingerprint
+/// implementations with metadata. Files or packs that fail to parse are
+/// skipped with a warning; definitions with invalid regexes or cell references
+/// are returned separately, so only runs that select them are refused.
 ///
 /// Override the scan directory with the `FINGERPRINT_DEFINITIONS` environment variable.
-pub fn discover_installed() -> Vec<(Box<dyn Fingerprint>, FingerprintInfo)> {
+pub fn discover_installed() -> (Vec<DiscoveredFingerprint>, Vec<InvalidInstalledDefinition>) {
     discover_from_dir(&definitions_dir())
 }
 
+/// A discovered fingerprint paired with its registry metadata.
+pub type DiscoveredFingerprint = (Box<dyn Fingerprint>, FingerprintInfo);
+
 /// Scan a directory for `.fp.yaml` fingerprint definitions.
-fn discover_from_dir(dir: &std::path::Path) -> Vec<(Box<dyn Fingerprint>, FingerprintInfo)> {
+fn discover_from_dir(
+    dir: &std::path::Path,
+) -> (Vec<DiscoveredFingerprint>, Vec<InvalidInstalledDefinition>) {
+    let mut discovered = Vec::new();
+    let mut invalid = Vec::new();
     if !dir.is_dir() {
-        return Vec::new();
+        return (discovered, invalid);
     }
 
-    let mut discovered = Vec::new();
+    let mut loose_sources = Vec::new();
+    let mut read_file = |path: &str| {
+        std::fs::read_to_string(path).map_err(|error| format!("failed reading '{path}': {error}"))
+    };
 
     let entries = match std::fs::read_dir(dir) {
         Ok(entries) => entries,
-        Err(_) => return Vec::new(),
+        Err(_) => return (discovered, invalid),
     };
 
     for entry in entries.flatten() {
         let path = entry.path();
+        if path.is_dir() {
+            if path.join(PACK_MANIFEST).is_file() {
+                discover_pack(&path, &mut discovered, &mut invalid);
+            }
+            continue;
+        }
+
         let is_yaml = path
             .extension()
             .is_some_and(|ext| ext == "yaml" || ext == "yml");
@@ -112,7 +171,27 @@ fn discover_from_dir(dir: &std::path::Path) -> Vec<(Box<dyn Fingerprint>, Finger
             continue;
         }
 
-        let def = match crate::dsl::parser::parse(&path) {
+        let sources = std::fs::read_to_string(&path)
+            .map_err(|error| format!("Failed reading '{}': {error}", path.display()))
+            .and_then(|yaml| parse_sources_str(&yaml, &path.display().to_string(), &mut read_file));
+        match sources {
+            Ok(sources) => loose_sources.extend(sources),
+            Err(error) => {
+                eprintln!(
+                    "Warning: skipping invalid definition '{}': {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+
+    // Loose files share one scope, so `extends` may reference a definition in
+    // another file of the same directory.
+    let resolved = resolve_definitions(&loose_sources, &mut read_file);
+    for (source, def) in loose_sources.iter().zip(resolved) {
+        let path = std::path::Path::new(&source.origin);
+        let def = match def {
             Ok(def) => def,
             Err(error) => {
                 eprintln!(
@@ -129,23 +208,84 @@ fn discover_from_dir(dir: &std::path::Path) -> Vec<(Box<dyn Fingerprint>, Finger
             .and_then(|n| n.to_str())
             .unwrap_or("unknown")
             .to_owned();
+        match compile_discovered(
+            def,
+            path,
+            source.template.as_deref().map(std::path::Path::new),
+            format!("dsl-runtime:{}", filename),
+            env!("CARGO_PKG_VERSION").to_owned(),
+        ) {
+            Ok(fingerprint) => discovered.push(fingerprint),
+            Err(error) => invalid.push(error),
+        }
+    }
 
-        let info = FingerprintInfo {
-            id: def.fingerprint_id.clone(),
-            crate_name: format!("dsl-runtime:{}", filename),
-            version: env!("CARGO_PKG_VERSION").to_owned(),
-            source: format!("installed:{}", def.fingerprint_id),
-            format: def.format.clone(),
-            parent: def.parent.clone(),
-        };
+    (discovered, invalid)
+}
 
-        discovered.push((
-            Box::new(DslFingerprint { def }) as Box<dyn Fingerprint>,
-            info,
-        ));
+/// Load every definition from an installed pack, attributing it to the pack.
+fn discover_pack(
+    dir: &std::path::Path,
+    discovered: &mut Vec<DiscoveredFingerprint>,
+    invalid: &mut Vec<InvalidInstalledDefinition>,
+) {
+    let pack = match load_pack(dir) {
+        Ok(pack) => pack,
+        Err(error) => {
+            eprintln!(
+                "Warning: skipping invalid pack '{}': {}",
+                dir.display(),
+                error
+            );
+            return;
+        }
+    };
+
+    for entry in pack.definitions {
+        match compile_discovered(
+            entry.definition,
+            &dir.join(&entry.file),
+            entry.template.map(|template| dir.join(template)).as_deref(),
+            pack.manifest.pack.clone(),
+            pack.manifest.version.clone(),
+        ) {
+            Ok(fingerprint) => discovered.push(fingerprint),
+            Err(error) => invalid.push(error),
+        }
     }
+}
 
-    discovered
+#[allow(clippy::result_large_err)]
+fn compile_discovered(
+    def: FingerprintDefinition,
+    path: &std::path::Path,
+    template: Option<&std::path::Path>,
+    crate_name: String,
+    version: String,
+) -> Result<DiscoveredFingerprint, InvalidInstalledDefinition> {
+    let plan = CompiledDefinition::compile(def).map_err(|error| InvalidInstalledDefinition {
+        path: path.to_path_buf(),
+        template: template.map(std::path::Path::to_path_buf),
+        error,
+    })?;
+    let def = plan.definition();
+
+    let info = FingerprintInfo {
+        id: def.fingerprint_id.clone(),
+        crate_name,
+        version,
+        source: format!("installed:{}", def.fingerprint_id),
+        format: def.format.clone(),
+        parent: def.parent.clone(),
+    };
+
+    Ok((
+        Box::new(DslFingerprint {
+            source_hash: definition_hash(plan.definition()).ok(),
+            plan,
+        }) as Box<dyn Fingerprint>,
+        info,
+    ))
 }
 
 #[cfg(test)]
@@ -156,10 +296,11 @@ mod tests {
 
     #[test]
     fn discover_returns_empty_when_directory_missing() {
-        let result = discover_from_dir(std::path::Path::new(
+        let (result, invalid) = discover_from_dir(std::path::Path::new(
             "/tmp/fingerprint-test-nonexistent-dir",
         ));
         assert!(result.is_empty());
+        assert!(invalid.is_empty());
     }
 
     #[test]
@@ -177,8 +318,9 @@ assertions:
         // Also write a non-fp file that should be ignored
         fs::write(tmp.path().join("notes.yaml"), "not a fingerprint").expect("write decoy file");
 
-        let result = discover_from_dir(tmp.path());
+        let (result, invalid) = discover_from_dir(tmp.path());
 
+        assert!(invalid.is_empty());
         assert_eq!(result.len(), 1);
         let (fp, info) = &result[0];
         assert_eq!(fp.id(), "test-discover.v1");
@@ -187,6 +329,138 @@ assertions:
         assert_eq!(info.source, "installed:test-discover.v1");
     }
 
+    #[test]
+    fn discover_loads_multi_document_files_and_installed_packs() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let family = r#"
+fingerprint_id: family.v1
+format: csv
+assertions:
+  - sheet_exists: "csv"
+---
+fingerprint_id: family-child.v1
+format: csv
+parent: family.v1
+assertions:
+  - sheet_exists: "csv"
+"#;
+        fs::write(tmp.path().join("family.fp.yaml"), family).expect("write family definitions");
+
+        let pack_dir = tmp.path().join("cmbs-pack");
+        fs::create_dir_all(&pack_dir).expect("create pack dir");
+        fs::write(
+            pack_dir.join(PACK_MANIFEST),
+            "pack: cmbs-pack\nversion: 2.1.0\ndefinitions:\n  - setup.fp.yaml\n",
+        )
+        .expect("write pack manifest");
+        fs::write(
+            pack_dir.join("setup.fp.yaml"),
+            "fingerprint_id: cmbs-setup.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+        )
+        .expect("write pack definition");
+
+        let (mut result, invalid) = discover_from_dir(tmp.path());
+        assert!(invalid.is_empty());
+        result.sort_by(|left, right| left.1.id.cmp(&right.1.id));
+
+        let summary: Vec<(&str, &str, &str)> = result
+            .iter()
+            .map(|(_, info)| {
+                (
+                    info.id.as_str(),
+                    info.crate_name.as_str(),
+                    info.version.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("cmbs-setup.v1", "cmbs-pack", "2.1.0"),
+                (
+                    "family-child.v1",
+                    "dsl-runtime:family.fp.yaml",
+                    env!("CARGO_PKG_VERSION")
+                ),
+                (
+                    "family.v1",
+                    "dsl-runtime:family.fp.yaml",
+                    env!("CARGO_PKG_VERSION")
+                ),
+            ]
+        );
+        assert_eq!(result[1].1.parent.as_deref(), Some("family.v1"));
+    }
+
+    #[test]
+    fn discover_resolves_extends_across_loose_files() {
+        let tmp = TempDir::new().expect("create temp dir");
+        fs::write(
+            tmp.path().join("base.fp.yaml"),
+            "fingerprint_id: base.v1\nformat: csv\ninclude: [shared.fpl.yaml]\nassertions: []\n",
+        )
+        .expect("write base definition");
+        fs::write(
+            tmp.path().join("shared.fpl.yaml"),
+            "assertions:\n  - name: is_csv\n    sheet_exists: \"csv\"\n",
+        )
+        .expect("write assertion library");
+        fs::write(
+            tmp.path().join("child.fp.yaml"),
+            "fingerprint_id: child.v1\nextends: base.v1\nassertions:\n  - name: has_rows\n    sheet_min_rows:\n      sheet: \"Sheet1\"\n      min_rows: 1\n",
+        )
+        .expect("write child definition");
+
+        let (result, invalid) = discover_from_dir(tmp.path());
+        assert!(invalid.is_empty());
+        let (child, info) = result
+            .iter()
+            .find(|(_, info)| info.id == "child.v1")
+            .expect("child discovered");
+        assert_eq!(child.format(), "csv");
+        assert_eq!(info.crate_name, "dsl-runtime:child.fp.yaml");
+        assert!(child.definition_hash().is_some());
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn discover_sets_aside_definitions_with_invalid_regex() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let yaml = r#"
+fingerprint_id: broken-regex.v1
+format: csv
+assertions:
+  - name: csv_extension
+    filename_regex:
+      pattern: "(?i)\\.csv$"
+  - name: bad_heading
+    text_regex:
+      pattern: "(unclosed"
+"#;
+        let path = tmp.path().join("broken-regex.fp.yaml");
+        fs::write(&path, yaml).expect("write test definition");
+
+        fs::write(
+            tmp.path().join("valid.fp.yaml"),
+            "fingerprint_id: valid.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+        )
+        .expect("write valid definition");
+
+        let (result, invalid) = discover_from_dir(tmp.path());
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].1.id, "valid.v1");
+        assert_eq!(invalid.len(), 1);
+        let error = &invalid[0];
+
+        assert_eq!(error.path, path);
+        assert_eq!(error.error.fingerprint_id, "broken-regex.v1");
+        assert_eq!(error.error.section, "assertions");
+        assert_eq!(error.error.index, 1);
+        assert_eq!(error.error.name, "bad_heading");
+        assert_eq!(error.error.field, "pattern");
+    }
+
     #[test]
     fn dsl_fingerprint_evaluates_assertions_at_runtime() {
         let def = FingerprintDefinition {
@@ -205,7 +479,10 @@ assertions:
             content_hash: None,
         };
 
-        let fp = DslFingerprint { def };
+        let fp = DslFingerprint {
+            source_hash: definition_hash(&def).ok(),
+            plan: CompiledDefinition::compile(def).expect("compile definition"),
+        };
 
         // Create a minimal CSV document
         let tmp = tempfile::NamedTempFile::with_suffix(".csv").expect("create csv");
@@ -1,6 +1,8 @@
 pub mod builtin;
+pub mod compiled;
 pub mod core;
 pub mod installed;
+pub mod plugin;
 
 pub use core::{
     AssertionResult, Fingerprint, FingerprintInfo, FingerprintRegistry, FingerprintResult,
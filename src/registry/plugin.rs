@@ -0,0 +1,610 @@
+use crate::document::{Document, open_document_with_text_path};
+use crate::pipeline::limits::panic_message;
+use crate::registry::core::{Fingerprint, FingerprintInfo, FingerprintResult, is_allowlisted};
+use crate::registry::installed::DiscoveredFingerprint;
+use serde::{Deserialize, Serialize};
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The unsafe side of the plugin ABI, re-exported so generated crates need
+/// no dependency beyond `fingerprint`.
+pub use fingerprint_plugin_ffi as ffi;
+pub use fingerprint_plugin_ffi::{
+    ABI_VERSION_SYMBOL, EVALUATE_SYMBOL, FREE_SYMBOL, METADATA_SYMBOL,
+};
+
+/// Version of the plugin ABI exported by compiled fingerprint crates.
+///
+/// Bump it whenever the exported symbols or the JSON they exchange change
+/// incompatibly; libraries reporting any other version are skipped.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Provenance a compiled fingerprint crate reports about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginMetadata {
+    pub crate_name: String,
+    pub fingerprint_id: String,
+    pub format: String,
+    pub parent: Option<String>,
+    pub compiler_version: String,
+    pub source_hash: String,
+    /// Canonical YAML of the compiled definition; absent from older crates.
+    #[serde(default)]
+    pub definition: Option<String>,
+}
+
+/// A document the host asks a plugin to evaluate.
+///
+/// The plugin reopens the document itself: Rust types do not cross the
+/// library boundary, only the path and the format dispatch already chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginRequest {
+    pub path: PathBuf,
+    /// Document kind, e.g. `xlsx` or `markdown`.
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_path: Option<PathBuf>,
+}
+
+impl PluginRequest {
+    fn for_document(doc: &Document) -> Self {
+        let text_path = match doc {
+            Document::Pdf(pdf) => pdf.text.as_ref().map(|text| text.path.clone()),
+            _ => None,
+        };
+        Self {
+            path: doc.path().to_path_buf(),
+            kind: doc.kind().as_str().to_owned(),
+            text_path,
+        }
+    }
+}
+
+/// A plugin's answer: the fingerprint result, or why it could not evaluate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginResponse {
+    Result(FingerprintResult),
+    Error(String),
+}
+
+/// Serialize metadata for a generated crate's `fingerprint_plugin_metadata` export.
+pub fn metadata_cstring(metadata: &PluginMetadata) -> CString {
+    let json = serde_json::to_string(metadata).expect("plugin metadata serialization");
+    CString::new(json).expect("JSON output never contains a NUL byte")
+}
+
+/// Answer a request for a generated crate's `fingerprint_plugin_evaluate`
+/// export with `PluginResponse` JSON; `request` is `None` for a null pointer.
+///
+/// Panics are caught and reported as errors so they never unwind across the
+/// library boundary.
+pub fn respond_json(fingerprint: &dyn Fingerprint, request: Option<&[u8]>) -> String {
+    let request = request
+        .ok_or_else(|| "plugin request is null".to_owned())
+        .and_then(|bytes| {
+            serde_json::from_slice::<PluginRequest>(bytes)
+                .map_err(|error| format!("invalid plugin request: {error}"))
+        });
+    let response = match request {
+        Ok(request) => respond(fingerprint, &request),
+        Err(error) => PluginResponse::Error(error),
+    };
+
+    serde_json::to_string(&response).unwrap_or_else(|error| {
+        serde_json::to_string(&PluginResponse::Error(format!(
+            "failed to serialize plugin response: {error}"
+        )))
+        .expect("error response serialization")
+    })
+}
+
+fn respond(fingerprint: &dyn Fingerprint, request: &PluginRequest) -> PluginResponse {
+    let document = match open_document_with_text_path(
+        &request.path,
+        &request.kind,
+        request.text_path.as_deref(),
+    ) {
+        Ok(document) => document,
+        Err(error) => {
+            return PluginResponse::Error(format!(
+                "failed to open '{}': {error}",
+                request.path.display()
+            ));
+        }
+    };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| fingerprint.fingerprint(&document))) {
+        Ok(result) => PluginResponse::Result(result),
+        Err(payload) => PluginResponse::Error(format!(
+            "fingerprint '{}' panicked: {}",
+            fingerprint.id(),
+            panic_message(payload.as_ref())
+        )),
+    }
+}
+
+/// Sends request JSON to a plugin and returns its response JSON.
+type EvaluateJson = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync>;
+
+/// A fingerprint evaluated by a compiled crate loaded at runtime.
+struct PluginFingerprint {
+    metadata: PluginMetadata,
+    evaluate: EvaluateJson,
+}
+
+impl PluginFingerprint {
+    fn call(&self, request: &PluginRequest) -> Result<FingerprintResult, String> {
+        let request = serde_json::to_vec(request)
+            .map_err(|error| format!("failed to encode plugin request: {error}"))?;
+        let response = (self.evaluate)(&request)
+            .map_err(|error| format!("plugin '{}': {error}", self.metadata.crate_name))?;
+
+        match serde_json::from_slice::<PluginResponse>(&response) {
+            Ok(PluginResponse::Result(result)) => Ok(result),
+            Ok(PluginResponse::Error(error)) => Err(error),
+            Err(error) => Err(format!(
+                "invalid response from plugin '{}': {error}",
+                self.metadata.crate_name
+            )),
+        }
+    }
+}
+
+impl Fingerprint for PluginFingerprint {
+    fn id(&self) -> &str {
+        &self.metadata.fingerprint_id
+    }
+
+    fn format(&self) -> &str {
+        &self.metadata.format
+    }
+
+    fn parent(&self) -> Option<&str> {
+        self.metadata.parent.as_deref()
+    }
+
+    fn definition_hash(&self) -> Option<&str> {
+        Some(&self.metadata.source_hash)
+    }
+
+    fn definition_source(&self) -> Option<&str> {
+        self.metadata.definition.as_deref()
+    }
+
+    fn fingerprint(&self, doc: &Document) -> FingerprintResult {
+        self.call(&PluginRequest::for_document(doc))
+            .unwrap_or_else(|error| FingerprintResult {
+                matched: false,
+                reason: Some(error),
+                assertions: Vec::new(),
+                extracted: None,
+                content_hash: None,
+            })
+    }
+}
+
+/// Discover compiled fingerprint crates from the `FINGERPRINT_PATH` directories.
+///
+/// `FINGERPRINT_PATH` lists directories in the platform's path syntax
+/// (colon-separated on Unix). Every shared library in them (`.so`, `.dylib`
+/// or `.dll`) is a candidate. Loading a library runs its code, so a library
+/// is loaded only when `allowlist` trusts it by content hash
+/// (`plugin:blake3:<hex>`) or canonical path (`plugin:<path>`); other
+/// libraries, and libraries that are not fingerprint plugins or were built
+/// for another plugin ABI version, are skipped with a warning.
+pub fn discover_plugins(allowlist: &[String]) -> Vec<DiscoveredFingerprint> {
+    match std::env::var_os("FINGERPRINT_PATH") {
+        Some(search_path) => discover_from_path(&search_path, allowlist),
+        None => Vec::new(),
+    }
+}
+
+fn discover_from_path(search_path: &OsStr, allowlist: &[String]) -> Vec<DiscoveredFingerprint> {
+    let mut discovered = Vec::new();
+
+    for dir in std::env::split_paths(search_path) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut libraries: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_shared_library(path))
+            .collect();
+        libraries.sort();
+
+        for path in libraries {
+            match load_plugin(&path, allowlist) {
+                Ok(plugin) => discovered.push(plugin),
+                Err(error) => {
+                    eprintln!("Warning: skipping plugin '{}': {}", path.display(), error);
+                }
+            }
+        }
+    }
+
+    discovered
+}
+
+fn is_shared_library(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+}
+
+/// A library read once for the trust check.
+struct LibraryFile {
+    bytes: Vec<u8>,
+    /// The trust entries the library can be allowlisted under, content hash
+    /// first.
+    identities: [String; 2],
+}
+
+/// Read the library at `path`, hashing the bytes that will be loaded.
+///
+/// The canonical path is that of the file actually opened: on Unix it must
+/// still name the opened file once resolved, so path-based trust cannot be
+/// satisfied by a file swapped in after the open.
+fn read_library(path: &Path) -> Result<LibraryFile, String> {
+    let mut file = File::open(path).map_err(|error| format!("failed to read library: {error}"))?;
+    let canonical = path
+        .canonicalize()
+        .map_err(|error| format!("failed to resolve library path: {error}"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let opened = file
+            .metadata()
+            .map_err(|error| format!("failed to read library: {error}"))?;
+        let named = std::fs::metadata(&canonical)
+            .map_err(|error| format!("failed to resolve library path: {error}"))?;
+        if (opened.dev(), opened.ino()) != (named.dev(), named.ino()) {
+            return Err("library was replaced while it was being read".to_owned());
+        }
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|error| format!("failed to read library: {error}"))?;
+    let identities = [
+        format!("plugin:blake3:{}", blake3::hash(&bytes).to_hex()),
+        format!("plugin:{}", canonical.display()),
+    ];
+    Ok(LibraryFile { bytes, identities })
+}
+
+/// The first identity of `library` that `allowlist` trusts.
+fn trusted_identity(library: &LibraryFile, allowlist: &[String]) -> Result<String, String> {
+    match library
+        .identities
+        .iter()
+        .find(|identity| is_allowlisted(identity, allowlist))
+    {
+        Some(identity) => Ok(identity.clone()),
+        None => Err(format!(
+            "not trusted; add \"{}\" to trust.yaml to load it",
+            library.identities[0]
+        )),
+    }
+}
+
+/// Distinguishes the staging directories of one process.
+static STAGED_LIBRARIES: AtomicUsize = AtomicUsize::new(0);
+
+/// A private copy of trusted library bytes, in a fresh directory only the
+/// current user can write to.
+///
+/// Loading the copy instead of the discovered file means a file swapped into
+/// a `FINGERPRINT_PATH` directory after the trust check is never mapped. The
+/// directory is removed on drop.
+struct StagedLibrary {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl StagedLibrary {
+    fn create(file_name: &OsStr, bytes: &[u8]) -> Result<Self, String> {
+        let dir = std::env::temp_dir().join(format!(
+            "fingerprint-plugin-{}-{}",
+            std::process::id(),
+            STAGED_LIBRARIES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        // `create` fails on an existing directory, so a directory planted in
+        // advance is never reused.
+        builder
+            .create(&dir)
+            .map_err(|error| format!("failed to stage library: {error}"))?;
+        let staged = Self {
+            path: dir.join(file_name),
+            dir,
+        };
+        std::fs::write(&staged.path, bytes)
+            .map_err(|error| format!("failed to stage library: {error}"))?;
+        Ok(staged)
+    }
+}
+
+impl Drop for StagedLibrary {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A loaded plugin and the staged copy it was loaded from.
+struct LoadedPlugin {
+    // Declared first so the library is unloaded before its copy is removed.
+    evaluator: ffi::Evaluator,
+    _staged: StagedLibrary,
+}
+
+impl LoadedPlugin {
+    fn call(&self, request: &[u8]) -> Result<Vec<u8>, String> {
+        self.evaluator.call(request)
+    }
+}
+
+fn load_plugin(path: &Path, allowlist: &[String]) -> Result<DiscoveredFingerprint, String> {
+    // Trust is decided before the library is mapped: loading runs its code.
+    // Only the bytes that were checked are loaded.
+    let file = read_library(path)?;
+    let source = trusted_identity(&file, allowlist)?;
+    let staged = StagedLibrary::create(path.file_name().unwrap_or_default(), &file.bytes)?;
+    let library = ffi::Plugin::open(&staged.path)?;
+
+    let abi_version = library.abi_version()?;
+    if abi_version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "plugin ABI version {abi_version} is not supported (expected {PLUGIN_ABI_VERSION}); rebuild it with this fingerprint release"
+        ));
+    }
+    let metadata: PluginMetadata = serde_json::from_slice(&library.metadata()?)
+        .map_err(|error| format!("invalid plugin metadata: {error}"))?;
+    let plugin = LoadedPlugin {
+        evaluator: library.into_evaluator()?,
+        _staged: staged,
+    };
+
+    Ok(plugin_fingerprint(
+        metadata,
+        source,
+        Box::new(move |request| plugin.call(request)),
+    ))
+}
+
+fn plugin_fingerprint(
+    metadata: PluginMetadata,
+    source: String,
+    evaluate: EvaluateJson,
+) -> DiscoveredFingerprint {
+    let info = FingerprintInfo {
+        id: metadata.fingerprint_id.clone(),
+        crate_name: metadata.crate_name.clone(),
+        version: metadata.compiler_version.clone(),
+        source,
+        format: metadata.format.clone(),
+        parent: metadata.parent.clone(),
+    };
+
+    (
+        Box::new(PluginFingerprint { metadata, evaluate }) as Box<dyn Fingerprint>,
+        info,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::core::AssertionResult;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Stands in for a generated crate's `GeneratedFingerprint`.
+    struct HeaderFingerprint;
+
+    impl Fingerprint for HeaderFingerprint {
+        fn id(&self) -> &str {
+            "header.v1"
+        }
+
+        fn format(&self) -> &str {
+            "csv"
+        }
+
+        fn fingerprint(&self, doc: &Document) -> FingerprintResult {
+            let contents = fs::read_to_string(doc.path()).unwrap_or_default();
+            if contents.contains("panic") {
+                panic!("header check exploded");
+            }
+            let passed = contents.starts_with("loan_id,");
+            FingerprintResult {
+                matched: passed,
+                reason: (!passed).then(|| "missing loan_id header".to_owned()),
+                assertions: vec![AssertionResult {
+                    name: "loan_id_header".to_owned(),
+                    passed,
+                    detail: None,
+                    context: None,
+                }],
+                extracted: None,
+                content_hash: None,
+            }
+        }
+    }
+
+    fn test_plugin() -> DiscoveredFingerprint {
+        plugin_fingerprint(
+            PluginMetadata {
+                crate_name: "header-v1".to_owned(),
+                fingerprint_id: "header.v1".to_owned(),
+                format: "csv".to_owned(),
+                parent: None,
+                compiler_version: "0.6.2".to_owned(),
+                source_hash: "blake3:abc".to_owned(),
+                definition: None,
+            },
+            "plugin:blake3:def".to_owned(),
+            // What a generated crate's evaluate export answers.
+            Box::new(|request| Ok(respond_json(&HeaderFingerprint, Some(request)).into_bytes())),
+        )
+    }
+
+    fn csv_document(dir: &TempDir, name: &str, contents: &str) -> Document {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).expect("write csv");
+        Document::Csv(crate::document::CsvDocument { path })
+    }
+
+    #[test]
+    fn plugin_fingerprint_round_trips_through_the_abi() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let (plugin, info) = test_plugin();
+
+        assert_eq!(info.source, "plugin:blake3:def");
+        assert_eq!(info.crate_name, "header-v1");
+        assert_eq!(info.version, "0.6.2");
+        assert_eq!(plugin.definition_hash(), Some("blake3:abc"));
+
+        let matched = plugin.fingerprint(&csv_document(&tmp, "tape.csv", "loan_id,balance\n"));
+        assert!(matched.matched);
+        assert_eq!(matched.assertions[0].name, "loan_id_header");
+
+        let unmatched = plugin.fingerprint(&csv_document(&tmp, "other.csv", "id,balance\n"));
+        assert!(!unmatched.matched);
+        assert_eq!(unmatched.reason.as_deref(), Some("missing loan_id header"));
+    }
+
+    #[test]
+    fn plugin_panics_and_open_failures_become_unmatched_results() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let (plugin, _) = test_plugin();
+
+        let panicked = plugin.fingerprint(&csv_document(&tmp, "bad.csv", "panic\n"));
+        assert!(!panicked.matched);
+        assert!(
+            panicked
+                .reason
+                .as_deref()
+                .is_some_and(|reason| reason.contains("header check exploded"))
+        );
+
+        let missing = Document::Xlsx(crate::document::XlsxDocument {
+            path: tmp.path().join("missing.xlsx"),
+        });
+        let failed = plugin.fingerprint(&missing);
+        assert!(!failed.matched);
+        assert!(
+            failed
+                .reason
+                .as_deref()
+                .is_some_and(|reason| reason.starts_with("failed to open"))
+        );
+    }
+
+    #[test]
+    fn respond_json_reports_null_and_invalid_requests() {
+        for (request, expected) in [
+            (None, "plugin request is null"),
+            (Some(b"{".as_slice()), "invalid plugin request"),
+        ] {
+            let response =
+                serde_json::from_str::<PluginResponse>(&respond_json(&HeaderFingerprint, request))
+                    .expect("response JSON");
+            assert!(
+                matches!(&response, PluginResponse::Error(error) if error.starts_with(expected)),
+                "{response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn libraries_are_trusted_by_content_hash_or_canonical_path() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let path = tmp
+            .path()
+            .join(format!("libheader.{}", std::env::consts::DLL_EXTENSION));
+        fs::write(&path, "library bytes").expect("write library");
+        let by_hash = format!("plugin:blake3:{}", blake3::hash(b"library bytes").to_hex());
+        let by_path = format!(
+            "plugin:{}",
+            path.canonicalize().expect("canonical").display()
+        );
+
+        let library = read_library(&path).expect("read library");
+        assert_eq!(
+            trusted_identity(&library, std::slice::from_ref(&by_hash)),
+            Ok(by_hash.clone())
+        );
+        assert_eq!(
+            trusted_identity(&library, std::slice::from_ref(&by_path)),
+            Ok(by_path.clone())
+        );
+        assert_eq!(
+            trusted_identity(&library, &["plugin:*".to_owned()]),
+            Ok(by_hash.clone())
+        );
+
+        let untrusted = trusted_identity(&library, &["plugin:header-v1".to_owned()])
+            .expect_err("crate names are not trust identities");
+        assert!(untrusted.contains(&by_hash), "{untrusted}");
+
+        fs::write(&path, "rebuilt library bytes").expect("rewrite library");
+        let rebuilt = read_library(&path).expect("read rebuilt library");
+        assert!(trusted_identity(&rebuilt, std::slice::from_ref(&by_hash)).is_err());
+    }
+
+    #[test]
+    fn staged_libraries_hold_the_checked_bytes_privately() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let path = tmp
+            .path()
+            .join(format!("libheader.{}", std::env::consts::DLL_EXTENSION));
+        fs::write(&path, "library bytes").expect("write library");
+        let library = read_library(&path).expect("read library");
+
+        let staged = StagedLibrary::create(path.file_name().expect("file name"), &library.bytes)
+            .expect("stage library");
+        fs::write(&path, "swapped library bytes").expect("swap library");
+        assert_eq!(
+            fs::read(&staged.path).expect("read staged library"),
+            b"library bytes"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&staged.dir)
+                .expect("staging dir metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let dir = staged.dir.clone();
+        drop(staged);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn discover_skips_files_that_are_not_plugins() {
+        let tmp = TempDir::new().expect("create temp dir");
+        fs::write(tmp.path().join("notes.txt"), "not a library").expect("write decoy");
+        fs::write(
+            tmp.path()
+                .join(format!("libbogus.{}", std::env::consts::DLL_EXTENSION)),
+            "not a library either",
+        )
+        .expect("write bogus library");
+
+        let search_path = std::env::join_paths([tmp.path(), Path::new("/nonexistent/plugins")])
+            .expect("join plugin path");
+        assert!(discover_from_path(&search_path, &[]).is_empty());
+        assert!(discover_from_path(&search_path, &["plugin:*".to_owned()]).is_empty());
+    }
+}
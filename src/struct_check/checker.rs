@@ -5,7 +5,8 @@ use globset::{Glob, GlobMatcher};
 use serde::Serialize;
 use serde_json::Value;
 
-use super::rules::Rule;
+use super::pattern::{GroupPattern, capture_names, substitute};
+use super::rules::{FilePattern, Rule};
 
 /// A single struct-check output record.
 #[derive(Debug, Clone, Serialize)]
@@ -14,13 +15,78 @@ pub struct CheckRecord {
     pub rule_id: String,
     pub group_pattern: String,
     pub matched_directory: String,
+    /// Values captured by `{name}` segments of `group_by`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub captures: BTreeMap<String, String>,
     pub outcome: String,
     pub present: Vec<String>,
     pub missing: Vec<String>,
     pub unexpected: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub present_fingerprints: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_fingerprints: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<PatternViolation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub present_groups: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_groups: Vec<String>,
     pub tool_versions: ToolVersions,
 }
 
+/// A file pattern whose match count is outside its bounds.
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternViolation {
+    pub pattern: String,
+    /// `too_few`, `too_many`, or `forbidden`.
+    pub kind: String,
+    /// Number of files matching `pattern`.
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
+    /// The matching files, sorted.
+    pub files: Vec<String>,
+}
+
+/// Totals over one struct-check run, emitted after the group records.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckSummary {
+    pub version: String,
+    pub rules_evaluated: usize,
+    pub groups: usize,
+    pub complete: usize,
+    pub partial: usize,
+    pub empty: usize,
+    pub invalid: usize,
+    pub tool_versions: ToolVersions,
+}
+
+impl CheckSummary {
+    pub fn new(rules: &[Rule], records: &[CheckRecord], version: &str) -> Self {
+        let count = |outcome: &str| {
+            records
+                .iter()
+                .filter(|record| record.outcome == outcome)
+                .count()
+        };
+        Self {
+            version: "struct-check-summary.v0".to_owned(),
+            rules_evaluated: rules.len(),
+            groups: records.len(),
+            complete: count("complete"),
+            partial: count("partial"),
+            empty: count("empty"),
+            invalid: count("invalid"),
+            tool_versions: ToolVersions {
+                fingerprint: version.to_owned(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ToolVersions {
     pub fingerprint: String,
@@ -37,12 +103,16 @@ pub enum InputError {
     RecordNotObject { line: u64 },
     /// The version field is missing.
     MissingVersion { line: u64 },
-    /// The version field is not "vacuum.v0".
+    /// The version field is neither "vacuum.v0" nor "fingerprint.v0".
     BadVersion { line: u64, version: String },
+    /// Rules require fingerprint IDs but the record is not "fingerprint.v0".
+    MissingFingerprints { line: u64 },
     /// The relative_path field is missing.
     MissingRelativePath { line: u64 },
     /// The relative_path field is not a string.
     InvalidRelativePath { line: u64 },
+    /// Walking an input directory failed.
+    WalkFailure(String),
 }
 
 impl std::fmt::Display for InputError {
@@ -59,7 +129,13 @@ impl std::fmt::Display for InputError {
             Self::BadVersion { line, version } => {
                 write!(
                     f,
-                    "line {line}: expected version \"vacuum.v0\", got \"{version}\""
+                    "line {line}: expected version \"vacuum.v0\" or \"fingerprint.v0\", got \"{version}\""
+                )
+            }
+            Self::MissingFingerprints { line } => {
+                write!(
+                    f,
+                    "line {line}: rules require fingerprint IDs, but the record is not fingerprint.v0; run fingerprint over the input first"
                 )
             }
             Self::MissingRelativePath { line } => {
@@ -68,17 +144,26 @@ impl std::fmt::Display for InputError {
             Self::InvalidRelativePath { line } => {
                 write!(f, "line {line}: field 'relative_path' must be a string")
             }
+            Self::WalkFailure(error) => write!(f, "failed walking input directory: {error}"),
         }
     }
 }
 
-/// Read vacuum.v0 JSONL records and extract (directory, filename) pairs.
+/// Files in one directory, each with the fingerprint IDs it matched.
+pub type DirectoryFiles = BTreeMap<String, BTreeSet<String>>;
+
+/// Read vacuum.v0 or fingerprint.v0 JSONL records and extract (directory,
+/// filename) pairs.
 ///
-/// Returns a map from directory path to the set of filenames in that directory.
+/// Returns a map from directory path to the files in that directory. Files
+/// from fingerprint.v0 records carry the IDs of the matched fingerprint and
+/// its matched children. With `require_fingerprints`, every record must be
+/// fingerprint.v0.
 pub fn read_vacuum_records(
     input: &mut dyn BufRead,
-) -> Result<BTreeMap<String, BTreeSet<String>>, InputError> {
-    let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    require_fingerprints: bool,
+) -> Result<BTreeMap<String, DirectoryFiles>, InputError> {
+    let mut groups: BTreeMap<String, DirectoryFiles> = BTreeMap::new();
     let mut line_number: u64 = 0;
     let mut line = String::new();
 
@@ -107,19 +192,24 @@ pub fn read_vacuum_records(
             .as_object()
             .ok_or(InputError::RecordNotObject { line: line_number })?;
 
-        // Validate version is "vacuum.v0"
-        match object.get("version") {
-            Some(Value::String(version)) => {
-                if version != "vacuum.v0" {
+        // Validate version is "vacuum.v0" or "fingerprint.v0"
+        let fingerprinted = match object.get("version") {
+            Some(Value::String(version)) => match version.as_str() {
+                "vacuum.v0" => false,
+                "fingerprint.v0" => true,
+                _ => {
                     return Err(InputError::BadVersion {
                         line: line_number,
                         version: version.clone(),
                     });
                 }
-            }
+            },
             Some(_) | None => {
                 return Err(InputError::MissingVersion { line: line_number });
             }
+        };
+        if require_fingerprints && !fingerprinted {
+            return Err(InputError::MissingFingerprints { line: line_number });
         }
 
         // Extract relative_path
@@ -139,68 +229,118 @@ pub fn read_vacuum_records(
             None => (String::new(), relative_path.to_owned()),
         };
 
-        groups.entry(dir).or_default().insert(filename);
+        let fingerprint_ids = groups.entry(dir).or_default().entry(filename).or_default();
+        if let Some(payload) = object.get("fingerprint") {
+            collect_matched_fingerprints(payload, fingerprint_ids);
+        }
     }
 
     Ok(groups)
 }
 
+/// Collect the IDs of a matched fingerprint payload and its matched children.
+fn collect_matched_fingerprints(payload: &Value, ids: &mut BTreeSet<String>) {
+    if payload.get("matched").and_then(Value::as_bool) != Some(true) {
+        return;
+    }
+    if let Some(id) = payload.get("fingerprint_id").and_then(Value::as_str) {
+        ids.insert(id.to_owned());
+    }
+    if let Some(Value::Array(children)) = payload.get("children") {
+        for child in children {
+            collect_matched_fingerprints(child, ids);
+        }
+    }
+}
+
 /// Check a set of directory groups against a list of rules.
 ///
-/// Returns sorted output records and whether all groups are complete.
+/// Returns sorted output records and whether all groups are complete, or an
+/// error naming the first glob in `rules` that does not compile.
 pub fn check_groups(
-    groups: &BTreeMap<String, BTreeSet<String>>,
+    groups: &BTreeMap<String, DirectoryFiles>,
     rules: &[Rule],
     version: &str,
-) -> (Vec<CheckRecord>, bool) {
+) -> Result<(Vec<CheckRecord>, bool), String> {
     let mut records = Vec::new();
     let mut all_complete = true;
+    let directories = all_directories(groups);
 
     for rule in rules {
-        let group_matcher = match Glob::new(&rule.group_by) {
-            Ok(glob) => glob.compile_matcher(),
-            Err(error) => {
-                eprintln!(
-                    "Warning: invalid group_by glob '{}' in rule '{}': {error}",
-                    rule.group_by, rule.id
-                );
+        let group_pattern = GroupPattern::new(&rule.group_by).map_err(|error| {
+            format!(
+                "rule '{}': invalid group_by glob '{}': {error}",
+                rule.id, rule.group_by
+            )
+        })?;
+
+        // Without captures the file patterns are the same for every group,
+        // so compile them once
+        let shared_matchers = capture_names(&rule.group_by)
+            .is_empty()
+            .then(|| RuleMatchers::new(rule, &BTreeMap::new()))
+            .transpose()?;
+
+        for (dir, files) in groups {
+            let Some(captures) = group_pattern.captures(dir) else {
                 continue;
-            }
-        };
-
-        // Build matchers for required patterns
-        let required_matchers: Vec<(String, GlobMatcher)> = rule
-            .required
-            .iter()
-            .filter_map(|pattern| {
-                Glob::new(pattern)
-                    .ok()
-                    .map(|glob| (pattern.clone(), glob.compile_matcher()))
-            })
-            .collect();
-
-        // Build matchers for optional patterns
-        let optional_matchers: Vec<GlobMatcher> = rule
-            .optional
-            .iter()
-            .filter_map(|pattern| Glob::new(pattern).ok().map(|glob| glob.compile_matcher()))
-            .collect();
-
-        for (dir, filenames) in groups {
-            if !group_matcher.is_match(dir) {
-                continue;
-            }
+            };
+            let group_matchers;
+            let matchers = match &shared_matchers {
+                Some(matchers) => matchers,
+                None => {
+                    group_matchers = RuleMatchers::new(rule, &captures)?;
+                    &group_matchers
+                }
+            };
 
             let mut present = Vec::new();
             let mut missing = Vec::new();
-
-            // Check each required pattern
-            for (pattern, matcher) in &required_matchers {
+            let mut violations = Vec::new();
+
+            // Count the files matching each required and optional pattern
+            for (is_required, matcher) in matchers
+                .required
+                .iter()
+                .map(|matcher| (true, matcher))
+                .chain(matchers.optional.iter().map(|matcher| (false, matcher)))
+            {
                 let matching_files: Vec<&String> =
-                    filenames.iter().filter(|f| matcher.is_match(f)).collect();
+                    files.keys().filter(|f| matcher.glob.is_match(f)).collect();
+                let count = matching_files.len();
+                if count == 0 && matcher.min > 0 {
+                    missing.push(matcher.pattern.clone());
+                } else if count < matcher.min {
+                    violations.push(PatternViolation::count(matcher, "too_few", &matching_files));
+                } else if matcher.max.is_some_and(|max| count > max) {
+                    violations.push(PatternViolation::count(
+                        matcher,
+                        "too_many",
+                        &matching_files,
+                    ));
+                }
+                if is_required {
+                    for file in matching_files {
+                        if !present.contains(file) {
+                            present.push(file.clone());
+                        }
+                    }
+                }
+            }
+
+            // Check each required fingerprint
+            let mut present_fingerprints = Vec::new();
+            let mut missing_fingerprints = Vec::new();
+            for fingerprint_id in &rule.required_fingerprints {
+                let matching_files: Vec<&String> = files
+                    .iter()
+                    .filter(|(_, ids)| ids.contains(fingerprint_id))
+                    .map(|(filename, _)| filename)
+                    .collect();
                 if matching_files.is_empty() {
-                    missing.push(pattern.clone());
+                    missing_fingerprints.push(fingerprint_id.clone());
                 } else {
+                    present_fingerprints.push(fingerprint_id.clone());
                     for file in matching_files {
                         if !present.contains(file) {
                             present.push(file.clone());
@@ -209,18 +349,72 @@ pub fn check_groups(
                 }
             }
 
-            // Find unexpected files (not matching any required or optional pattern)
+            // Report files matching forbidden patterns
+            let mut forbidden_files = BTreeSet::new();
+            for (pattern, matcher) in &matchers.forbidden {
+                let matching_files: Vec<String> = files
+                    .keys()
+                    .filter(|f| matcher.is_match(f))
+                    .cloned()
+                    .collect();
+                if !matching_files.is_empty() {
+                    forbidden_files.extend(matching_files.iter().cloned());
+                    violations.push(PatternViolation {
+                        pattern: pattern.clone(),
+                        kind: "forbidden".to_owned(),
+                        count: matching_files.len(),
+                        min: None,
+                        max: Some(0),
+                        files: matching_files,
+                    });
+                }
+            }
+
+            // Find unexpected files (not matching any required, optional or
+            // forbidden pattern, nor a required fingerprint)
             let mut unexpected = Vec::new();
-            for filename in filenames {
-                let matches_required = required_matchers.iter().any(|(_, m)| m.is_match(filename));
-                let matches_optional = optional_matchers.iter().any(|m| m.is_match(filename));
-                if !matches_required && !matches_optional {
+            for (filename, ids) in files {
+                let matches_pattern = matchers
+                    .required
+                    .iter()
+                    .chain(&matchers.optional)
+                    .any(|matcher| matcher.glob.is_match(filename));
+                let matches_fingerprint = rule
+                    .required_fingerprints
+                    .iter()
+                    .any(|fingerprint_id| ids.contains(fingerprint_id));
+                if !matches_pattern && !matches_fingerprint && !forbidden_files.contains(filename) {
                     unexpected.push(filename.clone());
                 }
             }
 
-            let outcome = if missing.is_empty() {
-                "complete"
+            // Check that each required counterpart directory exists
+            let mut present_groups = Vec::new();
+            let mut missing_groups = Vec::new();
+            for pattern in &rule.required_groups {
+                let pattern = substitute(pattern, &captures);
+                let matcher = compile_glob(rule, "required_groups", &pattern)?;
+                let matching: Vec<&String> = directories
+                    .iter()
+                    .filter(|directory| matcher.is_match(directory))
+                    .collect();
+                if matching.is_empty() {
+                    missing_groups.push(pattern);
+                } else {
+                    present_groups.extend(matching.into_iter().cloned());
+                }
+            }
+
+            let outcome = if missing.is_empty()
+                && missing_fingerprints.is_empty()
+                && missing_groups.is_empty()
+            {
+                if violations.is_empty() {
+                    "complete"
+                } else {
+                    all_complete = false;
+                    "invalid"
+                }
             } else if present.is_empty() {
                 all_complete = false;
                 "empty"
@@ -232,16 +426,27 @@ pub fn check_groups(
             present.sort();
             missing.sort();
             unexpected.sort();
+            present_fingerprints.sort();
+            missing_fingerprints.sort();
+            present_groups.sort();
+            present_groups.dedup();
+            missing_groups.sort();
 
             records.push(CheckRecord {
                 version: "struct-check.v0".to_owned(),
                 rule_id: rule.id.clone(),
                 group_pattern: rule.group_by.clone(),
                 matched_directory: dir.clone(),
+                captures,
                 outcome: outcome.to_owned(),
                 present,
                 missing,
                 unexpected,
+                present_fingerprints,
+                missing_fingerprints,
+                violations,
+                present_groups,
+                missing_groups,
                 tool_versions: ToolVersions {
                     fingerprint: version.to_owned(),
                 },
@@ -256,7 +461,98 @@ pub fn check_groups(
             .then_with(|| a.matched_directory.cmp(&b.matched_directory))
     });
 
-    (records, all_complete)
+    Ok((records, all_complete))
+}
+
+/// Every directory holding files, and every ancestor of one.
+fn all_directories(groups: &BTreeMap<String, DirectoryFiles>) -> BTreeSet<String> {
+    let mut directories = BTreeSet::new();
+    for dir in groups.keys() {
+        let mut end = dir.len();
+        while end > 0 && directories.insert(dir[..end].to_owned()) {
+            end = dir[..end].rfind('/').unwrap_or(0);
+        }
+    }
+    directories
+}
+
+/// A rule's file patterns compiled for one group, with `{name}` references
+/// replaced by the group's captures.
+struct RuleMatchers {
+    required: Vec<PatternMatcher>,
+    optional: Vec<PatternMatcher>,
+    forbidden: Vec<(String, GlobMatcher)>,
+}
+
+impl RuleMatchers {
+    fn new(rule: &Rule, captures: &BTreeMap<String, String>) -> Result<Self, String> {
+        Ok(Self {
+            required: pattern_matchers(rule, "required", &rule.required, 1, captures)?,
+            optional: pattern_matchers(rule, "optional", &rule.optional, 0, captures)?,
+            forbidden: rule
+                .forbidden
+                .iter()
+                .map(|pattern| {
+                    let pattern = substitute(pattern, captures);
+                    let matcher = compile_glob(rule, "forbidden", &pattern)?;
+                    Ok((pattern, matcher))
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+/// A compiled file pattern with its effective minimum count.
+struct PatternMatcher {
+    pattern: String,
+    glob: GlobMatcher,
+    min: usize,
+    max: Option<usize>,
+}
+
+fn pattern_matchers(
+    rule: &Rule,
+    field: &str,
+    patterns: &[FilePattern],
+    default_min: usize,
+    captures: &BTreeMap<String, String>,
+) -> Result<Vec<PatternMatcher>, String> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let substituted = substitute(&pattern.pattern, captures);
+            Ok(PatternMatcher {
+                glob: compile_glob(rule, field, &substituted)?,
+                pattern: substituted,
+                min: pattern.min_or(default_min),
+                max: pattern.max,
+            })
+        })
+        .collect()
+}
+
+fn compile_glob(rule: &Rule, field: &str, pattern: &str) -> Result<GlobMatcher, String> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|error| {
+            format!(
+                "rule '{}': invalid {field} glob '{pattern}': {error}",
+                rule.id
+            )
+        })
+}
+
+impl PatternViolation {
+    fn count(matcher: &PatternMatcher, kind: &str, files: &[&String]) -> Self {
+        Self {
+            pattern: matcher.pattern.clone(),
+            kind: kind.to_owned(),
+            count: files.len(),
+            min: Some(matcher.min),
+            max: matcher.max,
+            files: files.iter().map(|file| (*file).clone()).collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -271,12 +567,12 @@ mod tests {
 {"version":"vacuum.v0","relative_path":"org/packages/P002/report.pdf"}
 "#;
         let mut cursor = Cursor::new(input.as_bytes());
-        let groups = read_vacuum_records(&mut cursor).expect("read records");
+        let groups = read_vacuum_records(&mut cursor, false).expect("read records");
 
         assert_eq!(groups.len(), 2);
         assert_eq!(groups["org/packages/P001"].len(), 2);
-        assert!(groups["org/packages/P001"].contains("report.pdf"));
-        assert!(groups["org/packages/P001"].contains("jan_summary.xlsx"));
+        assert!(groups["org/packages/P001"].contains_key("report.pdf"));
+        assert!(groups["org/packages/P001"].contains_key("jan_summary.xlsx"));
         assert_eq!(groups["org/packages/P002"].len(), 1);
     }
 
@@ -284,7 +580,7 @@ mod tests {
     fn rejects_non_vacuum_version() {
         let input = r#"{"version":"hash.v0","relative_path":"a.pdf"}"#;
         let mut cursor = Cursor::new(input.as_bytes());
-        let error = read_vacuum_records(&mut cursor).expect_err("should reject");
+        let error = read_vacuum_records(&mut cursor, false).expect_err("should reject");
         assert!(error.to_string().contains("vacuum.v0"));
     }
 
@@ -292,26 +588,29 @@ mod tests {
     fn rejects_missing_relative_path() {
         let input = r#"{"version":"vacuum.v0","path":"a.pdf"}"#;
         let mut cursor = Cursor::new(input.as_bytes());
-        let error = read_vacuum_records(&mut cursor).expect_err("should reject");
+        let error = read_vacuum_records(&mut cursor, false).expect_err("should reject");
         assert!(error.to_string().contains("relative_path"));
     }
 
     #[test]
     fn check_groups_complete_outcome() {
         let mut groups = BTreeMap::new();
-        let mut files = BTreeSet::new();
-        files.insert("report.pdf".to_owned());
-        files.insert("jan_summary.xlsx".to_owned());
+        let mut files = DirectoryFiles::new();
+        files.insert("report.pdf".to_owned(), BTreeSet::new());
+        files.insert("jan_summary.xlsx".to_owned(), BTreeSet::new());
         groups.insert("org/packages/P001".to_owned(), files);
 
         let rules = vec![Rule {
             id: "pkg.v1".to_owned(),
             group_by: "*/packages/P*".to_owned(),
-            required: vec!["*.pdf".to_owned(), "*_summary.xlsx".to_owned()],
+            required: vec!["*.pdf".into(), "*_summary.xlsx".into()],
             optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec![],
         }];
 
-        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1");
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
         assert!(all_complete);
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].outcome, "complete");
@@ -321,18 +620,21 @@ mod tests {
     #[test]
     fn check_groups_partial_outcome() {
         let mut groups = BTreeMap::new();
-        let mut files = BTreeSet::new();
-        files.insert("report.pdf".to_owned());
+        let mut files = DirectoryFiles::new();
+        files.insert("report.pdf".to_owned(), BTreeSet::new());
         groups.insert("org/packages/P001".to_owned(), files);
 
         let rules = vec![Rule {
             id: "pkg.v1".to_owned(),
             group_by: "*/packages/P*".to_owned(),
-            required: vec!["*.pdf".to_owned(), "*_summary.xlsx".to_owned()],
+            required: vec!["*.pdf".into(), "*_summary.xlsx".into()],
             optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec![],
         }];
 
-        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1");
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
         assert!(!all_complete);
         assert_eq!(records[0].outcome, "partial");
         assert_eq!(records[0].missing, vec!["*_summary.xlsx"]);
@@ -341,18 +643,21 @@ mod tests {
     #[test]
     fn check_groups_empty_outcome() {
         let mut groups = BTreeMap::new();
-        let mut files = BTreeSet::new();
-        files.insert("draft.docx".to_owned());
+        let mut files = DirectoryFiles::new();
+        files.insert("draft.docx".to_owned(), BTreeSet::new());
         groups.insert("org/packages/P001".to_owned(), files);
 
         let rules = vec![Rule {
             id: "pkg.v1".to_owned(),
             group_by: "*/packages/P*".to_owned(),
-            required: vec!["*.pdf".to_owned(), "*_summary.xlsx".to_owned()],
+            required: vec!["*.pdf".into(), "*_summary.xlsx".into()],
             optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec![],
         }];
 
-        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1");
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
         assert!(!all_complete);
         assert_eq!(records[0].outcome, "empty");
         assert_eq!(records[0].unexpected, vec!["draft.docx"]);
@@ -361,21 +666,266 @@ mod tests {
     #[test]
     fn check_groups_detects_unexpected_files() {
         let mut groups = BTreeMap::new();
-        let mut files = BTreeSet::new();
-        files.insert("report.pdf".to_owned());
-        files.insert("jan_summary.xlsx".to_owned());
-        files.insert("draft.docx".to_owned());
+        let mut files = DirectoryFiles::new();
+        files.insert("report.pdf".to_owned(), BTreeSet::new());
+        files.insert("jan_summary.xlsx".to_owned(), BTreeSet::new());
+        files.insert("draft.docx".to_owned(), BTreeSet::new());
         groups.insert("org/packages/P001".to_owned(), files);
 
         let rules = vec![Rule {
             id: "pkg.v1".to_owned(),
             group_by: "*/packages/P*".to_owned(),
-            required: vec!["*.pdf".to_owned(), "*_summary.xlsx".to_owned()],
-            optional: vec!["*_notes.txt".to_owned()],
+            required: vec!["*.pdf".into(), "*_summary.xlsx".into()],
+            optional: vec!["*_notes.txt".into()],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec![],
         }];
 
-        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1");
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
         assert!(all_complete);
         assert_eq!(records[0].unexpected, vec!["draft.docx"]);
     }
+
+    #[test]
+    fn reads_matched_fingerprint_ids_from_enriched_records() {
+        let input = r#"{"version":"fingerprint.v0","relative_path":"props/A/rr.xlsx","fingerprint":{"fingerprint_id":"rent-roll.v2","matched":true,"children":[{"fingerprint_id":"rent-roll-yardi.v2","matched":true},{"fingerprint_id":"rent-roll-argus.v2","matched":false}]}}
+{"version":"fingerprint.v0","relative_path":"props/A/notes.pdf","fingerprint":{"fingerprint_id":"rent-roll.v2","matched":false}}
+{"version":"fingerprint.v0","relative_path":"props/A/photo.png","fingerprint":null}
+"#;
+        let mut cursor = Cursor::new(input.as_bytes());
+        let groups = read_vacuum_records(&mut cursor, true).expect("read records");
+
+        let files = &groups["props/A"];
+        assert_eq!(
+            files["rr.xlsx"].iter().collect::<Vec<_>>(),
+            vec!["rent-roll-yardi.v2", "rent-roll.v2"]
+        );
+        assert!(files["notes.pdf"].is_empty());
+        assert!(files["photo.png"].is_empty());
+    }
+
+    #[test]
+    fn rejects_vacuum_records_when_rules_require_fingerprints() {
+        let input = r#"{"version":"vacuum.v0","relative_path":"props/A/rr.xlsx"}"#;
+        let mut cursor = Cursor::new(input.as_bytes());
+        let error = read_vacuum_records(&mut cursor, true).expect_err("should reject");
+        assert!(error.to_string().contains("fingerprint.v0"));
+    }
+
+    #[test]
+    fn check_groups_reports_required_fingerprints_by_id() {
+        let mut groups = BTreeMap::new();
+        let mut complete = DirectoryFiles::new();
+        complete.insert(
+            "march.xlsx".to_owned(),
+            BTreeSet::from(["rent-roll.v2".to_owned()]),
+        );
+        complete.insert("appraisal.pdf".to_owned(), BTreeSet::new());
+        groups.insert("properties/A".to_owned(), complete);
+        let mut partial = DirectoryFiles::new();
+        partial.insert("rent_roll.xlsx".to_owned(), BTreeSet::new());
+        partial.insert("appraisal.pdf".to_owned(), BTreeSet::new());
+        groups.insert("properties/B".to_owned(), partial);
+
+        let rules = vec![Rule {
+            id: "property.v1".to_owned(),
+            group_by: "properties/*".to_owned(),
+            required: vec!["*.pdf".into()],
+            optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec!["rent-roll.v2".to_owned()],
+            required_groups: vec![],
+        }];
+
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
+        assert!(!all_complete);
+
+        assert_eq!(records[0].matched_directory, "properties/A");
+        assert_eq!(records[0].outcome, "complete");
+        assert_eq!(records[0].present, vec!["appraisal.pdf", "march.xlsx"]);
+        assert_eq!(records[0].present_fingerprints, vec!["rent-roll.v2"]);
+        assert!(records[0].missing_fingerprints.is_empty());
+        assert!(records[0].unexpected.is_empty());
+
+        assert_eq!(records[1].matched_directory, "properties/B");
+        assert_eq!(records[1].outcome, "partial");
+        assert!(records[1].present_fingerprints.is_empty());
+        assert_eq!(records[1].missing_fingerprints, vec!["rent-roll.v2"]);
+        assert_eq!(records[1].unexpected, vec!["rent_roll.xlsx"]);
+    }
+
+    #[test]
+    fn check_groups_enforces_counts_and_forbidden_patterns() {
+        let mut groups = BTreeMap::new();
+        let mut files = DirectoryFiles::new();
+        for name in [
+            "appraisal_2023.pdf",
+            "appraisal_2024.pdf",
+            "jan_operating_statement.xlsx",
+            "loan_FINAL.pdf",
+            "loan_FINAL_v2.pdf",
+            "~$budget.xlsx",
+            "scratch.tmp",
+        ] {
+            files.insert(name.to_owned(), BTreeSet::new());
+        }
+        groups.insert("deals/D1".to_owned(), files);
+
+        let rules = vec![Rule {
+            id: "data-room.v1".to_owned(),
+            group_by: "deals/*".to_owned(),
+            required: vec![
+                FilePattern {
+                    pattern: "*appraisal*.pdf".to_owned(),
+                    min: Some(1),
+                    max: Some(1),
+                },
+                FilePattern {
+                    pattern: "*operating_statement*.xlsx".to_owned(),
+                    min: Some(2),
+                    max: Some(12),
+                },
+            ],
+            optional: vec![FilePattern {
+                pattern: "*FINAL*".to_owned(),
+                min: None,
+                max: Some(1),
+            }],
+            forbidden: vec!["*.tmp".to_owned(), "~$*".to_owned()],
+            required_fingerprints: vec![],
+            required_groups: vec![],
+        }];
+
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
+        assert!(!all_complete);
+        let record = &records[0];
+        assert_eq!(record.outcome, "invalid");
+        assert!(record.missing.is_empty());
+        assert!(record.unexpected.is_empty());
+
+        let kinds: Vec<(&str, &str, usize)> = record
+            .violations
+            .iter()
+            .map(|v| (v.pattern.as_str(), v.kind.as_str(), v.count))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("*appraisal*.pdf", "too_many", 2),
+                ("*operating_statement*.xlsx", "too_few", 1),
+                ("*FINAL*", "too_many", 2),
+                ("*.tmp", "forbidden", 1),
+                ("~$*", "forbidden", 1),
+            ]
+        );
+        assert_eq!(
+            record.violations[0].files,
+            vec!["appraisal_2023.pdf", "appraisal_2024.pdf"]
+        );
+        assert_eq!(record.violations[1].min, Some(2));
+        assert_eq!(record.violations[3].max, Some(0));
+    }
+
+    #[test]
+    fn check_groups_substitutes_captures_and_checks_required_groups() {
+        let mut groups = BTreeMap::new();
+        let mut a = DirectoryFiles::new();
+        a.insert("A1_rent_roll.xlsx".to_owned(), BTreeSet::new());
+        groups.insert("deals/D1/properties/A1".to_owned(), a);
+        let mut b = DirectoryFiles::new();
+        b.insert("A1_rent_roll.xlsx".to_owned(), BTreeSet::new());
+        groups.insert("deals/D1/properties/B2".to_owned(), b);
+        let mut financials = DirectoryFiles::new();
+        financials.insert("2024.xlsx".to_owned(), BTreeSet::new());
+        groups.insert("deals/D1/financials/A1/annual".to_owned(), financials);
+
+        let rules = vec![Rule {
+            id: "property-docs.v1".to_owned(),
+            group_by: "deals/{deal}/properties/{property}".to_owned(),
+            required: vec!["{property}_rent_roll.xlsx".into()],
+            optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec!["deals/{deal}/financials/{property}".to_owned()],
+        }];
+
+        let (records, all_complete) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
+        assert!(!all_complete);
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].matched_directory, "deals/D1/properties/A1");
+        assert_eq!(
+            records[0].captures,
+            BTreeMap::from([
+                ("deal".to_owned(), "D1".to_owned()),
+                ("property".to_owned(), "A1".to_owned()),
+            ])
+        );
+        assert_eq!(records[0].outcome, "complete");
+        assert_eq!(records[0].present, vec!["A1_rent_roll.xlsx"]);
+        assert_eq!(records[0].present_groups, vec!["deals/D1/financials/A1"]);
+
+        assert_eq!(records[1].matched_directory, "deals/D1/properties/B2");
+        assert_eq!(records[1].outcome, "empty");
+        assert_eq!(records[1].missing, vec!["B2_rent_roll.xlsx"]);
+        assert_eq!(records[1].unexpected, vec!["A1_rent_roll.xlsx"]);
+        assert_eq!(records[1].missing_groups, vec!["deals/D1/financials/B2"]);
+    }
+
+    #[test]
+    fn summary_counts_outcomes() {
+        let mut groups = BTreeMap::new();
+        let mut complete = DirectoryFiles::new();
+        complete.insert("report.pdf".to_owned(), BTreeSet::new());
+        groups.insert("org/packages/P001".to_owned(), complete);
+        let mut empty = DirectoryFiles::new();
+        empty.insert("draft.docx".to_owned(), BTreeSet::new());
+        groups.insert("org/packages/P002".to_owned(), empty);
+
+        let rules = vec![Rule {
+            id: "pkg.v1".to_owned(),
+            group_by: "*/packages/P*".to_owned(),
+            required: vec!["*.pdf".into()],
+            optional: vec![],
+            forbidden: vec![],
+            required_fingerprints: vec![],
+            required_groups: vec![],
+        }];
+
+        let (records, _) = check_groups(&groups, &rules, "0.5.1").expect("check groups");
+        let summary = CheckSummary::new(&rules, &records, "0.5.1");
+
+        assert_eq!(summary.version, "struct-check-summary.v0");
+        assert_eq!(summary.rules_evaluated, 1);
+        assert_eq!(summary.groups, 2);
+        assert_eq!(summary.complete, 1);
+        assert_eq!(summary.partial, 0);
+        assert_eq!(summary.empty, 1);
+        assert_eq!(summary.invalid, 0);
+    }
+
+    #[test]
+    fn check_groups_refuses_invalid_globs() {
+        let mut groups = BTreeMap::new();
+        let mut files = DirectoryFiles::new();
+        files.insert("report.pdf".to_owned(), BTreeSet::new());
+        groups.insert("org/packages/P001".to_owned(), files);
+
+        let rules = vec![Rule {
+            id: "pkg.v1".to_owned(),
+            group_by: "*/packages/P*".to_owned(),
+            required: vec!["*.pdf".into()],
+            optional: vec![],
+            forbidden: vec!["[tmp".to_owned()],
+            required_fingerprints: vec![],
+            required_groups: vec![],
+        }];
+
+        let error = check_groups(&groups, &rules, "0.5.1").expect_err("invalid glob");
+        assert!(
+            error.starts_with("rule 'pkg.v1': invalid forbidden glob '[tmp'"),
+            "{error}"
+        );
+    }
 }
@@ -1,2 +1,4 @@
 pub mod checker;
+pub mod pattern;
 pub mod rules;
+pub mod walk;
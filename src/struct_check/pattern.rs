@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use globset::Glob;
+use regex::bytes::Regex;
+
+/// A `group_by` glob with named captures, e.g.
+/// `deals/{deal}/properties/{property}`.
+///
+/// `{name}` captures one path segment, or part of one; everything else is
+/// glob syntax, including `{a,b}` alternation.
+#[derive(Debug, Clone)]
+pub struct GroupPattern {
+    regex: Regex,
+    names: Vec<String>,
+}
+
+impl GroupPattern {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let names = capture_names(pattern);
+        if let Some(name) = names
+            .iter()
+            .enumerate()
+            .find_map(|(index, name)| names[..index].contains(name).then_some(name))
+        {
+            return Err(format!("capture '{{{name}}}' appears more than once"));
+        }
+
+        // Stand-ins globset keeps as literals, swapped for capture groups
+        // in the compiled regex.
+        let mut glob = pattern.to_owned();
+        for (index, name) in names.iter().enumerate() {
+            glob = glob.replacen(&format!("{{{name}}}"), &placeholder(index), 1);
+        }
+        let glob = Glob::new(&glob).map_err(|error| error.to_string())?;
+        let mut regex = glob.regex().to_owned();
+        for (index, name) in names.iter().enumerate() {
+            regex = regex.replacen(&placeholder(index), &format!("(?P<{name}>[^/]+)"), 1);
+        }
+        let regex = Regex::new(&regex).map_err(|error| error.to_string())?;
+
+        Ok(Self { regex, names })
+    }
+
+    /// Captured values when `dir` matches, keyed by capture name.
+    pub fn captures(&self, dir: &str) -> Option<BTreeMap<String, String>> {
+        let captures = self.regex.captures(dir.as_bytes())?;
+        Some(
+            self.names
+                .iter()
+                .filter_map(|name| {
+                    let value = captures.name(name)?;
+                    Some((
+                        name.clone(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    ))
+                })
+                .collect(),
+        )
+    }
+}
+
+fn placeholder(index: usize) -> String {
+    format!("fpcapture{index}x")
+}
+
+/// Names of the `{name}` captures in a pattern, in order.
+///
+/// Braces holding anything but an identifier, such as `{a,b}`, are glob
+/// alternations rather than captures.
+pub fn capture_names(pattern: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let candidate = &rest[..end];
+        if is_identifier(candidate) {
+            names.push(candidate.to_owned());
+            rest = &rest[end + 1..];
+        }
+    }
+    names
+}
+
+fn is_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replace `{name}` references to captured values, escaped so they match
+/// literally. Braces naming no capture are left for glob alternation.
+pub fn substitute(pattern: &str, captures: &BTreeMap<String, String>) -> String {
+    let mut substituted = pattern.to_owned();
+    for (name, value) in captures {
+        substituted = substituted.replace(&format!("{{{name}}}"), &globset::escape(value));
+    }
+    substituted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_named_segments() {
+        let pattern = GroupPattern::new("deals/{deal}/properties/{property}").expect("pattern");
+
+        let captures = pattern
+            .captures("deals/D-17/properties/Main St")
+            .expect("matches");
+
+        assert_eq!(captures["deal"], "D-17");
+        assert_eq!(captures["property"], "Main St");
+        assert!(pattern.captures("deals/D-17/properties").is_none());
+        assert!(pattern.captures("deals/D-17/properties/A/photos").is_none());
+    }
+
+    #[test]
+    fn keeps_glob_syntax_around_captures() {
+        let pattern = GroupPattern::new("*/packages/P{period}").expect("pattern");
+        let captures = pattern.captures("org/packages/P202401").expect("matches");
+        assert_eq!(captures["period"], "202401");
+
+        let alternation = GroupPattern::new("{north,south}/{site}").expect("pattern");
+        assert_eq!(
+            alternation.captures("south/S1").expect("matches")["site"],
+            "S1"
+        );
+        assert!(alternation.captures("east/S1").is_none());
+
+        let plain = GroupPattern::new("*/packages/P*").expect("pattern");
+        assert_eq!(
+            plain.captures("org/packages/P1").expect("matches"),
+            BTreeMap::new()
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_capture_names() {
+        let error = GroupPattern::new("{deal}/{deal}").expect_err("duplicate capture");
+        assert_eq!(error, "capture '{deal}' appears more than once");
+    }
+
+    #[test]
+    fn substitutes_captures_as_literals() {
+        let captures = BTreeMap::from([("property".to_owned(), "Lot [7]".to_owned())]);
+
+        assert_eq!(
+            substitute("{property}_rent_roll.{xlsx,xls}", &captures),
+            "Lot [[]7[]]_rent_roll.{xlsx,xls}"
+        );
+    }
+}
@@ -1,12 +1,26 @@
+use globset::Glob;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::Path;
 
+use super::pattern::{GroupPattern, capture_names, substitute};
+
 /// Top-level rules file structure (`.sf.yaml`).
 #[derive(Debug, Clone, Deserialize)]
 pub struct RulesFile {
     pub rules: Vec<Rule>,
 }
 
+impl RulesFile {
+    /// Whether any rule requires fingerprint IDs, so input must be
+    /// `fingerprint.v0`.
+    pub fn requires_fingerprints(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| !rule.required_fingerprints.is_empty())
+    }
+}
+
 /// A single struct-check rule.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Rule {
@@ -14,15 +28,86 @@ pub struct Rule {
     pub id: String,
 
     /// Glob pattern matched against directory paths to select groups.
+    ///
+    /// `{name}` segments capture part of the path; file patterns and
+    /// `required_groups` can refer to the captured values as `{name}`.
     pub group_by: String,
 
-    /// File patterns that must be present (at least one file matching each).
+    /// File patterns that must be present (at least one file matching each,
+    /// unless `min` says otherwise).
     #[serde(default)]
-    pub required: Vec<String>,
+    pub required: Vec<FilePattern>,
 
     /// File patterns that are allowed but not required.
     #[serde(default)]
-    pub optional: Vec<String>,
+    pub optional: Vec<FilePattern>,
+
+    /// File patterns that must not be present.
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+
+    /// Fingerprint IDs that must be present (at least one file matching each).
+    #[serde(default)]
+    pub required_fingerprints: Vec<String>,
+
+    /// Directory globs, usually built from captures, that must match at
+    /// least one directory in the input for each group.
+    #[serde(default)]
+    pub required_groups: Vec<String>,
+}
+
+/// A file glob with optional bounds on how many files may match it.
+///
+/// Written either as a bare glob or as `{ pattern, min, max }`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "FilePatternEntry")]
+pub struct FilePattern {
+    pub pattern: String,
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl FilePattern {
+    /// Minimum number of matching files: `min`, else `default_min`.
+    pub fn min_or(&self, default_min: usize) -> usize {
+        self.min.unwrap_or(default_min)
+    }
+}
+
+impl From<&str> for FilePattern {
+    fn from(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            min: None,
+            max: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FilePatternEntry {
+    Glob(String),
+    Counted {
+        pattern: String,
+        #[serde(default)]
+        min: Option<usize>,
+        #[serde(default)]
+        max: Option<usize>,
+    },
+}
+
+impl From<FilePatternEntry> for FilePattern {
+    fn from(entry: FilePatternEntry) -> Self {
+        match entry {
+            FilePatternEntry::Glob(pattern) => Self {
+                pattern,
+                min: None,
+                max: None,
+            },
+            FilePatternEntry::Counted { pattern, min, max } => Self { pattern, min, max },
+        }
+    }
 }
 
 /// Parse a `.sf.yaml` rules file from a path.
@@ -34,9 +119,99 @@ pub fn parse_rules_file(path: &Path) -> Result<RulesFile, String> {
     if rules_file.rules.is_empty() {
         return Err(format!("rules file '{}' contains no rules", path.display()));
     }
+    for rule in &rules_file.rules {
+        check_bounds(rule)
+            .and_then(|()| check_capture_references(rule))
+            .and_then(|()| check_globs(rule))
+            .map_err(|error| format!("invalid rules file '{}': {error}", path.display()))?;
+    }
     Ok(rules_file)
 }
 
+/// Reject patterns whose `min` exceeds their `max`; required patterns
+/// default to `min: 1`.
+fn check_bounds(rule: &Rule) -> Result<(), String> {
+    let required = rule.required.iter().map(|pattern| (pattern, 1));
+    let optional = rule.optional.iter().map(|pattern| (pattern, 0));
+    for (pattern, default_min) in required.chain(optional) {
+        let min = pattern.min_or(default_min);
+        if let Some(max) = pattern.max
+            && min > max
+        {
+            return Err(format!(
+                "rule '{}': pattern '{}' has min {min} greater than max {max}",
+                rule.id, pattern.pattern
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Every pattern of a rule that can refer to captures, with the field it
+/// comes from.
+fn capture_patterns(rule: &Rule) -> impl Iterator<Item = (&'static str, &str)> {
+    rule.required
+        .iter()
+        .map(|pattern| ("required", pattern.pattern.as_str()))
+        .chain(
+            rule.optional
+                .iter()
+                .map(|pattern| ("optional", pattern.pattern.as_str())),
+        )
+        .chain(
+            rule.forbidden
+                .iter()
+                .map(|pattern| ("forbidden", pattern.as_str())),
+        )
+        .chain(
+            rule.required_groups
+                .iter()
+                .map(|pattern| ("required_groups", pattern.as_str())),
+        )
+}
+
+/// Reject file patterns and `required_groups` entries that refer to names
+/// `group_by` does not capture.
+fn check_capture_references(rule: &Rule) -> Result<(), String> {
+    let captured = capture_names(&rule.group_by);
+    for (field, pattern) in capture_patterns(rule) {
+        if let Some(name) = capture_names(pattern)
+            .into_iter()
+            .find(|name| !captured.contains(name))
+        {
+            return Err(format!(
+                "rule '{}': {field} pattern '{pattern}' uses '{{{name}}}', which group_by does not capture",
+                rule.id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reject globs that do not compile. Captured values are substituted as
+/// escaped literals, so a pattern that compiles here compiles for every group.
+fn check_globs(rule: &Rule) -> Result<(), String> {
+    GroupPattern::new(&rule.group_by).map_err(|error| {
+        format!(
+            "rule '{}': invalid group_by glob '{}': {error}",
+            rule.id, rule.group_by
+        )
+    })?;
+    let captures: BTreeMap<String, String> = capture_names(&rule.group_by)
+        .into_iter()
+        .map(|name| (name, "capture".to_owned()))
+        .collect();
+    for (field, pattern) in capture_patterns(rule) {
+        Glob::new(&substitute(pattern, &captures)).map_err(|error| {
+            format!(
+                "rule '{}': invalid {field} glob '{pattern}': {error}",
+                rule.id
+            )
+        })?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,5 +246,187 @@ rules:
         let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
         assert!(rules_file.rules[0].required.is_empty());
         assert!(rules_file.rules[0].optional.is_empty());
+        assert!(rules_file.rules[0].forbidden.is_empty());
+        assert!(rules_file.rules[0].required_fingerprints.is_empty());
+        assert!(!rules_file.requires_fingerprints());
+    }
+
+    #[test]
+    fn parses_counted_and_forbidden_patterns() {
+        let yaml = r#"
+rules:
+  - id: data-room.v1
+    group_by: "deals/*"
+    required:
+      - pattern: "*appraisal*.pdf"
+        min: 1
+        max: 1
+      - pattern: "*operating_statement*.xlsx"
+        max: 12
+      - "*.pdf"
+    optional:
+      - pattern: "*FINAL*"
+        max: 1
+    forbidden:
+      - "*.tmp"
+      - "~$*"
+"#;
+        let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
+        let rule = &rules_file.rules[0];
+        assert_eq!(
+            rule.required[0],
+            FilePattern {
+                pattern: "*appraisal*.pdf".to_owned(),
+                min: Some(1),
+                max: Some(1),
+            }
+        );
+        assert_eq!(rule.required[1].min_or(1), 1);
+        assert_eq!(rule.required[1].max, Some(12));
+        assert_eq!(rule.required[2], FilePattern::from("*.pdf"));
+        assert_eq!(rule.optional[0].min_or(0), 0);
+        assert_eq!(rule.optional[0].max, Some(1));
+        assert_eq!(rule.forbidden, vec!["*.tmp", "~$*"]);
+        assert!(check_bounds(rule).is_ok());
+    }
+
+    #[test]
+    fn rejects_min_greater_than_max() {
+        let yaml = r#"
+rules:
+  - id: data-room.v1
+    group_by: "deals/*"
+    required:
+      - pattern: "*appraisal*.pdf"
+        max: 0
+"#;
+        let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
+        let error = check_bounds(&rules_file.rules[0]).expect_err("min above max");
+        assert_eq!(
+            error,
+            "rule 'data-room.v1': pattern '*appraisal*.pdf' has min 1 greater than max 0"
+        );
+    }
+
+    #[test]
+    fn parses_captures_and_required_groups() {
+        let yaml = r#"
+rules:
+  - id: property-docs.v1
+    group_by: "deals/{deal}/properties/{property}"
+    required:
+      - "{property}_rent_roll.xlsx"
+    required_groups:
+      - "deals/{deal}/financials/{property}"
+"#;
+        let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
+        let rule = &rules_file.rules[0];
+        assert_eq!(
+            rule.required_groups,
+            vec!["deals/{deal}/financials/{property}"]
+        );
+        assert!(check_capture_references(rule).is_ok());
+        assert!(check_globs(rule).is_ok());
+    }
+
+    #[test]
+    fn rejects_required_groups_with_uncaptured_names() {
+        let yaml = r#"
+rules:
+  - id: property-docs.v1
+    group_by: "deals/{deal}/properties/*"
+    required_groups:
+      - "deals/{deal}/financials/{property}"
+"#;
+        let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
+        let error = check_capture_references(&rules_file.rules[0]).expect_err("uncaptured name");
+        assert!(error.contains("uses '{property}'"), "{error}");
+    }
+
+    #[test]
+    fn rejects_file_patterns_with_uncaptured_names() {
+        for (field, entry, pattern) in [
+            (
+                "required",
+                "required:\n      - \"{property}_rent_roll.xlsx\"",
+                "{property}_rent_roll.xlsx",
+            ),
+            (
+                "optional",
+                "optional:\n      - pattern: \"{property}_notes.txt\"\n        max: 1",
+                "{property}_notes.txt",
+            ),
+            (
+                "forbidden",
+                "forbidden:\n      - \"{property}.tmp\"",
+                "{property}.tmp",
+            ),
+        ] {
+            let yaml = format!(
+                "rules:\n  - id: property-docs.v1\n    group_by: \"deals/{{deal}}/properties/*\"\n    {entry}\n"
+            );
+            let rules_file: RulesFile = serde_yaml::from_str(&yaml).expect("parse rules");
+            let error =
+                check_capture_references(&rules_file.rules[0]).expect_err("uncaptured name");
+            assert_eq!(
+                error,
+                format!(
+                    "rule 'property-docs.v1': {field} pattern '{pattern}' uses '{{property}}', which group_by does not capture"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_globs() {
+        for (entry, expected) in [
+            (
+                "required:\n      - \"[abc.pdf\"",
+                "invalid required glob '[abc.pdf'",
+            ),
+            (
+                "forbidden:\n      - \"{tmp,bak\"",
+                "invalid forbidden glob '{tmp,bak'",
+            ),
+            (
+                "required_groups:\n      - \"deals/{deal}/[x\"",
+                "invalid required_groups glob 'deals/{deal}/[x'",
+            ),
+        ] {
+            let yaml = format!(
+                "rules:\n  - id: data-room.v1\n    group_by: \"deals/{{deal}}\"\n    {entry}\n"
+            );
+            let rules_file: RulesFile = serde_yaml::from_str(&yaml).expect("parse rules");
+            let error = check_globs(&rules_file.rules[0]).expect_err("invalid glob");
+            assert!(error.contains(expected), "{error}");
+        }
+
+        let rules_file: RulesFile =
+            serde_yaml::from_str("rules:\n  - id: data-room.v1\n    group_by: \"deals/[*\"\n")
+                .expect("parse rules");
+        let error = check_globs(&rules_file.rules[0]).expect_err("invalid group_by");
+        assert!(
+            error.contains("invalid group_by glob 'deals/[*'"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn parses_required_fingerprints() {
+        let yaml = r#"
+rules:
+  - id: property-folder.v1
+    group_by: "properties/*"
+    required:
+      - "*.pdf"
+    required_fingerprints:
+      - rent-roll.v2
+"#;
+        let rules_file: RulesFile = serde_yaml::from_str(yaml).expect("parse rules");
+        assert_eq!(
+            rules_file.rules[0].required_fingerprints,
+            vec!["rent-roll.v2"]
+        );
+        assert!(rules_file.requires_fingerprints());
     }
 }
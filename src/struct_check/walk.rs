@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use ignore::WalkBuilder;
+
+use super::checker::{DirectoryFiles, InputError};
+
+/// How `walk_directory` treats ignore files and symlinks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkOptions {
+    /// Follow symlinks to files and directories instead of skipping them.
+    pub follow_symlinks: bool,
+    /// Include paths excluded by `.gitignore`/`.ignore` files under the root.
+    pub no_ignore: bool,
+}
+
+/// Walk a directory tree into the same groups `read_vacuum_records` builds,
+/// keyed by directory path relative to `root` with `/` separators.
+///
+/// Only `.gitignore` and `.ignore` files inside the tree apply: hidden files
+/// are kept except `.git`, which is never walked, and global, `.git/info/exclude` and parent-directory ignore
+/// files are not read, so the result depends only on the tree. Entries that
+/// cannot be read, and symlink loops, are returned as warnings alongside the
+/// groups instead of ending the walk. Files carry no fingerprint IDs.
+pub fn walk_directory(
+    root: &Path,
+    options: WalkOptions,
+) -> Result<(BTreeMap<String, DirectoryFiles>, Vec<String>), InputError> {
+    if !root.is_dir() {
+        return Err(InputError::WalkFailure(format!(
+            "'{}' is not a directory",
+            root.display()
+        )));
+    }
+
+    let mut groups: BTreeMap<String, DirectoryFiles> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    let walker = WalkBuilder::new(root)
+        .standard_filters(false)
+        .git_ignore(!options.no_ignore)
+        .ignore(!options.no_ignore)
+        .require_git(false)
+        .follow_links(options.follow_symlinks)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                warnings.push(error.to_string());
+                continue;
+            }
+        };
+        // Unfollowed symlinks report their own file type, so they are skipped
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(root).map_err(|error| {
+            InputError::WalkFailure(format!("{}: {error}", entry.path().display()))
+        })?;
+        let mut components = Vec::new();
+        for component in relative.components() {
+            let component = component.as_os_str().to_str().ok_or_else(|| {
+                InputError::WalkFailure(format!(
+                    "{}: path is not valid UTF-8",
+                    entry.path().display()
+                ))
+            })?;
+            components.push(component);
+        }
+        let Some(filename) = components.pop() else {
+            continue;
+        };
+
+        groups
+            .entry(components.join("/"))
+            .or_default()
+            .entry(filename.to_owned())
+            .or_default();
+    }
+
+    Ok((groups, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn touch(root: &Path, relative: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dirs");
+        fs::write(path, b"").expect("write file");
+    }
+
+    #[test]
+    fn groups_files_by_relative_directory() {
+        let root = TempDir::new().expect("temp dir");
+        touch(root.path(), "org/packages/P001/report.pdf");
+        touch(root.path(), "org/packages/P001/jan_summary.xlsx");
+        touch(root.path(), "org/packages/P002/report.pdf");
+        touch(root.path(), "top.txt");
+
+        let (groups, warnings) = walk_directory(root.path(), WalkOptions::default()).expect("walk");
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups["org/packages/P001"].len(), 2);
+        assert!(groups["org/packages/P001"].contains_key("jan_summary.xlsx"));
+        assert!(groups["org/packages/P001"]["report.pdf"].is_empty());
+        assert!(groups["org/packages/P002"].contains_key("report.pdf"));
+        assert!(groups[""].contains_key("top.txt"));
+    }
+
+    #[test]
+    fn honors_ignore_files_unless_disabled() {
+        let root = TempDir::new().expect("temp dir");
+        touch(root.path(), "deals/D1/appraisal.pdf");
+        touch(root.path(), "deals/D1/scratch.tmp");
+        touch(root.path(), "deals/D1/.DS_Store");
+        touch(root.path(), "deals/D1/draft.bak");
+        touch(root.path(), ".git/HEAD");
+        touch(root.path(), ".git/objects/ab/cdef");
+        fs::write(root.path().join(".gitignore"), "*.tmp\n").expect("write .gitignore");
+        fs::write(root.path().join("deals/.ignore"), "*.bak\n").expect("write .ignore");
+
+        let (groups, _) = walk_directory(root.path(), WalkOptions::default()).expect("walk");
+        assert_eq!(
+            groups["deals/D1"].keys().collect::<Vec<_>>(),
+            vec![".DS_Store", "appraisal.pdf"]
+        );
+        assert!(!groups.keys().any(|group| group.starts_with(".git")));
+
+        let options = WalkOptions {
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        let (groups, _) = walk_directory(root.path(), options).expect("walk");
+        assert_eq!(groups["deals/D1"].len(), 4);
+        assert!(groups[""].contains_key(".gitignore"));
+        assert!(!groups.keys().any(|group| group.starts_with(".git")));
+    }
+
+    #[test]
+    fn ignores_ignore_files_outside_the_root() {
+        let parent = TempDir::new().expect("temp dir");
+        fs::write(parent.path().join(".gitignore"), "*.pdf\n").expect("write .gitignore");
+        fs::write(parent.path().join(".ignore"), "deals/\n").expect("write .ignore");
+        let root = parent.path().join("tree");
+        touch(&root, "deals/D1/appraisal.pdf");
+
+        let (groups, warnings) = walk_directory(&root, WalkOptions::default()).expect("walk");
+        assert!(groups["deals/D1"].contains_key("appraisal.pdf"));
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_symlinks_unless_followed() {
+        let root = TempDir::new().expect("temp dir");
+        let outside = TempDir::new().expect("temp dir");
+        touch(outside.path(), "shared/report.pdf");
+        touch(root.path(), "deals/D1/appraisal.pdf");
+        std::os::unix::fs::symlink(outside.path().join("shared"), root.path().join("deals/D2"))
+            .expect("symlink dir");
+
+        let (groups, _) = walk_directory(root.path(), WalkOptions::default()).expect("walk");
+        assert!(!groups.contains_key("deals/D2"));
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let (groups, _) = walk_directory(root.path(), options).expect("walk");
+        assert!(groups["deals/D2"].contains_key("report.pdf"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_symlink_loops_and_keeps_walking() {
+        let root = TempDir::new().expect("temp dir");
+        touch(root.path(), "deals/D1/appraisal.pdf");
+        touch(root.path(), "deals/D2/appraisal.pdf");
+        std::os::unix::fs::symlink(root.path().join("deals"), root.path().join("deals/D1/loop"))
+            .expect("symlink loop");
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let (groups, warnings) = walk_directory(root.path(), options).expect("walk");
+
+        assert!(groups["deals/D1"].contains_key("appraisal.pdf"));
+        assert!(groups["deals/D2"].contains_key("appraisal.pdf"));
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("loop"), "{warnings:?}");
+    }
+
+    #[test]
+    fn refuses_a_file_root() {
+        let root = TempDir::new().expect("temp dir");
+        touch(root.path(), "a.pdf");
+
+        let error = walk_directory(&root.path().join("a.pdf"), WalkOptions::default())
+            .expect_err("not a directory");
+
+        assert!(error.to_string().contains("is not a directory"), "{error}");
+    }
+}
@@ -0,0 +1,333 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::witness::ledger::{self, sibling_path};
+use crate::witness::record::WitnessRecord;
+
+pub const CLASSIFICATION_VERSION: &str = "witness-classification.v0";
+
+/// How one input record was classified by a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Classification {
+    pub path: Option<String>,
+    pub bytes_hash: String,
+    /// The matched fingerprint, or null when none matched.
+    pub fingerprint_id: Option<String>,
+    /// `child_routing.status` of a routed parent.
+    pub child_route: Option<String>,
+    pub child_fingerprint_id: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// A classification linked to the witness record of the run that made it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassificationEntry {
+    pub version: String,
+    /// `id` of the run's witness record.
+    pub run: String,
+    pub ts: String,
+    #[serde(flatten)]
+    pub classification: Classification,
+}
+
+impl Classification {
+    /// Read a `fingerprint.v0` output record. Records without a
+    /// `bytes_hash`, such as skipped ones, are not classifications.
+    pub fn from_output(record: &Value) -> Option<Self> {
+        let string = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned)
+        };
+        let bytes_hash = string(record, "bytes_hash")?;
+        let fingerprint = record.get("fingerprint").unwrap_or(&Value::Null);
+        let matched = fingerprint
+            .get("matched")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let routing = fingerprint.get("child_routing").unwrap_or(&Value::Null);
+
+        Some(Self {
+            path: string(record, "path"),
+            bytes_hash,
+            fingerprint_id: string(fingerprint, "fingerprint_id").filter(|_| matched),
+            child_route: string(routing, "status"),
+            child_fingerprint_id: string(routing, "selected_child_fingerprint_id"),
+            content_hash: string(fingerprint, "content_hash"),
+        })
+    }
+}
+
+/// Path of a ledger's classification stream: `<ledger>.classifications`.
+pub fn classifications_path(ledger_path: &Path) -> PathBuf {
+    sibling_path(ledger_path, "classifications")
+}
+
+/// Classifications of a run in progress, spooled to a temporary file next
+/// to the ledger so memory does not grow with the manifest. The file is
+/// removed when the spool is dropped, whether or not it was appended.
+pub struct Spool {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Spool {
+    /// Create `<ledger>.classifications.<pid>.tmp`, so concurrent runs
+    /// spool separately.
+    pub fn create(ledger_path: &Path) -> Result<Self, String> {
+        if let Some(parent) = ledger_path.parent() {
+            fs::create_dir_all(parent).map_err(|error| {
+                format!(
+                    "failed to create witness directory '{}': {error}",
+                    parent.display()
+                )
+            })?;
+        }
+        let path = sibling_path(
+            ledger_path,
+            &format!("classifications.{}.tmp", std::process::id()),
+        );
+        let file = File::create(&path).map_err(|error| {
+            format!(
+                "failed to create classification spool '{}': {error}",
+                path.display()
+            )
+        })?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn push(&mut self, classification: &Classification) -> Result<(), String> {
+        let json = serde_json::to_string(classification)
+            .map_err(|error| format!("failed to serialize witness classification: {error}"))?;
+        writeln!(self.writer, "{json}").map_err(|error| {
+            format!(
+                "failed to write classification spool '{}': {error}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Append a run's spooled classifications, linked to its witness record,
+/// under the ledger's lock.
+pub fn append(ledger_path: &Path, run: &WitnessRecord, mut spool: Spool) -> Result<(), String> {
+    let spool_error = |error: std::io::Error| {
+        format!(
+            "failed to read classification spool '{}': {error}",
+            spool.path.display()
+        )
+    };
+    spool.writer.flush().map_err(spool_error)?;
+    let spooled = BufReader::new(File::open(&spool.path).map_err(spool_error)?);
+
+    let path = classifications_path(ledger_path);
+    let append_error = |error: std::io::Error| {
+        format!(
+            "failed to append witness classifications to '{}': {error}",
+            path.display()
+        )
+    };
+    let _lock = ledger::lock(ledger_path)?;
+    let mut output = None;
+    for line in spooled.lines() {
+        let line = line.map_err(spool_error)?;
+        let classification: Classification = serde_json::from_str(&line)
+            .map_err(|error| format!("invalid spooled witness classification: {error}"))?;
+        let entry = ClassificationEntry {
+            version: CLASSIFICATION_VERSION.to_owned(),
+            run: run.id.clone(),
+            ts: run.ts.clone(),
+            classification,
+        };
+        let json = serde_json::to_string(&entry)
+            .map_err(|error| format!("failed to serialize witness classification: {error}"))?;
+
+        // Opened on the first line, so a run without classifications
+        // creates no file
+        let output = match &mut output {
+            Some(output) => output,
+            None => output.insert(BufWriter::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(append_error)?,
+            )),
+        };
+        writeln!(output, "{json}").map_err(append_error)?;
+    }
+    match output {
+        Some(mut output) => output.flush().map_err(append_error),
+        None => Ok(()),
+    }
+}
+
+/// Every recorded classification of the document with this `bytes_hash`,
+/// oldest first.
+pub fn history(ledger_path: &Path, bytes_hash: &str) -> Result<Vec<ClassificationEntry>, String> {
+    let path = classifications_path(ledger_path);
+    let _lock = ledger::lock_shared(ledger_path);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            return Err(format!(
+                "failed to open witness classifications '{}': {error}",
+                path.display()
+            ));
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (line_index, line_result) in BufReader::new(file).lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line_result.map_err(|error| {
+            format!(
+                "failed to read witness classifications '{}' at line {line_number}: {error}",
+                path.display()
+            )
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: ClassificationEntry = serde_json::from_str(&line).map_err(|error| {
+            format!(
+                "invalid witness classification at '{}' line {line_number}: {error}",
+                path.display()
+            )
+        })?;
+        if entry.classification.bytes_hash == bytes_hash {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(ts: &str) -> WitnessRecord {
+        WitnessRecord::new(
+            "0.1.0",
+            "blake3:binary",
+            vec![],
+            json!({ "fingerprints": ["bdc-soi.v1"] }),
+            "ALL_MATCHED",
+            0,
+            "blake3:output",
+            ts,
+        )
+        .expect("build witness record")
+    }
+
+    #[test]
+    fn reads_matches_routes_and_no_matches_from_output() {
+        let routed = Classification::from_output(&json!({
+            "path": "/data/soi.html",
+            "bytes_hash": "sha256:soi",
+            "fingerprint": {
+                "fingerprint_id": "bdc-soi.v1",
+                "matched": true,
+                "child_routing": {
+                    "status": "selected",
+                    "selected_child_fingerprint_id": "bdc-soi-ares.v1"
+                },
+                "content_hash": "blake3:content"
+            }
+        }))
+        .expect("classification");
+        assert_eq!(routed.fingerprint_id.as_deref(), Some("bdc-soi.v1"));
+        assert_eq!(routed.child_route.as_deref(), Some("selected"));
+        assert_eq!(
+            routed.child_fingerprint_id.as_deref(),
+            Some("bdc-soi-ares.v1")
+        );
+        assert_eq!(routed.content_hash.as_deref(), Some("blake3:content"));
+
+        let unmatched = Classification::from_output(&json!({
+            "bytes_hash": "sha256:other",
+            "fingerprint": { "fingerprint_id": "argus-model.v1", "matched": false, "content_hash": null }
+        }))
+        .expect("classification");
+        assert_eq!(unmatched.fingerprint_id, None);
+        assert_eq!(unmatched.content_hash, None);
+
+        assert_eq!(
+            Classification::from_output(&json!({ "path": "/data/big.pdf", "_skipped": true })),
+            None
+        );
+    }
+
+    #[test]
+    fn history_returns_a_documents_classifications_across_runs() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let classification = |bytes_hash: &str, fingerprint_id: Option<&str>| Classification {
+            path: None,
+            bytes_hash: bytes_hash.to_owned(),
+            fingerprint_id: fingerprint_id.map(ToOwned::to_owned),
+            child_route: None,
+            child_fingerprint_id: None,
+            content_hash: None,
+        };
+        let first = run("2026-02-01T00:00:00Z");
+        let second = run("2026-02-02T00:00:00Z");
+
+        let spool = |classifications: &[Classification]| {
+            let mut spool = Spool::create(&ledger_path).expect("create spool");
+            for classification in classifications {
+                spool.push(classification).expect("spool classification");
+            }
+            spool
+        };
+
+        let first_spool = spool(&[
+            classification("sha256:a", Some("csv.v0")),
+            classification("sha256:b", None),
+        ]);
+        let spool_path = first_spool.path.clone();
+        assert!(spool_path.exists());
+        append(&ledger_path, &first, first_spool).expect("append first run");
+        assert!(!spool_path.exists(), "the spool is removed once appended");
+        append(
+            &ledger_path,
+            &second,
+            spool(&[classification("sha256:a", None)]),
+        )
+        .expect("append second run");
+
+        let entries = history(&ledger_path, "sha256:a").expect("read history");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].run, first.id);
+        assert_eq!(
+            entries[0].classification.fingerprint_id.as_deref(),
+            Some("csv.v0")
+        );
+        assert_eq!(entries[1].run, second.id);
+        assert_eq!(entries[1].ts, "2026-02-02T00:00:00Z");
+        assert_eq!(entries[1].classification.fingerprint_id, None);
+        assert!(
+            history(&ledger_path, "sha256:missing")
+                .expect("read history")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::witness::ledger::{self, Segment, segments, sibling_path};
+
+/// One ledger record's filterable fields and where the record is stored.
+///
+/// The field names match the records themselves, so index entries go
+/// through the same filters as full records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: Option<String>,
+    pub tool: Option<String>,
+    pub outcome: Option<String>,
+    /// `ts`, or a legacy record's `created_ts`.
+    pub ts: Option<String>,
+    pub inputs: Vec<IndexInput>,
+    /// Segment number; see `ledger::segments`.
+    pub segment: u32,
+    /// Byte offset of the record's line in its segment.
+    pub offset: u64,
+    /// Length of the line, including its newline.
+    pub len: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexInput {
+    pub hash: Option<String>,
+}
+
+impl IndexEntry {
+    fn from_record(record: &Value, segment: u32, offset: u64, len: u64) -> Self {
+        let string = |key: &str| {
+            record
+                .get(key)
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned)
+        };
+        Self {
+            id: string("id"),
+            tool: string("tool"),
+            outcome: string("outcome"),
+            ts: string("ts").or_else(|| string("created_ts")),
+            inputs: record
+                .get("inputs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|input| IndexInput {
+                    hash: input
+                        .get("hash")
+                        .and_then(Value::as_str)
+                        .map(ToOwned::to_owned),
+                })
+                .collect(),
+            segment,
+            offset,
+            len,
+        }
+    }
+
+    /// Read the full record this entry points at.
+    pub fn read_record(&self, ledger_path: &Path) -> Result<Value, String> {
+        let segment = segments(ledger_path)?
+            .into_iter()
+            .find(|segment| segment.number == self.segment)
+            .ok_or_else(|| format!("witness segment {} no longer exists", self.segment))?;
+        let mut file = File::open(&segment.path).map_err(|error| {
+            format!(
+                "failed to open witness ledger '{}': {error}",
+                segment.path.display()
+            )
+        })?;
+        let mut line = vec![0; self.len as usize];
+        file.seek(SeekFrom::Start(self.offset))
+            .and_then(|_| file.read_exact(&mut line))
+            .map_err(|error| {
+                format!(
+                    "failed to read witness ledger '{}' at byte {}: {error}",
+                    segment.path.display(),
+                    self.offset
+                )
+            })?;
+        serde_json::from_slice(&line).map_err(|error| {
+            format!(
+                "invalid witness JSON at '{}' byte {}: {error}",
+                segment.path.display(),
+                self.offset
+            )
+        })
+    }
+}
+
+/// Path of a ledger's index: `<ledger>.idx`.
+pub fn index_path(ledger_path: &Path) -> PathBuf {
+    sibling_path(ledger_path, "idx")
+}
+
+/// Index entries for every record in the ledger, in ledger order.
+///
+/// The stored index is brought up to date with the segments first: records
+/// appended without updating it (for instance by older versions) are
+/// scanned from the end of what it covers, and a segment that no longer
+/// matches its entries is rescanned. Returns whether the stored index was
+/// out of date.
+pub fn load(ledger_path: &Path) -> Result<(Vec<IndexEntry>, bool), String> {
+    let stored = read_index(&index_path(ledger_path));
+    let mut by_segment: BTreeMap<u32, Vec<IndexEntry>> = BTreeMap::new();
+    for entry in stored {
+        by_segment.entry(entry.segment).or_default().push(entry);
+    }
+
+    let mut entries = Vec::new();
+    let mut stale = false;
+    for segment in segments(ledger_path)? {
+        let existing = by_segment.remove(&segment.number).unwrap_or_default();
+        let size = match fs::metadata(&segment.path) {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == ErrorKind::NotFound => 0,
+            Err(error) => {
+                return Err(format!(
+                    "failed to read witness ledger '{}': {error}",
+                    segment.path.display()
+                ));
+            }
+        };
+
+        // Keep the entries that still fit, in order, within the segment
+        let mut end = 0;
+        let mut kept = 0;
+        for entry in &existing {
+            if entry.offset < end || entry.offset + entry.len > size {
+                break;
+            }
+            end = entry.offset + entry.len;
+            kept += 1;
+        }
+        // Entries that stop mid-line no longer describe this file
+        if kept > 0 && end < size && !ends_line(&segment, end)? {
+            kept = 0;
+            end = 0;
+        }
+        stale |= kept < existing.len();
+        entries.extend(existing.into_iter().take(kept));
+
+        if end < size {
+            let scanned = scan_segment(&segment, end)?;
+            stale |= !scanned.is_empty();
+            entries.extend(scanned);
+        }
+    }
+    stale |= !by_segment.is_empty();
+
+    Ok((entries, stale))
+}
+
+/// Index entries for a reader that holds no lock on the ledger, with the
+/// lock it should hold while reading records through them.
+///
+/// When the ledger's lock is free, the reader takes it exclusively and
+/// writes back an index that was out of date, so the next reader does not
+/// rescan. When an append or another reader holds it, the reader takes it
+/// shared and rebuilds the index in memory only.
+pub fn load_for_read(ledger_path: &Path) -> Result<(Vec<IndexEntry>, Option<File>), String> {
+    if let Some(lock) = ledger::try_lock(ledger_path) {
+        let (entries, stale) = load(ledger_path)?;
+        if stale {
+            // Like after an append, a failed write is repaired on a later load
+            let _ = write(ledger_path, &entries);
+        }
+        return Ok((entries, Some(lock)));
+    }
+
+    let lock = ledger::lock_shared(ledger_path);
+    let (entries, _) = load(ledger_path)?;
+    Ok((entries, lock))
+}
+
+/// Replace the stored index.
+pub fn write(ledger_path: &Path, entries: &[IndexEntry]) -> Result<(), String> {
+    let path = index_path(ledger_path);
+    let temp = sibling_path(ledger_path, "idx.tmp");
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&entry_line(entry)?);
+    }
+
+    fs::write(&temp, contents)
+        .and_then(|()| fs::rename(&temp, &path))
+        .map_err(|error| {
+            format!(
+                "failed to write witness index '{}': {error}",
+                path.display()
+            )
+        })
+}
+
+/// Add one entry to the stored index.
+pub fn append(ledger_path: &Path, entry: &IndexEntry) -> Result<(), String> {
+    let path = index_path(ledger_path);
+    let line = entry_line(entry)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|error| {
+            format!(
+                "failed to append to witness index '{}': {error}",
+                path.display()
+            )
+        })
+}
+
+/// Build the entry for a record line written to a segment at `offset`.
+pub fn entry_for_line(line: &str, segment: u32, offset: u64) -> Result<IndexEntry, String> {
+    let record = serde_json::from_str::<Value>(line)
+        .map_err(|error| format!("invalid witness JSON: {error}"))?;
+    Ok(IndexEntry::from_record(
+        &record,
+        segment,
+        offset,
+        line.len() as u64,
+    ))
+}
+
+fn entry_line(entry: &IndexEntry) -> Result<String, String> {
+    let json = serde_json::to_string(entry)
+        .map_err(|error| format!("failed to serialize witness index entry: {error}"))?;
+    Ok(format!("{json}\n"))
+}
+
+/// Whether the byte before `end` is a newline.
+fn ends_line(segment: &Segment, end: u64) -> Result<bool, String> {
+    let mut byte = [0];
+    File::open(&segment.path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::Start(end - 1))?;
+            file.read_exact(&mut byte)
+        })
+        .map_err(|error| {
+            format!(
+                "failed to read witness ledger '{}': {error}",
+                segment.path.display()
+            )
+        })?;
+    Ok(byte[0] == b'\n')
+}
+
+/// Stored entries, or none when the index is missing or unreadable, in
+/// which case it is rebuilt from the ledger.
+fn read_index(path: &Path) -> Vec<IndexEntry> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()
+        .unwrap_or_default()
+}
+
+/// Index the records in a segment from byte `start`. A final line without
+/// a newline is still being written and is left for later. Lines that are
+/// not JSON get no entry, so a corrupt line neither hides the records after
+/// it nor blocks appends, which chain to the last indexed record;
+/// `witness verify` reports it as a break once the chain has started.
+fn scan_segment(segment: &Segment, start: u64) -> Result<Vec<IndexEntry>, String> {
+    let mut file = File::open(&segment.path).map_err(|error| {
+        format!(
+            "failed to open witness ledger '{}': {error}",
+            segment.path.display()
+        )
+    })?;
+    file.seek(SeekFrom::Start(start)).map_err(|error| {
+        format!(
+            "failed to read witness ledger '{}': {error}",
+            segment.path.display()
+        )
+    })?;
+
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::new();
+    let mut offset = start;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).map_err(|error| {
+            format!(
+                "failed to read witness ledger '{}' at byte {offset}: {error}",
+                segment.path.display()
+            )
+        })?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        if let Ok(record) = serde_json::from_slice::<Value>(&line) {
+            entries.push(IndexEntry::from_record(
+                &record,
+                segment.number,
+                offset,
+                read as u64,
+            ));
+        }
+        offset += read as u64;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record_line(id: &str, pad: &str) -> String {
+        format!(
+            "{}\n",
+            json!({
+                "id": id,
+                "tool": "fingerprint",
+                "outcome": "ALL_MATCHED",
+                "inputs": [{ "path": "m.jsonl", "hash": "blake3:in" }],
+                "ts": "2026-01-01T00:00:00Z",
+                "pad": pad
+            })
+        )
+    }
+
+    #[test]
+    fn load_scans_an_unindexed_ledger_and_reads_records_back() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let long = "x".repeat(20_000);
+        fs::write(
+            &ledger_path,
+            format!(
+                "{}\n{}\n",
+                record_line("first", &long),
+                record_line("last", "")
+            ),
+        )
+        .expect("write ledger");
+
+        let (entries, stale) = load(&ledger_path).expect("load index");
+
+        assert!(stale);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].id.as_deref(), Some("last"));
+        assert_eq!(entries[1].inputs[0].hash.as_deref(), Some("blake3:in"));
+        let record = entries[0].read_record(&ledger_path).expect("read record");
+        assert_eq!(record["pad"], long);
+    }
+
+    #[test]
+    fn load_catches_up_with_unindexed_appends_and_skips_partial_lines() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(&ledger_path, record_line("first", "")).expect("write ledger");
+        let (entries, _) = load(&ledger_path).expect("load index");
+        write(&ledger_path, &entries).expect("write index");
+
+        let (entries, stale) = load(&ledger_path).expect("reload index");
+        assert!(!stale);
+        assert_eq!(entries.len(), 1);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&ledger_path)
+            .expect("open ledger");
+        file.write_all(record_line("second", "").as_bytes())
+            .expect("append record");
+        file.write_all(b"{\"id\":\"partial\"")
+            .expect("append partial line");
+
+        let (entries, stale) = load(&ledger_path).expect("reload index");
+        assert!(stale);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.id.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("first"), Some("second")]
+        );
+    }
+
+    #[test]
+    fn load_skips_lines_that_are_not_json() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(
+            &ledger_path,
+            format!(
+                "{}{{\"id\":\"torn\n{}",
+                record_line("first", ""),
+                record_line("last", "")
+            ),
+        )
+        .expect("write ledger");
+
+        let (entries, _) = load(&ledger_path).expect("load index");
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.id.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("first"), Some("last")]
+        );
+        let record = entries[1].read_record(&ledger_path).expect("read record");
+        assert_eq!(record["id"], "last");
+    }
+
+    #[test]
+    fn load_for_read_writes_back_a_stale_index_when_the_lock_is_free() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(&ledger_path, record_line("first", "")).expect("write ledger");
+
+        {
+            let _reader = ledger::lock_shared(&ledger_path).expect("shared lock");
+            let (entries, _lock) = load_for_read(&ledger_path).expect("load for read");
+            assert_eq!(entries.len(), 1);
+        }
+        let (_, stale) = load(&ledger_path).expect("load index");
+        assert!(stale, "a busy ledger's index is not written");
+
+        let (entries, _lock) = load_for_read(&ledger_path).expect("load for read");
+        assert_eq!(entries.len(), 1);
+        let (_, stale) = load(&ledger_path).expect("load index");
+        assert!(!stale);
+    }
+
+    #[test]
+    fn load_rescans_a_segment_that_shrank() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(
+            &ledger_path,
+            format!("{}{}", record_line("a", ""), record_line("b", "")),
+        )
+        .expect("write ledger");
+        let (entries, _) = load(&ledger_path).expect("load index");
+        write(&ledger_path, &entries).expect("write index");
+
+        // One byte longer than the first record, so its entry still fits
+        fs::write(&ledger_path, record_line("rr", "")).expect("rewrite ledger");
+
+        let (entries, stale) = load(&ledger_path).expect("reload index");
+        assert!(stale);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id.as_deref(), Some("rr"));
+    }
+}
@@ -1,10 +1,64 @@
+use crate::witness::index;
 use crate::witness::record::WitnessRecord;
-use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use chrono::{DateTime, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
 
-/// Append a witness record to the ledger file.
-pub fn append(ledger_path: &Path, record: &WitnessRecord) -> Result<(), String> {
+/// When the active ledger file is moved aside into a numbered segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    Never,
+    /// Before an append would grow the active file past this many bytes.
+    Size(u64),
+    /// Before the first append on a new UTC day.
+    Daily,
+}
+
+impl Rotation {
+    fn is_due(self, size: u64, line_len: usize, last_ts: Option<&str>, ts: &str) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Size(max_bytes) => size.saturating_add(line_len as u64) > max_bytes,
+            Self::Daily => {
+                let day = |ts: &str| {
+                    DateTime::parse_from_rfc3339(ts)
+                        .ok()
+                        .map(|ts| ts.with_timezone(&Utc).date_naive())
+                };
+                last_ts
+                    .and_then(day)
+                    .zip(day(ts))
+                    .is_some_and(|(last, now)| last != now)
+            }
+        }
+    }
+}
+
+/// One file of a ledger: a rotated segment `<ledger>.<number>`, or the
+/// active ledger itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub number: u32,
+    pub path: PathBuf,
+}
+
+/// Append a witness record to the ledger, chained to the ledger's last
+/// record and rotated as `$EPISTEMIC_WITNESS_ROTATE` asks.
+///
+/// Returns the record as written, with `prev` set and its ID recomputed.
+pub fn append(ledger_path: &Path, record: &WitnessRecord) -> Result<WitnessRecord, String> {
+    append_with_rotation(ledger_path, record, rotation()?)
+}
+
+/// Append a witness record while holding the ledger's lock, so concurrent
+/// runs neither interleave lines nor fork the chain.
+pub fn append_with_rotation(
+    ledger_path: &Path,
+    record: &WitnessRecord,
+    rotation: Rotation,
+) -> Result<WitnessRecord, String> {
     if let Some(parent) = ledger_path.parent() {
         fs::create_dir_all(parent).map_err(|error| {
             format!(
@@ -13,6 +67,49 @@ pub fn append(ledger_path: &Path, record: &WitnessRecord) -> Result<(), String>
             )
         })?;
     }
+    let _lock = lock(ledger_path)?;
+
+    let (mut entries, stale) = index::load(ledger_path)?;
+    let prev = entries
+        .last()
+        .map(|entry| {
+            entry.id.clone().ok_or_else(|| {
+                format!(
+                    "last record of witness ledger '{}' has no id",
+                    ledger_path.display()
+                )
+            })
+        })
+        .transpose()?;
+    let record = record.clone().chained_to(prev)?;
+    let line = record.to_jsonl()?;
+
+    let mut active = segments(ledger_path)?
+        .pop()
+        .expect("segments end with the active ledger");
+    let mut size = match fs::metadata(ledger_path) {
+        Ok(metadata) => metadata.len(),
+        Err(error) if error.kind() == ErrorKind::NotFound => 0,
+        Err(error) => {
+            return Err(format!(
+                "failed to read witness ledger '{}': {error}",
+                ledger_path.display()
+            ));
+        }
+    };
+    let last_ts = entries.last().and_then(|entry| entry.ts.as_deref());
+    if size > 0 && rotation.is_due(size, line.len(), last_ts, &record.ts) {
+        let rotated = sibling_path(ledger_path, &active.number.to_string());
+        fs::rename(ledger_path, &rotated).map_err(|error| {
+            format!(
+                "failed to rotate witness ledger '{}' to '{}': {error}",
+                ledger_path.display(),
+                rotated.display()
+            )
+        })?;
+        active.number += 1;
+        size = 0;
+    }
 
     let mut file = OpenOptions::new()
         .create(true)
@@ -24,8 +121,6 @@ pub fn append(ledger_path: &Path, record: &WitnessRecord) -> Result<(), String>
                 ledger_path.display()
             )
         })?;
-
-    let line = record.to_jsonl()?;
     file.write_all(line.as_bytes()).map_err(|error| {
         format!(
             "failed to append witness record to '{}': {error}",
@@ -39,7 +134,154 @@ pub fn append(ledger_path: &Path, record: &WitnessRecord) -> Result<(), String>
         )
     })?;
 
-    Ok(())
+    // The record is in the ledger; a failed index write is repaired from the
+    // ledger on the next load
+    let entry = index::entry_for_line(&line, active.number, size)?;
+    let _ = if stale {
+        entries.push(entry);
+        index::write(ledger_path, &entries)
+    } else {
+        index::append(ledger_path, &entry)
+    };
+
+    Ok(record)
+}
+
+/// The ledger's rotated segments in order, then the active ledger, numbered
+/// one past the last rotated segment. The active ledger may not exist yet.
+pub fn segments(ledger_path: &Path) -> Result<Vec<Segment>, String> {
+    let name = ledger_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let dir = match ledger_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut numbers = Vec::new();
+    match fs::read_dir(dir) {
+        Ok(dir_entries) => {
+            for dir_entry in dir_entries {
+                let dir_entry = dir_entry.map_err(|error| {
+                    format!(
+                        "failed to list witness directory '{}': {error}",
+                        dir.display()
+                    )
+                })?;
+                let file_name = dir_entry.file_name();
+                if let Some(number) = file_name
+                    .to_str()
+                    .and_then(|file_name| file_name.strip_prefix(name)?.strip_prefix('.'))
+                    .filter(|suffix| suffix.bytes().all(|byte| byte.is_ascii_digit()))
+                    .and_then(|suffix| suffix.parse::<u32>().ok())
+                {
+                    numbers.push(number);
+                }
+            }
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(format!(
+                "failed to list witness directory '{}': {error}",
+                dir.display()
+            ));
+        }
+    }
+    numbers.sort_unstable();
+
+    let active = numbers.last().map_or(1, |number| number + 1);
+    let mut segments: Vec<Segment> = numbers
+        .into_iter()
+        .map(|number| Segment {
+            number,
+            path: sibling_path(ledger_path, &number.to_string()),
+        })
+        .collect();
+    segments.push(Segment {
+        number: active,
+        path: ledger_path.to_path_buf(),
+    });
+    Ok(segments)
+}
+
+/// `<ledger>.<suffix>`, next to the ledger.
+pub fn sibling_path(ledger_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = ledger_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    ledger_path.with_file_name(name)
+}
+
+/// Take the ledger's exclusive lock, held until the returned file is
+/// dropped. The lock is advisory: it orders appends from this tool, not
+/// writes by anything else.
+pub fn lock(ledger_path: &Path) -> Result<File, String> {
+    let path = sibling_path(ledger_path, "lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|error| format!("failed to open witness lock '{}': {error}", path.display()))?;
+    file.lock().map_err(|error| {
+        format!(
+            "failed to lock witness ledger '{}': {error}",
+            path.display()
+        )
+    })?;
+    Ok(file)
+}
+
+/// Take the ledger's exclusive lock only if nobody holds it, so a reader
+/// can also write back an index it had to rebuild.
+pub fn try_lock(ledger_path: &Path) -> Option<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling_path(ledger_path, "lock"))
+        .ok()?;
+    file.try_lock().ok()?;
+    Some(file)
+}
+
+/// Take the ledger's lock shared, so no segment is rotated while it is
+/// read. Readers go ahead unlocked when the lock file cannot be opened,
+/// e.g. for a read-only ledger.
+pub fn lock_shared(ledger_path: &Path) -> Option<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling_path(ledger_path, "lock"))
+        .ok()?;
+    file.lock_shared().ok()?;
+    Some(file)
+}
+
+/// Rotation policy from `$EPISTEMIC_WITNESS_ROTATE`: `daily`, or a maximum
+/// active file size in bytes (0 or unset disables rotation).
+pub fn rotation() -> Result<Rotation, String> {
+    rotation_from_env(|key| std::env::var(key).ok())
+}
+
+fn rotation_from_env<F>(get_env: F) -> Result<Rotation, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Some(value) = get_env("EPISTEMIC_WITNESS_ROTATE") else {
+        return Ok(Rotation::Never);
+    };
+    match value.trim() {
+        "" | "0" => Ok(Rotation::Never),
+        "daily" => Ok(Rotation::Daily),
+        size => size.parse().map(Rotation::Size).map_err(|_| {
+            format!(
+                "invalid EPISTEMIC_WITNESS_ROTATE '{size}': expected 'daily' or a size in bytes"
+            )
+        }),
+    }
 }
 
 /// Resolve the witness ledger path from `$EPISTEMIC_WITNESS` or default.
@@ -70,7 +312,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{append, ledger_path_from_env};
+    use super::{
+        Rotation, append, append_with_rotation, ledger_path_from_env, rotation_from_env, segments,
+    };
+    use crate::witness::index;
     use crate::witness::record::{WitnessInput, WitnessRecord};
     use serde_json::json;
     use std::fs;
@@ -136,4 +381,145 @@ mod tests {
             assert_eq!(value["outcome"], "ALL_MATCHED");
         }
     }
+
+    #[test]
+    fn append_chains_each_record_to_the_previous_one() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+
+        let first = append(&ledger_path, &sample_record()).expect("append first record");
+        let second = append(&ledger_path, &sample_record()).expect("append second record");
+
+        assert_eq!(first.prev, None);
+        assert_eq!(first.id, sample_record().id);
+        assert_eq!(second.prev.as_deref(), Some(first.id.as_str()));
+        assert_ne!(second.id, first.id);
+    }
+
+    #[test]
+    fn append_chains_after_unindexed_long_lines_and_blank_lines() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let long = "x".repeat(20_000);
+        fs::write(
+            &ledger_path,
+            format!(
+                "{}\n{}\n\n",
+                json!({ "id": "first", "pad": long }),
+                json!({ "id": "last", "pad": long })
+            ),
+        )
+        .expect("write ledger");
+
+        let record = append(&ledger_path, &sample_record()).expect("append record");
+
+        assert_eq!(record.prev.as_deref(), Some("last"));
+        let (entries, stale) = index::load(&ledger_path).expect("load index");
+        assert!(!stale);
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn size_rotation_moves_the_active_file_into_numbered_segments() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let line_len = sample_record().to_jsonl().expect("serialize").len() as u64;
+        let rotation = Rotation::Size(line_len * 3);
+
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let record = append_with_rotation(&ledger_path, &sample_record(), rotation)
+                .expect("append record");
+            ids.push(record.id);
+        }
+
+        let segments = segments(&ledger_path).expect("list segments");
+        let numbers: Vec<u32> = segments.iter().map(|segment| segment.number).collect();
+        assert_eq!(numbers, [1, 2, 3]);
+        assert_eq!(segments[0].path, tempdir.path().join("witness.jsonl.1"));
+        assert_eq!(segments[2].path, ledger_path);
+        assert_eq!(
+            fs::read_to_string(&ledger_path)
+                .expect("read active ledger")
+                .lines()
+                .count(),
+            1
+        );
+
+        let (entries, stale) = index::load(&ledger_path).expect("load index");
+        assert!(!stale);
+        let indexed: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.id.clone().expect("id"))
+            .collect();
+        assert_eq!(indexed, ids);
+        assert_eq!(entries[4].segment, 3);
+        let last = entries[4]
+            .read_record(&ledger_path)
+            .expect("read last record");
+        assert_eq!(last["prev"], ids[3].as_str());
+    }
+
+    #[test]
+    fn daily_rotation_starts_a_segment_on_a_new_day() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let at = |ts: &str| {
+            WitnessRecord::new(
+                "0.1.0",
+                "blake3:binary",
+                vec![],
+                json!({}),
+                "ALL_MATCHED",
+                0,
+                "blake3:output",
+                ts,
+            )
+            .expect("build witness record")
+        };
+
+        for ts in [
+            "2026-02-24T10:00:00Z",
+            "2026-02-24T23:00:00Z",
+            "2026-02-25T01:00:00Z",
+        ] {
+            append_with_rotation(&ledger_path, &at(ts), Rotation::Daily).expect("append record");
+        }
+
+        let segments = segments(&ledger_path).expect("list segments");
+        assert_eq!(segments.len(), 2);
+        let rotated = fs::read_to_string(&segments[0].path).expect("read rotated segment");
+        assert_eq!(rotated.lines().count(), 2);
+    }
+
+    #[test]
+    fn rotation_reads_daily_or_a_byte_size() {
+        let parse = |value: &str| rotation_from_env(|_| Some(value.to_owned()));
+
+        assert_eq!(rotation_from_env(|_| None), Ok(Rotation::Never));
+        assert_eq!(parse("daily"), Ok(Rotation::Daily));
+        assert_eq!(parse("1048576"), Ok(Rotation::Size(1_048_576)));
+        assert_eq!(parse("0"), Ok(Rotation::Never));
+        assert!(parse("weekly").is_err());
+    }
+
+    #[test]
+    fn concurrent_appends_keep_the_chain_intact() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        append(&ledger_path, &sample_record()).expect("append record");
+                    }
+                });
+            }
+        });
+
+        let report = crate::witness::verify::verify(&ledger_path).expect("verify ledger");
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.records, 40);
+    }
 }
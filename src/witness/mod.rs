@@ -1,5 +1,9 @@
+pub mod classification;
+pub mod index;
 pub mod ledger;
 pub mod query;
 pub mod record;
+pub mod replay;
+pub mod verify;
 
 pub use record::WitnessRecord;
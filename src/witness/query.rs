@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::{
     fs::File,
@@ -5,40 +7,264 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
-use serde_json::Value;
+use globset::{Glob, GlobMatcher};
+use serde_json::{Map, Value};
 
 use crate::cli::WitnessFilters;
+use crate::witness::{index, ledger};
 
-/// Query witness records matching filter criteria.
+/// Fields the ledger index carries; grouping on anything else reads full
+/// records. `day` is derived from `ts`.
+const INDEXED_GROUP_FIELDS: &[&str] = &["day", "id", "tool", "outcome", "ts"];
+
+/// Query witness records matching filter criteria, across every segment of
+/// the ledger in order.
 pub fn query(ledger_path: &Path, filters: &WitnessFilters) -> Result<Vec<Value>, String> {
-    let records = read_records(ledger_path)?;
-    filter_records(records, filters)
+    let criteria = Criteria::new(filters)?;
+    let _lock = ledger::lock_shared(ledger_path);
+
+    let mut records = Vec::new();
+    visit_matches(ledger_path, &criteria, |record| records.push(record))?;
+    Ok(records)
 }
 
-/// Return the last witness record matching filter criteria.
+/// Return the last witness record matching filter criteria. When the
+/// filters only use indexed fields, only the matching record is read from
+/// the ledger; the rest come from its index.
 pub fn last(ledger_path: &Path, filters: &WitnessFilters) -> Result<Option<Value>, String> {
-    let mut records = query(ledger_path, filters)?;
-    Ok(records.pop())
+    let criteria = Criteria::new(filters)?;
+
+    if !criteria.is_indexed() {
+        let _lock = ledger::lock_shared(ledger_path);
+        let mut found = None;
+        visit_matches(ledger_path, &criteria, |record| found = Some(record))?;
+        return Ok(found);
+    }
+
+    let (entries, _lock) = index::load_for_read(ledger_path)?;
+    for entry in entries.iter().rev() {
+        if criteria.matches(&entry_value(entry)?) {
+            return entry.read_record(ledger_path).map(Some);
+        }
+    }
+    Ok(None)
 }
 
-/// Count witness records matching filter criteria.
+/// Count witness records matching filter criteria, from the ledger's index
+/// when the filters only use indexed fields.
 pub fn count(ledger_path: &Path, filters: &WitnessFilters) -> Result<u64, String> {
-    Ok(query(ledger_path, filters)?.len() as u64)
+    let groups = count_groups(ledger_path, filters, &[])?;
+    Ok(groups.values().sum())
 }
 
-fn filter_records(records: Vec<Value>, filters: &WitnessFilters) -> Result<Vec<Value>, String> {
-    let since = parse_bound("since", filters.since.as_deref())?;
-    let until = parse_bound("until", filters.until.as_deref())?;
-
-    Ok(records
+/// Count matching witness records per distinct value of `group_by`, in
+/// order of those values (see `GroupKey`). Each group is an object holding
+/// the grouped fields and its `count`, so `count` cannot be grouped on.
+pub fn count_by(
+    ledger_path: &Path,
+    filters: &WitnessFilters,
+    group_by: &[String],
+) -> Result<Vec<Value>, String> {
+    if group_by.iter().any(|key| key == "count") {
+        return Err(
+            "cannot group by 'count': it names each group's total; group by a dotted path such as 'params.count' instead"
+                .to_owned(),
+        );
+    }
+    let groups = count_groups(ledger_path, filters, group_by)?;
+    Ok(groups
         .into_iter()
-        .filter(|record| matches_tool(record, filters.tool.as_deref()))
-        .filter(|record| matches_outcome(record, filters.outcome.as_deref()))
-        .filter(|record| within_bounds(record, since.as_ref(), until.as_ref()))
-        .filter(|record| matches_input_hash(record, filters.input_hash.as_deref()))
+        .map(|(GroupKey(values), count)| {
+            let mut group: Map<String, Value> = group_by.iter().cloned().zip(values).collect();
+            group.insert("count".to_owned(), Value::from(count));
+            Value::Object(group)
+        })
         .collect())
 }
 
+/// Keep only `fields` of a record, keyed by their dotted paths. Missing
+/// fields are null.
+pub fn project(record: &Value, fields: &[String]) -> Value {
+    Value::Object(
+        fields
+            .iter()
+            .map(|path| {
+                let value = field(record, path).cloned().unwrap_or(Value::Null);
+                (path.clone(), value)
+            })
+            .collect(),
+    )
+}
+
+type Groups = BTreeMap<GroupKey, u64>;
+
+/// The grouped values of a `--group-by` group, ordered field by field on
+/// their JSON types: null, then booleans, numbers by value, strings, and
+/// arrays and objects by their JSON text.
+struct GroupKey(Vec<Value>);
+
+impl Ord for GroupKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .map(|(left, right)| compare_values(left, right))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| self.0.len().cmp(&other.0.len()))
+    }
+}
+
+impl PartialOrd for GroupKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for GroupKey {}
+
+fn compare_values(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    rank(left)
+        .cmp(&rank(right))
+        .then_with(|| match (left, right) {
+            (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+            (Value::Number(left), Value::Number(right)) => {
+                match (left.as_i64(), right.as_i64(), left.as_u64(), right.as_u64()) {
+                    (Some(left), Some(right), _, _) => left.cmp(&right),
+                    (_, _, Some(left), Some(right)) => left.cmp(&right),
+                    _ => {
+                        let float = |number: &serde_json::Number| number.as_f64().unwrap_or(0.0);
+                        float(left).total_cmp(&float(right))
+                    }
+                }
+            }
+            (Value::String(left), Value::String(right)) => left.cmp(right),
+            _ => left.to_string().cmp(&right.to_string()),
+        })
+}
+
+fn count_groups(
+    ledger_path: &Path,
+    filters: &WitnessFilters,
+    group_by: &[String],
+) -> Result<Groups, String> {
+    let criteria = Criteria::new(filters)?;
+
+    let mut groups = Groups::new();
+    let mut add = |record: &Value| {
+        let values: Vec<Value> = group_by
+            .iter()
+            .map(|key| group_value(record, key))
+            .collect();
+        *groups.entry(GroupKey(values)).or_insert(0) += 1;
+    };
+
+    let indexed = criteria.is_indexed()
+        && group_by
+            .iter()
+            .all(|key| INDEXED_GROUP_FIELDS.contains(&key.as_str()));
+    if indexed {
+        let (entries, _lock) = index::load_for_read(ledger_path)?;
+        for entry in &entries {
+            let entry = entry_value(entry)?;
+            if criteria.matches(&entry) {
+                add(&entry);
+            }
+        }
+    } else {
+        let _lock = ledger::lock_shared(ledger_path);
+        visit_matches(ledger_path, &criteria, |record| add(&record))?;
+    }
+    Ok(groups)
+}
+
+fn visit_matches<F>(ledger_path: &Path, criteria: &Criteria, mut visit: F) -> Result<(), String>
+where
+    F: FnMut(Value),
+{
+    for segment in ledger::segments(ledger_path)? {
+        read_records(&segment.path, |record| {
+            if criteria.matches(&record) {
+                visit(record);
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn entry_value(entry: &index::IndexEntry) -> Result<Value, String> {
+    serde_json::to_value(entry)
+        .map_err(|error| format!("failed to serialize witness index entry: {error}"))
+}
+
+/// Filters with their timestamp bounds and input path glob parsed.
+struct Criteria<'a> {
+    filters: &'a WitnessFilters,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    input_path: Option<GlobMatcher>,
+}
+
+impl<'a> Criteria<'a> {
+    fn new(filters: &'a WitnessFilters) -> Result<Self, String> {
+        let input_path = filters
+            .input_path
+            .as_deref()
+            .map(|glob| {
+                Glob::new(glob)
+                    .map(|glob| glob.compile_matcher())
+                    .map_err(|error| format!("invalid --input-path glob '{glob}': {error}"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            filters,
+            since: parse_bound("since", filters.since.as_deref())?,
+            until: parse_bound("until", filters.until.as_deref())?,
+            input_path,
+        })
+    }
+
+    /// Whether the index carries every field these filters look at.
+    fn is_indexed(&self) -> bool {
+        self.filters.params.is_empty()
+            && self.filters.binary_hash.is_none()
+            && self.filters.output_hash.is_none()
+            && self.input_path.is_none()
+    }
+
+    fn matches(&self, record: &Value) -> bool {
+        matches_tool(record, self.filters.tool.as_deref())
+            && matches_outcome(record, self.filters.outcome.as_deref())
+            && within_bounds(record, self.since.as_ref(), self.until.as_ref())
+            && matches_input_hash(record, self.filters.input_hash.as_deref())
+            && self
+                .filters
+                .params
+                .iter()
+                .all(|param| matches_param(record, param))
+            && matches_hash(record, "binary_hash", self.filters.binary_hash.as_deref())
+            && matches_hash(record, "output_hash", self.filters.output_hash.as_deref())
+            && matches_input_path(record, self.input_path.as_ref())
+    }
+}
+
 fn parse_bound(label: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
     let Some(value) = value else {
         return Ok(None);
@@ -81,6 +307,84 @@ fn matches_input_hash(record: &Value, input_hash: Option<&str>) -> bool {
         })
 }
 
+/// `KEY=VALUE` matches a params field equal to VALUE, or a list containing
+/// it. `KEY` alone matches a field that is set and not false.
+fn matches_param(record: &Value, param: &str) -> bool {
+    let (key, expected) = match param.split_once('=') {
+        Some((key, expected)) => (key, Some(expected)),
+        None => (param, None),
+    };
+    let Some(value) = record.get("params").and_then(|params| field(params, key)) else {
+        return false;
+    };
+
+    match (value, expected) {
+        (Value::Null, _) => false,
+        (value, None) => value != &Value::Bool(false),
+        (Value::Array(items), Some(expected)) => {
+            items.iter().any(|item| scalar_equals(item, expected))
+        }
+        (value, Some(expected)) => scalar_equals(value, expected),
+    }
+}
+
+fn scalar_equals(value: &Value, expected: &str) -> bool {
+    match value {
+        Value::String(value) => value == expected,
+        Value::Bool(_) | Value::Number(_) => value.to_string() == expected,
+        _ => false,
+    }
+}
+
+/// Exact match, or a prefix match when `pattern` ends with `*`.
+fn matches_hash(record: &Value, key: &str, pattern: Option<&str>) -> bool {
+    let Some(pattern) = pattern else {
+        return true;
+    };
+    let Some(hash) = record.get(key).and_then(Value::as_str) else {
+        return false;
+    };
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => hash.starts_with(prefix),
+        None => hash == pattern,
+    }
+}
+
+fn matches_input_path(record: &Value, glob: Option<&GlobMatcher>) -> bool {
+    let Some(glob) = glob else {
+        return true;
+    };
+
+    record
+        .get("inputs")
+        .and_then(Value::as_array)
+        .is_some_and(|inputs| {
+            inputs.iter().any(|input| {
+                input
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .is_some_and(|path| glob.is_match(path))
+            })
+        })
+}
+
+/// A field by dotted path, e.g. `params.jobs`.
+fn field<'a>(record: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(record, |value, key| value.get(key))
+}
+
+/// A record's value for a `--group-by` field; `day` is its UTC date.
+fn group_value(record: &Value, key: &str) -> Value {
+    if key == "day" {
+        return record_timestamp(record)
+            .map(|ts| Value::from(ts.date_naive().to_string()))
+            .unwrap_or(Value::Null);
+    }
+    field(record, key).cloned().unwrap_or(Value::Null)
+}
+
 fn within_bounds(
     record: &Value,
     since: Option<&DateTime<Utc>>,
@@ -118,10 +422,13 @@ fn record_timestamp(record: &Value) -> Option<DateTime<Utc>> {
         .map(|ts| ts.with_timezone(&Utc))
 }
 
-fn read_records(ledger_path: &Path) -> Result<Vec<Value>, String> {
+fn read_records<F>(ledger_path: &Path, mut visit: F) -> Result<(), String>
+where
+    F: FnMut(Value),
+{
     let file = match File::open(ledger_path) {
         Ok(file) => file,
-        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
         Err(error) => {
             return Err(format!(
                 "failed to open witness ledger '{}': {error}",
@@ -131,7 +438,6 @@ fn read_records(ledger_path: &Path) -> Result<Vec<Value>, String> {
     };
 
     let reader = BufReader::new(file);
-    let mut records = Vec::new();
 
     for (line_index, line_result) in reader.lines().enumerate() {
         let line_number = line_index + 1;
@@ -147,30 +453,34 @@ fn read_records(ledger_path: &Path) -> Result<Vec<Value>, String> {
             continue;
         }
 
-        let value = serde_json::from_str::<Value>(&line).map_err(|error| {
-            format!(
-                "invalid witness JSON at '{}' line {}: {error}",
+        // A torn or corrupt line is skipped, as the index scan skips it, so
+        // indexed and full-record reads see the same records.
+        match serde_json::from_str::<Value>(&line) {
+            Ok(value) => visit(value),
+            Err(error) => eprintln!(
+                "Warning: skipping invalid witness JSON at '{}' line {}: {error}",
                 ledger_path.display(),
                 line_number
-            )
-        })?;
-        records.push(value);
+            ),
+        }
     }
 
-    Ok(records)
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{count, last, query};
+    use super::{count, count_by, last, project, query};
     use crate::cli::WitnessFilters;
-    use serde_json::json;
+    use serde_json::{Value, json};
     use std::fs;
+    use std::path::PathBuf;
 
-    fn write_ledger(contents: &str) -> tempfile::NamedTempFile {
-        let ledger = tempfile::NamedTempFile::new().expect("create temp ledger");
-        fs::write(ledger.path(), contents).expect("write ledger");
-        ledger
+    fn write_ledger(contents: &str) -> (tempfile::TempDir, PathBuf) {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(&ledger_path, contents).expect("write ledger");
+        (tempdir, ledger_path)
     }
 
     fn record_line(
@@ -201,7 +511,7 @@ mod tests {
 
     #[test]
     fn query_returns_all_records_without_filters() {
-        let ledger = write_ledger(&format!(
+        let (_tempdir, ledger) = write_ledger(&format!(
             "{}\n{}\n",
             record_line(
                 "1",
@@ -219,7 +529,7 @@ mod tests {
             )
         ));
 
-        let records = query(ledger.path(), &WitnessFilters::default()).expect("query ledger");
+        let records = query(&ledger, &WitnessFilters::default()).expect("query ledger");
         assert_eq!(records.len(), 2);
         assert_eq!(records[0]["id"], "1");
         assert_eq!(records[1]["id"], "2");
@@ -227,7 +537,7 @@ mod tests {
 
     #[test]
     fn query_filters_by_tool_outcome_and_input_hash() {
-        let ledger = write_ledger(&format!(
+        let (_tempdir, ledger) = write_ledger(&format!(
             "{}\n{}\n{}\n",
             record_line(
                 "1",
@@ -257,16 +567,17 @@ mod tests {
             until: None,
             outcome: Some("ALL_MATCHED".to_owned()),
             input_hash: Some("keep".to_owned()),
+            ..WitnessFilters::default()
         };
 
-        let records = query(ledger.path(), &filters).expect("filtered query");
+        let records = query(&ledger, &filters).expect("filtered query");
         assert_eq!(records.len(), 1);
         assert_eq!(records[0]["id"], "1");
     }
 
     #[test]
     fn query_filters_by_time_bounds_and_legacy_created_ts() {
-        let ledger = write_ledger(&format!(
+        let (_tempdir, ledger) = write_ledger(&format!(
             "{}\n{}\n",
             json!({
                 "id": "1",
@@ -289,16 +600,17 @@ mod tests {
             until: Some("2026-01-02T12:00:00Z".to_owned()),
             outcome: None,
             input_hash: None,
+            ..WitnessFilters::default()
         };
 
-        let records = query(ledger.path(), &filters).expect("time-bounded query");
+        let records = query(&ledger, &filters).expect("time-bounded query");
         assert_eq!(records.len(), 1);
         assert_eq!(records[0]["id"], "2");
     }
 
     #[test]
     fn last_returns_last_matching_record() {
-        let ledger = write_ledger(&format!(
+        let (_tempdir, ledger) = write_ledger(&format!(
             "{}\n{}\n{}\n",
             record_line(
                 "1",
@@ -328,9 +640,10 @@ mod tests {
             until: None,
             outcome: None,
             input_hash: None,
+            ..WitnessFilters::default()
         };
 
-        let record = last(ledger.path(), &filters)
+        let record = last(&ledger, &filters)
             .expect("last record")
             .expect("record exists");
         assert_eq!(record["id"], "3");
@@ -338,7 +651,7 @@ mod tests {
 
     #[test]
     fn count_returns_number_of_matching_records() {
-        let ledger = write_ledger(&format!(
+        let (_tempdir, ledger) = write_ledger(&format!(
             "{}\n{}\n",
             record_line(
                 "1",
@@ -361,9 +674,300 @@ mod tests {
             until: None,
             outcome: None,
             input_hash: Some("shared".to_owned()),
+            ..WitnessFilters::default()
+        };
+
+        assert_eq!(count(&ledger, &filters).expect("count ledger"), 2);
+    }
+
+    #[test]
+    fn query_last_and_count_read_across_rotated_segments() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        fs::write(
+            tempdir.path().join("witness.jsonl.1"),
+            format!(
+                "{}\n{}\n",
+                record_line(
+                    "1",
+                    "fingerprint",
+                    "ALL_MATCHED",
+                    Some("blake3:in-1"),
+                    "2026-01-01T00:00:00Z"
+                ),
+                record_line(
+                    "2",
+                    "fingerprint",
+                    "PARTIAL",
+                    Some("blake3:in-2"),
+                    "2026-01-01T01:00:00Z"
+                )
+            ),
+        )
+        .expect("write rotated segment");
+        fs::write(
+            &ledger_path,
+            format!(
+                "{}\n",
+                record_line(
+                    "3",
+                    "hash",
+                    "ALL_HASHED",
+                    Some("blake3:in-3"),
+                    "2026-01-01T02:00:00Z"
+                )
+            ),
+        )
+        .expect("write active ledger");
+        let filters = WitnessFilters {
+            tool: Some("fingerprint".to_owned()),
+            ..WitnessFilters::default()
         };
 
-        assert_eq!(count(ledger.path(), &filters).expect("count ledger"), 2);
+        let ids: Vec<Value> = query(&ledger_path, &WitnessFilters::default())
+            .expect("query ledger")
+            .into_iter()
+            .map(|record| record["id"].clone())
+            .collect();
+        assert_eq!(ids, [json!("1"), json!("2"), json!("3")]);
+        let record = last(&ledger_path, &filters)
+            .expect("last record")
+            .expect("record exists");
+        assert_eq!(record["id"], "2");
+        assert_eq!(record["output_hash"], "blake3:out:2");
+        assert_eq!(count(&ledger_path, &filters).expect("count ledger"), 2);
+    }
+
+    fn run_record(id: &str, params: Value, binary_hash: &str, path: &str, ts: &str) -> String {
+        json!({
+            "id": id,
+            "tool": "fingerprint",
+            "binary_hash": binary_hash,
+            "inputs": [{ "path": path, "hash": format!("blake3:in:{id}") }],
+            "params": params,
+            "outcome": if id == "3" { "PARTIAL" } else { "ALL_MATCHED" },
+            "output_hash": format!("blake3:out{id}"),
+            "ts": ts
+        })
+        .to_string()
+    }
+
+    fn run_ledger() -> (tempfile::TempDir, PathBuf) {
+        write_ledger(&format!(
+            "{}\n{}\n{}\n",
+            run_record(
+                "1",
+                json!({ "fingerprints": ["csv.v0"], "diagnose": false, "jobs": 1 }),
+                "blake3:aaaa",
+                "deals/a.jsonl",
+                "2026-01-01T00:00:00Z"
+            ),
+            run_record(
+                "2",
+                json!({ "fingerprints": ["csv.v0", "argus-model.v1"], "diagnose": true, "jobs": 4 }),
+                "blake3:aabb",
+                "deals/b.jsonl",
+                "2026-01-01T12:00:00Z"
+            ),
+            run_record(
+                "3",
+                json!({ "fingerprints": ["argus-model.v1"], "diagnose": true, "jobs": 4 }),
+                "blake3:bbbb",
+                "stdin",
+                "2026-01-02T00:00:00Z"
+            )
+        ))
+    }
+
+    fn ids(records: &[Value]) -> Vec<&str> {
+        records
+            .iter()
+            .map(|record| record["id"].as_str().expect("id"))
+            .collect()
+    }
+
+    #[test]
+    fn query_filters_by_params() {
+        let (_tempdir, ledger) = run_ledger();
+        let with_params = |params: &[&str]| WitnessFilters {
+            params: params.iter().map(|param| (*param).to_owned()).collect(),
+            ..WitnessFilters::default()
+        };
+
+        let listed = query(&ledger, &with_params(&["fingerprints=argus-model.v1"]))
+            .expect("query by list membership");
+        assert_eq!(ids(&listed), ["2", "3"]);
+
+        let diagnosed = query(&ledger, &with_params(&["diagnose"])).expect("query by flag");
+        assert_eq!(ids(&diagnosed), ["2", "3"]);
+
+        let both = query(&ledger, &with_params(&["jobs=4", "fingerprints=csv.v0"]))
+            .expect("query by several params");
+        assert_eq!(ids(&both), ["2"]);
+
+        let missing = query(&ledger, &with_params(&["resume"])).expect("query missing param");
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn query_filters_by_exact_or_prefix_hash_and_input_path() {
+        let (_tempdir, ledger) = run_ledger();
+
+        let exact = WitnessFilters {
+            output_hash: Some("blake3:out2".to_owned()),
+            ..WitnessFilters::default()
+        };
+        assert_eq!(ids(&query(&ledger, &exact).expect("exact")), ["2"]);
+
+        let not_prefix = WitnessFilters {
+            binary_hash: Some("blake3:aa".to_owned()),
+            ..WitnessFilters::default()
+        };
+        assert!(query(&ledger, &not_prefix).expect("exact").is_empty());
+
+        let prefix = WitnessFilters {
+            binary_hash: Some("blake3:aa*".to_owned()),
+            ..WitnessFilters::default()
+        };
+        assert_eq!(ids(&query(&ledger, &prefix).expect("prefix")), ["1", "2"]);
+
+        let glob = WitnessFilters {
+            input_path: Some("deals/*.jsonl".to_owned()),
+            ..WitnessFilters::default()
+        };
+        assert_eq!(ids(&query(&ledger, &glob).expect("glob")), ["1", "2"]);
+
+        let invalid = WitnessFilters {
+            input_path: Some("deals/[".to_owned()),
+            ..WitnessFilters::default()
+        };
+        let error = query(&ledger, &invalid).expect_err("invalid glob");
+        assert!(error.contains("--input-path"), "{error}");
+    }
+
+    #[test]
+    fn last_and_count_fall_back_to_records_for_unindexed_filters() {
+        let (_tempdir, ledger) = run_ledger();
+        let filters = WitnessFilters {
+            params: vec!["fingerprints=csv.v0".to_owned()],
+            ..WitnessFilters::default()
+        };
+
+        let record = last(&ledger, &filters)
+            .expect("last record")
+            .expect("record exists");
+        assert_eq!(record["id"], "2");
+        assert_eq!(count(&ledger, &filters).expect("count"), 2);
+    }
+
+    #[test]
+    fn count_by_groups_per_day_and_outcome() {
+        let (_tempdir, ledger) = run_ledger();
+
+        let groups = count_by(
+            &ledger,
+            &WitnessFilters::default(),
+            &["day".to_owned(), "outcome".to_owned()],
+        )
+        .expect("group by indexed fields");
+        assert_eq!(
+            groups,
+            [
+                json!({ "day": "2026-01-01", "outcome": "ALL_MATCHED", "count": 2 }),
+                json!({ "day": "2026-01-02", "outcome": "PARTIAL", "count": 1 }),
+            ]
+        );
+
+        let groups = count_by(
+            &ledger,
+            &WitnessFilters::default(),
+            &["params.jobs".to_owned()],
+        )
+        .expect("group by params");
+        assert_eq!(
+            groups,
+            [
+                json!({ "params.jobs": 1, "count": 1 }),
+                json!({ "params.jobs": 4, "count": 2 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_by_orders_groups_by_typed_value() {
+        let (_tempdir, ledger) = write_ledger(&format!(
+            "{}\n{}\n{}\n{}\n",
+            run_record(
+                "1",
+                json!({ "jobs": 10 }),
+                "blake3:aaaa",
+                "a.jsonl",
+                "2026-01-01T00:00:00Z"
+            ),
+            run_record(
+                "2",
+                json!({ "jobs": 9 }),
+                "blake3:bbbb",
+                "b.jsonl",
+                "2026-01-01T00:00:00Z"
+            ),
+            run_record(
+                "3",
+                json!({ "jobs": "8" }),
+                "blake3:cccc",
+                "c.jsonl",
+                "2026-01-01T00:00:00Z"
+            ),
+            run_record(
+                "4",
+                json!({}),
+                "blake3:dddd",
+                "d.jsonl",
+                "2026-01-01T00:00:00Z"
+            )
+        ));
+
+        let groups = count_by(
+            &ledger,
+            &WitnessFilters::default(),
+            &["params.jobs".to_owned()],
+        )
+        .expect("group by params");
+
+        let jobs: Vec<&Value> = groups.iter().map(|group| &group["params.jobs"]).collect();
+        assert_eq!(
+            jobs,
+            [&Value::Null, &json!(9), &json!(10), &json!("8")],
+            "null, then numbers by value, then strings"
+        );
+    }
+
+    #[test]
+    fn count_by_refuses_to_group_on_count() {
+        let (_tempdir, ledger) = run_ledger();
+
+        let error = count_by(
+            &ledger,
+            &WitnessFilters::default(),
+            &["outcome".to_owned(), "count".to_owned()],
+        )
+        .expect_err("count is the group total");
+        assert!(error.contains("cannot group by 'count'"), "{error}");
+    }
+
+    #[test]
+    fn project_keeps_requested_fields() {
+        let record = json!({ "id": "1", "params": { "jobs": 4 }, "outcome": "ALL_MATCHED" });
+
+        let projected = project(
+            &record,
+            &["id".to_owned(), "params.jobs".to_owned(), "ts".to_owned()],
+        );
+
+        assert_eq!(
+            projected,
+            json!({ "id": "1", "params.jobs": 4, "ts": null })
+        );
     }
 
     #[test]
@@ -387,17 +991,41 @@ mod tests {
     }
 
     #[test]
-    fn invalid_json_line_returns_error() {
-        let ledger = write_ledger("{\"id\":\"1\"}\nnot-json\n");
+    fn invalid_json_lines_are_skipped_by_indexed_and_full_reads() {
+        let (_tempdir, ledger) = write_ledger(&format!(
+            "{}\n{{\"id\":\"2\",\"tool\n{}\n",
+            run_record(
+                "1",
+                json!({ "diagnose": true }),
+                "blake3:aaaa",
+                "deals/a.jsonl",
+                "2026-01-01T00:00:00Z"
+            ),
+            run_record(
+                "3",
+                json!({ "diagnose": true }),
+                "blake3:bbbb",
+                "deals/b.jsonl",
+                "2026-01-02T00:00:00Z"
+            )
+        ));
 
-        let error =
-            query(ledger.path(), &WitnessFilters::default()).expect_err("query should fail");
-        assert!(error.contains("line 2"));
+        assert_eq!(
+            count(&ledger, &WitnessFilters::default()).expect("indexed count"),
+            2
+        );
+        let unindexed = WitnessFilters {
+            params: vec!["diagnose".to_owned()],
+            ..WitnessFilters::default()
+        };
+        assert_eq!(count(&ledger, &unindexed).expect("unindexed count"), 2);
+        let records = query(&ledger, &WitnessFilters::default()).expect("query ledger");
+        assert_eq!(ids(&records), ["1", "3"]);
     }
 
     #[test]
     fn invalid_timestamp_filter_returns_error() {
-        let ledger = write_ledger(&record_line(
+        let (_tempdir, ledger) = write_ledger(&record_line(
             "1",
             "fingerprint",
             "ALL_MATCHED",
@@ -410,9 +1038,10 @@ mod tests {
             until: None,
             outcome: None,
             input_hash: None,
+            ..WitnessFilters::default()
         };
 
-        let error = query(ledger.path(), &filters).expect_err("invalid filter should fail");
+        let error = query(&ledger, &filters).expect_err("invalid filter should fail");
         assert!(error.contains("--since"));
     }
 }
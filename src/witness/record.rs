@@ -1,9 +1,9 @@
 use blake3::Hasher;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// Witness record following the witness.v0 schema.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WitnessRecord {
     pub id: String,
     pub tool: String,
@@ -15,10 +15,14 @@ pub struct WitnessRecord {
     pub exit_code: u8,
     pub output_hash: String,
     pub ts: String,
+    /// ID of the record before this one in the ledger. Absent on the first
+    /// record of a ledger and on records written before ledgers were chained.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
 }
 
 /// An input source referenced in a witness record.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WitnessInput {
     pub path: String,
     pub hash: Option<String>,
@@ -54,6 +58,7 @@ impl WitnessRecord {
             exit_code,
             &output_hash,
             &ts,
+            None,
         )?;
 
         Ok(Self {
@@ -67,9 +72,34 @@ impl WitnessRecord {
             exit_code,
             output_hash,
             ts,
+            prev: None,
         })
     }
 
+    /// Link this record to the record before it, recomputing its ID.
+    pub fn chained_to(mut self, prev: Option<String>) -> Result<Self, String> {
+        self.prev = prev;
+        self.id = self.recompute_id()?;
+        Ok(self)
+    }
+
+    /// The ID this record's fields hash to; differs from `id` when the record
+    /// was edited after it was written.
+    pub fn recompute_id(&self) -> Result<String, String> {
+        compute_record_id(
+            &self.tool,
+            &self.version,
+            &self.binary_hash,
+            &self.inputs,
+            &self.params,
+            &self.outcome,
+            self.exit_code,
+            &self.output_hash,
+            &self.ts,
+            self.prev.as_deref(),
+        )
+    }
+
     pub fn to_jsonl(&self) -> Result<String, String> {
         let json = serde_json::to_string(self)
             .map_err(|error| format!("failed to serialize witness record: {error}"))?;
@@ -88,6 +118,8 @@ struct WitnessRecordIdPayload<'a> {
     exit_code: u8,
     output_hash: &'a str,
     ts: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev: Option<&'a str>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -101,6 +133,7 @@ fn compute_record_id(
     exit_code: u8,
     output_hash: &str,
     ts: &str,
+    prev: Option<&str>,
 ) -> Result<String, String> {
     let payload = WitnessRecordIdPayload {
         tool,
@@ -112,6 +145,7 @@ fn compute_record_id(
         exit_code,
         output_hash,
         ts,
+        prev,
     };
     let encoded = serde_json::to_vec(&payload)
         .map_err(|error| format!("failed to encode witness record ID payload: {error}"))?;
@@ -187,6 +221,33 @@ mod tests {
         assert_ne!(record_a.id, record_b.id);
     }
 
+    #[test]
+    fn chaining_changes_id_and_round_trips() {
+        let record = sample_record();
+        let unchained_id = record.id.clone();
+
+        let chained = record
+            .chained_to(Some("blake3:previous".to_owned()))
+            .expect("chain record");
+        assert_ne!(chained.id, unchained_id);
+        assert_eq!(chained.recompute_id().expect("recompute"), chained.id);
+
+        let line = chained.to_jsonl().expect("serialize JSONL");
+        let parsed: WitnessRecord = serde_json::from_str(line.trim_end()).expect("parse record");
+        assert_eq!(parsed, chained);
+    }
+
+    #[test]
+    fn unchained_record_omits_prev_and_keeps_its_id() {
+        let record = sample_record();
+        let line = record.to_jsonl().expect("serialize JSONL");
+        assert!(!line.contains("\"prev\""));
+
+        let parsed: WitnessRecord = serde_json::from_str(line.trim_end()).expect("parse record");
+        assert_eq!(parsed.prev, None);
+        assert_eq!(parsed.recompute_id().expect("recompute"), record.id);
+    }
+
     #[test]
     fn serializes_to_jsonl_with_schema_fields() {
         let record = sample_record();
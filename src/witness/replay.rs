@@ -0,0 +1,558 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::witness::index;
+use crate::witness::record::WitnessRecord;
+
+/// Result of re-running a recorded invocation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub id: String,
+    /// Arguments the run was replayed with.
+    pub args: Vec<String>,
+    /// Whether this binary is the one that made the recording.
+    pub binary_matches: bool,
+    pub inputs: Vec<InputCheck>,
+    /// `true` when every input hashes as recorded; the run is only replayed
+    /// then.
+    pub inputs_match: bool,
+    pub recorded: RunResult,
+    pub replayed: Option<RunResult>,
+    /// `true` when the replayed output hash equals the recorded one.
+    pub matches: bool,
+    /// Record-level differences against the recorded output, when it is
+    /// available; see `replay`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub differences: Option<Vec<RecordDifference>>,
+    /// Why `differences` is absent after a mismatch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// An input file's recorded and current hash: a recorded manifest, or a
+/// document one of its records lists with a `bytes_hash`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputCheck {
+    pub path: String,
+    pub recorded_hash: Option<String>,
+    pub current_hash: Option<String>,
+    pub matches: bool,
+    /// Why the input could not be checked. An unverifiable input is not
+    /// treated as changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl InputCheck {
+    /// Whether the input is known to differ from the recording.
+    pub fn changed(&self) -> bool {
+        !self.matches && self.note.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunResult {
+    pub exit_code: u8,
+    pub output_hash: String,
+}
+
+/// One output line that differs between the recorded and replayed runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordDifference {
+    pub line: usize,
+    /// `changed`, `removed` (only in the recording) or `added` (only in the
+    /// replay).
+    pub kind: String,
+    /// Top-level fields whose values differ, for `changed` lines.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    pub recorded: Option<Value>,
+    pub replayed: Option<Value>,
+}
+
+/// Find a witness record by ID or unique ID prefix. IDs are looked up in
+/// the ledger's index, so only the matching record is read.
+pub fn find_record(ledger_path: &Path, id: &str) -> Result<WitnessRecord, String> {
+    let (entries, _lock) = index::load_for_read(ledger_path)?;
+    let mut matching = entries.iter().filter(|entry| {
+        entry
+            .id
+            .as_deref()
+            .is_some_and(|entry_id| entry_id.starts_with(id))
+    });
+    let Some(entry) = matching.next() else {
+        return Err(format!("no witness record with id '{id}'"));
+    };
+    if matching.next().is_some() {
+        return Err(format!(
+            "witness id '{id}' is ambiguous; give more of the id"
+        ));
+    }
+
+    serde_json::from_value(entry.read_record(ledger_path)?)
+        .map_err(|error| format!("witness record '{id}' is not a witness.v0 record: {error}"))
+}
+
+/// Command-line arguments that reproduce a recorded run-mode invocation.
+///
+/// Witness output is suppressed so a replay leaves the ledger unchanged.
+/// Records written before limits and `--diagnose` were recorded replay with
+/// the defaults.
+pub fn replay_args(record: &WitnessRecord) -> Result<Vec<String>, String> {
+    let params = &record.params;
+    if record.tool != "fingerprint" || params.get("mode").is_some() {
+        return Err(format!(
+            "witness record '{}' is not a fingerprint run; only run mode can be replayed",
+            record.id
+        ));
+    }
+    let Some(input) = params.get("input").and_then(Value::as_str) else {
+        return Err(format!(
+            "witness record '{}' read its manifest from stdin, which was not kept",
+            record.id
+        ));
+    };
+
+    let mut args = vec![input.to_owned()];
+    for fingerprint in params
+        .get("fingerprints")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        args.extend(["--fp".to_owned(), fingerprint.to_owned()]);
+    }
+    if let Some(jobs) = params.get("jobs").and_then(Value::as_u64) {
+        args.extend(["--jobs".to_owned(), jobs.to_string()]);
+    }
+    for (param, flag) in [
+        ("max_file_size", "--max-file-size"),
+        ("max_decompressed_size", "--max-decompressed-size"),
+        ("timeout", "--timeout"),
+    ] {
+        if let Some(value) = params.get(param).and_then(Value::as_u64) {
+            args.extend([flag.to_owned(), value.to_string()]);
+        }
+    }
+    if params.get("diagnose").and_then(Value::as_bool) == Some(true) {
+        args.push("--diagnose".to_owned());
+    }
+    args.push("--no-witness".to_owned());
+    Ok(args)
+}
+
+/// Rehash each recorded input file, then each document an unchanged
+/// manifest lists against the record's `bytes_hash`.
+///
+/// A changed manifest already fails; its records are not the recorded ones,
+/// so their documents are not checked.
+pub fn check_inputs(record: &WitnessRecord) -> Vec<InputCheck> {
+    let mut checks = Vec::new();
+    for input in &record.inputs {
+        let contents = std::fs::read(&input.path).ok();
+        let current_hash = contents
+            .as_deref()
+            .map(|bytes| format!("blake3:{}", blake3::hash(bytes).to_hex()));
+        let matches = input.hash.is_some() && input.hash == current_hash;
+        checks.push(InputCheck {
+            path: input.path.clone(),
+            matches,
+            recorded_hash: input.hash.clone(),
+            current_hash,
+            note: None,
+        });
+        if matches && let Some(contents) = contents {
+            checks.extend(document_checks(&contents));
+        }
+    }
+    checks
+}
+
+/// Rehash the documents listed by a manifest's records. Records without a
+/// `bytes_hash`, such as skipped ones, were never read and are not checked.
+///
+/// Only `blake3:` hashes can be recomputed; documents hashed with another
+/// algorithm are reported as unverifiable rather than changed.
+fn document_checks(manifest: &[u8]) -> Vec<InputCheck> {
+    String::from_utf8_lossy(manifest)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|record| {
+            let path = record.get("path")?.as_str()?.to_owned();
+            let recorded_hash = record.get("bytes_hash")?.as_str()?.to_owned();
+            if !recorded_hash.starts_with("blake3:") {
+                let algorithm = recorded_hash
+                    .split_once(':')
+                    .map_or("unprefixed", |(a, _)| a);
+                return Some(InputCheck {
+                    path,
+                    recorded_hash: Some(recorded_hash.clone()),
+                    current_hash: None,
+                    matches: false,
+                    note: Some(format!(
+                        "{algorithm} hashes cannot be recomputed; not verified"
+                    )),
+                });
+            }
+            let current_hash = hash_file(Path::new(&path));
+            Some(InputCheck {
+                matches: current_hash.as_deref() == Some(recorded_hash.as_str()),
+                path,
+                recorded_hash: Some(recorded_hash),
+                current_hash,
+                note: None,
+            })
+        })
+        .collect()
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(file).ok()?;
+    Some(format!("blake3:{}", hasher.finalize().to_hex()))
+}
+
+/// Re-run a recorded invocation with `binary` and compare its output hash.
+///
+/// When the hashes differ, `baseline` is the recorded output to diff
+/// against: `expected` if given, otherwise the run's `--output` file. It is
+/// only used when it still hashes to the recorded `output_hash`.
+pub fn replay(
+    record: &WitnessRecord,
+    binary: &Path,
+    binary_hash: &str,
+    expected: Option<&Path>,
+) -> Result<ReplayReport, String> {
+    let args = replay_args(record)?;
+    let inputs = check_inputs(record);
+    let inputs_match = !inputs.iter().any(InputCheck::changed);
+    let mut report = ReplayReport {
+        id: record.id.clone(),
+        args,
+        binary_matches: record.binary_hash == binary_hash,
+        inputs,
+        inputs_match,
+        recorded: RunResult {
+            exit_code: record.exit_code,
+            output_hash: record.output_hash.clone(),
+        },
+        replayed: None,
+        matches: false,
+        differences: None,
+        note: None,
+    };
+    if !inputs_match {
+        report.note = Some("inputs changed since the recording; not replayed".to_owned());
+        return Ok(report);
+    }
+
+    let output = Command::new(binary)
+        .args(&report.args)
+        .output()
+        .map_err(|error| format!("failed to run '{}': {error}", binary.display()))?;
+    let output_hash = format!("blake3:{}", blake3::hash(&output.stdout).to_hex());
+    let exit_code = output
+        .status
+        .code()
+        .and_then(|code| u8::try_from(code).ok())
+        .unwrap_or(u8::MAX);
+    report.matches = output_hash == record.output_hash;
+    report.replayed = Some(RunResult {
+        exit_code,
+        output_hash,
+    });
+    if report.matches {
+        return Ok(report);
+    }
+
+    let baseline = expected.map(Path::to_path_buf).or_else(|| {
+        record
+            .params
+            .get("output")
+            .and_then(Value::as_str)
+            .map(Into::into)
+    });
+    let Some(baseline) = baseline else {
+        report.note =
+            Some("the recorded output was not kept; pass --expected to diff against it".to_owned());
+        return Ok(report);
+    };
+    match std::fs::read(&baseline) {
+        Ok(recorded) => {
+            let baseline_hash = format!("blake3:{}", blake3::hash(&recorded).to_hex());
+            if baseline_hash == record.output_hash {
+                report.differences = Some(diff_outputs(&recorded, &output.stdout));
+            } else {
+                report.note = Some(format!(
+                    "'{}' hashes to {baseline_hash}, not the recorded output hash; not diffed",
+                    baseline.display()
+                ));
+            }
+        }
+        Err(error) => {
+            report.note = Some(format!(
+                "failed to read recorded output '{}': {error}",
+                baseline.display()
+            ));
+        }
+    }
+    Ok(report)
+}
+
+/// Compare two JSONL outputs line by line.
+pub fn diff_outputs(recorded: &[u8], replayed: &[u8]) -> Vec<RecordDifference> {
+    let recorded = parse_lines(recorded);
+    let replayed = parse_lines(replayed);
+    let mut differences = Vec::new();
+
+    for index in 0..recorded.len().max(replayed.len()) {
+        let (kind, fields) = match (recorded.get(index), replayed.get(index)) {
+            (Some(before), Some(after)) if before == after => continue,
+            (Some(before), Some(after)) => ("changed", changed_fields(before, after)),
+            (Some(_), None) => ("removed", Vec::new()),
+            (None, _) => ("added", Vec::new()),
+        };
+        differences.push(RecordDifference {
+            line: index + 1,
+            kind: kind.to_owned(),
+            fields,
+            recorded: recorded.get(index).cloned(),
+            replayed: replayed.get(index).cloned(),
+        });
+    }
+
+    differences
+}
+
+/// Parse JSONL lines, keeping lines that are not JSON as strings.
+fn parse_lines(output: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_owned())))
+        .collect()
+}
+
+fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let (Value::Object(before), Value::Object(after)) = (before, after) else {
+        return Vec::new();
+    };
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::ledger::append;
+    use crate::witness::record::WitnessInput;
+    use serde_json::json;
+    use std::fs;
+
+    fn run_record(params: Value, inputs: Vec<WitnessInput>) -> WitnessRecord {
+        WitnessRecord::new(
+            "0.1.0",
+            "blake3:binary",
+            inputs,
+            params,
+            "ALL_MATCHED",
+            0,
+            "blake3:output",
+            "2026-02-24T10:00:00Z",
+        )
+        .expect("build witness record")
+    }
+
+    #[test]
+    fn replay_args_rebuild_the_invocation() {
+        let record = run_record(
+            json!({
+                "fingerprints": ["csv.v0", "xlsx.v0"],
+                "jobs": 4,
+                "input": "manifest.jsonl",
+                "output": "out.jsonl",
+                "diagnose": true,
+                "max_file_size": 1024,
+                "max_decompressed_size": null,
+                "timeout": 30
+            }),
+            vec![],
+        );
+
+        assert_eq!(
+            replay_args(&record).expect("replay args"),
+            [
+                "manifest.jsonl",
+                "--fp",
+                "csv.v0",
+                "--fp",
+                "xlsx.v0",
+                "--jobs",
+                "4",
+                "--max-file-size",
+                "1024",
+                "--timeout",
+                "30",
+                "--diagnose",
+                "--no-witness",
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_args_refuse_stdin_and_other_modes() {
+        let stdin = run_record(json!({ "fingerprints": ["csv.v0"], "jobs": 1 }), vec![]);
+        let error = replay_args(&stdin).expect_err("stdin run");
+        assert!(error.contains("stdin"), "{error}");
+
+        let infer = run_record(json!({ "mode": "infer", "format": "xlsx" }), vec![]);
+        let error = replay_args(&infer).expect_err("infer run");
+        assert!(error.contains("only run mode"), "{error}");
+    }
+
+    #[test]
+    fn check_inputs_rehashes_files() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let manifest = tempdir.path().join("manifest.jsonl");
+        fs::write(&manifest, b"{}\n").expect("write manifest");
+        let hash = format!("blake3:{}", blake3::hash(b"{}\n").to_hex());
+        let record = run_record(
+            json!({}),
+            vec![WitnessInput {
+                path: manifest.display().to_string(),
+                hash: Some(hash.clone()),
+                bytes: Some(3),
+            }],
+        );
+
+        assert!(check_inputs(&record)[0].matches);
+
+        fs::write(&manifest, b"{\"changed\":true}\n").expect("rewrite manifest");
+        let checks = check_inputs(&record);
+        assert!(!checks[0].matches);
+        assert_eq!(checks[0].recorded_hash.as_deref(), Some(hash.as_str()));
+    }
+
+    #[test]
+    fn check_inputs_rehashes_manifest_documents() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let document = tempdir.path().join("tape.csv");
+        fs::write(&document, b"loan_id\n").expect("write document");
+        let manifest = tempdir.path().join("manifest.jsonl");
+        let contents = format!(
+            "{}\n{}\n",
+            json!({
+                "version": "hash.v0",
+                "path": document.display().to_string(),
+                "bytes_hash": format!("blake3:{}", blake3::hash(b"loan_id\n").to_hex()),
+            }),
+            json!({ "version": "hash.v0", "path": "skipped.csv", "_skipped": true })
+        );
+        fs::write(&manifest, &contents).expect("write manifest");
+        let record = run_record(
+            json!({}),
+            vec![WitnessInput {
+                path: manifest.display().to_string(),
+                hash: Some(format!(
+                    "blake3:{}",
+                    blake3::hash(contents.as_bytes()).to_hex()
+                )),
+                bytes: Some(contents.len() as u64),
+            }],
+        );
+
+        let checks = check_inputs(&record);
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|check| check.matches), "{checks:?}");
+        assert_eq!(checks[1].path, document.display().to_string());
+
+        fs::write(&document, b"loan_id,balance\n").expect("rewrite document");
+        let checks = check_inputs(&record);
+        assert!(checks[0].matches);
+        assert!(!checks[1].matches);
+        assert_ne!(checks[1].current_hash, checks[1].recorded_hash);
+    }
+
+    #[test]
+    fn check_inputs_reports_other_hash_algorithms_as_unverifiable() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let document = tempdir.path().join("tape.csv");
+        fs::write(&document, b"loan_id\n").expect("write document");
+        let manifest = tempdir.path().join("manifest.jsonl");
+        let contents = format!(
+            "{}\n",
+            json!({
+                "version": "hash.v0",
+                "path": document.display().to_string(),
+                "bytes_hash": "sha256:e3b0c44298fc1c149afbf4c8996fb924",
+            })
+        );
+        fs::write(&manifest, &contents).expect("write manifest");
+        let record = run_record(
+            json!({}),
+            vec![WitnessInput {
+                path: manifest.display().to_string(),
+                hash: Some(format!(
+                    "blake3:{}",
+                    blake3::hash(contents.as_bytes()).to_hex()
+                )),
+                bytes: Some(contents.len() as u64),
+            }],
+        );
+
+        let checks = check_inputs(&record);
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[1].current_hash, None);
+        assert!(!checks[1].changed(), "{checks:?}");
+        assert_eq!(
+            checks[1].note.as_deref(),
+            Some("sha256 hashes cannot be recomputed; not verified")
+        );
+        assert!(!checks.iter().any(InputCheck::changed));
+    }
+
+    #[test]
+    fn diff_outputs_reports_changed_removed_and_added_lines() {
+        let recorded = b"{\"path\":\"a\",\"matched\":true}\n{\"path\":\"b\",\"matched\":true}\n";
+        let replayed = b"{\"path\":\"a\",\"matched\":true}\n{\"path\":\"b\",\"matched\":false}\n{\"path\":\"c\"}\n";
+
+        let differences = diff_outputs(recorded, replayed);
+
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].line, 2);
+        assert_eq!(differences[0].kind, "changed");
+        assert_eq!(differences[0].fields, vec!["matched"]);
+        assert_eq!(differences[1].line, 3);
+        assert_eq!(differences[1].kind, "added");
+        assert_eq!(differences[1].recorded, None);
+
+        let differences = diff_outputs(replayed, recorded);
+        assert_eq!(differences[1].kind, "removed");
+    }
+
+    #[test]
+    fn find_record_accepts_unique_prefixes() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let first =
+            append(&ledger_path, &run_record(json!({ "jobs": 1 }), vec![])).expect("append first");
+        append(&ledger_path, &run_record(json!({ "jobs": 2 }), vec![])).expect("append second");
+
+        let found = find_record(&ledger_path, &first.id[..20]).expect("find by prefix");
+        assert_eq!(found, first);
+
+        let error = find_record(&ledger_path, "blake3:").expect_err("ambiguous prefix");
+        assert!(error.contains("ambiguous"), "{error}");
+        let error = find_record(&ledger_path, "blake3:zz").expect_err("unknown id");
+        assert!(error.contains("no witness record"), "{error}");
+    }
+}
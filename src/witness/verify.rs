@@ -0,0 +1,376 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::witness::ledger;
+use crate::witness::record::WitnessRecord;
+
+/// Result of walking a witness ledger's hash chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    /// Records checked before the first break, or in total.
+    pub records: u64,
+    /// Leading records written before the ledger was chained, or by other
+    /// tools. Nothing links them, so editing or removing them is
+    /// undetectable.
+    pub legacy: u64,
+    /// ID of the last record checked, which the next record must link to.
+    pub head: Option<String>,
+    /// The first break in the ledger, if any.
+    #[serde(rename = "break")]
+    pub chain_break: Option<ChainBreak>,
+}
+
+/// A record whose ID or link does not verify.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainBreak {
+    /// Ledger segment holding the record.
+    pub path: PathBuf,
+    /// Line of the record within its segment.
+    pub line: u64,
+    pub id: Option<String>,
+    pub reason: String,
+}
+
+impl VerifyReport {
+    pub fn is_intact(&self) -> bool {
+        self.chain_break.is_none()
+    }
+}
+
+/// Recompute every record ID in the ledger and check that each record's
+/// `prev` names the record before it, stopping at the first break.
+///
+/// The chain runs through rotated segments in order, then the active ledger.
+/// Lines without `prev` are accepted only before the first chained record,
+/// so ledgers written before chaining still verify. Such leading lines are
+/// legacy even when they are not `witness.v0` records or their IDs do not
+/// recompute; the first chained record must still link to the last one.
+pub fn verify(ledger_path: &Path) -> Result<VerifyReport, String> {
+    let mut report = VerifyReport {
+        records: 0,
+        legacy: 0,
+        head: None,
+        chain_break: None,
+    };
+    let _lock = ledger::lock_shared(ledger_path);
+
+    let mut chained = false;
+    // Whether the last legacy line is a record whose ID verifies: the first
+    // chained record's `prev` then protects it as the chain's root
+    let mut root_verified = false;
+    'segments: for segment in ledger::segments(ledger_path)? {
+        let file = match File::open(&segment.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => {
+                return Err(format!(
+                    "failed to open witness ledger '{}': {error}",
+                    segment.path.display()
+                ));
+            }
+        };
+
+        for (line_index, line_result) in BufReader::new(file).lines().enumerate() {
+            let line_number = line_index as u64 + 1;
+            let line = line_result.map_err(|error| {
+                format!(
+                    "failed to read witness ledger '{}' at line {line_number}: {error}",
+                    segment.path.display()
+                )
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match check_record(&line, report.head.as_deref(), chained) {
+                Ok(record) => {
+                    if record.prev.is_none() {
+                        report.legacy += 1;
+                        root_verified = true;
+                    } else if !chained {
+                        chained = true;
+                        if root_verified {
+                            report.legacy -= 1;
+                        }
+                    }
+                    report.records += 1;
+                    report.head = Some(record.id);
+                }
+                Err((id, _)) if !chained && is_unlinked(&line) => {
+                    report.legacy += 1;
+                    root_verified = false;
+                    report.records += 1;
+                    if id.is_some() {
+                        report.head = id;
+                    }
+                }
+                Err((id, reason)) => {
+                    report.chain_break = Some(ChainBreak {
+                        path: segment.path,
+                        line: line_number,
+                        id,
+                        reason,
+                    });
+                    break 'segments;
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Whether a line has no `prev` link: unparseable lines, foreign records and
+/// records written before chaining.
+fn is_unlinked(line: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(line).map_or(true, |value| {
+        value.get("prev").is_none_or(|prev| prev.is_null())
+    })
+}
+
+/// Check one ledger line against the ID of the record before it.
+fn check_record(
+    line: &str,
+    previous: Option<&str>,
+    chained: bool,
+) -> Result<WitnessRecord, (Option<String>, String)> {
+    let record: WitnessRecord = serde_json::from_str(line).map_err(|error| {
+        let id = serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|value| value.get("id")?.as_str().map(ToOwned::to_owned));
+        (id, format!("not a witness.v0 record: {error}"))
+    })?;
+    let id = Some(record.id.clone());
+
+    let recomputed = record.recompute_id().map_err(|error| (id.clone(), error))?;
+    if recomputed != record.id {
+        return Err((
+            id,
+            format!("record contents hash to {recomputed}, not its id"),
+        ));
+    }
+
+    match (record.prev.as_deref(), previous) {
+        (Some(prev), Some(previous)) if prev != previous => Err((
+            id,
+            format!("prev is {prev}, but the previous record is {previous}"),
+        )),
+        (Some(prev), None) => Err((
+            id,
+            format!("prev is {prev}, but there is no previous record"),
+        )),
+        (None, Some(_)) if chained => Err((
+            id,
+            "record has no prev, but the ledger is already chained".to_owned(),
+        )),
+        _ => Ok(record),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify;
+    use crate::witness::ledger::{Rotation, append, append_with_rotation};
+    use crate::witness::record::{WitnessInput, WitnessRecord};
+    use serde_json::json;
+    use std::fs;
+
+    fn record(outcome: &str) -> WitnessRecord {
+        WitnessRecord::new(
+            "0.1.0",
+            "blake3:binary",
+            vec![WitnessInput {
+                path: "stdin".to_owned(),
+                hash: None,
+                bytes: None,
+            }],
+            json!({ "fingerprints": ["csv.v0"], "jobs": 1 }),
+            outcome,
+            0,
+            "blake3:output",
+            "2026-02-24T10:00:00Z",
+        )
+        .expect("build witness record")
+    }
+
+    fn chained_ledger(count: usize) -> (tempfile::TempDir, std::path::PathBuf) {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        for index in 0..count {
+            append(&ledger_path, &record(&format!("RUN_{index}"))).expect("append record");
+        }
+        (tempdir, ledger_path)
+    }
+
+    #[test]
+    fn intact_chain_verifies() {
+        let (_tempdir, ledger_path) = chained_ledger(3);
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        assert!(report.is_intact());
+        assert_eq!(report.records, 3);
+        assert_eq!(report.legacy, 0);
+        let last = fs::read_to_string(&ledger_path).expect("read ledger");
+        let last: serde_json::Value =
+            serde_json::from_str(last.lines().last().expect("last line")).expect("parse");
+        assert_eq!(report.head.as_deref(), last["id"].as_str());
+    }
+
+    #[test]
+    fn legacy_records_before_the_chain_verify() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let legacy = format!(
+            "{}{}",
+            record("LEGACY_0").to_jsonl().expect("serialize"),
+            record("LEGACY_1").to_jsonl().expect("serialize")
+        );
+        fs::write(&ledger_path, legacy).expect("write legacy ledger");
+        append(&ledger_path, &record("RUN_0")).expect("append chained record");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.records, 3);
+        assert_eq!(report.legacy, 1);
+    }
+
+    #[test]
+    fn unchained_ledger_is_all_legacy() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let legacy = format!(
+            "{}{}",
+            record("LEGACY_0").to_jsonl().expect("serialize"),
+            record("LEGACY_1").to_jsonl().expect("serialize")
+        );
+        fs::write(&ledger_path, legacy).expect("write legacy ledger");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.records, 2);
+        assert_eq!(report.legacy, 2);
+    }
+
+    #[test]
+    fn foreign_leading_lines_are_legacy() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        let chained = record("RUN_0")
+            .chained_to(Some("old".to_owned()))
+            .expect("chain record");
+        let content = format!(
+            "not json\n{}\n{}",
+            json!({ "id": "old", "created_ts": "2025-01-01T00:00:00Z" }),
+            chained.to_jsonl().expect("serialize")
+        );
+        fs::write(&ledger_path, content).expect("write ledger");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.records, 3);
+        // The root's own ID does not verify, so it is legacy too
+        assert_eq!(report.legacy, 2);
+        assert_eq!(report.head.as_deref(), Some(chained.id.as_str()));
+
+        let (_tempdir, ledger_path) = chained_ledger(2);
+        let content = fs::read_to_string(&ledger_path).expect("read ledger");
+        fs::write(&ledger_path, format!("not json\n{content}")).expect("prepend line");
+        let report = verify(&ledger_path).expect("verify ledger");
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.legacy, 1);
+    }
+
+    #[test]
+    fn edited_record_breaks_at_its_line() {
+        let (_tempdir, ledger_path) = chained_ledger(3);
+        let content = fs::read_to_string(&ledger_path).expect("read ledger");
+        fs::write(&ledger_path, content.replacen("RUN_1", "RUN_X", 1)).expect("edit ledger");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        let chain_break = report.chain_break.expect("break");
+        assert_eq!(chain_break.line, 2);
+        assert!(chain_break.reason.contains("not its id"), "{chain_break:?}");
+        assert_eq!(report.records, 1);
+    }
+
+    #[test]
+    fn deleted_record_breaks_the_next_link() {
+        let (_tempdir, ledger_path) = chained_ledger(3);
+        let content = fs::read_to_string(&ledger_path).expect("read ledger");
+        let kept: Vec<&str> = content
+            .lines()
+            .enumerate()
+            .filter(|(index, _)| *index != 1)
+            .map(|(_, line)| line)
+            .collect();
+        fs::write(&ledger_path, kept.join("\n") + "\n").expect("delete record");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        let chain_break = report.chain_break.expect("break");
+        assert_eq!(chain_break.line, 2);
+        assert!(
+            chain_break.reason.contains("but the previous record is"),
+            "{chain_break:?}"
+        );
+    }
+
+    #[test]
+    fn unchained_record_after_the_chain_breaks() {
+        let (_tempdir, ledger_path) = chained_ledger(2);
+        let mut content = fs::read_to_string(&ledger_path).expect("read ledger");
+        content.push_str(&record("INSERTED").to_jsonl().expect("serialize"));
+        fs::write(&ledger_path, content).expect("insert record");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+
+        let chain_break = report.chain_break.expect("break");
+        assert_eq!(chain_break.line, 3);
+        assert!(chain_break.reason.contains("already chained"));
+    }
+
+    #[test]
+    fn missing_ledger_is_empty_and_intact() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+
+        let report = verify(&tempdir.path().join("missing.jsonl")).expect("verify");
+
+        assert!(report.is_intact());
+        assert_eq!(report.records, 0);
+        assert_eq!(report.head, None);
+    }
+
+    #[test]
+    fn chain_runs_across_rotated_segments() {
+        let tempdir = tempfile::tempdir().expect("create temp dir");
+        let ledger_path = tempdir.path().join("witness.jsonl");
+        for index in 0..4 {
+            append_with_rotation(
+                &ledger_path,
+                &record(&format!("RUN_{index}")),
+                Rotation::Size(1),
+            )
+            .expect("append record");
+        }
+
+        let report = verify(&ledger_path).expect("verify ledger");
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.records, 4);
+
+        fs::remove_file(tempdir.path().join("witness.jsonl.2")).expect("remove segment");
+
+        let report = verify(&ledger_path).expect("verify ledger");
+        let chain_break = report.chain_break.expect("break");
+        assert_eq!(chain_break.path, tempdir.path().join("witness.jsonl.3"));
+        assert_eq!(chain_break.line, 1);
+        assert_eq!(report.records, 1);
+    }
+}
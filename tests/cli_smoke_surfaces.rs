@@ -184,6 +184,184 @@ assertions:
     }
 }
 
+#[test]
+fn smoke_compile_binary_bundles_definitions() {
+    let dir = tempfile::tempdir().expect("create compile dir");
+    let first = dir.path().join("first.fp.yaml");
+    fs::write(
+        &first,
+        "fingerprint_id: bundle-b.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+    )
+    .expect("write first definition");
+    let second = dir.path().join("second.fp.yaml");
+    fs::write(
+        &second,
+        "fingerprint_id: bundle-a.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+    )
+    .expect("write second definition");
+    let out = dir.path().join("bundle");
+
+    let compile = run_fingerprint(&[
+        "compile",
+        "--binary",
+        first.to_str().expect("first path"),
+        second.to_str().expect("second path"),
+        "--out",
+        out.to_str().expect("out path"),
+    ]);
+    assert_eq!(compile.status.code(), Some(0));
+    let cargo_toml = fs::read_to_string(out.join("Cargo.toml")).expect("read Cargo.toml");
+    assert!(cargo_toml.contains("name = \"fingerprint-custom\""));
+    let mod_rs = fs::read_to_string(out.join("src/fingerprints/mod.rs")).expect("read mod.rs");
+    assert!(mod_rs.contains("pub mod bundle_a_v1;\npub mod bundle_b_v1;\n"));
+
+    let several_without_out = run_fingerprint(&[
+        "compile",
+        first.to_str().expect("first path"),
+        second.to_str().expect("second path"),
+    ]);
+    assert_eq!(several_without_out.status.code(), Some(2));
+
+    let invalid = dir.path().join("invalid.fp.yaml");
+    fs::write(
+        &invalid,
+        "fingerprint_id: bundle-bad.v1\nformat: xml\nassertions:\n  - sheet_exists: \"csv\"\n",
+    )
+    .expect("write invalid definition");
+    let refused = run_fingerprint(&[
+        "compile",
+        "--binary",
+        first.to_str().expect("first path"),
+        invalid.to_str().expect("invalid path"),
+        "--out",
+        dir.path().join("refused").to_str().expect("refused path"),
+    ]);
+    assert_eq!(refused.status.code(), Some(2));
+    let refusal: Value = serde_json::from_slice(&refused.stdout).expect("refusal is JSON");
+    assert_eq!(
+        refusal["refusal"]["detail"]["diagnostics"][0]["code"],
+        "E_UNSUPPORTED_FORMAT"
+    );
+    assert!(!dir.path().join("refused").exists());
+}
+
+#[test]
+fn smoke_compile_directory_into_one_crate() {
+    let dir = tempfile::tempdir().expect("create compile dir");
+    let definitions = dir.path().join("definitions");
+    fs::create_dir_all(&definitions).expect("create definitions dir");
+    fs::write(
+        definitions.join("family.fp.yaml"),
+        "fingerprint_id: family.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n---\nfingerprint_id: family-child.v1\nparent: family.v1\nformat: csv\nassertions:\n  - sheet_min_rows: { sheet: \"csv\", min_rows: 1 }\n",
+    )
+    .expect("write family definitions");
+    let out = dir.path().join("family");
+
+    let check = run_fingerprint(&[
+        "compile",
+        definitions.to_str().expect("definitions path"),
+        "--check",
+    ]);
+    assert_eq!(check.status.code(), Some(0));
+    assert!(!out.exists());
+
+    let compile = run_fingerprint(&[
+        "compile",
+        definitions.to_str().expect("definitions path"),
+        "--out",
+        out.to_str().expect("out path"),
+    ]);
+    assert_eq!(compile.status.code(), Some(0));
+    let lib_rs = fs::read_to_string(out.join("src/lib.rs")).expect("read lib.rs");
+    assert!(lib_rs.contains("pub use fingerprints::register_all;"));
+    let mod_rs = fs::read_to_string(out.join("src/fingerprints/mod.rs")).expect("read mod.rs");
+    assert!(mod_rs.contains("pub mod family_child_v1;\npub mod family_v1;\n"));
+
+    fs::write(
+        definitions.join("orphan.fp.yaml"),
+        "fingerprint_id: orphan.v1\nparent: missing.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+    )
+    .expect("write orphan definition");
+    let refused = run_fingerprint(&[
+        "compile",
+        definitions.to_str().expect("definitions path"),
+        "--check",
+    ]);
+    assert_eq!(refused.status.code(), Some(2));
+    let refusal: Value = serde_json::from_slice(&refused.stdout).expect("refusal is JSON");
+    assert_eq!(
+        refusal["refusal"]["detail"]["diagnostics"][0]["code"],
+        "E_ORPHAN_PARENT"
+    );
+}
+
+#[test]
+fn smoke_lint_reports_findings_and_exit_codes() {
+    let dir = tempfile::tempdir().expect("create lint dir");
+    fs::write(
+        dir.path().join("family.fp.yaml"),
+        r#"
+fingerprint_id: lint-parent.v1
+format: csv
+assertions:
+  - sheet_exists: "csv"
+  - sheet_min_rows:
+      sheet: "csv"
+      min_rows: 1
+---
+fingerprint_id: lint-child.v1
+format: csv
+parent: lint-parent.v1
+valid_from: "2025-01-01"
+valid_until: "2024-01-01"
+assertions:
+  - filename_regex:
+      pattern: "x*"
+"#,
+    )
+    .expect("write definitions");
+
+    let lint = run_fingerprint(&["lint", dir.path().to_str().expect("lint dir")]);
+    assert_eq!(lint.status.code(), Some(1));
+    let findings: Vec<Value> = String::from_utf8(lint.stdout)
+        .expect("lint output utf8")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("finding is JSON"))
+        .collect();
+    let codes: Vec<(&str, &str)> = findings
+        .iter()
+        .map(|finding| {
+            (
+                finding["code"].as_str().expect("code"),
+                finding["severity"].as_str().expect("severity"),
+            )
+        })
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            ("redundant_assertion", "warning"),
+            ("inverted_validity_window", "error"),
+            ("empty_match_regex", "warning"),
+        ]
+    );
+    assert_eq!(findings[0]["version"], "lint.v0");
+    assert_eq!(findings[0]["location"], "assertions[0]");
+
+    let clean = NamedTempFile::with_suffix(".fp.yaml").expect("create clean yaml");
+    fs::write(
+        clean.path(),
+        "fingerprint_id: lint-clean.v1\nformat: csv\nassertions:\n  - sheet_exists: \"csv\"\n",
+    )
+    .expect("write clean definition");
+    let lint = run_fingerprint(&["lint", clean.path().to_str().expect("clean path")]);
+    assert_eq!(lint.status.code(), Some(0));
+    assert!(lint.stdout.is_empty());
+
+    let missing = run_fingerprint(&["lint", "does-not-exist.fp.yaml"]);
+    assert_eq!(missing.status.code(), Some(2));
+}
+
 #[test]
 fn smoke_process_boundary_exit_codes_0_1_2() {
     let csv_manifest = manifest_with_record(&fixture("tests/fixtures/files/sample.csv"), ".csv");
@@ -415,3 +593,201 @@ fn smoke_witness_invalid_timestamp_filter_exits_two() {
     let stderr = String::from_utf8(query.stderr).expect("stderr utf8");
     assert!(stderr.contains("invalid --since"));
 }
+
+#[test]
+fn smoke_witness_params_projection_and_group_by() {
+    let tempdir = tempfile::tempdir().expect("create tempdir");
+    let witness_path = tempdir.path().join("witness.jsonl");
+    let record = |id: &str, fingerprints: Value, outcome: &str, ts: &str| {
+        json!({
+            "id": id,
+            "tool": "fingerprint",
+            "version": "0.1.0",
+            "binary_hash": "blake3:binary",
+            "inputs": [{ "path": "manifest.jsonl", "hash": "blake3:in", "bytes": 10 }],
+            "params": { "fingerprints": fingerprints, "diagnose": false },
+            "outcome": outcome,
+            "exit_code": 0,
+            "output_hash": format!("blake3:out-{id}"),
+            "ts": ts
+        })
+    };
+    write_witness_ledger(
+        &witness_path,
+        &[
+            record(
+                "one",
+                json!(["csv.v0"]),
+                "ALL_MATCHED",
+                "2026-02-01T00:00:00Z",
+            ),
+            record(
+                "two",
+                json!(["csv.v0", "pdf.v0"]),
+                "PARTIAL",
+                "2026-02-01T09:00:00Z",
+            ),
+            record(
+                "three",
+                json!(["pdf.v0"]),
+                "ALL_MATCHED",
+                "2026-02-02T00:00:00Z",
+            ),
+        ],
+    );
+
+    let query = run_fingerprint_with_witness(
+        &[
+            "witness",
+            "query",
+            "--param",
+            "fingerprints=pdf.v0",
+            "--fields",
+            "id,outcome",
+            "--json",
+        ],
+        &witness_path,
+    );
+    assert_eq!(query.status.code(), Some(0));
+    let records: Value = serde_json::from_slice(&query.stdout).expect("query should be JSON");
+    assert_eq!(
+        records,
+        json!([
+            { "id": "two", "outcome": "PARTIAL" },
+            { "id": "three", "outcome": "ALL_MATCHED" }
+        ])
+    );
+
+    let diagnosed =
+        run_fingerprint_with_witness(&["witness", "count", "--param", "diagnose"], &witness_path);
+    assert_eq!(diagnosed.status.code(), Some(1));
+
+    let grouped = run_fingerprint_with_witness(
+        &["witness", "count", "--group-by", "day,outcome"],
+        &witness_path,
+    );
+    assert_eq!(grouped.status.code(), Some(0));
+    let stdout = String::from_utf8(grouped.stdout).expect("count output utf8");
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "2026-02-01\tALL_MATCHED\t1",
+            "2026-02-01\tPARTIAL\t1",
+            "2026-02-02\tALL_MATCHED\t1"
+        ]
+    );
+}
+
+#[test]
+fn smoke_witness_verify_detects_tampering() {
+    let tempdir = tempfile::tempdir().expect("create tempdir");
+    let witness_path = tempdir.path().join("witness.jsonl");
+    let csv_manifest = manifest_with_record(&fixture("tests/fixtures/files/sample.csv"), ".csv");
+    let manifest_path = csv_manifest.path().to_str().expect("manifest path");
+
+    for jobs in ["1", "2"] {
+        let run = run_fingerprint_with_witness(
+            &["--fp", "csv.v0", "--jobs", jobs, manifest_path],
+            &witness_path,
+        );
+        assert_eq!(run.status.code(), Some(0));
+    }
+
+    let verify = run_fingerprint_with_witness(&["witness", "verify", "--json"], &witness_path);
+    assert_eq!(verify.status.code(), Some(0));
+    let report: Value = serde_json::from_slice(&verify.stdout).expect("verify should be JSON");
+    assert_eq!(report["records"], 2);
+    assert_eq!(report["break"], Value::Null);
+
+    let ledger = fs::read_to_string(&witness_path).expect("read witness ledger");
+    fs::write(
+        &witness_path,
+        ledger.replacen("\"jobs\":1", "\"jobs\":8", 1),
+    )
+    .expect("tamper with witness ledger");
+
+    let verify = run_fingerprint_with_witness(&["witness", "verify", "--json"], &witness_path);
+    assert_eq!(verify.status.code(), Some(1));
+    let report: Value = serde_json::from_slice(&verify.stdout).expect("verify should be JSON");
+    assert_eq!(report["break"]["line"], 1);
+    assert_eq!(report["records"], 0);
+}
+
+#[test]
+fn smoke_witness_records_classification_history() {
+    let tempdir = tempfile::tempdir().expect("create tempdir");
+    let witness_path = tempdir.path().join("witness.jsonl");
+    let manifest = manifest_with_record(&fixture("tests/fixtures/files/sample.csv"), ".csv");
+    let manifest_path = manifest.path().to_str().expect("manifest path");
+
+    let plain = run_fingerprint_with_witness(&["--fp", "csv.v0", manifest_path], &witness_path);
+    assert_eq!(plain.status.code(), Some(0));
+    let recorded = run_fingerprint_with_witness(
+        &["--fp", "csv.v0", "--witness-records", manifest_path],
+        &witness_path,
+    );
+    assert_eq!(recorded.status.code(), Some(0));
+
+    let last = run_fingerprint_with_witness(&["witness", "last"], &witness_path);
+    let last_json: Value = serde_json::from_slice(&last.stdout).expect("last should be JSON");
+    let history = run_fingerprint_with_witness(
+        &["witness", "history", "sha256:test", "--json"],
+        &witness_path,
+    );
+    assert_eq!(history.status.code(), Some(0));
+    let entries: Value = serde_json::from_slice(&history.stdout).expect("history should be JSON");
+    let entries = entries.as_array().expect("history array");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["run"], last_json["id"]);
+    assert_eq!(entries[0]["bytes_hash"], "sha256:test");
+    assert_eq!(entries[0]["fingerprint_id"], "csv.v0");
+
+    let unknown =
+        run_fingerprint_with_witness(&["witness", "history", "sha256:unknown"], &witness_path);
+    assert_eq!(unknown.status.code(), Some(1));
+}
+
+#[test]
+fn smoke_witness_replay_reproduces_run() {
+    let tempdir = tempfile::tempdir().expect("create tempdir");
+    let witness_path = tempdir.path().join("witness.jsonl");
+    let output_path = tempdir.path().join("out.jsonl");
+    let manifest_path = tempdir.path().join("manifest.jsonl");
+    let record = format!(
+        r#"{{"version":"hash.v0","path":"{}","extension":".csv","bytes_hash":"sha256:test","tool_versions":{{"hash":"0.1.0"}}}}"#,
+        fixture("tests/fixtures/files/sample.csv").display()
+    );
+    fs::write(&manifest_path, format!("{record}\n")).expect("write manifest");
+
+    let run = run_fingerprint_with_witness(
+        &[
+            "--fp",
+            "csv.v0",
+            manifest_path.to_str().expect("manifest path"),
+            "--output",
+            output_path.to_str().expect("output path"),
+        ],
+        &witness_path,
+    );
+    assert_eq!(run.status.code(), Some(0));
+    let last = run_fingerprint_with_witness(&["witness", "last"], &witness_path);
+    let last_json: Value = serde_json::from_slice(&last.stdout).expect("last should be JSON");
+    let id = last_json["id"].as_str().expect("witness id");
+
+    let replay = run_fingerprint_with_witness(&["witness", "replay", id, "--json"], &witness_path);
+    assert_eq!(replay.status.code(), Some(0));
+    let report: Value = serde_json::from_slice(&replay.stdout).expect("replay should be JSON");
+    assert_eq!(report["matches"], true);
+    assert_eq!(report["inputs_match"], true);
+    assert_eq!(report["replayed"]["output_hash"], last_json["output_hash"]);
+
+    let count = run_fingerprint_with_witness(&["witness", "count"], &witness_path);
+    assert_eq!(String::from_utf8_lossy(&count.stdout).trim(), "1");
+
+    fs::write(&manifest_path, format!("{record}\n{record}\n")).expect("change manifest");
+    let replay = run_fingerprint_with_witness(&["witness", "replay", id, "--json"], &witness_path);
+    assert_eq!(replay.status.code(), Some(1));
+    let report: Value = serde_json::from_slice(&replay.stdout).expect("replay should be JSON");
+    assert_eq!(report["inputs_match"], false);
+    assert_eq!(report["replayed"], Value::Null);
+}
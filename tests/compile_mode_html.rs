@@ -363,3 +363,85 @@ assertions:
             .contains("requires at least one of 'min' or 'max'")
     );
 }
+
+#[test]
+fn compile_mode_reports_every_problem_with_positions() {
+    let definition = write_yaml(
+        r#"
+fingerprint_id: many-problems.v1
+format: markdown
+assertions:
+  - heading_regex:
+      pattern: "(unclosed"
+  - name: sections
+    page_section_count:
+      min: 1
+extract:
+  - name: notes
+    type: section
+content_hash:
+  algorithm: blake3
+  over: [notes]
+"#
+        .trim(),
+    );
+    let unknown = write_yaml(
+        r#"
+fingerprint_id: unknown-twice.v1
+format: html
+assertions:
+  - html_magic: true
+  - sheet_exists: "Sheet1"
+  - html_wizardry: true
+"#
+        .trim(),
+    );
+
+    let output = run_fingerprint(&[
+        "compile",
+        definition.path().to_str().expect("definition path"),
+        "--check",
+    ]);
+    assert_failure(&output, "compile with validation problems");
+    let refusal: Value = serde_json::from_slice(&output.stdout).expect("parse refusal");
+    let diagnostics = refusal["refusal"]["detail"]["diagnostics"]
+        .as_array()
+        .expect("diagnostics array");
+    let summary: Vec<(&str, Option<u64>, Option<u64>)> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic["code"].as_str().expect("code"),
+                diagnostic["line"].as_u64(),
+                diagnostic["column"].as_u64(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("E_FORMAT_MISMATCH", Some(6), Some(3)),
+            ("E_INVALID_EXTRACT", Some(10), Some(3)),
+            ("E_INVALID_PATTERN", Some(5), Some(7)),
+        ]
+    );
+    assert_eq!(diagnostics[0]["name"], "sections");
+    assert_eq!(diagnostics[0]["index"], 1);
+    assert_eq!(refusal["refusal"]["detail"]["line"], 6);
+
+    let output = run_fingerprint(&[
+        "compile",
+        unknown.path().to_str().expect("unknown path"),
+        "--check",
+    ]);
+    assert_failure(&output, "compile with unknown assertions");
+    let refusal: Value = serde_json::from_slice(&output.stdout).expect("parse refusal");
+    assert_eq!(refusal["refusal"]["code"], "E_UNKNOWN_ASSERTION");
+    let lines: Vec<u64> = refusal["refusal"]["detail"]["diagnostics"]
+        .as_array()
+        .expect("diagnostics array")
+        .iter()
+        .map(|diagnostic| diagnostic["line"].as_u64().expect("line"))
+        .collect();
+    assert_eq!(lines, vec![4, 6]);
+}
@@ -0,0 +1,371 @@
+//! Compiled fingerprints must produce the same output as the interpreter.
+//!
+//! These tests generate a `compile --binary` crate against this checkout and
+//! build it with cargo, so they are ignored by default:
+//!
+//! ```text
+//! cargo test --test compiled_equivalence -- --ignored
+//! ```
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const SOURCE_KEYS: &[&str] = &[
+    "fingerprint_crate",
+    "fingerprint_version",
+    "fingerprint_source",
+];
+
+const CSV_DEFINITION: &str = r#"fingerprint_id: equivalence-csv.v1
+format: csv
+assertions:
+  - name: any_sheet
+    sheet_name_regex:
+      pattern: ".*"
+      bind: data
+  - sheet_exists: "$data"
+  - cell_eq:
+      sheet: "$data"
+      cell: A1
+      value: Tenant
+  - cell_regex:
+      sheet: "$data"
+      cell: D2
+      pattern: "^\\d+$"
+  - range_non_null:
+      sheet: "$data"
+      range: A1:D3
+  - sheet_min_rows:
+      sheet: "$data"
+      min_rows: 2
+  - column_search:
+      sheet: "$data"
+      column: A
+      row_range: "1:3"
+      pattern: "(?i)beacon"
+  - header_row_match:
+      sheet: "$data"
+      row_range: "1:1"
+      min_match: 2
+      columns:
+        - pattern: "(?i)tenant"
+        - pattern: "(?i)rent"
+        - pattern: "(?i)missing"
+extract:
+  - name: rows
+    type: range
+    sheet: csv
+    range: A1:D3
+content_hash:
+  algorithm: blake3
+  over:
+    - rows
+"#;
+
+const CSV_FAILING_DEFINITION: &str = r#"fingerprint_id: equivalence-csv-miss.v1
+format: csv
+assertions:
+  - sheet_name_regex:
+      pattern: ".*"
+      bind: data
+  - cell_eq:
+      sheet: "$data"
+      cell: A1
+      value: Landlord
+  - sheet_min_rows:
+      sheet: "$data"
+      min_rows: 100
+  - sheet_exists: "$unbound"
+"#;
+
+const MARKDOWN_DEFINITION: &str = r#"fingerprint_id: equivalence-md.v1
+format: markdown
+assertions:
+  - heading_exists: "Rent Roll"
+  - heading_regex:
+      pattern: "(?i)cbre"
+  - heading_level:
+      level: 2
+      pattern: "(?i)capitalization"
+  - text_contains: "capitalization rate"
+  - text_regex:
+      pattern: "\\d+\\.\\d+%"
+  - text_near:
+      anchor: "(?i)capitalization rate"
+      pattern: "\\d+\\.\\d+%"
+      within_chars: 40
+  - section_non_empty:
+      heading: "(?i)property description"
+  - section_min_lines:
+      heading: "(?i)rent roll"
+      min_lines: 2
+  - table_exists:
+      heading: "(?i)rent roll"
+  - table_columns:
+      heading: "(?i)rent roll"
+      patterns: ["(?i)tenant", "(?i)suite"]
+  - table_shape:
+      heading: "(?i)rent roll"
+      min_columns: 4
+      column_types: [string, number, number, number]
+  - table_min_rows:
+      heading: "(?i)rent roll"
+      min_rows: 2
+extract:
+  - name: rent_roll
+    type: table
+    anchor_heading: "(?i)rent roll"
+    index: 0
+  - name: cap_rate
+    type: text_match
+    anchor: "(?i)capitalization rate"
+    pattern: "\\d+\\.\\d+%"
+    within_chars: 40
+content_hash:
+  algorithm: blake3
+  over:
+    - rent_roll
+"#;
+
+const MARKDOWN_FAILING_DEFINITION: &str = r#"fingerprint_id: equivalence-md-miss.v1
+format: markdown
+assertions:
+  - heading_exists: "Income Approach"
+  - table_min_rows:
+      heading: "(?i)rent roll"
+      min_rows: 50
+  - text_near:
+      anchor: "(?i)capitalization rate"
+      pattern: "(?i)percent"
+      within_chars: 5
+"#;
+
+const HTML_RULES: &[&str] = &[
+    "bdc-soi.v1.fp.yaml",
+    "bdc-soi-ares.v1.fp.yaml",
+    "bdc-soi-blackrock.v1.fp.yaml",
+    "bdc-soi-bxsl.v1.fp.yaml",
+    "bdc-soi-golub.v1.fp.yaml",
+    "bdc-soi-pennant.v1.fp.yaml",
+];
+
+fn repo_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+/// Definitions under test, copied into one directory.
+fn definitions_dir(root: &Path) -> PathBuf {
+    let dir = root.join("definitions");
+    fs::create_dir_all(&dir).expect("create definitions dir");
+    for rule in HTML_RULES {
+        fs::copy(repo_path(&format!("rules/{rule}")), dir.join(rule)).expect("copy rule");
+    }
+    for (name, definition) in [
+        ("equivalence-csv.v1.fp.yaml", CSV_DEFINITION),
+        ("equivalence-csv-miss.v1.fp.yaml", CSV_FAILING_DEFINITION),
+        ("equivalence-md.v1.fp.yaml", MARKDOWN_DEFINITION),
+        (
+            "equivalence-md-miss.v1.fp.yaml",
+            MARKDOWN_FAILING_DEFINITION,
+        ),
+    ] {
+        fs::write(dir.join(name), definition).expect("write definition");
+    }
+    dir
+}
+
+/// Generate the compiled binary crate, point it at this checkout, and build it.
+fn build_compiled_binary(root: &Path, definitions: &Path) -> PathBuf {
+    let out = root.join("compiled");
+    let compile = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .args(["compile", "--binary"])
+        .arg(definitions)
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .expect("run fingerprint compile");
+    assert_success(&compile, "compile --binary");
+
+    let cargo_toml = out.join("Cargo.toml");
+    let manifest = fs::read_to_string(&cargo_toml).expect("read generated Cargo.toml");
+    let dependency = format!(
+        "fingerprint = {{ path = {:?} }}",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let manifest = manifest
+        .lines()
+        .map(|line| {
+            if line.starts_with("fingerprint = ") {
+                dependency.as_str()
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    fs::write(&cargo_toml, manifest).expect("write generated Cargo.toml");
+
+    let target_dir = root.join("target");
+    let build = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(&cargo_toml)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .output()
+        .expect("run cargo build");
+    assert_success(&build, "cargo build of compiled crate");
+
+    target_dir.join("debug").join("fingerprint")
+}
+
+fn manifest(root: &Path) -> PathBuf {
+    let mut lines: Vec<String> =
+        fs::read_to_string(repo_path("tests/fixtures/manifests/html_corpus.jsonl"))
+            .expect("read html corpus manifest")
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+    for (path, extension) in [
+        ("tests/fixtures/files/sample.csv", ".csv"),
+        ("tests/fixtures/files/sample.md", ".md"),
+        ("tests/fixtures/files/cbre_appraisal_sample.md", ".md"),
+    ] {
+        lines.push(format!(
+            r#"{{"version":"hash.v0","path":"{path}","extension":"{extension}","bytes_hash":"sha256:{path}","tool_versions":{{"hash":"0.1.0"}}}}"#
+        ));
+    }
+    let path = root.join("manifest.jsonl");
+    fs::write(&path, lines.join("\n") + "\n").expect("write manifest");
+    path
+}
+
+fn fingerprint_ids(definitions: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = fs::read_dir(definitions)
+        .expect("read definitions dir")
+        .map(|entry| {
+            let name = entry.expect("definitions entry").file_name();
+            let name = name.to_string_lossy();
+            name.strip_suffix(".fp.yaml")
+                .expect("definition file name")
+                .to_owned()
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn run(
+    binary: &Path,
+    root: &Path,
+    manifest: &Path,
+    ids: &[String],
+    definitions: &Path,
+    extra: &[&str],
+) -> Output {
+    let trust_file = root.join("trust.yaml");
+    fs::write(
+        &trust_file,
+        "trust:\n  - \"installed:*\"\n  - \"compiled:*\"\n",
+    )
+    .expect("write trust file");
+    let mut command = Command::new(binary);
+    command.arg(manifest).arg("--no-witness").args(extra);
+    for id in ids {
+        command.arg("--fp").arg(id);
+    }
+    command
+        .env("FINGERPRINT_DEFINITIONS", definitions)
+        .env("FINGERPRINT_TRUST", &trust_file)
+        .env_remove("FINGERPRINT_PATH")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("run fingerprint")
+}
+
+/// Output records with the fields naming where a fingerprint came from removed.
+fn normalized(output: &Output) -> Vec<Value> {
+    String::from_utf8(output.stdout.clone())
+        .expect("stdout UTF-8")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut value: Value = serde_json::from_str(line).expect("parse JSON line");
+            strip_source_keys(&mut value);
+            value
+        })
+        .collect()
+}
+
+fn strip_source_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for key in SOURCE_KEYS {
+                map.remove(*key);
+            }
+            map.values_mut().for_each(strip_source_keys);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_source_keys),
+        _ => {}
+    }
+}
+
+fn assert_success(output: &Output, context: &str) {
+    assert!(
+        output.status.success(),
+        "{context} failed\nstatus: {:?}\nstdout:\n{}\nstderr:\n{}",
+        output.status.code(),
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
+
+#[test]
+#[ignore = "builds a generated crate with cargo"]
+fn compiled_binary_matches_interpreter_output() {
+    let root = TempDir::new().expect("create temp dir");
+    let definitions = definitions_dir(root.path());
+    let compiled = build_compiled_binary(root.path(), &definitions);
+    let manifest = manifest(root.path());
+    let ids = fingerprint_ids(&definitions);
+    let no_definitions = root.path().join("no-definitions");
+    fs::create_dir_all(&no_definitions).expect("create empty definitions dir");
+
+    for extra in [&[][..], &["--diagnose"][..]] {
+        let interpreted = run(
+            Path::new(env!("CARGO_BIN_EXE_fingerprint")),
+            root.path(),
+            &manifest,
+            &ids,
+            &definitions,
+            extra,
+        );
+        let native = run(
+            &compiled,
+            root.path(),
+            &manifest,
+            &ids,
+            &no_definitions,
+            extra,
+        );
+
+        assert_eq!(
+            interpreted.status.code(),
+            native.status.code(),
+            "exit codes differ with {extra:?}\ninterpreted stderr:\n{}\ncompiled stderr:\n{}",
+            String::from_utf8_lossy(&interpreted.stderr),
+            String::from_utf8_lossy(&native.stderr),
+        );
+        let interpreted = normalized(&interpreted);
+        let native = normalized(&native);
+        assert!(!interpreted.is_empty(), "no output records with {extra:?}");
+        assert_eq!(interpreted.len(), native.len());
+        for (expected, actual) in interpreted.iter().zip(&native) {
+            assert_eq!(expected, actual, "records differ with {extra:?}");
+        }
+    }
+}
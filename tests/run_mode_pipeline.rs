@@ -249,6 +249,268 @@ assertions:
     assert_eq!(lines[0]["fingerprint"]["matched"], true);
 }
 
+#[test]
+fn run_mode_reports_installed_pack_provenance() {
+    let pack_dir = tempdir().expect("create pack dir");
+    let definitions_dir = tempdir().expect("create definitions dir");
+    std::fs::write(
+        pack_dir.path().join("pack.yaml"),
+        "pack: csv-family\nversion: 3.0.1\nprovenance:\n  author: data-team\ndefinitions:\n  - family.fp.yaml\n",
+    )
+    .expect("write pack manifest");
+    std::fs::write(
+        pack_dir.path().join("family.fp.yaml"),
+        r#"
+fingerprint_id: csv-family.v1
+format: csv
+assertions:
+  - sheet_exists: "csv"
+---
+fingerprint_id: csv-family-child.v1
+format: csv
+parent: csv-family.v1
+assertions:
+  - sheet_min_rows:
+      sheet: "Sheet1"
+      min_rows: 1
+"#,
+    )
+    .expect("write pack definitions");
+
+    let install = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .args(["pack", "install"])
+        .arg(pack_dir.path())
+        .env("FINGERPRINT_DEFINITIONS", definitions_dir.path())
+        .output()
+        .expect("run pack install");
+    assert_eq!(install.status.code(), Some(0));
+    assert!(
+        definitions_dir
+            .path()
+            .join("csv-family/family.fp.yaml")
+            .is_file()
+    );
+
+    let csv_path = repo_path("tests/fixtures/files/sample.csv");
+    let manifest = write_jsonl(&[json!({
+        "version": "hash.v0",
+        "path": csv_path.display().to_string(),
+        "extension": ".csv",
+        "bytes_hash": "blake3:csv",
+        "tool_versions": { "hash": "0.1.0" }
+    })]);
+    let output = run_fingerprint_with_definitions(
+        manifest.path(),
+        &[
+            "--fp",
+            "csv-family.v1",
+            "--fp",
+            "csv-family-child.v1",
+            "--no-witness",
+        ],
+        definitions_dir.path(),
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    let lines = parse_jsonl(&output.stdout);
+    let fingerprint = &lines[0]["fingerprint"];
+    assert_eq!(fingerprint["fingerprint_id"], "csv-family.v1");
+    assert_eq!(fingerprint["fingerprint_crate"], "csv-family");
+    assert_eq!(fingerprint["fingerprint_version"], "3.0.1");
+    assert_eq!(
+        fingerprint["children"][0]["fingerprint_id"],
+        "csv-family-child.v1"
+    );
+}
+
+#[test]
+fn run_mode_matches_template_instances_and_compiles_one() {
+    let definitions_dir = tempdir().expect("create definitions dir");
+    std::fs::create_dir(definitions_dir.path().join("templates")).expect("create templates dir");
+    std::fs::write(
+        definitions_dir.path().join("templates/rows.fpt.yaml"),
+        r#"
+parameters:
+  sheet:
+    type: string
+    default: "Sheet1"
+  min_rows:
+    type: integer
+definition:
+  format: csv
+  assertions:
+    - name: enough_rows
+      sheet_min_rows:
+        sheet: "{{sheet}}"
+        min_rows: "{{min_rows}}"
+"#,
+    )
+    .expect("write template");
+    let instances_path = definitions_dir.path().join("row-counts.fp.yaml");
+    std::fs::write(
+        &instances_path,
+        r#"
+template: templates/rows.fpt.yaml
+instances:
+  - fingerprint_id: rows-large.v1
+    params: { min_rows: 50 }
+  - fingerprint_id: rows-small.v1
+    params: { min_rows: 1 }
+"#,
+    )
+    .expect("write instances");
+
+    let csv_path = repo_path("tests/fixtures/files/sample.csv");
+    let manifest = write_jsonl(&[json!({
+        "version": "hash.v0",
+        "path": csv_path.display().to_string(),
+        "extension": ".csv",
+        "bytes_hash": "blake3:csv",
+        "tool_versions": { "hash": "0.1.0" }
+    })]);
+    let output = run_fingerprint_with_definitions(
+        manifest.path(),
+        &[
+            "--fp",
+            "rows-large.v1",
+            "--fp",
+            "rows-small.v1",
+            "--no-witness",
+        ],
+        definitions_dir.path(),
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    let lines = parse_jsonl(&output.stdout);
+    let fingerprint = &lines[0]["fingerprint"];
+    assert_eq!(fingerprint["fingerprint_id"], "rows-small.v1");
+    assert_eq!(
+        fingerprint["fingerprint_crate"],
+        "dsl-runtime:row-counts.fp.yaml"
+    );
+
+    let check = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .args(["compile", "--check", "--fp", "rows-large.v1"])
+        .arg(&instances_path)
+        .output()
+        .expect("run compile --check");
+    assert_eq!(check.status.code(), Some(0));
+
+    let ambiguous = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .args(["compile", "--check"])
+        .arg(&instances_path)
+        .output()
+        .expect("run compile --check without --fp");
+    assert_eq!(ambiguous.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&ambiguous.stdout)
+            .contains("expected one fingerprint definition, found 2")
+    );
+}
+
+#[test]
+fn run_mode_cache_serves_identical_output_until_invalidated() {
+    let definitions_dir = tempdir().expect("create definitions dir");
+    let cache_dir = tempdir().expect("create cache dir");
+    let csv_fp = r#"
+fingerprint_id: cached-setup.v1
+format: csv
+assertions:
+  - filename_regex:
+      pattern: "(?i).*_STUP\\.txt$"
+  - sheet_min_rows:
+      sheet: "Sheet1"
+      min_rows: 2
+"#
+    .trim();
+    std::fs::write(definitions_dir.path().join("cached-setup.fp.yaml"), csv_fp)
+        .expect("write csv fingerprint definition");
+
+    let csv_file = NamedTempFile::with_suffix("_STUP.txt").expect("create csv text file");
+    std::fs::write(csv_file.path(), "loan_id,balance\nA-1,1000000\n")
+        .expect("write csv text fixture");
+    let manifest = write_jsonl(&[json!({
+        "version": "hash.v0",
+        "path": csv_file.path().display().to_string(),
+        "extension": ".txt",
+        "bytes_hash": "blake3:cached-setup",
+        "tool_versions": { "hash": "0.1.0" }
+    })]);
+    let cache_dir_arg = cache_dir.path().display().to_string();
+    let args = [
+        "--fp",
+        "cached-setup.v1",
+        "--cache-dir",
+        cache_dir_arg.as_str(),
+        "--no-witness",
+    ];
+
+    let uncached = run_fingerprint_with_definitions(
+        manifest.path(),
+        &["--fp", "cached-setup.v1", "--no-witness"],
+        definitions_dir.path(),
+    );
+    let first = run_fingerprint_with_definitions(manifest.path(), &args, definitions_dir.path());
+    assert_eq!(first.status.code(), Some(0));
+    assert_eq!(first.stdout, uncached.stdout);
+
+    let inspect_entries = || {
+        let inspect = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+            .args([
+                "cache",
+                "inspect",
+                "--json",
+                "--cache-dir",
+                cache_dir_arg.as_str(),
+            ])
+            .output()
+            .expect("run cache inspect");
+        assert_eq!(inspect.status.code(), Some(0));
+        let stats: Value = serde_json::from_slice(&inspect.stdout).expect("parse cache stats");
+        stats["entries"].as_u64().expect("entry count")
+    };
+    let second = run_fingerprint_with_definitions(manifest.path(), &args, definitions_dir.path());
+    assert_eq!(second.stdout, first.stdout);
+    assert_eq!(inspect_entries(), 1);
+
+    // The manifest still claims the original bytes_hash, but the file was
+    // rewritten, so the cached result is not served.
+    std::fs::write(csv_file.path(), "loan_id\n").expect("rewrite csv text fixture");
+    let rewritten =
+        run_fingerprint_with_definitions(manifest.path(), &args, definitions_dir.path());
+    assert_eq!(rewritten.status.code(), Some(1));
+    let lines = parse_jsonl(&rewritten.stdout);
+    assert_eq!(lines[0]["fingerprint"]["matched"], false);
+    assert_eq!(inspect_entries(), 2);
+
+    // A missing file fails exactly as it would without the cache.
+    let csv_path = csv_file.into_temp_path();
+    std::fs::remove_file(&csv_path).expect("remove csv text fixture");
+    let missing = run_fingerprint_with_definitions(manifest.path(), &args, definitions_dir.path());
+    let missing_uncached = run_fingerprint_with_definitions(
+        manifest.path(),
+        &["--fp", "cached-setup.v1", "--no-witness"],
+        definitions_dir.path(),
+    );
+    assert_eq!(missing.status.code(), missing_uncached.status.code());
+    assert_eq!(missing.stdout, missing_uncached.stdout);
+    assert_eq!(parse_jsonl(&missing.stdout)[0]["_skipped"], true);
+
+    let invalidate = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .args([
+            "cache",
+            "invalidate",
+            "--bytes-hash",
+            "blake3:cached-setup",
+            "--cache-dir",
+            cache_dir_arg.as_str(),
+        ])
+        .output()
+        .expect("run cache invalidate");
+    assert_eq!(invalidate.status.code(), Some(0));
+    assert_eq!(inspect_entries(), 0);
+}
+
 #[test]
 fn run_mode_html_specific_assertions_keep_content_hash_stable_and_null_on_no_match() {
     let definitions_dir = tempdir().expect("create definitions dir");
@@ -650,6 +912,122 @@ fn run_mode_witness_records_exact_output_hash_and_append_receipts() {
     assert_eq!(witness_rows[1]["output_hash"], expected_second_output_hash);
 }
 
+#[test]
+fn run_mode_resume_from_checkpoint_matches_uninterrupted_run() {
+    let csv_path = repo_path("tests/fixtures/files/sample.csv");
+    let xlsx_path = repo_path("tests/fixtures/files/sample.xlsx");
+    let manifest = write_jsonl(&[
+        json!({
+            "version": "hash.v0",
+            "path": csv_path.display().to_string(),
+            "extension": ".csv",
+            "bytes_hash": "blake3:csv",
+            "tool_versions": { "hash": "0.1.0" }
+        }),
+        json!({
+            "version": "hash.v0",
+            "path": xlsx_path.display().to_string(),
+            "extension": ".xlsx",
+            "bytes_hash": "blake3:xlsx",
+            "tool_versions": { "hash": "0.1.0" }
+        }),
+    ]);
+    let work_dir = tempdir().expect("create work dir");
+    let witness_path = work_dir.path().join("witness.jsonl");
+    let output_path = work_dir.path().join("out.jsonl");
+    let checkpoint_path = work_dir.path().join("run.checkpoint");
+    let run = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+            .arg(manifest.path())
+            .args(["--fp", "csv.v0", "--fp", "xlsx.v0", "--output"])
+            .arg(&output_path)
+            .arg("--checkpoint")
+            .arg(&checkpoint_path)
+            .args(extra)
+            .env("EPISTEMIC_WITNESS", &witness_path)
+            .output()
+            .expect("run fingerprint binary")
+    };
+
+    let uninterrupted = run(&[]);
+    assert_eq!(uninterrupted.status.code(), Some(0));
+    assert!(uninterrupted.stdout.is_empty());
+    let full_output = std::fs::read(&output_path).expect("read full output");
+    let checkpoint: Value =
+        serde_json::from_slice(&std::fs::read(&checkpoint_path).expect("read final checkpoint"))
+            .expect("parse final checkpoint");
+    assert_eq!(checkpoint["next_record"], 2);
+
+    // Simulate an interruption after the first record: the checkpoint covers
+    // one line and the output ends with a partially written second record.
+    let first_line_len = full_output
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .expect("first output line")
+        + 1;
+    let mut interrupted_output = full_output[..first_line_len].to_vec();
+    interrupted_output.extend_from_slice(b"{\"version\":");
+    std::fs::write(&output_path, &interrupted_output).expect("write interrupted output");
+    let mut interrupted_checkpoint = checkpoint.clone();
+    interrupted_checkpoint["next_record"] = json!(1);
+    interrupted_checkpoint["output_bytes"] = json!(first_line_len);
+    interrupted_checkpoint["output_hash"] = json!(format!(
+        "blake3:{}",
+        blake3::hash(&full_output[..first_line_len]).to_hex()
+    ));
+    std::fs::write(
+        &checkpoint_path,
+        serde_json::to_vec(&interrupted_checkpoint).expect("serialize checkpoint"),
+    )
+    .expect("write interrupted checkpoint");
+
+    let resumed = run(&["--resume"]);
+    assert_eq!(resumed.status.code(), Some(0));
+    assert_eq!(
+        std::fs::read(&output_path).expect("read resumed output"),
+        full_output
+    );
+
+    let witness_rows = parse_witness_ledger(&witness_path);
+    assert_eq!(witness_rows.len(), 2);
+    assert_eq!(
+        witness_rows[0]["output_hash"],
+        format!("blake3:{}", blake3::hash(&full_output).to_hex())
+    );
+    assert_eq!(
+        witness_rows[1]["output_hash"],
+        witness_rows[0]["output_hash"]
+    );
+
+    let mismatched = Command::new(env!("CARGO_BIN_EXE_fingerprint"))
+        .arg(manifest.path())
+        .args(["--fp", "csv.v0", "--output"])
+        .arg(&output_path)
+        .arg("--checkpoint")
+        .arg(&checkpoint_path)
+        .args(["--resume", "--no-witness"])
+        .output()
+        .expect("run fingerprint binary");
+    assert_eq!(mismatched.status.code(), Some(2));
+    assert!(mismatched.stdout.is_empty());
+    let refused_output = std::fs::read(&output_path).expect("read refused output");
+    assert!(refused_output.starts_with(&full_output));
+    let refusal: Value =
+        serde_json::from_slice(&refused_output[full_output.len()..]).expect("parse refusal");
+    assert_eq!(refusal["refusal"]["code"], "E_BAD_INPUT");
+    assert!(
+        refusal["refusal"]["detail"]["error"]
+            .as_str()
+            .expect("refusal error")
+            .contains("fingerprints")
+    );
+
+    let slower = run(&["--resume", "--timeout", "5"]);
+    assert_eq!(slower.status.code(), Some(2));
+    let refused_output = std::fs::read(&output_path).expect("read refused output");
+    assert!(refused_output.starts_with(&full_output));
+}
+
 #[test]
 fn run_mode_refusal_appends_witness_record() {
     let mut manifest = NamedTempFile::new().expect("create malformed manifest");
@@ -55,7 +55,9 @@ rules:
     );
 
     let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
-    let record: serde_json::Value = serde_json::from_str(stdout.trim()).expect("parse output JSON");
+    let record: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("group record"))
+            .expect("parse output JSON");
 
     assert_eq!(record["version"], "struct-check.v0");
     assert_eq!(record["rule_id"], "monthly-package.v1");
@@ -108,7 +110,9 @@ rules:
     );
 
     let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
-    let record: serde_json::Value = serde_json::from_str(stdout.trim()).expect("parse output JSON");
+    let record: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("group record"))
+            .expect("parse output JSON");
 
     assert_eq!(record["outcome"], "partial");
     let missing = record["missing"].as_array().unwrap();
@@ -153,7 +157,9 @@ rules:
     );
 
     let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
-    let record: serde_json::Value = serde_json::from_str(stdout.trim()).expect("parse output JSON");
+    let record: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("group record"))
+            .expect("parse output JSON");
 
     assert_eq!(record["outcome"], "empty");
     let missing = record["missing"].as_array().unwrap();
@@ -200,7 +206,9 @@ rules:
     assert!(output.status.success());
 
     let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
-    let record: serde_json::Value = serde_json::from_str(stdout.trim()).expect("parse output JSON");
+    let record: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("group record"))
+            .expect("parse output JSON");
 
     assert_eq!(record["outcome"], "complete");
     assert_eq!(record["unexpected"].as_array().unwrap(), &["draft.docx"]);
@@ -283,7 +291,7 @@ rules:
 
     let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
     let lines: Vec<&str> = stdout.trim().lines().collect();
-    assert_eq!(lines.len(), 3, "should emit 3 records");
+    assert_eq!(lines.len(), 4, "should emit 3 records and a summary");
 
     let r1: serde_json::Value = serde_json::from_str(lines[0]).expect("parse line 1");
     let r2: serde_json::Value = serde_json::from_str(lines[1]).expect("parse line 2");
@@ -296,4 +304,375 @@ rules:
     assert_eq!(r2["outcome"], "complete");
     assert_eq!(r3["matched_directory"], "org/packages/P003");
     assert_eq!(r3["outcome"], "empty");
+
+    let summary: serde_json::Value = serde_json::from_str(lines[3]).expect("parse summary");
+    assert_eq!(summary["version"], "struct-check-summary.v0");
+    assert_eq!(summary["rules_evaluated"], 1);
+    assert_eq!(summary["groups"], 3);
+    assert_eq!(summary["complete"], 2);
+    assert_eq!(summary["partial"], 0);
+    assert_eq!(summary["empty"], 1);
+}
+
+#[test]
+fn struct_check_requires_fingerprint_ids_from_enriched_input() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: property-folder.v1
+    group_by: "properties/*"
+    required:
+      - "*.pdf"
+    required_fingerprints:
+      - rent-roll.v2
+"#,
+    );
+
+    let input = write_temp_file(
+        &[
+            r#"{"version":"fingerprint.v0","relative_path":"properties/A/appraisal.pdf","fingerprint":null}"#,
+            r#"{"version":"fingerprint.v0","relative_path":"properties/A/march.xlsx","fingerprint":{"fingerprint_id":"rent-roll.v2","matched":true}}"#,
+            r#"{"version":"fingerprint.v0","relative_path":"properties/B/appraisal.pdf","fingerprint":null}"#,
+            r#"{"version":"fingerprint.v0","relative_path":"properties/B/rent_roll.xlsx","fingerprint":{"fingerprint_id":"rent-roll.v2","matched":false}}"#,
+        ]
+        .join("\n"),
+    );
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
+    let lines: Vec<serde_json::Value> = stdout
+        .trim()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse output JSON"))
+        .collect();
+    assert_eq!(lines.len(), 3);
+
+    assert_eq!(lines[0]["matched_directory"], "properties/A");
+    assert_eq!(lines[0]["outcome"], "complete");
+    assert_eq!(
+        lines[0]["present_fingerprints"],
+        serde_json::json!(["rent-roll.v2"])
+    );
+    assert_eq!(lines[1]["matched_directory"], "properties/B");
+    assert_eq!(lines[1]["outcome"], "partial");
+    assert_eq!(
+        lines[1]["missing_fingerprints"],
+        serde_json::json!(["rent-roll.v2"])
+    );
+
+    let vacuum_input =
+        write_temp_file(r#"{"version":"vacuum.v0","relative_path":"properties/A/march.xlsx"}"#);
+    let refused = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            vacuum_input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+    assert_eq!(refused.status.code(), Some(2));
+    let stderr = String::from_utf8(refused.stderr).expect("valid UTF-8");
+    assert!(stderr.contains("fingerprint.v0"), "stderr: {stderr}");
+}
+
+#[test]
+fn struct_check_reports_count_and_forbidden_violations() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: data-room.v1
+    group_by: "deals/*"
+    required:
+      - pattern: "*appraisal*.pdf"
+        max: 1
+    forbidden:
+      - "*.tmp"
+"#,
+    );
+
+    let input = write_temp_file(
+        &[
+            r#"{"version":"vacuum.v0","relative_path":"deals/D1/appraisal.pdf"}"#,
+            r#"{"version":"vacuum.v0","relative_path":"deals/D1/scratch.tmp"}"#,
+            r#"{"version":"vacuum.v0","relative_path":"deals/D2/appraisal.pdf"}"#,
+        ]
+        .join("\n"),
+    );
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
+    let lines: Vec<serde_json::Value> = stdout
+        .trim()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse output JSON"))
+        .collect();
+    assert_eq!(lines[0]["matched_directory"], "deals/D1");
+    assert_eq!(lines[0]["outcome"], "invalid");
+    assert_eq!(
+        lines[0]["violations"],
+        serde_json::json!([{
+            "pattern": "*.tmp",
+            "kind": "forbidden",
+            "count": 1,
+            "max": 0,
+            "files": ["scratch.tmp"]
+        }])
+    );
+    assert!(lines[0]["unexpected"].as_array().unwrap().is_empty());
+    assert_eq!(lines[1]["matched_directory"], "deals/D2");
+    assert_eq!(lines[1]["outcome"], "complete");
+}
+
+#[test]
+fn struct_check_refuses_min_above_max() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: data-room.v1
+    group_by: "deals/*"
+    required:
+      - pattern: "*appraisal*.pdf"
+        min: 2
+        max: 1
+"#,
+    );
+    let input = write_temp_file(r#"{"version":"vacuum.v0","relative_path":"deals/D1/a.pdf"}"#);
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).expect("valid UTF-8");
+    assert!(
+        stderr.contains("min 2 greater than max 1"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn struct_check_substitutes_captures_and_requires_counterpart_groups() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: property-docs.v1
+    group_by: "deals/{deal}/properties/{property}"
+    required:
+      - "{property}_rent_roll.xlsx"
+    required_groups:
+      - "deals/{deal}/financials/{property}"
+"#,
+    );
+
+    let input = write_temp_file(
+        &[
+            r#"{"version":"vacuum.v0","relative_path":"deals/D1/properties/A1/A1_rent_roll.xlsx"}"#,
+            r#"{"version":"vacuum.v0","relative_path":"deals/D1/properties/B2/B2_rent_roll.xlsx"}"#,
+            r#"{"version":"vacuum.v0","relative_path":"deals/D1/financials/A1/2024.xlsx"}"#,
+        ]
+        .join("\n"),
+    );
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
+    let lines: Vec<serde_json::Value> = stdout
+        .trim()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse output JSON"))
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0]["captures"],
+        serde_json::json!({"deal": "D1", "property": "A1"})
+    );
+    assert_eq!(lines[0]["outcome"], "complete");
+    assert_eq!(
+        lines[0]["present_groups"],
+        serde_json::json!(["deals/D1/financials/A1"])
+    );
+    assert_eq!(lines[1]["matched_directory"], "deals/D1/properties/B2");
+    assert_eq!(lines[1]["outcome"], "partial");
+    assert_eq!(
+        lines[1]["present"],
+        serde_json::json!(["B2_rent_roll.xlsx"])
+    );
+    assert_eq!(
+        lines[1]["missing_groups"],
+        serde_json::json!(["deals/D1/financials/B2"])
+    );
+}
+
+#[test]
+fn struct_check_refuses_uncaptured_group_reference() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: property-docs.v1
+    group_by: "deals/{deal}/properties/*"
+    required_groups:
+      - "deals/{deal}/financials/{property}"
+"#,
+    );
+    let input = write_temp_file(r#"{"version":"vacuum.v0","relative_path":"deals/D1/a.pdf"}"#);
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--input",
+            input.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).expect("valid UTF-8");
+    assert!(
+        stderr.contains("which group_by does not capture"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn struct_check_walks_a_directory() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: monthly-package.v1
+    group_by: "*/packages/P*"
+    required:
+      - "*.pdf"
+      - "*_summary.xlsx"
+"#,
+    );
+
+    let root = tempfile::TempDir::new().expect("create temp dir");
+    for relative in [
+        "org/packages/P001/report.pdf",
+        "org/packages/P001/jan_summary.xlsx",
+        "org/packages/P001/scratch.tmp",
+        "org/packages/P002/report.pdf",
+    ] {
+        let path = root.path().join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("create dirs");
+        std::fs::write(path, b"").expect("write file");
+    }
+    std::fs::write(root.path().join(".gitignore"), "*.tmp\n").expect("write .gitignore");
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--dir",
+            root.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).expect("valid UTF-8");
+    let lines: Vec<serde_json::Value> = stdout
+        .trim()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse output JSON"))
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["version"], "struct-check.v0");
+    assert_eq!(lines[0]["matched_directory"], "org/packages/P001");
+    assert_eq!(lines[0]["outcome"], "complete");
+    assert!(lines[0]["unexpected"].as_array().unwrap().is_empty());
+    // Fields for features the rule does not use are left out.
+    for field in [
+        "captures",
+        "present_fingerprints",
+        "missing_fingerprints",
+        "violations",
+        "present_groups",
+        "missing_groups",
+    ] {
+        assert!(lines[0].get(field).is_none(), "unexpected field {field}");
+    }
+    assert_eq!(lines[1]["matched_directory"], "org/packages/P002");
+    assert_eq!(lines[1]["outcome"], "partial");
+    assert_eq!(lines[2]["version"], "struct-check-summary.v0");
+    assert_eq!(lines[2]["groups"], 2);
+    assert_eq!(lines[2]["complete"], 1);
+    assert_eq!(lines[2]["partial"], 1);
+}
+
+#[test]
+fn struct_check_refuses_fingerprint_rules_with_dir() {
+    let rules = write_temp_file(
+        r#"
+rules:
+  - id: property-folder.v1
+    group_by: "properties/*"
+    required_fingerprints:
+      - rent-roll.v2
+"#,
+    );
+    let root = tempfile::TempDir::new().expect("create temp dir");
+
+    let output = fingerprint_bin()
+        .args([
+            "struct-check",
+            "--rules",
+            rules.path().to_str().unwrap(),
+            "--dir",
+            root.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("run struct-check");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).expect("valid UTF-8");
+    assert!(stderr.contains("--dir cannot provide"), "stderr: {stderr}");
 }